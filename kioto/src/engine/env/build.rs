@@ -1,6 +1,10 @@
-use super::{default_create_env, EngineConfig, Env};
+use super::{default_create_env, source, ConfigSource, EngineConfig, Env};
 use reality::{plugin::Name, Content, Repr, Resource, Uuid};
-use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 use tracing::{debug, error};
 
 /// Type-alias for a function that creates an environment
@@ -16,6 +20,17 @@ pub struct Builder {
     env_loader: LoadEnv,
 }
 
+/// Summary of the files written to the target root by `Builder::build_env`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BuildSummary {
+    /// Files that did not exist in the target root
+    pub added: Vec<PathBuf>,
+    /// Files that were rewritten because their content changed
+    pub changed: Vec<PathBuf>,
+    /// Files that were not written because their content already matched
+    pub unchanged: Vec<PathBuf>,
+}
+
 impl Builder {
     /// Creates an new builder using the default env_loader
     ///
@@ -36,11 +51,16 @@ impl Builder {
 
     /// Tries to build an environment from files in a source root and,
     /// constructing the required folder structure to load from the target root
+    ///
+    /// Files in the target root are only written if their content changed, so that rebuilding an env does not touch
+    /// files that are already up to date
+    ///
+    /// Returns a summary of the files that were added, changed, or unchanged in the target root
     pub fn build_env(
         &self,
         source_root: impl Into<PathBuf>,
         target_root: impl Into<PathBuf>,
-    ) -> std::io::Result<()> {
+    ) -> std::io::Result<BuildSummary> {
         let source_root: PathBuf = source_root.into().join(&self.label);
         let target_root: PathBuf = target_root.into().join(&self.label);
         let dir_reader = source_root.read_dir()?;
@@ -109,17 +129,18 @@ impl Builder {
 
         match toml::to_string(&config) {
             Ok(config) => {
+                let mut summary = BuildSummary::default();
                 std::fs::create_dir_all(&target_root)?;
                 let config_path = target_root.join("config.toml");
-                std::fs::write(config_path, config)?;
+                write_if_changed(&config_path, config.as_bytes(), &mut summary)?;
                 for ((name, event_name), source) in copy_tasks {
                     let to_dir = target_root.join("etc").join(name.path());
                     std::fs::create_dir_all(&to_dir)?;
                     let to = to_dir.join(format!("{event_name}.toml"));
                     debug!("Copying {source:?} -> {to:?}");
-                    std::fs::copy(&source, &to)?;
+                    write_if_changed(&to, &std::fs::read(&source)?, &mut summary)?;
                 }
-                Ok(())
+                Ok(summary)
             }
             Err(err) => Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
@@ -128,11 +149,63 @@ impl Builder {
         }
     }
 
+    /// Fetches the config tree of this env from a source into `root`, and then loads the env from `root`
+    ///
+    /// The config tree is fetched in order of `config.toml`, its includes, and then the file of each plugin and handler
+    /// declared by the config. Plugins configured w/ an explicit `load` path are read from the local file system.
+    ///
+    /// Fetched configs are rendered w/ the env vars before their includes are resolved, the same as configs loaded from
+    /// the file system.
+    ///
+    /// **Note**: `root` is used as a local cache of the fetched files, existing files are overwritten
+    ///
+    /// Returns an error if a file could not be fetched or written, if a fetched path is absolute or has a `..`
+    /// component, if a config include is a glob pattern, or if the env could not be loaded
+    pub async fn load_env_from(
+        &self,
+        source: &dyn ConfigSource,
+        root: impl Into<PathBuf>,
+    ) -> std::io::Result<Env> {
+        let root = root.into();
+        let env_root = root.join(&self.label);
+
+        let mut configs = vec![PathBuf::from("config.toml")];
+        let mut fetched = vec![];
+        while let Some(path) = configs.pop() {
+            if fetched.contains(&path) {
+                continue;
+            }
+            let content = source::fetch(source, &self.label, &path, &env_root).await?;
+            let config = std::str::from_utf8(&content)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+                .and_then(EngineConfig::parse_rendered)?;
+            for include in config.includes() {
+                if include.contains(['*', '?', '[']) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Glob include `{include}` cannot be resolved from a config source"),
+                    ));
+                }
+                configs.push(PathBuf::from(include));
+            }
+            fetched.push(path);
+        }
+
+        let config = EngineConfig::from_file_system(&root, &self.label)?;
+        for path in config.plugin_files() {
+            source::fetch(source, &self.label, &path, &env_root).await?;
+        }
+
+        self.load_env(root)
+    }
+
     /// Tries to initialize from some root directory,
     ///
     /// Will load all config immediately and set the env loader with the loaded config.
     ///
     /// The EnvLoader can then be used to load events from event configurations
+    ///
+    /// Returns an error if the dependencies of a loaded plugin are not loaded, see `EngineConfig::check_dependencies`
     #[inline]
     pub fn load_env(&self, root: impl Into<PathBuf>) -> std::io::Result<Env> {
         let root = root.into();
@@ -141,12 +214,42 @@ impl Builder {
         config
             .load(&mut loader)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{e:?}")))?;
+        config.check_dependencies(&loader)?;
         loader.config = config;
         loader.label = self.label.clone();
         Ok(loader)
     }
 }
 
+/// Writes content to a path if the crc of the content does not match the crc of the existing file
+fn write_if_changed(
+    path: &Path,
+    content: &[u8],
+    summary: &mut BuildSummary,
+) -> std::io::Result<()> {
+    let existing = match std::fs::read(path) {
+        Ok(existing) => Some(existing),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+
+    let crc = reality::content::crc();
+    match existing {
+        Some(existing) if crc.checksum(&existing) == crc.checksum(content) => {
+            summary.unchanged.push(path.to_path_buf());
+        }
+        Some(_) => {
+            std::fs::write(path, content)?;
+            summary.changed.push(path.to_path_buf());
+        }
+        None => {
+            std::fs::write(path, content)?;
+            summary.added.push(path.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
 impl Resource for Builder {}
 impl Repr for Builder {}
 impl Content for Builder {
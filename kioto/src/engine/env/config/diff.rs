@@ -0,0 +1,158 @@
+use serde::Serialize;
+use std::fmt::Display;
+
+/// Change to a field between two versions of a config, see `config_diff`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum FieldChange {
+    /// Field was added w/ a value
+    Added { field: String, value: toml::Value },
+    /// Field was removed, w/ the value it had
+    Removed { field: String, value: toml::Value },
+    /// Value of a field changed
+    Changed {
+        field: String,
+        old: toml::Value,
+        new: toml::Value,
+    },
+}
+
+impl FieldChange {
+    /// Returns the dotted path of the field that changed, i.e. `-kt-build.labels.env`
+    #[inline]
+    pub fn field(&self) -> &str {
+        match self {
+            FieldChange::Added { field, .. }
+            | FieldChange::Removed { field, .. }
+            | FieldChange::Changed { field, .. } => field,
+        }
+    }
+}
+
+impl Display for FieldChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldChange::Added { field, value } => write!(f, "+ {field} = {value}"),
+            FieldChange::Removed { field, value } => write!(f, "- {field} = {value}"),
+            FieldChange::Changed { field, old, new } => write!(f, "~ {field} = {old} -> {new}"),
+        }
+    }
+}
+
+/// Compares two serialized configs and returns the fields that were added, removed, or changed, ordered by field
+///
+/// Tables are compared field by field, and each field is reported w/ its dotted path. Any other value, including
+/// arrays, is compared as a whole.
+///
+/// # Example
+/// ```ignore
+/// for change in config_diff(&toml::Value::try_from(&old)?, &toml::Value::try_from(&new)?) {
+///     debug!("{change}");
+/// }
+/// ```
+pub fn config_diff(old: &toml::Value, new: &toml::Value) -> Vec<FieldChange> {
+    let mut changes = vec![];
+    diff_value(String::new(), old, new, &mut changes);
+    changes.sort_by(|a, b| a.field().cmp(b.field()));
+    changes
+}
+
+/// Compares two values at `field` and appends the changes
+fn diff_value(field: String, old: &toml::Value, new: &toml::Value, changes: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (toml::Value::Table(old), toml::Value::Table(new)) => {
+            let join = |key: &str| {
+                if field.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{field}.{key}")
+                }
+            };
+            for (key, old_value) in old.iter() {
+                match new.get(key) {
+                    Some(new_value) => diff_value(join(key), old_value, new_value, changes),
+                    None => changes.push(FieldChange::Removed {
+                        field: join(key),
+                        value: old_value.clone(),
+                    }),
+                }
+            }
+            for (key, new_value) in new.iter().filter(|(k, _)| !old.contains_key(*k)) {
+                changes.push(FieldChange::Added {
+                    field: join(key),
+                    value: new_value.clone(),
+                });
+            }
+        }
+        (old, new) if old != new => changes.push(FieldChange::Changed {
+            field,
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_diff() {
+        let old = toml::from_str::<toml::Value>(
+            r#"
+url = "https://example.com"
+method = "GET"
+headers = ["a: 1"]
+
+[-kt-build.labels]
+env = "dev"
+team = "runplat"
+"#,
+        )
+        .unwrap();
+        let new = toml::from_str::<toml::Value>(
+            r#"
+url = "https://example.com/v2"
+headers = ["a: 1", "b: 2"]
+timeout_ms = 100
+
+[-kt-build.labels]
+env = "prod"
+team = "runplat"
+"#,
+        )
+        .unwrap();
+
+        let changes = config_diff(&old, &new);
+        assert_eq!(
+            vec![
+                "-kt-build.labels.env",
+                "headers",
+                "method",
+                "timeout_ms",
+                "url"
+            ],
+            changes.iter().map(|c| c.field()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            FieldChange::Removed {
+                field: "method".to_string(),
+                value: toml::Value::from("GET")
+            },
+            changes[2]
+        );
+        assert_eq!(
+            FieldChange::Added {
+                field: "timeout_ms".to_string(),
+                value: toml::Value::from(100)
+            },
+            changes[3]
+        );
+        assert_eq!(
+            "~ -kt-build.labels.env = \"dev\" -> \"prod\"",
+            changes[0].to_string()
+        );
+
+        assert!(config_diff(&old, &old).is_empty());
+    }
+}
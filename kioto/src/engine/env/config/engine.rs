@@ -2,10 +2,22 @@ use super::{EventConfig, PluginConfig};
 use crate::{engine::env::Env, Result};
 use reality::plugin::{Address, Event, HandlerThunk, Name};
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, path::PathBuf};
-use toml_edit::DocumentMut;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+use toml_edit::{visit_mut::VisitMut, DocumentMut, Formatted};
 use tracing::debug;
 
+/// Type-alias for a function that upgrades a config table from one schema version to the next
+type Migration = fn(&mut toml::Table) -> std::io::Result<()>;
+
+/// Migrations applied to configs w/ an older schema version, `MIGRATIONS[i]` upgrades a config from version `i + 1`
+/// to version `i + 2`
+///
+/// **Note**: When `Config::SCHEMA_VERSION` is incremented, a migration from the previous version must be added
+const MIGRATIONS: &[Migration] = &[];
+
 /// Configures an engine environment
 ///
 /// - `plugins`: map of plugins to load into the environment
@@ -13,19 +25,67 @@ use tracing::debug;
 ///
 /// The key of each map will be set as a label in Labels, `event = <key>`
 ///
+/// A plugin or handler w/ `enabled = false` is skipped when the config is loaded, so an event or handler is not
+/// created for it
+///
+/// - `default_labels`: labels applied to every plugin and handler, labels set by a plugin's `-kt-build` table take
+///   precedence
+///
+/// ## Instances
+///
+/// - `for_each`: list of parameter sets, a plugin or handler is loaded once for each parameter set
+///
+/// Each instance is loaded from the same settings w/ mustache tags in the settings rendered from the parameter set,
+/// and is keyed by `<key>-<index>`, i.e. `health-0`.
+///
+/// ## Schema version
+///
+/// - `schema_version`: version of the config schema, defaults to `Config::SCHEMA_VERSION` if absent
+///
+/// Configs w/ an older schema version are migrated to the current version when they are read. Configs w/ a newer
+/// schema version cannot be read.
+///
+/// ## Includes
+///
+/// - `includes`: list of config files to merge into this config, relative to the env root, i.e. `<root>/<env>`
+///
+/// Each include can be a glob pattern, i.e. `prod/*.toml`. Includes are merged in order, so later includes override
+/// keys from earlier includes, and keys declared by the including config override keys from all of its includes.
+///
+/// ## Templates
+///
+/// The string values of a config can use mustache tags, i.e. `{{region}}`, which are rendered before the config is
+/// deserialized. Tags are resolved from the context passed to `Config::from_file_system_with_context`, and env vars can
+/// be referenced w/ `{{env.<NAME>}}`. Loading a config w/ a tag that could not be resolved returns an error.
+///
+/// **Note**: Values are rendered w/o html escaping, so `{{tag}}` renders the same as `{{{tag}}}`. Tags are only
+/// rendered inside of string values, so a rendered value cannot change the structure of the config.
+///
 /// ## Default file location
 ///
 /// If a file location is not specified, this type will be constructed from the path format,
 ///
 /// `<root>/<env>/config.toml`
-#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Config {
+    /// Version of the config schema
+    #[serde(default = "default_schema_version")]
+    schema_version: u32,
+    /// Config files to include into this config
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    includes: Vec<String>,
+    /// Labels applied to every plugin and handler loaded by this config
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    default_labels: BTreeMap<String, String>,
     /// Plugins to be loaded by the environment
     #[serde(default)]
     plugins: BTreeMap<String, PluginConfig>,
     /// Plugin handlers to be loaded by the environment
     #[serde(default)]
     handlers: BTreeMap<String, PluginConfig>,
+    /// Event names of the plugins in the order they were declared, see `Config::loaded_plugins_in_order`
+    #[serde(skip)]
+    plugin_order: Vec<String>,
     /// Map of plugins loaded by this config
     #[serde(skip)]
     loaded_plugins: BTreeMap<String, Address>,
@@ -35,6 +95,9 @@ pub struct Config {
 }
 
 impl Config {
+    /// Current version of the config schema
+    pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
     /// Tries to parse build metadata from a document,
     ///
     /// Returns an error message if unsuccessful, otherwise adds the plugin config to the current config
@@ -58,6 +121,8 @@ impl Config {
                                         plugin: metadata.plugin.to_string(),
                                         load: metadata.load.clone(),
                                         labels: metadata.labels.clone(),
+                                        enabled: metadata.enabled,
+                                        for_each: vec![],
                                     },
                                 );
                             } else {
@@ -67,6 +132,8 @@ impl Config {
                                         plugin: metadata.plugin.to_string(),
                                         load: metadata.load.clone(),
                                         labels: metadata.labels.clone(),
+                                        enabled: metadata.enabled,
+                                        for_each: vec![],
                                     },
                                 );
                             }
@@ -84,34 +151,321 @@ impl Config {
 
     /// Tries to load an env engine config from some root directory, i.e. `<root>/<env>/config.toml`
     ///
-    /// Returns an error if the file could not be read, found, or deserialized
+    /// Returns an error if the file or any of its includes could not be read, found, or deserialized, or if
+    /// the includes form a cycle
     #[inline]
     pub fn from_file_system(root: impl Into<PathBuf>, name: &str) -> std::io::Result<Self> {
-        let root = root.into();
-        let config = root.join(name).join("config.toml");
-        let config = std::fs::read_to_string(config)?;
-        toml::from_str(&config)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.message()))
+        Self::from_file_system_with_context(root, name, &BTreeMap::new())
+    }
+
+    /// Tries to load an env engine config from some root directory, i.e. `<root>/<env>/config.toml`, rendering the
+    /// config and its includes w/ a template context
+    ///
+    /// Returns an error if a template tag could not be resolved from the context or env vars
+    #[inline]
+    pub fn from_file_system_with_context(
+        root: impl Into<PathBuf>,
+        name: &str,
+        context: &BTreeMap<String, String>,
+    ) -> std::io::Result<Self> {
+        let env_root = root.into().join(name);
+        let config = env_root.join("config.toml");
+        Self::read_with_includes(&env_root, &config, &template_context(context), &mut vec![])
+    }
+
+    /// Renders a config w/ the env vars as the template context and parses the config, w/o reading its includes
+    ///
+    /// Returns an error if a template tag could not be resolved or if the config could not be parsed
+    #[inline]
+    pub(crate) fn parse_rendered(content: &str) -> std::io::Result<Self> {
+        Self::parse(&render_template(
+            content,
+            &template_context(&BTreeMap::new()),
+        )?)
+    }
+
+    /// Reads a config file and merges any includes into the config
+    ///
+    /// The stack contains the config files currently being read and is used to detect cycles
+    fn read_with_includes(
+        env_root: &Path,
+        path: &Path,
+        context: &serde_json::Map<String, serde_json::Value>,
+        stack: &mut Vec<PathBuf>,
+    ) -> std::io::Result<Self> {
+        let path = path.canonicalize()?;
+        if stack.contains(&path) {
+            let cycle = stack
+                .iter()
+                .chain(Some(&path))
+                .map(|p| p.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Include cycle detected: {cycle}"),
+            ));
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let content = render_template(&content, context).map_err(|e| {
+            std::io::Error::new(e.kind(), format!("Could not render config {path:?}: {e}"))
+        })?;
+        let mut config = Self::parse(&content)?;
+        if config.includes.is_empty() {
+            return Ok(config);
+        }
+
+        stack.push(path);
+        let mut default_labels = BTreeMap::new();
+        let mut plugins = BTreeMap::new();
+        let mut handlers = BTreeMap::new();
+        let mut plugin_order = vec![];
+        for pattern in config.includes.iter() {
+            for include in resolve_include(env_root, pattern)? {
+                debug!("Including config {include:?}");
+                let included = Self::read_with_includes(env_root, &include, context, stack)?;
+                default_labels.extend(included.default_labels);
+                plugins.extend(included.plugins);
+                handlers.extend(included.handlers);
+                plugin_order.extend(included.plugin_order);
+            }
+        }
+        stack.pop();
+
+        // Plugins of included configs are declared before the plugins of this config
+        plugin_order.append(&mut config.plugin_order);
+        let mut declared = BTreeSet::new();
+        plugin_order.retain(|event| declared.insert(event.clone()));
+        config.plugin_order = plugin_order;
+
+        default_labels.append(&mut config.default_labels);
+        plugins.append(&mut config.plugins);
+        handlers.append(&mut config.handlers);
+        config.default_labels = default_labels;
+        config.plugins = plugins;
+        config.handlers = handlers;
+        Ok(config)
+    }
+
+    /// Parses a config, migrating the config to the current schema version before it is deserialized
+    ///
+    /// Returns an error if the config could not be parsed or migrated, or if the schema version is newer than
+    /// `Config::SCHEMA_VERSION`
+    pub(crate) fn parse(content: &str) -> std::io::Result<Self> {
+        let mut table = toml::from_str::<toml::Table>(content)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.message()))?;
+        migrate(&mut table, MIGRATIONS)?;
+        let mut config = table
+            .try_into::<Self>()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.message()))?;
+
+        // **Note**: The toml table is sorted by key, so the declaration order is read from the document
+        config.plugin_order = content
+            .parse::<DocumentMut>()
+            .ok()
+            .and_then(|doc| {
+                doc.get("plugins")
+                    .and_then(|p| p.as_table_like())
+                    .map(|p| p.iter().map(|(k, _)| k.to_string()).collect())
+            })
+            .unwrap_or_default();
+        Ok(config)
+    }
+
+    /// Returns the schema version of this config
+    #[inline]
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
     }
 
     /// Load the engine config into state, creates map of loaded handlers and plugins
+    ///
+    /// Plugins and handlers that are not enabled are skipped
     #[inline]
     pub fn load(&mut self, loader: &mut Env) -> Result<()> {
         for (event_name, conf) in self.plugins.iter() {
-            debug!("Loading event `{event_name}`");
-            let address = conf.load(&event_name, loader)?;
-            self.loaded_plugins.insert(event_name.to_string(), address);
+            if !conf.is_enabled() {
+                debug!("Skipping disabled event `{event_name}`");
+                continue;
+            }
+            for (instance, params) in conf.instances(event_name) {
+                debug!("Loading event `{instance}`");
+                let address = conf.load(
+                    event_name,
+                    (&instance, params),
+                    loader,
+                    &self.default_labels,
+                )?;
+                self.loaded_plugins.insert(instance, address);
+            }
         }
 
         for (handler_name, conf) in self.handlers.iter() {
-            debug!("Loading handler `{handler_name}`");
-            let address = conf.load(&handler_name, loader)?;
-            self.loaded_handlers
-                .insert(handler_name.to_string(), address);
+            if !conf.is_enabled() {
+                debug!("Skipping disabled handler `{handler_name}`");
+                continue;
+            }
+            for (instance, params) in conf.instances(handler_name) {
+                debug!("Loading handler `{instance}`");
+                let address = conf.load(
+                    handler_name,
+                    (&instance, params),
+                    loader,
+                    &self.default_labels,
+                )?;
+                self.loaded_handlers.insert(instance, address);
+            }
         }
         Ok(())
     }
 
+    /// Checks that the dependencies of each plugin and handler loaded by this config are loaded, see
+    /// `Plugin::dependencies`
+    ///
+    /// Returns an error listing the missing dependencies of each event
+    #[inline]
+    pub fn check_dependencies(&self, loader: &Env) -> std::io::Result<()> {
+        let missing = self
+            .loaded_plugins
+            .iter()
+            .chain(self.loaded_handlers.iter())
+            .filter_map(
+                |(event, address)| match loader.state.check_dependencies(address) {
+                    Ok(()) => None,
+                    Err(missing) => Some(format!(
+                        "`{event}` is missing {}",
+                        missing
+                            .iter()
+                            .map(|m| m.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )),
+                },
+            )
+            .collect::<Vec<_>>();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Missing plugin dependencies: {}", missing.join("; ")),
+            ))
+        }
+    }
+
+    /// Exports this config, and the settings of each plugin and handler loaded by this config, into a new env tree
+    /// rooted at `env_root`
+    ///
+    /// Settings are copied from their source w/o interpolating secrets, w/ the labels each plugin was loaded w/ written
+    /// to the `-kt-build` table, see `Env::export`. Includes are merged into the exported config and each plugin is
+    /// exported to its default file location.
+    ///
+    /// **Note**: The source of a plugin w/ `for_each` is copied as-is, since each instance is rendered from it
+    pub(crate) fn export_to(&self, loader: &Env, env_root: &Path) -> std::io::Result<()> {
+        let mut exported = self.clone();
+        exported.schema_version = Self::SCHEMA_VERSION;
+        exported.includes.clear();
+        for (configs, loaded) in [
+            (&mut exported.plugins, &self.loaded_plugins),
+            (&mut exported.handlers, &self.loaded_handlers),
+        ] {
+            for (event, conf) in configs.iter_mut() {
+                if conf.is_enabled() {
+                    let (name, source) = conf.source(event, loader).map_err(|e| {
+                        std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{e:?}"))
+                    })?;
+                    let mut settings = std::fs::read_to_string(&source)?
+                        .parse::<DocumentMut>()
+                        .map_err(|e| {
+                            std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+                        })?;
+                    settings.remove(crate::KT_LOADER_METADATA_TABLE);
+                    if let Some(address) = loaded.get(event) {
+                        loader.export(address, &mut settings);
+                    }
+
+                    let target = env_root
+                        .join("etc")
+                        .join(name.path())
+                        .join(format!("{event}.toml"));
+                    if let Some(parent) = target.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(target, settings.to_string())?;
+                }
+                conf.load = None;
+            }
+        }
+
+        std::fs::create_dir_all(env_root)?;
+        let config = toml::to_string(&exported)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+        std::fs::write(env_root.join("config.toml"), config)
+    }
+
+    /// Returns the config files included by this config
+    #[inline]
+    pub(crate) fn includes(&self) -> &[String] {
+        &self.includes
+    }
+
+    /// Returns the paths of the files plugins and handlers are loaded from, relative to the env root
+    ///
+    /// **Note**: Plugins w/ an explicit `load` source, an invalid plugin name, or that are not enabled are not
+    /// included
+    pub(crate) fn plugin_files(&self) -> Vec<PathBuf> {
+        self.plugins
+            .iter()
+            .chain(self.handlers.iter())
+            .filter(|(_, conf)| conf.load.is_none() && conf.is_enabled())
+            .filter_map(|(event, conf)| {
+                let name = conf.plugin.parse::<Name>().ok()?;
+                Some(
+                    PathBuf::from("etc")
+                        .join(name.path())
+                        .join(format!("{event}.toml")),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns an iterator over the event names and addresses of plugins loaded by this config
+    #[inline]
+    pub fn loaded_plugins(&self) -> impl Iterator<Item = (&str, &Address)> {
+        self.loaded_plugins.iter().map(|(e, a)| (e.as_str(), a))
+    }
+
+    /// Returns the event names and addresses of plugins loaded by this config, in the order the plugins were declared
+    ///
+    /// Plugins of included configs are declared before the plugins of the including config, and the instances of a
+    /// plugin w/ `for_each` are returned in the order of their parameter sets. Plugins w/o a declaration order, i.e.
+    /// plugins added by `parse_build_document`, are returned last, sorted by event name.
+    pub fn loaded_plugins_in_order(&self) -> Vec<(&str, &Address)> {
+        let mut seen = BTreeSet::new();
+        let mut ordered = vec![];
+        for event in self.plugin_order.iter().chain(self.plugins.keys()) {
+            let Some(conf) = self.plugins.get(event) else {
+                continue;
+            };
+            for (instance, _) in conf.instances(event) {
+                if let Some((name, address)) = self.loaded_plugins.get_key_value(&instance) {
+                    if seen.insert(name.as_str()) {
+                        ordered.push((name.as_str(), address));
+                    }
+                }
+            }
+        }
+        ordered
+    }
+
+    /// Returns an iterator over the handler names and addresses of handlers loaded by this config
+    #[inline]
+    pub fn loaded_handlers(&self) -> impl Iterator<Item = (&str, &Address)> {
+        self.loaded_handlers.iter().map(|(h, a)| (h.as_str(), a))
+    }
+
     /// Tries to return an event loaded by this config w/ the provided env loader
     ///
     /// Returns an error if the plugin could not found or event created
@@ -139,14 +493,747 @@ impl Config {
     /// If the event config specifies a handler, the handler will be applied to the returned event
     #[inline]
     pub fn configure_event(&self, config: &EventConfig, loader: &Env) -> reality::Result<Event> {
-        match config.split_for_lookup() {
-            (event, None) => self.event(&event, loader).map(|(_, e)| e),
+        let mut event = match config.split_for_lookup() {
+            (event, None) => self.event(&event, loader).map(|(_, e)| e)?,
             (event, Some(handler)) => {
                 let (_, mut event) = self.event(&event, loader)?;
                 let (address, handler) = self.handler(&handler, loader)?;
                 event.set_handler(address, &handler)?;
-                Ok(event)
+                event
+            }
+        };
+
+        if let Some(input) = config.input_data()? {
+            event.with_input(input)?;
+        }
+        Ok(event)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            schema_version: Config::SCHEMA_VERSION,
+            includes: vec![],
+            default_labels: BTreeMap::new(),
+            plugins: BTreeMap::new(),
+            handlers: BTreeMap::new(),
+            plugin_order: vec![],
+            loaded_plugins: BTreeMap::new(),
+            loaded_handlers: BTreeMap::new(),
+        }
+    }
+}
+
+/// Returns the current schema version, used when a config does not declare a schema version
+fn default_schema_version() -> u32 {
+    Config::SCHEMA_VERSION
+}
+
+/// Applies migrations to a config table until it is at the current schema version
+///
+/// `migrations[i]` upgrades a config from version `i + 1` to version `i + 2`, so the current schema version is
+/// `migrations.len() + 1`
+fn migrate(table: &mut toml::Table, migrations: &[Migration]) -> std::io::Result<()> {
+    let current = migrations.len() as u32 + 1;
+    let version = match table.get("schema_version") {
+        None => current,
+        Some(toml::Value::Integer(v)) if *v >= 1 => u32::try_from(*v).unwrap_or(u32::MAX),
+        Some(v) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid config schema version `{v}`"),
+            ))
+        }
+    };
+    if version > current {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Config schema version {version} is newer than the supported schema version {current}"
+            ),
+        ));
+    }
+
+    for (from, migration) in migrations.iter().enumerate().skip(version as usize - 1) {
+        debug!("Migrating config from schema version {}", from + 1);
+        migration(table)?;
+    }
+    table.insert(
+        "schema_version".to_string(),
+        toml::Value::Integer(current.into()),
+    );
+    Ok(())
+}
+
+/// Returns the template context configs are rendered w/, the env vars under `env` and the entries of `context`
+fn template_context(
+    context: &BTreeMap<String, String>,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut data = serde_json::Map::new();
+    data.insert(
+        "env".to_string(),
+        std::env::vars()
+            .map(|(k, v)| (k, serde_json::Value::String(v)))
+            .collect(),
+    );
+    data.extend(
+        context
+            .iter()
+            .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.to_string()))),
+    );
+    data
+}
+
+/// Renders the mustache tags in the string values of a config w/ a template context
+///
+/// **Note**: The config is parsed before rendering, so that a rendered value is never interpreted as toml
+///
+/// Returns an error if the config could not be parsed or if a tag could not be rendered, see `render_str`
+pub(super) fn render_template(
+    content: &str,
+    context: &serde_json::Map<String, serde_json::Value>,
+) -> std::io::Result<String> {
+    if !content.contains("{{") {
+        return Ok(content.to_string());
+    }
+
+    let mut document = content
+        .parse::<DocumentMut>()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+    let mut visitor = TemplateVisitor {
+        context,
+        error: None,
+    };
+    visitor.visit_document_mut(&mut document);
+    match visitor.error {
+        Some(error) => Err(error),
+        None => Ok(document.to_string()),
+    }
+}
+
+/// Visits the string values of a toml document to render mustache tags
+struct TemplateVisitor<'a> {
+    /// Context tags are resolved from
+    context: &'a serde_json::Map<String, serde_json::Value>,
+    /// First error returned while rendering
+    error: Option<std::io::Error>,
+}
+
+impl VisitMut for TemplateVisitor<'_> {
+    fn visit_string_mut(&mut self, node: &mut Formatted<String>) {
+        if self.error.is_some() || !node.value().contains("{{") {
+            return;
+        }
+        match render_str(node.value(), self.context) {
+            Ok(rendered) => {
+                let mut value = Formatted::new(rendered);
+                std::mem::swap(value.decor_mut(), node.decor_mut());
+                *node = value;
             }
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+/// Renders the mustache tags of a string w/ a template context
+///
+/// Values are rendered w/o html escaping, so `{{x}}` renders the same as `{{{x}}}`
+///
+/// Returns an error if a variable tag outside of a section could not be resolved from the context
+fn render_str(
+    content: &str,
+    context: &serde_json::Map<String, serde_json::Value>,
+) -> std::io::Result<String> {
+    let mut depth = 0usize;
+    let mut rest = content;
+    while let Some((tag, remaining)) = rest.split_once("{{").and_then(|(_, r)| r.split_once("}}")) {
+        rest = remaining;
+        let tag = tag.trim();
+        match tag.chars().next() {
+            Some('#' | '^') => depth += 1,
+            Some('/') => depth = depth.saturating_sub(1),
+            Some('!' | '>' | '=') => {}
+            _ if depth > 0 => {}
+            _ => {
+                let path = tag.trim_start_matches(['&', '{']).trim();
+                let resolved = path
+                    .split('.')
+                    .try_fold(None::<&serde_json::Value>, |value, key| match value {
+                        None => context.get(key).map(Some),
+                        Some(value) => value.get(key).map(Some),
+                    })
+                    .flatten();
+                if resolved.is_none() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        format!("Unresolved template tag `{path}`"),
+                    ));
+                }
+            }
+        }
+    }
+
+    // **Note**: Escaped variable tags are rewritten to unescaped tags, since html escaping corrupts urls and headers
+    let content = rewrite_tags(content, |tag| {
+        (!tag.starts_with("{{{") && !tag[2..].trim_start().starts_with('&'))
+            .then(|| variable_tag(tag))
+            .flatten()
+            .map(|name| format!("{{{{&{name}}}}}"))
+    });
+    mustache::compile_str(&content)
+        .and_then(|t| t.render_to_string(context))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Renders the variable tags of a plugin config that name a parameter, w/o escaping the value
+///
+/// Other tags are left as-is, so that tags rendered at runtime, i.e. by a `TemplateField`, are preserved
+pub(super) fn render_params(content: &str, params: &BTreeMap<String, String>) -> String {
+    rewrite_tags(content, |tag| {
+        variable_tag(tag).and_then(|name| params.get(name)).cloned()
+    })
+}
+
+/// Replaces each mustache tag in `content`, i.e. `{{x}}` or `{{{x}}}`, w/ the value returned by `rewrite`
+///
+/// Tags that `rewrite` returns None for are left as-is
+fn rewrite_tags(content: &str, mut rewrite: impl FnMut(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        let close = if rest[start + 2..].starts_with('{') {
+            "}}}"
+        } else {
+            "}}"
+        };
+        let Some(end) = rest[start..]
+            .find(close)
+            .map(|end| start + end + close.len())
+        else {
+            break;
+        };
+        let tag = &rest[start..end];
+        output.push_str(&rest[..start]);
+        output.push_str(rewrite(tag).as_deref().unwrap_or(tag));
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Returns the name of a variable tag, i.e. `x` for `{{ x }}`, `{{{x}}}`, or `{{&x}}`
+///
+/// Returns None if the tag is a section, comment, partial, or delimiter tag
+fn variable_tag(tag: &str) -> Option<&str> {
+    let inner = match tag.strip_prefix("{{{") {
+        Some(inner) => inner.strip_suffix("}}}")?,
+        None => tag.strip_prefix("{{")?.strip_suffix("}}")?,
+    };
+    let inner = inner.trim();
+    match inner.chars().next()? {
+        '&' => Some(inner[1..].trim()),
+        '#' | '^' | '/' | '!' | '>' | '=' => None,
+        _ => Some(inner),
+    }
+}
+
+/// Resolves an include pattern relative to the env root
+///
+/// If the pattern is not a glob pattern, the path is returned as-is so that a missing file can be reported
+fn resolve_include(env_root: &Path, pattern: &str) -> std::io::Result<Vec<PathBuf>> {
+    let path = env_root.join(pattern);
+    if !pattern.contains(['*', '?', '[']) {
+        return Ok(vec![path]);
+    }
+
+    glob::glob(&path.to_string_lossy())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?
+        .map(|p| p.map_err(std::io::Error::from))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a config file for an include test
+    fn write_config(env_root: &Path, path: &str, content: &str) {
+        let path = env_root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_engine_config_includes() {
+        let root = PathBuf::from(".test");
+        let env_root = root.join("test_includes");
+        let _ = std::fs::remove_dir_all(&env_root);
+        write_config(
+            &env_root,
+            "config.toml",
+            r#"
+includes = ["common.toml", "prod/*.toml"]
+
+[plugins.main]
+plugin = "kioto/plugins.request"
+"#,
+        );
+        write_config(
+            &env_root,
+            "common.toml",
+            r#"
+[plugins.a]
+plugin = "kioto/plugins.request"
+
+[plugins.main]
+plugin = "kioto/plugins.process"
+"#,
+        );
+        write_config(
+            &env_root,
+            "prod/a.toml",
+            r#"
+[plugins.a]
+plugin = "kioto/plugins.process"
+
+[handlers.h]
+plugin = "kioto/plugins.client"
+"#,
+        );
+        write_config(
+            &env_root,
+            "prod/b.toml",
+            r#"
+[plugins.b]
+plugin = "kioto/plugins.request"
+"#,
+        );
+
+        let config = Config::from_file_system(&root, "test_includes").unwrap();
+        assert_eq!("kioto/plugins.request", config.plugins["main"].plugin);
+        assert_eq!("kioto/plugins.process", config.plugins["a"].plugin);
+        assert_eq!("kioto/plugins.request", config.plugins["b"].plugin);
+        assert!(config.handlers.contains_key("h"));
+    }
+
+    #[test]
+    fn test_engine_config_template() {
+        let root = PathBuf::from(".test");
+        let env_root = root.join("test_config_template");
+        let _ = std::fs::remove_dir_all(&env_root);
+        std::env::set_var("KT_TEST_CONFIG_TEMPLATE_TEAM", "runplat");
+        write_config(
+            &env_root,
+            "config.toml",
+            r#"
+includes = ["{{stage}}.toml"]
+
+[default_labels]
+team = "{{env.KT_TEST_CONFIG_TEMPLATE_TEAM}}"
+"#,
+        );
+        write_config(
+            &env_root,
+            "prod.toml",
+            r#"
+[plugins.main]
+plugin = "kioto/plugins.request"
+labels = { region = "{{{region}}}" }
+"#,
+        );
+
+        let context = BTreeMap::from_iter([
+            ("stage".to_string(), "prod".to_string()),
+            ("region".to_string(), "us&west".to_string()),
+        ]);
+        let config =
+            Config::from_file_system_with_context(&root, "test_config_template", &context).unwrap();
+        assert_eq!("runplat", config.default_labels["team"]);
+        assert_eq!("us&west", config.plugins["main"].labels["region"]);
+
+        // Escaped tags are rendered w/o html escaping
+        write_config(
+            &env_root,
+            "prod.toml",
+            r#"
+[plugins.main]
+plugin = "kioto/plugins.request"
+labels = { region = "{{region}}", url = "<{{ region }}>" }
+"#,
+        );
+        let config =
+            Config::from_file_system_with_context(&root, "test_config_template", &context).unwrap();
+        assert_eq!("us&west", config.plugins["main"].labels["region"]);
+        assert_eq!("<us&west>", config.plugins["main"].labels["url"]);
+
+        // Values are rendered into the parsed string, so they cannot inject toml
+        let context = BTreeMap::from_iter([
+            ("stage".to_string(), "prod".to_string()),
+            (
+                "region".to_string(),
+                "us\"\n[plugins.injected]\nplugin = \"x".to_string(),
+            ),
+        ]);
+        let config =
+            Config::from_file_system_with_context(&root, "test_config_template", &context).unwrap();
+        assert_eq!(
+            "us\"\n[plugins.injected]\nplugin = \"x",
+            config.plugins["main"].labels["region"]
+        );
+        assert!(!config.plugins.contains_key("injected"));
+
+        let err = Config::from_file_system(&root, "test_config_template").unwrap_err();
+        assert_eq!(std::io::ErrorKind::NotFound, err.kind());
+        assert!(err.to_string().contains("Unresolved template tag `stage`"));
+    }
+
+    #[tokio::test]
+    async fn test_engine_config_default_labels() {
+        let root = PathBuf::from(".test");
+        let env_root = root.join("test_default_labels");
+        let _ = std::fs::remove_dir_all(&env_root);
+        write_config(
+            &env_root,
+            "config.toml",
+            r#"
+includes = ["common.toml"]
+
+[default_labels]
+env = "prod"
+
+[plugins.a]
+plugin = "kioto/plugins.file@0.1.0"
+
+[plugins.b]
+plugin = "kioto/plugins.file@0.1.0"
+"#,
+        );
+        write_config(
+            &env_root,
+            "common.toml",
+            r#"
+[default_labels]
+env = "dev"
+team = "runplat"
+"#,
+        );
+        write_config(
+            &env_root,
+            "etc/kioto/0.1.0/plugins/file/a.toml",
+            r#"path = "a.txt""#,
+        );
+        write_config(
+            &env_root,
+            "etc/kioto/0.1.0/plugins/file/b.toml",
+            "-kt-build.labels.env = \"staging\"\n\npath = \"b.txt\"\n",
+        );
+
+        let config = Config::from_file_system(&root, "test_default_labels").unwrap();
+        assert_eq!("prod", config.default_labels["env"]);
+        assert_eq!("runplat", config.default_labels["team"]);
+
+        // Default labels are applied to loaded plugins, labels from build metadata take precedence
+        let env = crate::engine::EnvBuilder::default_env("test_default_labels")
+            .load_env(&root)
+            .unwrap();
+        let (a, _) = env.config.event("a", &env).unwrap();
+        let labels = env.state.labels_of(&a).unwrap();
+        assert_eq!("prod", labels["env"]);
+        assert_eq!("runplat", labels["team"]);
+
+        let (b, _) = env.config.event("b", &env).unwrap();
+        let labels = env.state.labels_of(&b).unwrap();
+        assert_eq!("staging", labels["env"]);
+        assert_eq!("runplat", labels["team"]);
+    }
+
+    #[test]
+    fn test_engine_config_include_cycle() {
+        let root = PathBuf::from(".test");
+        let env_root = root.join("test_include_cycle");
+        let _ = std::fs::remove_dir_all(&env_root);
+        write_config(&env_root, "config.toml", r#"includes = ["a.toml"]"#);
+        write_config(&env_root, "a.toml", r#"includes = ["b.toml"]"#);
+        write_config(&env_root, "b.toml", r#"includes = ["a.toml"]"#);
+
+        let err = Config::from_file_system(&root, "test_include_cycle").unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        assert!(err.to_string().contains("Include cycle detected"));
+
+        write_config(&env_root, "config.toml", r#"includes = ["missing.toml"]"#);
+        let err = Config::from_file_system(&root, "test_include_cycle").unwrap_err();
+        assert_eq!(std::io::ErrorKind::NotFound, err.kind());
+    }
+
+    #[tokio::test]
+    async fn test_engine_config_disabled_plugins() {
+        let root = PathBuf::from(".test");
+        let env_root = root.join("test_disabled_plugins");
+        let _ = std::fs::remove_dir_all(&env_root);
+        // Neither plugin file exists, so loading fails if a disabled plugin is not skipped
+        write_config(
+            &env_root,
+            "config.toml",
+            r#"
+[plugins.a]
+plugin = "kioto/plugins.request"
+enabled = false
+
+[handlers.h]
+plugin = "kioto/plugins.process"
+enabled = "false"
+"#,
+        );
+
+        let config = Config::from_file_system(&root, "test_disabled_plugins").unwrap();
+        assert!(config.plugin_files().is_empty());
+
+        let env = crate::engine::EnvBuilder::default_env("test_disabled_plugins")
+            .load_env(&root)
+            .unwrap();
+        assert_eq!(0, env.config.loaded_plugins().count());
+        assert!(env.config.event("a", &env).is_err());
+        assert!(env.config.handler("h", &env).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_engine_config_for_each() {
+        let root = PathBuf::from(".test");
+        let env_root = root.join("test_for_each");
+        let _ = std::fs::remove_dir_all(&env_root);
+        write_config(
+            &env_root,
+            "config.toml",
+            r#"
+[plugins.read]
+plugin = "kioto/plugins.file@0.1.0"
+for_each = [{ file = "a.txt" }, { file = "b.txt" }]
+"#,
+        );
+        write_config(
+            &env_root,
+            "etc/kioto/0.1.0/plugins/file/read.toml",
+            r#"path = "{{{file}}}""#,
+        );
+
+        let env = crate::engine::EnvBuilder::default_env("test_for_each")
+            .load_env(&root)
+            .unwrap();
+        let loaded = env.config.loaded_plugins().collect::<Vec<_>>();
+        assert_eq!(
+            vec!["read-0", "read-1"],
+            loaded.iter().map(|(e, _)| *e).collect::<Vec<_>>()
+        );
+        assert_ne!(loaded[0].1.commit(), loaded[1].1.commit());
+        assert!(env.config.event("read-1", &env).is_ok());
+        assert!(env.config.event("read", &env).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_engine_config_declaration_order() {
+        let root = PathBuf::from(".test");
+        let env_root = root.join("test_declaration_order");
+        let _ = std::fs::remove_dir_all(&env_root);
+        write_config(
+            &env_root,
+            "config.toml",
+            r#"
+includes = ["common.toml"]
+
+[plugins.c]
+plugin = "kioto/plugins.file@0.1.0"
+
+[plugins.a]
+plugin = "kioto/plugins.file@0.1.0"
+for_each = [{ file = "a.txt" }, { file = "b.txt" }]
+"#,
+        );
+        write_config(
+            &env_root,
+            "common.toml",
+            r#"
+[plugins.z]
+plugin = "kioto/plugins.file@0.1.0"
+"#,
+        );
+        for event in ["a", "c", "z"] {
+            write_config(
+                &env_root,
+                &format!("etc/kioto/0.1.0/plugins/file/{event}.toml"),
+                r#"path = "{{{file}}}""#,
+            );
         }
+
+        let env = crate::engine::EnvBuilder::default_env("test_declaration_order")
+            .load_env(&root)
+            .unwrap();
+        assert_eq!(
+            vec!["z", "c", "a-0", "a-1"],
+            env.create_all_events()
+                .iter()
+                .map(|(e, _)| e.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_engine_config_for_each_templates() {
+        let root = PathBuf::from(".test");
+        let env_root = root.join("test_for_each_templates");
+        let _ = std::fs::remove_dir_all(&env_root);
+        write_config(
+            &env_root,
+            "config.toml",
+            r#"
+[plugins.fetch]
+plugin = "kioto/plugins.request@0.1.0"
+for_each = [{ region = "us&west" }]
+"#,
+        );
+        write_config(
+            &env_root,
+            "etc/kioto/0.1.0/plugins/request/fetch.toml",
+            r#"
+-kt-build.plugin = "kioto/plugins.request@0.1.0"
+url = "https://{{host}}/{{path}}?region={{region}}"
+
+[-kt-build.templates.url]
+host = "example.com"
+path = ""
+"#,
+        );
+
+        let env = crate::engine::EnvBuilder::default_env("test_for_each_templates")
+            .load_env(&root)
+            .unwrap();
+        let (address, _) = env.config.event("fetch-0", &env).unwrap();
+        let item = env.state.find_plugin(&address).unwrap();
+        let request = item.borrow::<crate::plugins::Request>().unwrap();
+        assert_eq!(
+            "https://{{host}}/{{path}}?region=us&west",
+            toml::Value::try_from(request).unwrap()["url"]
+                .as_str()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render_params() {
+        let params = BTreeMap::from_iter([("region".to_string(), "us&west".to_string())]);
+        assert_eq!(
+            r#"url = "https://{{host}}/{{ path }}?region=us&west&r=us&west&r=us&west"
+{{#items}}{{.}}{{/items}}"#,
+            render_params(
+                r#"url = "https://{{host}}/{{ path }}?region={{region}}&r={{{ region }}}&r={{& region}}"
+{{#items}}{{.}}{{/items}}"#,
+                &params
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_engine_config_export_environment() {
+        let root = PathBuf::from(".test");
+        let env_root = root.join("test_export_source");
+        let _ = std::fs::remove_dir_all(&env_root);
+        let _ = std::fs::remove_dir_all(root.join("test_export_target"));
+        write_config(
+            &env_root,
+            "config.toml",
+            r#"
+includes = ["handlers.toml"]
+
+[default_labels]
+team = "runplat"
+
+[plugins.a]
+plugin = "kioto/plugins.file@0.1.0"
+
+[plugins.b]
+plugin = "kioto/plugins.file@0.1.0"
+load = { type = "file", path = ".test/test_export_source/b.toml" }
+"#,
+        );
+        write_config(
+            &env_root,
+            "handlers.toml",
+            r#"
+[handlers.h]
+plugin = "kioto/plugins.file@0.1.0"
+"#,
+        );
+        write_config(
+            &env_root,
+            "etc/kioto/0.1.0/plugins/file/a.toml",
+            "-kt-build.labels.env = \"dev\"\n\npath = \"a.txt\"\n",
+        );
+        write_config(&env_root, "b.toml", r#"path = "b.txt""#);
+        write_config(
+            &env_root,
+            "etc/kioto/0.1.0/plugins/file/h.toml",
+            r#"path = "h.txt""#,
+        );
+
+        let env = crate::engine::EnvBuilder::default_env("test_export_source")
+            .load_env(&root)
+            .unwrap();
+        env.export_environment("test_export_target", &root)
+            .expect("should export the env");
+
+        let exported = Config::from_file_system(&root, "test_export_target").unwrap();
+        assert!(exported.includes.is_empty());
+        assert!(exported.plugins["b"].load.is_none());
+        assert_eq!(3, exported.plugin_files().len());
+
+        let settings = std::fs::read_to_string(
+            root.join("test_export_target/etc/kioto/0.1.0/plugins/file/a.toml"),
+        )
+        .unwrap();
+        assert!(!settings.contains(crate::KT_LOADER_METADATA_TABLE));
+
+        let target = crate::engine::EnvBuilder::default_env("test_export_target")
+            .load_env(&root)
+            .unwrap();
+        assert_eq!(2, target.config.loaded_plugins().count());
+        assert_eq!(1, target.config.loaded_handlers().count());
+        let (a, _) = target.config.event("a", &target).unwrap();
+        let labels = target.state.labels_of(&a).unwrap();
+        assert_eq!("dev", labels["env"]);
+        assert_eq!("runplat", labels["team"]);
+    }
+
+    #[test]
+    fn test_engine_config_schema_version() {
+        let config = Config::parse("[plugins.a]\nplugin = \"kioto/plugins.request\"").unwrap();
+        assert_eq!(Config::SCHEMA_VERSION, config.schema_version());
+        assert_eq!(Config::SCHEMA_VERSION, Config::default().schema_version());
+        assert!(toml::to_string(&config)
+            .unwrap()
+            .contains(&format!("schema_version = {}", Config::SCHEMA_VERSION)));
+
+        let err =
+            Config::parse(&format!("schema_version = {}", Config::SCHEMA_VERSION + 1)).unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        assert!(Config::parse("schema_version = 0").is_err());
+
+        // Renames `labels` to `default_labels` when upgrading from version 1 to version 2
+        let migrations: &[Migration] = &[|table| {
+            if let Some(labels) = table.remove("labels") {
+                table.insert("default_labels".to_string(), labels);
+            }
+            Ok(())
+        }];
+        let mut table =
+            toml::from_str::<toml::Table>("schema_version = 1\n[labels]\nenv = \"prod\"").unwrap();
+        migrate(&mut table, migrations).unwrap();
+        let config: Config = table.try_into().unwrap();
+        assert_eq!(2, config.schema_version());
+        assert_eq!("prod", config.default_labels["env"]);
+
+        let mut table = toml::from_str::<toml::Table>("[default_labels]\nenv = \"prod\"").unwrap();
+        migrate(&mut table, migrations).unwrap();
+        assert_eq!(Some(&toml::Value::Integer(2)), table.get("schema_version"));
     }
 }
@@ -1,3 +1,4 @@
+use reality::plugin::MessageData;
 use serde::{Deserialize, Serialize};
 
 /// Configuration for an event to be loaded by an engine
@@ -9,6 +10,12 @@ pub struct Config {
     /// Handler name from the map of handlers in the engine config which will handle configuring from
     /// this event config
     pub handler: Option<String>,
+    /// Input data sent to the event's plugin before the event is returned
+    ///
+    /// A table is sent as `MessageData::Toml`, a string is sent as `MessageData::Bytes`, and an array is sent as
+    /// `MessageData::Json`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<toml::Value>,
 }
 
 impl Config {
@@ -20,4 +27,32 @@ impl Config {
             self.handler.as_ref().map(|h| h.to_lowercase()),
         )
     }
+
+    /// Returns the input data as message data
+    ///
+    /// Returns an error if the input is not a table, string, or array
+    #[inline]
+    pub fn input_data(&self) -> std::io::Result<Option<MessageData>> {
+        input_data(&self.event, self.input.as_ref())
+    }
+}
+
+/// Converts the input of an event into message data, `name` is used in the error returned for unsupported input
+#[inline]
+pub(crate) fn input_data(
+    name: &str,
+    input: Option<&toml::Value>,
+) -> std::io::Result<Option<MessageData>> {
+    match input {
+        Some(toml::Value::Table(table)) => Ok(Some(MessageData::Toml(table.clone()))),
+        Some(toml::Value::String(value)) => Ok(Some(MessageData::Bytes(value.clone().into()))),
+        Some(toml::Value::Array(array)) => serde_json::to_value(array)
+            .map(|v| Some(MessageData::from(v)))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+        Some(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Unsupported input for event `{name}`"),
+        )),
+        None => Ok(None),
+    }
 }
@@ -79,6 +79,18 @@ pub struct Build {
     pub templates: Option<BTreeMap<String, toml::Table>>,
     /// True if the plugin should be added as a handler
     pub handler: Option<BuildHandler>,
+    /// Framework the config was produced under, i.e. `reality@0.1.0`
+    ///
+    /// If set, the framework is checked when the plugin is loaded, see `State::check_framework`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub framework: Option<String>,
+    /// Whether the plugin should be loaded, defaults to true
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "super::plugin::deserialize_enabled"
+    )]
+    pub enabled: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -88,7 +100,7 @@ pub struct BuildHandler {
 
 /// Loader metadata that can be used to build a collection of .toml files
 /// Can be deserialized by plugins with the field name "-kt-load"
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Loader {
     /// Name of the environment that loaded this plugin
     pub env: String,
@@ -102,6 +114,9 @@ pub struct Loader {
     /// Event or Event Handler identifier that was loader
     pub event: String,
     /// CRC digest of the source using the CRC_64_MS algo
+    ///
+    /// Computed over the bytes of the source file before secrets are interpolated and loader metadata is inserted, so
+    /// the digest only changes when the source file changes
     #[serde(rename = "crc-ms")]
     pub crc_ms: String,
 }
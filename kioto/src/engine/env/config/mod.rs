@@ -1,8 +1,12 @@
+mod diff;
 mod engine;
 mod event;
 mod metadata;
 mod plugin;
 mod template;
+pub use diff::config_diff;
+pub use diff::FieldChange;
+pub use template::check_template_fields;
 pub use template::TemplateData;
 pub use template::TemplateField;
 pub use template::TemplateMap;
@@ -12,3 +16,5 @@ pub use metadata::Build as BuildMetadata;
 pub use metadata::Loader as LoaderMetadata;
 pub use metadata::Metadata;
 pub use plugin::Config as PluginConfig;
+pub(crate) use event::input_data;
+pub(crate) use plugin::insert_build_labels;
@@ -1,10 +1,14 @@
-use crate::{engine::env::Env, Errors, PluginLoadErrors, Result};
+use super::engine::render_params;
+use crate::{
+    engine::env::{interpolate_document_secrets, mask_document_secrets, Env},
+    Errors, PluginLoadErrors, Result,
+};
 use reality::{
     content::crc,
     plugin::{Address, Name},
     repr::Labels,
 };
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use std::{collections::BTreeMap, io::Read, path::PathBuf, str::FromStr};
 use toml_edit::value;
 use tracing::debug;
@@ -19,43 +23,173 @@ pub struct Config {
     /// Labels to add as an attribute after loading the plugin
     #[serde(default)]
     pub labels: BTreeMap<String, String>,
+    /// Whether the plugin should be loaded, defaults to true
+    ///
+    /// Can be set to a string, i.e. `"false"`, so that the value can be interpolated
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_enabled"
+    )]
+    pub enabled: Option<bool>,
+    /// Parameter sets to load an instance of the plugin for, see `Config::instances`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub for_each: Vec<BTreeMap<String, String>>,
 }
 
 impl Config {
-    /// Loads the plugin from this config into state
+    /// Returns true if the plugin should be loaded
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    /// Returns the event name and parameters of each instance of the plugin to load
+    ///
+    /// If `for_each` is empty, a single instance is returned w/ the event name and w/o parameters. Otherwise an
+    /// instance is returned for each parameter set w/ the event name `<event>-<index>`, i.e. `health-0`.
+    ///
+    /// # Example
+    /// ```toml
+    /// [plugins.health]
+    /// plugin = "kioto/plugins.request"
+    /// for_each = [{ host = "a.example.com" }, { host = "b.example.com" }]
+    /// ```
+    #[inline]
+    pub fn instances(&self, event: &str) -> Vec<(String, Option<&BTreeMap<String, String>>)> {
+        if self.for_each.is_empty() {
+            vec![(event.to_string(), None)]
+        } else {
+            self.for_each
+                .iter()
+                .enumerate()
+                .map(|(index, params)| (format!("{event}-{index}"), Some(params)))
+                .collect()
+        }
+    }
+
+    /// Loads an instance of the plugin from this config into state, applying default labels before any labels from
+    /// the plugin's build metadata, see `Config::instances`
+    ///
+    /// The settings of each instance are read from the source of `event`, and variable tags in the settings that name a
+    /// parameter of the instance, i.e. `{{host}}`, are rendered w/ the parameter. Other tags are left for templates
+    /// rendered at runtime, see `TemplateField`.
     ///
     /// Returns an error if the plugin could not be loaded successfully
     #[inline]
-    pub fn load(&self, event: &str, loader: &mut Env) -> Result<Address> {
+    pub fn load(
+        &self,
+        event: &str,
+        instance: (&str, Option<&BTreeMap<String, String>>),
+        loader: &mut Env,
+        default_labels: &BTreeMap<String, String>,
+    ) -> Result<Address> {
+        let (name, path) = self.source(event, loader)?;
+        load_toml(instance, name, &path, loader, default_labels)
+    }
+
+    /// Returns the plugin name and the path of the file the plugin for `event` is loaded from
+    ///
+    /// If a load source is not set, the path is `<root>/<env>/etc/<plugin-path>/<event>.toml`
+    ///
+    /// Returns an error if the plugin name could not be parsed
+    #[inline]
+    pub(crate) fn source(&self, event: &str, loader: &Env) -> reality::Result<(Name, PathBuf)> {
         let name = Name::from_str(&self.plugin)?;
-        if let Some(load) = self.load.as_ref() {
-            match load {
-                LoadSource::File {
-                    path,
-                    format: SourceFormats::Toml,
-                } => load_toml(event, name, path, loader),
-            }
-        } else {
-            let path = loader
+        let path = match self.load.as_ref() {
+            Some(LoadSource::File {
+                path,
+                format: SourceFormats::Toml,
+            }) => path.clone(),
+            None => loader
                 .root_dir
                 .join(loader.label.clone())
                 .join("etc")
                 .join(name.path())
-                .join(format!("{event}.toml"));
-            load_toml(event, name, &path, loader)
-        }
+                .join(format!("{event}.toml")),
+        };
+        Ok((name, path))
+    }
+}
+
+/// Deserializes an `enabled` flag from a bool, or from a string that parses as a bool
+pub(crate) fn deserialize_enabled<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Enabled {
+        Bool(bool),
+        String(String),
+    }
+
+    match Option::<Enabled>::deserialize(deserializer)? {
+        Some(Enabled::Bool(enabled)) => Ok(Some(enabled)),
+        Some(Enabled::String(enabled)) => enabled.trim().parse().map(Some).map_err(|_| {
+            D::Error::custom(format!(
+                "Invalid value for `enabled`, expected a bool: `{enabled}`"
+            ))
+        }),
+        None => Ok(None),
     }
 }
 
 /// Loads toml from an env loader
-fn load_toml(event: &str, name: Name, path: &PathBuf, loader: &mut Env) -> Result<Address> {
+///
+/// `instance` is the event name of the plugin being loaded and the parameters to render the toml w/, if any
+fn load_toml(
+    instance: (&str, Option<&BTreeMap<String, String>>),
+    name: Name,
+    path: &PathBuf,
+    loader: &mut Env,
+    default_labels: &BTreeMap<String, String>,
+) -> Result<Address> {
+    let (event, params) = instance;
     debug!("Trying to load {path:?}");
     match std::fs::OpenOptions::new().read(true).open(path) {
         Ok(mut opened) => {
             let mut toml = String::new();
             match opened.read_to_string(&mut toml) {
                 Ok(size) => {
-                    let mut settings = toml_edit::DocumentMut::from_str(&toml).unwrap();
+                    if let Some(params) = params {
+                        toml = render_params(&toml, params);
+                    }
+                    // **Note**: The crc is computed over the source before secrets are interpolated
+                    let crc_ms = {
+                        let mut crc = crc().digest();
+                        crc.update(toml.as_bytes());
+                        hex::encode(crc.finalize().to_be_bytes())
+                    };
+                    let mut settings = match toml_edit::DocumentMut::from_str(&toml) {
+                        Ok(settings) => settings,
+                        Err(e) => {
+                            return Err(Errors::PluginLoadError(
+                                PluginLoadErrors::CouldNotReadFile {
+                                    error: crate::CouldNotLoadPlugin::new(event, name),
+                                    io: std::io::Error::new(
+                                        std::io::ErrorKind::InvalidData,
+                                        e.to_string(),
+                                    ),
+                                },
+                            ))
+                        }
+                    };
+                    // **Note**: The resolved secrets must never be logged
+                    let mut masked = settings.clone();
+                    mask_document_secrets(&mut masked);
+                    if let Err(io) =
+                        interpolate_document_secrets(&mut settings, loader.secrets.as_ref())
+                    {
+                        return Err(Errors::PluginLoadError(
+                            PluginLoadErrors::CouldNotReadFile {
+                                error: crate::CouldNotLoadPlugin::new(event, name),
+                                io,
+                            },
+                        ));
+                    }
 
                     // Insert a metadata table w/ information on the source being loaded
                     let mut metadata = toml_edit::table();
@@ -63,17 +197,23 @@ fn load_toml(event: &str, name: Name, path: &PathBuf, loader: &mut Env) -> Resul
                     metadata["src"] = value(path.to_string_lossy().to_string());
                     metadata["src-size"] = value(size as i64);
                     metadata["event"] = value(event);
-                    let mut crc = crc().digest();
-                    crc.update(settings.to_string().as_bytes());
-                    metadata["crc-ms"] = value(hex::encode(crc.finalize().to_be_bytes()));
+                    metadata["crc-ms"] = value(crc_ms);
                     metadata["env"] = value(&loader.label);
+                    insert_loader_metadata(&mut settings, metadata);
 
-                    // **Note**: Store in a field that isn't a native rust field, however
-                    // callers can opt in to deserialize if they wish
-                    settings[crate::KT_LOADER_METADATA_TABLE] = metadata;
+                    // Check the framework the config was produced under, if one was recorded
+                    if let Some(framework) = settings
+                        .get(crate::KT_BUILD_METADATA_TABLE)
+                        .and_then(|t| t.get("framework"))
+                        .and_then(|f| f.as_str())
+                    {
+                        let (framework, version) =
+                            framework.split_once('@').unwrap_or((framework, ""));
+                        loader.state.check_framework((framework, version))?;
+                    }
 
-                    // Apply labels
-                    let mut labels = Labels::default();
+                    // Apply labels, labels from build metadata override default labels
+                    let mut labels = Labels::from(default_labels.clone());
                     if let Some(_labels) = settings
                         .get(crate::KT_BUILD_METADATA_TABLE)
                         .and_then(|t| t.get("labels"))
@@ -87,7 +227,13 @@ fn load_toml(event: &str, name: Name, path: &PathBuf, loader: &mut Env) -> Resul
                         }
                     }
 
-                    Ok(loader.load(&name, settings, labels).unwrap())
+                    let address = loader.load(&name, settings, labels).unwrap();
+                    if let Ok(masked) = masked.to_string().parse::<toml::Table>() {
+                        loader
+                            .loaded_settings
+                            .insert(address.commit(), toml::Value::Table(masked));
+                    }
+                    Ok(address)
                 }
                 Err(io) => Err(Errors::PluginLoadError(
                     PluginLoadErrors::CouldNotReadFile {
@@ -106,6 +252,40 @@ fn load_toml(event: &str, name: Name, path: &PathBuf, loader: &mut Env) -> Resul
     }
 }
 
+/// Inserts a loader metadata table into the settings of a plugin being loaded
+///
+/// The table is appended after the existing tables, so the comments and formatting of the source are preserved. Any
+/// existing loader metadata table is replaced.
+fn insert_loader_metadata(settings: &mut toml_edit::DocumentMut, metadata: toml_edit::Item) {
+    // **Note**: Store in a field that isn't a native rust field, however
+    // callers can opt in to deserialize if they wish
+    settings.remove(crate::KT_LOADER_METADATA_TABLE);
+    settings[crate::KT_LOADER_METADATA_TABLE] = metadata;
+}
+
+/// Inserts the labels of a plugin into the build metadata table of the plugin's settings
+///
+/// Labels already set in the build metadata table are replaced, so that loading the settings again applies the same
+/// labels the plugin was loaded w/.
+pub(crate) fn insert_build_labels(settings: &mut toml_edit::DocumentMut, labels: &Labels) {
+    let mut table = toml_edit::Table::new();
+    for (k, v) in labels.iter() {
+        table.insert(k, value(v));
+    }
+    if !settings.contains_table(crate::KT_BUILD_METADATA_TABLE) {
+        let mut build = toml_edit::Table::new();
+        build.set_implicit(true);
+        settings.insert(
+            crate::KT_BUILD_METADATA_TABLE,
+            toml_edit::Item::Table(build),
+        );
+    }
+    if let Some(build) = settings[crate::KT_BUILD_METADATA_TABLE].as_table_like_mut() {
+        build.remove("labels");
+        build.insert("labels", toml_edit::Item::Table(table));
+    }
+}
+
 /// Enumeration of load plugin source variants
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
 #[serde(tag = "type")]
@@ -138,9 +318,12 @@ pub enum SourceFormats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "http")]
     use crate::plugins::Request;
+    #[cfg(feature = "http")]
     use reality::Plugin;
 
+    #[cfg(feature = "http")]
     #[test]
     fn test_deser_config_enum_types() {
         let s = toml::from_str::<Config>(
@@ -157,12 +340,15 @@ load = { type = "file", path = "etc/test", format = "toml" }
                     path: PathBuf::from("etc/test"),
                     format: SourceFormats::Toml
                 }),
-                labels: BTreeMap::new()
+                labels: BTreeMap::new(),
+                enabled: None,
+                for_each: vec![],
             },
             s
         );
     }
 
+    #[cfg(feature = "http")]
     #[test]
     fn test_deser_config_defaults() {
         let s = toml::from_str::<Config>(
@@ -179,9 +365,62 @@ load = { type = "file" }
                     path: PathBuf::default(),
                     format: SourceFormats::Toml
                 }),
-                labels: BTreeMap::new()
+                labels: BTreeMap::new(),
+                enabled: None,
+                for_each: vec![],
             },
             s
         );
+        assert!(s.is_enabled());
+    }
+
+    #[test]
+    fn test_deser_config_enabled() {
+        let s = toml::from_str::<Config>("plugin = \"kioto/plugins.request\"\nenabled = false")
+            .unwrap();
+        assert!(!s.is_enabled());
+        let s = toml::from_str::<Config>("plugin = \"kioto/plugins.request\"\nenabled = \"true\"")
+            .unwrap();
+        assert!(s.is_enabled());
+        assert!(toml::from_str::<Config>(
+            "plugin = \"kioto/plugins.request\"\nenabled = \"maybe\""
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_insert_loader_metadata_preserves_source() {
+        let source = r#"# Request sent on login
+[-kt-build]
+labels = { team = "auth" }    # owners
+
+[request]
+url   = "https://example.com/login" # aligned
+"#;
+        let mut settings = toml_edit::DocumentMut::from_str(source).unwrap();
+        let mut metadata = toml_edit::table();
+        metadata["event"] = value("login");
+        insert_loader_metadata(&mut settings, metadata);
+
+        let output = settings.to_string();
+        assert!(output.starts_with(source), "{output}");
+        assert_eq!(
+            "login",
+            settings[crate::KT_LOADER_METADATA_TABLE]["event"]
+                .as_str()
+                .unwrap()
+        );
+
+        // Inserting again replaces the existing table
+        let mut metadata = toml_edit::table();
+        metadata["event"] = value("logout");
+        insert_loader_metadata(&mut settings, metadata);
+        assert_eq!(1, settings.to_string().matches("[-kt-loader]").count());
+        assert_eq!(
+            "logout",
+            settings[crate::KT_LOADER_METADATA_TABLE]["event"]
+                .as_str()
+                .unwrap()
+        );
     }
 }
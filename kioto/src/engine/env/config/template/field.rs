@@ -1,5 +1,12 @@
-use std::str::FromStr;
-use serde::{de::Visitor, Deserialize, Serialize};
+use std::{cell::RefCell, ops::Range, str::FromStr};
+use serde::{de::DeserializeOwned, de::Visitor, Deserialize, Serialize};
+use tracing::debug;
+
+thread_local! {
+    /// Errors of template fields that failed to deserialize while `check_template_fields` is running, None if errors are
+    /// not being collected
+    static COLLECTED_ERRORS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
 
 /// Container type for a field that is either a template string or the actual value,
 /// When deserializing, if the value is a string that contains mustache tags and can be compiled into mustache template,
@@ -8,6 +15,9 @@ use serde::{de::Visitor, Deserialize, Serialize};
 /// Otherwise, if the inner type does not contain mustache tags, then it will try to deserialize as the inner type T via `FromStr`.
 /// 
 /// When serializing, if the inner value is set, than the inner value will be serialized, otherwise the template will be serialized instead.
+///
+/// Non-string values, i.e. `port = 8080`, are converted to a string and parsed via `FromStr` so that typed fields can round-trip.
+#[derive(Clone)]
 pub struct TemplateField<T> {
     inner: Option<T>,
     template: Option<String>
@@ -36,6 +46,172 @@ impl<T> TemplateField<T> {
     }
 }
 
+impl<T: FromStr> TemplateField<T>
+where
+    T::Err: std::fmt::Display,
+{
+    /// Renders the template w/ data and parses the result into the inner type via `FromStr`,
+    ///
+    /// If the inner value is already set, the inner value is returned and the template is not rendered.
+    ///
+    /// Returns an error that includes the name of the `field` if the template could not be rendered, or if the rendered
+    /// value could not be parsed
+    pub fn render(&mut self, field: &str, data: &impl Serialize) -> std::io::Result<&T> {
+        if self.inner.is_none() {
+            let template = self.template.as_deref().ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Template field `{field}` does not have a template or value set"),
+                )
+            })?;
+
+            let rendered = mustache::compile_str(template)
+                .and_then(|t| t.render_to_string(data))
+                .map_err(|e| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Could not render template for field `{field}`: {e}"),
+                    )
+                })?;
+
+            let value = parse_rendered(field, &rendered)?;
+            debug!("Rendered template field `{field}` {template} -> {rendered}");
+            self.inner = Some(value);
+        }
+
+        self.try_as_inner().map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Template field `{field}` does not have a value set"),
+            )
+        })
+    }
+}
+
+/// Parses a rendered template into `T` via `FromStr`, returns an error that includes the name of the `field`
+pub(crate) fn parse_rendered<T: FromStr>(field: &str, rendered: &str) -> std::io::Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    T::from_str(rendered).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Could not parse rendered template for field `{field}` as {}: {e}",
+                std::any::type_name::<T>()
+            ),
+        )
+    })
+}
+
+/// Deserializes `T` from toml and returns an error listing every template field that could not be compiled or parsed
+///
+/// When deserializing normally, deserialization stops at the first template field that fails. While checking, the
+/// value of each template field that fails is replaced w/ a placeholder template and the toml is deserialized again,
+/// so that all of the failures can be reported together. Each failure is reported w/ the dotted path of the field.
+///
+/// **Note**: Errors not caused by template fields are ignored, since they are returned when the toml is deserialized
+pub fn check_template_fields<T: DeserializeOwned>(toml: &str) -> std::io::Result<()> {
+    let _collecting = CollectGuard::start();
+    let mut toml = toml.to_string();
+    let mut errors = vec![];
+    while let Err(e) = toml::from_str::<T>(&toml) {
+        let Some(error) = COLLECTED_ERRORS.with_borrow_mut(|c| c.as_mut().and_then(Vec::pop))
+        else {
+            break;
+        };
+        let Some(span) = e.span() else {
+            errors.push((String::from("<unknown>"), error));
+            break;
+        };
+        let field = find_field(&toml, &span).unwrap_or_else(|| String::from("<unknown>"));
+        errors.push((field, error));
+        toml.replace_range(span, "\"{{_}}\"");
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    let mut message = format!("{} template field(s) could not be deserialized", errors.len());
+    for (field, error) in errors.iter() {
+        message.push_str(&format!("\n  `{field}`: {error}"));
+    }
+    Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, message))
+}
+
+/// Collects the errors of template fields until dropped, then restores the previous collector
+///
+/// **Note**: Restoring on drop ensures errors are no longer collected if deserializing panics
+struct CollectGuard {
+    previous: Option<Vec<String>>,
+}
+
+impl CollectGuard {
+    /// Starts collecting the errors of template fields on this thread
+    fn start() -> Self {
+        Self {
+            previous: COLLECTED_ERRORS.with_borrow_mut(|c| c.replace(vec![])),
+        }
+    }
+}
+
+impl Drop for CollectGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        COLLECTED_ERRORS.with_borrow_mut(|c| *c = previous);
+    }
+}
+
+/// Returns the dotted path of the value in a toml document at `span`
+fn find_field(toml: &str, span: &Range<usize>) -> Option<String> {
+    fn find_item(path: String, item: &toml_edit::Item, span: &Range<usize>) -> Option<String> {
+        match item {
+            toml_edit::Item::Value(v) => find_value(path, v, span),
+            toml_edit::Item::Table(t) => t
+                .iter()
+                .find_map(|(k, v)| find_item(format!("{path}.{k}"), v, span)),
+            toml_edit::Item::ArrayOfTables(a) => a.iter().enumerate().find_map(|(i, t)| {
+                t.iter()
+                    .find_map(|(k, v)| find_item(format!("{path}[{i}].{k}"), v, span))
+            }),
+            toml_edit::Item::None => None,
+        }
+    }
+
+    fn find_value(path: String, v: &toml_edit::Value, span: &Range<usize>) -> Option<String> {
+        match v {
+            toml_edit::Value::InlineTable(t) => t
+                .iter()
+                .find_map(|(k, v)| find_value(format!("{path}.{k}"), v, span)),
+            toml_edit::Value::Array(a) => a
+                .iter()
+                .enumerate()
+                .find_map(|(i, v)| find_value(format!("{path}[{i}]"), v, span)),
+            v if v.span().as_ref() == Some(span) => Some(path),
+            _ => None,
+        }
+    }
+
+    let doc = toml_edit::ImDocument::parse(toml).ok()?;
+    let field = doc
+        .iter()
+        .find_map(|(k, v)| find_item(k.to_string(), v, span));
+    field
+}
+
+/// Records a template field error if errors are being collected, and returns the error
+///
+/// **Note**: The error is returned even while collecting, so that the span of the failing value is known
+fn collect_error<E: serde::de::Error>(error: String) -> E {
+    COLLECTED_ERRORS.with_borrow_mut(|c| {
+        if let Some(errors) = c {
+            errors.push(error.clone());
+        }
+    });
+    E::custom(error)
+}
+
 impl<T> From<T> for TemplateField<T> {
     fn from(value: T) -> Self {
         TemplateField { inner: Some(value), template: None }
@@ -69,15 +245,54 @@ where
             E: serde::de::Error,
     {
         if v.contains("{{") && v.contains("}}") {
-            let _ = mustache::compile_str(&v).map_err(|e| serde::de::Error::custom(e.to_string()))?;
-            self.template = Some(v);
+            match mustache::compile_str(&v) {
+                Ok(_) => self.template = Some(v),
+                Err(e) => return Err(collect_error(e.to_string())),
+            }
             Ok(self)
         } else {
-            let value: T = T::from_str(&v).map_err(|e| serde::de::Error::custom(e.to_string()))?;
-            self.inner = Some(value);
+            match T::from_str(&v) {
+                Ok(value) => self.inner = Some(value),
+                Err(e) => return Err(collect_error(e.to_string())),
+            }
             Ok(self)
         }
     }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+    {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+    {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+    {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+    {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+    {
+        self.visit_string(v.to_string())
+    }
 }
 
 impl<'de, T: FromStr> Deserialize<'de> for TemplateField<T> 
@@ -111,4 +326,110 @@ impl<T> Default for TemplateField<T> {
     fn default() -> Self {
         Self { inner: None, template: None }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize)]
+    struct TestSubject {
+        port: TemplateField<u16>,
+        enabled: TemplateField<bool>,
+    }
+
+    #[test]
+    fn test_template_field_render_typed() {
+        let mut subject: TestSubject = toml::from_str(
+            r#"
+port = "{{port}}"
+enabled = true
+"#,
+        )
+        .unwrap();
+        assert!(subject.port.is_template());
+        assert_eq!(Some(&true), subject.enabled.as_inner());
+
+        let port = subject.port.render("port", &json!({ "port": 8080 })).unwrap();
+        assert_eq!(8080u16, *port);
+
+        // Typed values should round-trip
+        let ser = toml::to_string(&subject).unwrap();
+        let subject: TestSubject = toml::from_str(&ser).unwrap();
+        assert_eq!(Some(&8080), subject.port.as_inner());
+    }
+
+    #[test]
+    fn test_template_field_render_parse_error() {
+        let mut subject: TestSubject = toml::from_str(
+            r#"
+port = "{{port}}"
+enabled = "{{enabled}}"
+"#,
+        )
+        .unwrap();
+
+        let err = subject
+            .port
+            .render("port", &json!({ "port": "not-a-port" }))
+            .expect_err("should not parse as u16");
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        assert!(err.to_string().contains("`port`"));
+
+        let err = subject
+            .enabled
+            .render("enabled", &json!({ "enabled": "maybe" }))
+            .expect_err("should not parse as bool");
+        assert!(err.to_string().contains("`enabled`"));
+    }
+
+    #[test]
+    fn test_check_template_fields() {
+        let toml = r#"
+port = "not-a-port"
+enabled = "{{#enabled}}"
+"#;
+        // Deserializing normally only reports the first error
+        assert!(toml::from_str::<TestSubject>(toml).is_err());
+
+        let err = check_template_fields::<TestSubject>(toml).expect_err("should have 2 errors");
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+        let message = err.to_string();
+        assert!(message.starts_with("2 template field(s)"));
+        assert!(message.contains("`port`"));
+        assert!(message.contains("`enabled`"));
+
+        check_template_fields::<TestSubject>("port = \"{{port}}\"\nenabled = true").unwrap();
+
+        // Errors are not collected once the check completes
+        assert!(toml::from_str::<TestSubject>(toml).is_err());
+        assert!(COLLECTED_ERRORS.with_borrow(|c| c.is_none()));
+    }
+
+    #[test]
+    fn test_check_template_fields_reports_path() {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Nested {
+            name: String,
+            ports: Vec<TemplateField<u16>>,
+            server: TestSubject,
+        }
+
+        let toml = r#"
+name = "not-a-port"
+ports = [8080, "not-a-port"]
+
+[server]
+port = "not-a-port"
+enabled = true
+"#;
+        let err = check_template_fields::<Nested>(toml).expect_err("should have 2 errors");
+        let message = err.to_string();
+        assert!(message.starts_with("2 template field(s)"));
+        assert!(message.contains("`ports[1]`"));
+        assert!(message.contains("`server.port`"));
+        assert!(!message.contains("`name`"));
+    }
+}
@@ -61,7 +61,8 @@ impl<'a> TemplateMap<'a> {
             }
         }
 
-        toml::from_str(doc.to_string().as_str()).map_err(convert_toml_de_err_to_io_err)
+        let rendered = doc.to_string();
+        toml::from_str(rendered.as_str()).map_err(|e| self.convert_render_err(&rendered, e))
     }
 
     /// Apply json data to map of template fields from input type
@@ -112,7 +113,34 @@ impl<'a> TemplateMap<'a> {
             }
         }
 
-        toml::from_str(doc.to_string().as_str()).map_err(convert_toml_de_err_to_io_err)
+        let rendered = doc.to_string();
+        toml::from_str(rendered.as_str()).map_err(|e| self.convert_render_err(&rendered, e))
+    }
+}
+
+impl<'a> TemplateMap<'a> {
+    /// Converts an error deserializing the rendered document into an io error,
+    ///
+    /// If the error occurred in a template field, i.e. the rendered value could not be parsed, the error will
+    /// include the name of the field
+    fn convert_render_err(&self, rendered: &str, err: toml::de::Error) -> std::io::Error {
+        let field = err.span().and_then(|span| {
+            let line = rendered[..span.start].rsplit('\n').next()?;
+            let (key, _) = line.split_once('=')?;
+            let key = key.trim().trim_matches('"');
+            self.fields.contains_key(key).then_some(key)
+        });
+
+        match field {
+            Some(field) => std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "Could not parse rendered template for field `{field}`: {}",
+                    err.message()
+                ),
+            ),
+            None => convert_toml_de_err_to_io_err(err),
+        }
     }
 }
 
@@ -223,4 +251,32 @@ mod tests {
         assert_eq!("https://example.com/test_path", result.url);
         assert_eq!("test_hello_world", result.args[1].as_str());
     }
+
+    #[derive(Serialize, Deserialize)]
+    struct TypedSubject {
+        port: super::super::TemplateField<u16>,
+    }
+
+    #[test]
+    fn test_apply_template_map_typed_field() {
+        let mut fields = BTreeMap::new();
+        fields.insert("port".to_string(), toml! { port = "" });
+
+        let subject: TypedSubject = toml::from_str(r#"port = "{{port}}""#).unwrap();
+        let template_map = TemplateMap::from(&fields);
+
+        let input = json!({ "port": { "port": 8080 } });
+        let result = template_map
+            .apply_json(&subject, input.as_object().unwrap())
+            .unwrap();
+        assert_eq!(Some(&8080), result.port.as_inner());
+
+        let input = json!({ "port": { "port": "not-a-port" } });
+        let err = template_map
+            .apply_json(&subject, input.as_object().unwrap())
+            .err()
+            .expect("should not parse as u16");
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        assert!(err.to_string().contains("`port`"));
+    }
 }
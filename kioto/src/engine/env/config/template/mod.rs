@@ -3,4 +3,5 @@ mod field;
 mod data;
 pub use data::TemplateData;
 pub use map::TemplateMap;
+pub use field::check_template_fields;
 pub use field::TemplateField;
\ No newline at end of file
@@ -1,38 +1,83 @@
 mod config;
+pub use config::check_template_fields;
+pub use config::config_diff;
 pub use config::BuildMetadata;
 pub use config::EngineConfig;
 pub use config::EventConfig;
+pub use config::FieldChange;
 pub use config::LoaderMetadata;
 pub use config::Metadata;
 pub use config::TemplateMap;
 pub use config::TemplateField;
+pub(crate) use config::input_data;
 
 mod build;
+pub use build::BuildSummary;
 pub use build::Builder as EnvBuilder;
+
+mod reload;
+pub use reload::ReloadReport;
+
+mod secrets;
+mod source;
+pub use source::ConfigSource;
+pub use source::EmbeddedConfigSource;
+#[cfg(feature = "http")]
+pub use source::HttpConfigSource;
+pub use source::ReadFuture;
+pub use secrets::interpolate_document_secrets;
+pub(crate) use secrets::mask_document_secrets;
+pub use secrets::FileSecretResolver;
+pub use secrets::SecretResolver;
+
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "watch")]
+pub use watch::ReloadEvent;
+#[cfg(feature = "watch")]
+pub use watch::WatchHandle;
 use reality::plugin::Broker;
 
 use super::{Load, LoadInput, Operation};
-use crate::plugins::{Process, Request, RequestArgs};
+use crate::plugins::File;
+#[cfg(feature = "process")]
+use crate::plugins::Process;
+#[cfg(feature = "http")]
+use crate::plugins::{Request, RequestArgs};
 use clap::FromArgMatches;
 use reality::{
-    plugin::{Event, Handler, Name}, repo::Handle, repr::Labels, Plugin, State
+    plugin::{Address, Event, Handler, Name}, repo::Handle, repr::Labels, Plugin, State
 };
 use serde::de::DeserializeOwned;
-use std::{collections::BTreeSet, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    sync::Arc,
+};
 
 /// Creates an env w/ default set of plugin loaders
+///
+/// Loaders for the `Process` and `Request` plugins are only added if the `process` and `http` features are enabled
 pub fn default_create_env(label: String, root_dir: PathBuf) -> Env {
+    let secrets = Arc::new(FileSecretResolver::new(root_dir.join(&label).join("secrets")));
     let mut loader = Env {
         label,
         root_dir,
+        secrets,
         state: State::new(),
         config: EngineConfig::default(),
         loaders: BTreeSet::new(),
+        loaded_settings: BTreeMap::new(),
     };
     loader.add_toml_loader::<Operation>();
+    #[cfg(feature = "process")]
     loader.add_toml_loader::<Process>();
-    loader.add_toml_loader::<Request>();
-    loader.add_args_loader::<RequestArgs>();
+    loader.add_toml_loader::<File>();
+    #[cfg(feature = "http")]
+    {
+        loader.add_toml_loader::<Request>();
+        loader.add_args_loader::<RequestArgs>();
+    }
     loader
 }
 
@@ -43,12 +88,18 @@ pub struct Env {
     pub label: String,
     /// Root directory
     pub root_dir: PathBuf,
+    /// Resolves `${secret:NAME}` references when plugin config is loaded
+    pub secrets: Arc<dyn SecretResolver>,
     /// State
     pub state: State,
     /// Engine config for this environment
     pub config: EngineConfig,
     /// Map of prepared loaders
     pub loaders: BTreeSet<(Name, Handle)>,
+    /// Settings each plugin and handler was loaded from, keyed by the commit of its address
+    ///
+    /// **Note**: Secrets are masked, so that the settings can be logged, see `Env::reload_all`
+    pub loaded_settings: BTreeMap<u64, toml::Value>,
 }
 
 impl Env {
@@ -88,18 +139,48 @@ impl Env {
         self.loaders.insert((H::name(), h));
     }
 
+    /// Sets the secret resolver used when loading plugin config
+    #[inline]
+    pub fn set_secret_resolver(&mut self, resolver: impl SecretResolver + 'static) {
+        self.secrets = Arc::new(resolver);
+    }
+
     /// Finds a loader by name
     #[inline]
     pub fn find_loader(&self, name: &Name) -> Option<Load> {
+        self.find_loader_handle(name)
+            .and_then(|l| self.state.store().item(l.commit()))
+            .and_then(|i| i.borrow::<Load>().cloned())
+    }
+
+    /// Returns the names of all plugins this env has a loader for
+    #[inline]
+    pub fn available_loaders(&self) -> Vec<&Name> {
+        let mut names = self.loaders.iter().map(|(n, _)| n).collect::<Vec<_>>();
+        names.dedup();
+        names
+    }
+
+    /// Returns true if this env has a loader that matches the plugin name
+    ///
+    /// **Note**: Uses the same matching as `find_loader`, i.e. the full plugin reference is matched first, and then the
+    /// plugin reference w/o the version
+    #[inline]
+    pub fn can_load(&self, name: &Name) -> bool {
+        self.find_loader_handle(name).is_some()
+    }
+
+    /// Finds the handle to a loader by name
+    #[inline]
+    fn find_loader_handle(&self, name: &Name) -> Option<&Handle> {
         self.loaders
             .iter()
             .find(|(n, _)| n.full_plugin_ref() == name.full_plugin_ref())
             .or(self
                 .loaders
                 .iter()
-                .find(|(n, _)| n.plugin_ref() == name.plugin_ref()))
-            .and_then(|(_, l)| self.state.store().item(l.commit()))
-            .and_then(|i| i.borrow::<Load>().cloned())
+                .find(|(n, _)| n.same_plugin(name)))
+            .map(|(_, l)| l)
     }
 
     /// Tries to load a plugin w/ input
@@ -127,18 +208,77 @@ impl Env {
         self.config.configure_event(config, self)
     }
 
+    /// Tries to create an event for each plugin loaded by the config of this env
+    ///
+    /// Returns the event name of each plugin paired w/ the event, or the error returned while creating the event. Events
+    /// are returned in the order the plugins were declared, see `EngineConfig::loaded_plugins_in_order`.
+    #[inline]
+    pub fn create_all_events(&self) -> Vec<(String, reality::Result<Event>)> {
+        self.config
+            .loaded_plugins_in_order()
+            .into_iter()
+            .map(|(name, address)| (name.to_string(), self.state.event(address)))
+            .collect()
+    }
+
+    /// Exports the labels a plugin was loaded w/ into the plugin's settings
+    ///
+    /// The labels are written to the `-kt-build.labels` table, so that building and loading the exported settings
+    /// preserves them. Returns false if the plugin could not be found.
+    #[inline]
+    pub fn export(&self, address: &Address, settings: &mut toml_edit::DocumentMut) -> bool {
+        if !self.state.is_loaded(address) {
+            return false;
+        }
+        let labels = self.state.labels_of(address).unwrap_or_default();
+        config::insert_build_labels(settings, &labels);
+        true
+    }
+
+    /// Exports the config, plugins, and handlers of this env into a new env named `name`, i.e.
+    /// `<target_root>/<name>`, so that the exported env can be built or loaded w/ `EnvBuilder`
+    ///
+    /// Returns an error if the source of a plugin could not be read, or if the env tree could not be written
+    #[inline]
+    pub fn export_environment(
+        &self,
+        name: &str,
+        target_root: impl Into<PathBuf>,
+    ) -> std::io::Result<()> {
+        self.config.export_to(self, &target_root.into().join(name))
+    }
+
     /// Returns access to requests state
     #[inline]
     pub fn broker(&self) -> &Broker {
         self.state.broker()
     }
+
+    /// Reloads the engine config and plugins of this env from `<root_dir>/<label>`
+    ///
+    /// Returns the event name and address of each plugin that was loaded
+    #[inline]
+    pub fn reload(&mut self) -> std::io::Result<Vec<(String, Address)>> {
+        let mut config = EngineConfig::from_file_system(self.root_dir.clone(), &self.label)?;
+        config
+            .load(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{e:?}")))?;
+        let reloaded = config
+            .loaded_plugins()
+            .map(|(event, address)| (event.to_string(), address.clone()))
+            .collect();
+        self.config = config;
+        Ok(reloaded)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "http")]
     use std::str::FromStr;
 
+    #[cfg(feature = "http")]
     #[tokio::test]
     async fn test_test_env_macro() {
         let env = EnvBuilder::default_env("test");
@@ -160,6 +300,162 @@ url = "https://jsonplaceholder.typicode.com/posts"
         event.expect("should be able to find request and create event");
     }
 
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_env_export_labels() {
+        let mut env = default_create_env("test".to_string(), PathBuf::from(".test"));
+        let address = env
+            .load(
+                &Request::name(),
+                toml_edit::DocumentMut::from_str(r#"url = "https://example.com""#).unwrap(),
+                Labels::from(&[("env", "dev"), ("team", "runplat")][..]),
+            )
+            .expect("should be able to load");
+
+        let mut settings = toml_edit::DocumentMut::from_str(
+            r#"
+-kt-build.plugin = "kioto/plugins.request@0.1.0"
+-kt-build.labels.env = "prod"
+
+url = "https://example.com"
+"#,
+        )
+        .unwrap();
+        assert!(env.export(&address, &mut settings));
+        let labels = settings[crate::KT_BUILD_METADATA_TABLE]["labels"]
+            .as_table()
+            .expect("should have a labels table");
+        assert_eq!(Some("dev"), labels["env"].as_str());
+        assert_eq!(Some("runplat"), labels["team"].as_str());
+        assert_eq!(
+            Some("kioto/plugins.request@0.1.0"),
+            settings[crate::KT_BUILD_METADATA_TABLE]["plugin"].as_str()
+        );
+
+        let mut empty = toml_edit::DocumentMut::new();
+        assert!(env.export(&address, &mut empty));
+        assert_eq!(
+            Some("dev"),
+            empty[crate::KT_BUILD_METADATA_TABLE]["labels"]["env"].as_str()
+        );
+
+        env.state.unload(&address).unwrap();
+        assert!(!env.export(&address, &mut empty));
+    }
+
+    #[cfg(all(feature = "http", feature = "process"))]
+    #[tokio::test]
+    async fn test_env_available_loaders() {
+        let env = default_create_env("test".to_string(), PathBuf::from(".test"));
+
+        let available = env.available_loaders();
+        assert!(available.contains(&&Request::name()));
+        assert!(available.contains(&&Process::name()));
+        assert!(available.contains(&&Operation::name()));
+
+        assert!(env.can_load(&Request::name()));
+        assert!(env.can_load(&RequestArgs::name()));
+        assert!(!env.can_load(&crate::plugins::utils::HttpRequestClient::name()));
+    }
+
+    #[test]
+    fn test_env_build_incremental() {
+        let source = PathBuf::from(".test").join("build_src");
+        let target = PathBuf::from(".test").join("build_target");
+        let _ = std::fs::remove_dir_all(source.join("test_incremental"));
+        let _ = std::fs::remove_dir_all(target.join("test_incremental"));
+        std::fs::create_dir_all(source.join("test_incremental")).unwrap();
+        let write = |event: &str, url: &str| {
+            std::fs::write(
+                source
+                    .join("test_incremental")
+                    .join(format!("{event}.toml")),
+                format!("-kt-build.plugin = \"kioto/plugins.request@0.1.0\"\n\nurl = \"{url}\"\n"),
+            )
+            .unwrap();
+        };
+        write("first", "https://example.com/first");
+        write("second", "https://example.com/second");
+
+        let builder = EnvBuilder::default_env("test_incremental");
+        let summary = builder.build_env(&source, &target).unwrap();
+        assert_eq!(3, summary.added.len());
+        assert!(summary.changed.is_empty() && summary.unchanged.is_empty());
+
+        let summary = builder.build_env(&source, &target).unwrap();
+        assert_eq!(3, summary.unchanged.len());
+        assert!(summary.added.is_empty() && summary.changed.is_empty());
+
+        write("second", "https://example.com/changed");
+        let summary = builder.build_env(&source, &target).unwrap();
+        assert_eq!(
+            vec![target.join("test_incremental/etc/kioto/0.1.0/plugins/request/second.toml")],
+            summary.changed
+        );
+        assert_eq!(2, summary.unchanged.len());
+        assert!(summary
+            .unchanged
+            .contains(&target.join("test_incremental/config.toml")));
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_env_create_all_events() {
+        let builder = EnvBuilder::default_env("test_engine_events");
+        let target = PathBuf::from(".test").join("create_all_events");
+        builder
+            .build_env("tests/data", &target)
+            .expect("should be able to build");
+        let env = builder.load_env(&target).expect("should be able to load");
+
+        let events = env.create_all_events();
+        assert_eq!(
+            vec!["first", "second"],
+            events.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>()
+        );
+        for (name, event) in events {
+            let (address, _) = env.config.event(&name, &env).unwrap();
+            assert_eq!(&address, event.unwrap().address());
+        }
+
+        let (first, _) = env.config.event("first", &env).unwrap();
+        let engine = crate::engine::Engine::from_env(env).unwrap();
+        assert_eq!(2, engine.len());
+        assert_eq!(&first, engine.event(0).unwrap().address());
+        assert!(engine.state().find_plugin(&first).is_some());
+
+        // An event that could not be created is returned as an error
+        let mut env = builder.load_env(&target).expect("should be able to load");
+        env.state.unload(&first).unwrap();
+        assert!(crate::engine::Engine::from_env(env).is_err());
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_env_check_framework() {
+        fn deny_create_env(label: String, root_dir: PathBuf) -> Env {
+            let mut env = default_create_env(label, root_dir);
+            env.state
+                .set_framework_check(reality::plugin::FrameworkCheck::Deny);
+            env
+        }
+
+        let target = PathBuf::from(".test").join("check_framework");
+        let builder = EnvBuilder::default_env("test_framework");
+        builder
+            .build_env("tests/data", &target)
+            .expect("should be able to build");
+        builder
+            .load_env(&target)
+            .expect("should load w/ a warning by default");
+
+        match EnvBuilder::new("test_framework", deny_create_env).load_env(&target) {
+            Err(err) => assert!(err.to_string().contains("IncompatibleFramework")),
+            Ok(_) => panic!("should not load a config produced under an incompatible framework"),
+        }
+    }
+
+    #[cfg(all(feature = "http", feature = "process"))]
     #[tokio::test]
     #[tracing_test::traced_test]
     async fn test_env_build() {
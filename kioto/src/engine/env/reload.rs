@@ -0,0 +1,262 @@
+use super::{config_diff, EngineConfig, Env, FieldChange};
+use reality::plugin::Address;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::debug;
+
+/// Report of the plugins and handlers changed by `Env::reload_all`, keyed by event or handler name
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ReloadReport {
+    /// Plugins that were not loaded before the reload
+    pub added: BTreeMap<String, Address>,
+    /// Plugins whose content changed, w/ the previous and current address
+    pub changed: BTreeMap<String, (Address, Address)>,
+    /// Fields that changed in the settings of each changed plugin, w/ secrets masked
+    ///
+    /// **Note**: Plugins that were not loaded from a toml file are not included
+    pub changed_settings: BTreeMap<String, Vec<FieldChange>>,
+    /// Plugins that are no longer loaded by the config, w/ the address they were unloaded from
+    pub removed: BTreeMap<String, Address>,
+    /// Plugins whose content did not change
+    pub unchanged: BTreeSet<String>,
+}
+
+impl ReloadReport {
+    /// Returns true if no plugins were added, changed, or removed
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    /// Compares the plugins loaded before and after a reload and adds the differences to this report
+    fn diff<'a>(
+        &mut self,
+        previous: impl Iterator<Item = (&'a str, &'a Address)>,
+        current: impl Iterator<Item = (&'a str, &'a Address)>,
+    ) {
+        let mut previous = previous.collect::<BTreeMap<_, _>>();
+        for (name, address) in current {
+            match previous.remove(name) {
+                None => {
+                    self.added.insert(name.to_string(), address.clone());
+                }
+                Some(prev) if prev.commit() != address.commit() => {
+                    self.changed
+                        .insert(name.to_string(), (prev.clone(), address.clone()));
+                }
+                Some(_) => {
+                    self.unchanged.insert(name.to_string());
+                }
+            }
+        }
+        self.removed.extend(
+            previous
+                .into_iter()
+                .map(|(name, address)| (name.to_string(), address.clone())),
+        );
+    }
+}
+
+impl Env {
+    /// Reloads the engine config of this env from `<root_dir>/<label>` and reconciles the state w/ the config
+    ///
+    /// Plugins and handlers are loaded from the reloaded config, so plugins whose content did not change resolve to the
+    /// same address. Plugins that changed or that are no longer loaded by the config are unloaded, unless the address is
+    /// still used by another event.
+    ///
+    /// Returns a report of the plugins and handlers that were added, changed, or removed
+    ///
+    /// **Note**: If a plugin or handler cannot be loaded, the plugins and handlers already loaded by the reload are
+    /// unloaded again and an error is returned, so the state and config of this env are left as they were
+    pub fn reload_all(&mut self) -> std::io::Result<ReloadReport> {
+        let mut config = EngineConfig::from_file_system(self.root_dir.clone(), &self.label)?;
+        if let Err(e) = config.load(self) {
+            let previous = self
+                .config
+                .loaded_plugins()
+                .chain(self.config.loaded_handlers())
+                .map(|(_, a)| a.commit())
+                .collect::<BTreeSet<_>>();
+            let partial = config
+                .loaded_plugins()
+                .chain(config.loaded_handlers())
+                .filter(|(_, a)| !previous.contains(&a.commit()))
+                .collect::<BTreeMap<_, _>>();
+            for (name, address) in partial.iter() {
+                debug!(env = self.label, event = name, "env_reload_rolled_back");
+                // **Note**: An address shared by more than one event returns `PluginNotFound` once already unloaded
+                let _ = self.state.unload(address);
+                self.loaded_settings.remove(&address.commit());
+            }
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{e:?}"),
+            ));
+        }
+
+        let mut report = ReloadReport::default();
+        report.diff(self.config.loaded_plugins(), config.loaded_plugins());
+        report.diff(self.config.loaded_handlers(), config.loaded_handlers());
+        for (event, (previous, current)) in report.changed.iter() {
+            match self.settings_diff(previous, current) {
+                Some(changes) => {
+                    for change in changes.iter() {
+                        debug!(
+                            env = self.label,
+                            event,
+                            change = change.to_string(),
+                            "plugin_config_changed"
+                        );
+                    }
+                    report.changed_settings.insert(event.clone(), changes);
+                }
+                None => debug!(env = self.label, event, "plugin_config_diff_skipped"),
+            }
+        }
+
+        let loaded = config
+            .loaded_plugins()
+            .chain(config.loaded_handlers())
+            .map(|(_, a)| a.commit())
+            .collect::<BTreeSet<_>>();
+        let stale = report
+            .changed
+            .values()
+            .map(|(previous, _)| previous)
+            .chain(report.removed.values())
+            .filter(|a| !loaded.contains(&a.commit()))
+            .collect::<Vec<_>>();
+        for address in stale {
+            // **Note**: An address shared by more than one stale event returns `PluginNotFound` once already unloaded
+            let _ = self.state.unload(address);
+            self.loaded_settings.remove(&address.commit());
+        }
+
+        debug!(
+            env = self.label,
+            added = report.added.len(),
+            changed = report.changed.len(),
+            removed = report.removed.len(),
+            "env_reconciled"
+        );
+        self.config = config;
+        Ok(report)
+    }
+
+    /// Returns the fields that changed between the settings two plugins were loaded from, see `config_diff`
+    ///
+    /// Secrets referenced by the settings are masked, so the changes can be logged. Returns None if the settings of
+    /// either plugin are not known, i.e. the plugin was not loaded from a toml file.
+    #[inline]
+    fn settings_diff(&self, previous: &Address, current: &Address) -> Option<Vec<FieldChange>> {
+        Some(config_diff(
+            self.loaded_settings.get(&previous.commit())?,
+            self.loaded_settings.get(&current.commit())?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{EnvBuilder, FieldChange};
+    use std::path::{Path, PathBuf};
+
+    /// Writes a file for a reload test
+    fn write(env_root: &Path, path: &str, content: &str) {
+        let path = env_root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_env_reload_all() {
+        let root = PathBuf::from(".test");
+        let env_root = root.join("test_reload_all");
+        let _ = std::fs::remove_dir_all(&env_root);
+        let config = r#"
+[plugins.a]
+plugin = "kioto/plugins.file@0.1.0"
+
+[plugins.b]
+plugin = "kioto/plugins.file@0.1.0"
+"#;
+        write(&env_root, "config.toml", config);
+        write(
+            &env_root,
+            "etc/kioto/0.1.0/plugins/file/a.toml",
+            r#"path = "a.txt""#,
+        );
+        write(
+            &env_root,
+            "etc/kioto/0.1.0/plugins/file/b.toml",
+            r#"path = "b.txt""#,
+        );
+        write(
+            &env_root,
+            "etc/kioto/0.1.0/plugins/file/c.toml",
+            r#"path = "c.txt""#,
+        );
+
+        let mut env = EnvBuilder::default_env("test_reload_all")
+            .load_env(&root)
+            .unwrap();
+        let report = env.reload_all().unwrap();
+        assert!(report.is_empty());
+        assert_eq!(2, report.unchanged.len());
+        let (b, _) = env.config.event("b", &env).unwrap();
+
+        // Change `a` to a path from a secret, remove `b`, and add `c`
+        write(&env_root, "secrets/A_PATH", "a2.txt");
+        write(
+            &env_root,
+            "etc/kioto/0.1.0/plugins/file/a.toml",
+            r#"path = "${secret:A_PATH}""#,
+        );
+        write(
+            &env_root,
+            "config.toml",
+            &config.replace("[plugins.b]", "[plugins.c]"),
+        );
+        let report = env.reload_all().unwrap();
+        assert_eq!(vec!["c"], report.added.keys().collect::<Vec<_>>());
+        assert_eq!(vec!["b"], report.removed.keys().collect::<Vec<_>>());
+        let (previous, current) = &report.changed["a"];
+        assert_ne!(previous.commit(), current.commit());
+        assert!(report.unchanged.is_empty());
+
+        // The value of the secret is masked in the changes
+        assert_eq!(
+            vec![FieldChange::Changed {
+                field: String::from("path"),
+                old: toml::Value::from("a.txt"),
+                new: toml::Value::from("***"),
+            }],
+            report.changed_settings["a"]
+        );
+
+        assert!(!env.state.is_loaded(previous));
+        assert!(!env.state.is_loaded(&b));
+        assert!(env.state.is_loaded(current));
+        assert!(env.config.event("c", &env).is_ok());
+
+        // Add `d` and an event that cannot be loaded, the reload is rolled back
+        write(
+            &env_root,
+            "etc/kioto/0.1.0/plugins/file/d.toml",
+            r#"path = "d.txt""#,
+        );
+        write(
+            &env_root,
+            "config.toml",
+            &format!(
+                "{}\n[plugins.d]\nplugin = \"kioto/plugins.file@0.1.0\"\n\n[plugins.e]\nplugin = \"kioto/plugins.missing@0.1.0\"\n",
+                config.replace("[plugins.b]", "[plugins.c]")
+            ),
+        );
+        assert!(env.reload_all().is_err());
+        assert!(env.state.find_by_label("event", "d").is_empty());
+        assert!(env.config.event("d", &env).is_err());
+        assert!(env.state.is_loaded(current));
+        assert_eq!(2, env.config.loaded_plugins().count());
+    }
+}
@@ -0,0 +1,199 @@
+use std::{borrow::Cow, path::PathBuf};
+use toml_edit::{visit_mut::VisitMut, DocumentMut, Formatted};
+
+/// Prefix of a secret reference in plugin config, i.e. `${secret:NAME}`
+const SECRET_PREFIX: &str = "${secret:";
+
+/// Trait for resolving secrets referenced by plugin config
+///
+/// **Note**: Implementations should never log the resolved value
+pub trait SecretResolver: Send + Sync {
+    /// Resolves the value of a secret by key
+    ///
+    /// Returns an error if the secret could not be resolved
+    fn resolve(&self, key: &str) -> std::io::Result<String>;
+}
+
+/// Secret resolver that reads each secret from a file in a directory, i.e. `<env root>/secrets/<key>`
+///
+/// Trailing newlines are removed from the file contents
+pub struct FileSecretResolver {
+    /// Directory secrets are stored in
+    dir: PathBuf,
+}
+
+impl FileSecretResolver {
+    /// Creates a new resolver that reads secrets from a directory
+    #[inline]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl SecretResolver for FileSecretResolver {
+    fn resolve(&self, key: &str) -> std::io::Result<String> {
+        if key.is_empty() || key.contains(['/', '\\']) || key == "." || key == ".." {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid secret name `{key}`"),
+            ));
+        }
+
+        let mut value = std::fs::read_to_string(self.dir.join(key)).map_err(|e| {
+            std::io::Error::new(e.kind(), format!("Could not resolve secret `{key}`: {e}"))
+        })?;
+        let trimmed = value.trim_end_matches(['\r', '\n']).len();
+        value.truncate(trimmed);
+        Ok(value)
+    }
+}
+
+/// Replaces each `${secret:NAME}` in the source w/ the value resolved for `NAME`
+///
+/// Returns an error if a reference is not terminated, or if a secret could not be resolved
+pub fn interpolate_secrets<'a>(
+    src: &'a str,
+    resolver: &dyn SecretResolver,
+) -> std::io::Result<Cow<'a, str>> {
+    if !src.contains(SECRET_PREFIX) {
+        return Ok(Cow::Borrowed(src));
+    }
+
+    let mut output = String::with_capacity(src.len());
+    let mut rest = src;
+    while let Some(start) = rest.find(SECRET_PREFIX) {
+        output.push_str(&rest[..start]);
+        rest = &rest[start + SECRET_PREFIX.len()..];
+        let end = rest.find('}').ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Secret reference is missing a closing `}`",
+            )
+        })?;
+        output.push_str(&resolver.resolve(rest[..end].trim())?);
+        rest = &rest[end + 1..];
+    }
+    output.push_str(rest);
+    Ok(Cow::Owned(output))
+}
+
+/// Replaces each `${secret:NAME}` in the string values of a parsed toml document w/ the value resolved for `NAME`
+///
+/// Secrets are substituted into the parsed values, so a resolved value is never parsed as toml and cannot add keys to
+/// the document. Keys are not interpolated.
+///
+/// Returns the first error returned by `interpolate_secrets`
+pub fn interpolate_document_secrets(
+    document: &mut DocumentMut,
+    resolver: &dyn SecretResolver,
+) -> std::io::Result<()> {
+    let mut visitor = SecretVisitor {
+        resolver,
+        error: None,
+    };
+    visitor.visit_document_mut(document);
+    match visitor.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Replaces each `${secret:NAME}` in the string values of a parsed toml document w/ a mask, so that the document can
+/// be logged w/o resolving any secrets
+///
+/// **Note**: A reference that is not terminated is left as-is
+pub(crate) fn mask_document_secrets(document: &mut DocumentMut) {
+    let _ = interpolate_document_secrets(document, &MaskedSecrets);
+}
+
+/// Secret resolver that resolves every secret to a mask
+struct MaskedSecrets;
+
+impl SecretResolver for MaskedSecrets {
+    fn resolve(&self, _: &str) -> std::io::Result<String> {
+        Ok(String::from("***"))
+    }
+}
+
+/// Visits the string values of a toml document to interpolate secrets
+struct SecretVisitor<'a> {
+    /// Resolver for secret values
+    resolver: &'a dyn SecretResolver,
+    /// First error returned while interpolating
+    error: Option<std::io::Error>,
+}
+
+impl VisitMut for SecretVisitor<'_> {
+    fn visit_string_mut(&mut self, node: &mut Formatted<String>) {
+        if self.error.is_some() {
+            return;
+        }
+        match interpolate_secrets(node.value(), self.resolver) {
+            Ok(Cow::Borrowed(_)) => {}
+            Ok(Cow::Owned(interpolated)) => {
+                let mut value = Formatted::new(interpolated);
+                std::mem::swap(value.decor_mut(), node.decor_mut());
+                *node = value;
+            }
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolate_secrets() {
+        let dir = PathBuf::from(".test").join("test_secrets");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("TOKEN"), "abc123\n").unwrap();
+        let resolver = FileSecretResolver::new(&dir);
+
+        let src = r#"headers = ["Authorization: Bearer ${secret:TOKEN}"]"#;
+        assert_eq!(
+            r#"headers = ["Authorization: Bearer abc123"]"#,
+            interpolate_secrets(src, &resolver).unwrap()
+        );
+        assert!(matches!(
+            interpolate_secrets("url = \"x\"", &resolver).unwrap(),
+            Cow::Borrowed(_)
+        ));
+
+        let err = interpolate_secrets("${secret:MISSING}", &resolver).unwrap_err();
+        assert_eq!(std::io::ErrorKind::NotFound, err.kind());
+        let err = interpolate_secrets("${secret:../TOKEN}", &resolver).unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+        let err = interpolate_secrets("${secret:TOKEN", &resolver).unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+    }
+
+    #[test]
+    fn test_interpolate_document_secrets() {
+        let dir = PathBuf::from(".test").join("test_document_secrets");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("TOKEN"), "a\"b\\c\nenabled = false").unwrap();
+        let resolver = FileSecretResolver::new(&dir);
+
+        let mut document = r#"
+headers = ["Authorization: Bearer ${secret:TOKEN}"]
+url = "https://example.com" # ${secret:TOKEN}
+"#
+        .parse::<DocumentMut>()
+        .unwrap();
+        interpolate_document_secrets(&mut document, &resolver).unwrap();
+
+        let interpolated = document.to_string().parse::<DocumentMut>().unwrap();
+        assert_eq!(
+            Some("Authorization: Bearer a\"b\\c\nenabled = false"),
+            interpolated["headers"][0].as_str()
+        );
+        assert_eq!(Some("https://example.com"), interpolated["url"].as_str());
+        assert!(interpolated.get("enabled").is_none());
+
+        let mut document = r#"url = "${secret:MISSING}""#.parse::<DocumentMut>().unwrap();
+        let err = interpolate_document_secrets(&mut document, &resolver).unwrap_err();
+        assert_eq!(std::io::ErrorKind::NotFound, err.kind());
+    }
+}
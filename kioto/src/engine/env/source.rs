@@ -0,0 +1,245 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    path::{Component, Path, PathBuf},
+    pin::Pin,
+};
+use tracing::debug;
+#[cfg(feature = "http")]
+use url::Url;
+
+/// Type-alias for the future returned when reading a file from a config source
+pub type ReadFuture<'a> = Pin<Box<dyn Future<Output = std::io::Result<Vec<u8>>> + Send + 'a>>;
+
+/// Source of the files in an env's config tree, i.e. `config.toml`, its includes, and the plugin files under `etc/`
+///
+/// Used by `EnvBuilder::load_env_from` to load an env from somewhere other than the local file system
+pub trait ConfigSource: Send + Sync {
+    /// Reads a file from the config tree of an env
+    ///
+    /// `path` is relative to the env root, i.e. `config.toml` or `etc/kioto/0.1.0/plugins/request/test.toml`
+    ///
+    /// Returns an error w/ `ErrorKind::NotFound` if the file does not exist
+    fn read<'a>(&'a self, label: &'a str, path: &'a Path) -> ReadFuture<'a>;
+}
+
+/// Config source that fetches files over http(s), from `<base>/<label>/<path>`
+///
+/// **Note**: Files are fetched w/ the same client used by the `Request` plugin
+#[cfg(feature = "http")]
+#[derive(Clone, Debug)]
+pub struct HttpConfigSource {
+    /// Base url env config trees are fetched from
+    base: Url,
+}
+
+#[cfg(feature = "http")]
+impl HttpConfigSource {
+    /// Creates a new source that fetches from a base url
+    #[inline]
+    pub fn new(mut base: Url) -> Self {
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        Self { base }
+    }
+
+    /// Returns the url of a file in the config tree of an env
+    ///
+    /// Returns an error if the url could not be joined
+    fn url(&self, label: &str, path: &Path) -> std::io::Result<Url> {
+        self.base
+            .join(&format!(
+                "{label}/{}",
+                path.to_string_lossy().replace('\\', "/")
+            ))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))
+    }
+}
+
+#[cfg(feature = "http")]
+impl ConfigSource for HttpConfigSource {
+    fn read<'a>(&'a self, label: &'a str, path: &'a Path) -> ReadFuture<'a> {
+        Box::pin(async move {
+            let url = self.url(label, path)?;
+            debug!("Fetching config {url}");
+            Ok(crate::plugins::Request::fetch(url).await?.to_vec())
+        })
+    }
+}
+
+/// Config source that reads files embedded in the binary
+///
+/// # Example
+/// ```rust
+/// use kioto::engine::EmbeddedConfigSource;
+///
+/// static FILES: &[(&str, &[u8])] = &[
+///     ("prod/config.toml", b"[plugins.status]\nplugin = \"kioto/plugins.request\"\n"),
+///     (
+///         "prod/etc/kioto/0.1.0/plugins/request/status.toml",
+///         b"url = \"https://example.com/status\"\n",
+///     ),
+/// ];
+///
+/// let source = EmbeddedConfigSource::new(FILES);
+/// ```
+///
+/// **Note**: Contents are typically embedded w/ `include_bytes!`, paths are relative to the root of the config bundle,
+/// i.e. `<label>/<path>`
+#[derive(Clone, Debug, Default)]
+pub struct EmbeddedConfigSource {
+    /// Map of file path to file content
+    files: BTreeMap<PathBuf, &'static [u8]>,
+}
+
+impl EmbeddedConfigSource {
+    /// Creates a new source from a list of embedded files
+    #[inline]
+    pub fn new(files: &[(&str, &'static [u8])]) -> Self {
+        Self {
+            files: files
+                .iter()
+                .map(|(path, content)| (PathBuf::from(path), *content))
+                .collect(),
+        }
+    }
+}
+
+impl ConfigSource for EmbeddedConfigSource {
+    fn read<'a>(&'a self, label: &'a str, path: &'a Path) -> ReadFuture<'a> {
+        let content = self
+            .files
+            .get(&Path::new(label).join(path))
+            .map(|c| c.to_vec());
+        Box::pin(async move {
+            content.ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!(
+                        "Embedded config does not contain {label}/{}",
+                        path.display()
+                    ),
+                )
+            })
+        })
+    }
+}
+
+/// Reads a file from a config source and writes it to the env root
+///
+/// Returns the content of the file, or an error w/ `ErrorKind::InvalidInput` if the path is absolute or has a `..`
+/// component, since the file would be written outside of the env root
+pub(super) async fn fetch(
+    source: &dyn ConfigSource,
+    label: &str,
+    path: &Path,
+    env_root: &Path,
+) -> std::io::Result<Vec<u8>> {
+    if let Some(component) = path.components().find(|c| {
+        matches!(
+            c,
+            Component::RootDir | Component::Prefix(_) | Component::ParentDir
+        )
+    }) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "Config path {path:?} must be relative to the env root, found {:?}",
+                component.as_os_str()
+            ),
+        ));
+    }
+    let content = source.read(label, path).await?;
+    let to = env_root.join(path);
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    debug!("Writing fetched config to {to:?}");
+    tokio::fs::write(&to, &content).await?;
+    Ok(content)
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+    use super::*;
+    use crate::engine::{EnvBuilder, EventConfig};
+
+    #[tokio::test]
+    async fn test_env_load_env_from_embedded_source() {
+        static FILES: &[(&str, &[u8])] = &[
+            (
+                "test_embedded/config.toml",
+                b"includes = [\"common.toml\"]\n\n[plugins.status]\nplugin = \"kioto/plugins.request@0.1.0\"\n",
+            ),
+            (
+                "test_embedded/common.toml",
+                b"[default_labels]\nsource = \"embedded\"\n",
+            ),
+            (
+                "test_embedded/etc/kioto/0.1.0/plugins/request/status.toml",
+                b"url = \"https://example.com/status\"\n",
+            ),
+        ];
+
+        let root = PathBuf::from(".test").join("test_load_env_from");
+        let _ = std::fs::remove_dir_all(&root);
+        let env = EnvBuilder::default_env("test_embedded")
+            .load_env_from(&EmbeddedConfigSource::new(FILES), &root)
+            .await
+            .unwrap();
+        assert!(root.join("test_embedded/common.toml").exists());
+
+        let event = env
+            .create_event(&EventConfig {
+                event: "status".to_string(),
+                handler: None,
+                input: None,
+            })
+            .unwrap();
+        assert_eq!(Some("embedded"), event.label("source"));
+
+        let loaded = EnvBuilder::default_env("missing")
+            .load_env_from(&EmbeddedConfigSource::new(FILES), &root)
+            .await;
+        assert!(loaded.is_err_and(|e| e.kind() == std::io::ErrorKind::NotFound));
+    }
+
+    #[tokio::test]
+    async fn test_env_load_env_from_rejects_paths_outside_env_root() {
+        static FILES: &[(&str, &[u8])] = &[(
+            "test_traversal/config.toml",
+            b"includes = [\"../outside.toml\"]\n",
+        )];
+
+        let root = PathBuf::from(".test").join("test_load_env_from_traversal");
+        let _ = std::fs::remove_dir_all(&root);
+        let loaded = EnvBuilder::default_env("test_traversal")
+            .load_env_from(&EmbeddedConfigSource::new(FILES), &root)
+            .await;
+        assert!(loaded.is_err_and(|e| e.kind() == std::io::ErrorKind::InvalidInput));
+        assert!(!root.join("outside.toml").exists());
+
+        let source = EmbeddedConfigSource::new(FILES);
+        let env_root = root.join("test_traversal");
+        for path in ["/etc/passwd", "etc/../../outside.toml"] {
+            let fetched = fetch(&source, "test_traversal", Path::new(path), &env_root).await;
+            assert!(fetched.is_err_and(|e| e.kind() == std::io::ErrorKind::InvalidInput));
+        }
+    }
+
+    #[test]
+    fn test_http_config_source_url() {
+        let source = HttpConfigSource::new("https://example.com/envs".parse().unwrap());
+        assert_eq!(
+            "https://example.com/envs/prod/etc/kioto/0.1.0/plugins/request/status.toml",
+            source
+                .url(
+                    "prod",
+                    Path::new("etc/kioto/0.1.0/plugins/request/status.toml")
+                )
+                .unwrap()
+                .as_str()
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use super::Env;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use reality::plugin::Address;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::{broadcast, mpsc};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error};
+
+/// Window of time to wait for file changes to settle before reloading
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Capacity of the reload event channel
+const RELOAD_EVENT_CAPACITY: usize = 64;
+
+/// Lifecycle event published when a plugin is reloaded by a watcher
+#[derive(Clone, Debug)]
+pub struct ReloadEvent {
+    /// Name of the event the plugin was loaded for
+    pub event: String,
+    /// Address of the reloaded plugin
+    pub address: Address,
+}
+
+/// Handle to a running env watcher, the watcher is stopped when the handle is dropped
+pub struct WatchHandle {
+    /// Env that is reloaded when files change
+    env: Arc<Mutex<Env>>,
+    /// Sender used to publish reload events
+    events: broadcast::Sender<ReloadEvent>,
+    /// Cancellation token to stop the watcher
+    stop: CancellationToken,
+    /// Filesystem watcher, changes stop being delivered when dropped
+    _watcher: RecommendedWatcher,
+}
+
+impl WatchHandle {
+    /// Returns a receiver for reload events
+    #[inline]
+    pub fn subscribe(&self) -> broadcast::Receiver<ReloadEvent> {
+        self.events.subscribe()
+    }
+
+    /// Returns a clone of the most recently reloaded env
+    #[inline]
+    pub fn env(&self) -> Env {
+        match self.env.lock() {
+            Ok(env) => env.clone(),
+            Err(e) => e.into_inner().clone(),
+        }
+    }
+
+    /// Stops the watcher
+    #[inline]
+    pub fn stop(&self) {
+        self.stop.cancel();
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.stop.cancel();
+    }
+}
+
+impl Env {
+    /// Watches `config.toml` and the `etc/` tree of this env and reloads the env when files change
+    ///
+    /// Changes are debounced so that a burst of writes results in a single reload. A `ReloadEvent` is published for
+    /// each plugin loaded by a reload.
+    ///
+    /// The watcher stops when the handle is dropped or when this env's state is closed.
+    ///
+    /// **Note**: The watcher reloads a clone of this env, use `WatchHandle::env` to get the reloaded env
+    ///
+    /// Returns an error if not called within a tokio runtime, or if the env's files could not be watched
+    pub fn watch(&self) -> std::io::Result<WatchHandle> {
+        let handle = tokio::runtime::Handle::try_current()
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        let env_root = self.root_dir.join(&self.label);
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                    let _ = tx.send(());
+                }
+                Ok(_) => {}
+                Err(err) => error!("Error watching env, {err}"),
+            })
+            .map_err(into_io_error)?;
+        watcher
+            .watch(&env_root.join("config.toml"), RecursiveMode::NonRecursive)
+            .map_err(into_io_error)?;
+        let etc = env_root.join("etc");
+        if etc.exists() {
+            watcher
+                .watch(&etc, RecursiveMode::Recursive)
+                .map_err(into_io_error)?;
+        }
+
+        let env = Arc::new(Mutex::new(self.clone()));
+        let (events, _) = broadcast::channel(RELOAD_EVENT_CAPACITY);
+        let stop = self.state.child_token();
+
+        let reload_env = env.clone();
+        let reload_events = events.clone();
+        let stopped = stop.clone();
+        handle.spawn(async move {
+            while let Some(()) = tokio::select! {
+                changed = rx.recv() => changed,
+                _ = stopped.cancelled() => None,
+            } {
+                // Wait until no changes have been received for the debounce window
+                loop {
+                    tokio::select! {
+                        _ = tokio::time::sleep(DEBOUNCE) => break,
+                        Some(()) = rx.recv() => continue,
+                        _ = stopped.cancelled() => return,
+                    }
+                }
+
+                let mut env = match reload_env.lock() {
+                    Ok(env) => env,
+                    Err(e) => e.into_inner(),
+                };
+                match env.reload() {
+                    Ok(reloaded) => {
+                        debug!(env = env.label, count = reloaded.len(), "Reloaded env");
+                        for (event, address) in reloaded {
+                            let _ = reload_events.send(ReloadEvent { event, address });
+                        }
+                    }
+                    Err(err) => error!("Could not reload env `{}`, {err}", env.label),
+                }
+            }
+            debug!("Stopped watching env");
+        });
+
+        Ok(WatchHandle {
+            env,
+            events,
+            stop,
+            _watcher: watcher,
+        })
+    }
+}
+
+/// Converts a watcher error into an io error
+fn into_io_error(err: notify::Error) -> std::io::Error {
+    match err.kind {
+        notify::ErrorKind::Io(err) => err,
+        notify::ErrorKind::PathNotFound => {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Watched path was not found")
+        }
+        _ => std::io::Error::other(err.to_string()),
+    }
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+    use crate::engine::EnvBuilder;
+
+    #[tokio::test]
+    async fn test_env_watch() {
+        let env = EnvBuilder::default_env("test_env_watch");
+        env.build_env("tests/data", ".test").unwrap();
+        let env = env.load_env(".test").unwrap();
+
+        let handle = env.watch().unwrap();
+        let mut events = handle.subscribe();
+
+        let path = env
+            .root_dir
+            .join(&env.label)
+            .join("etc/kioto/0.1.0/plugins/request/watched.toml");
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, content.replace("/watched", "/reloaded")).unwrap();
+
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), events.recv())
+            .await
+            .expect("should reload before the timeout")
+            .unwrap();
+        assert_eq!("watched", event.event);
+        assert!(handle.env().state.find_plugin(&event.address).is_some());
+
+        // Closing the state stops the watcher
+        handle.env().state.close();
+        std::fs::write(&path, content).unwrap();
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(500), events.recv())
+                .await
+                .is_err()
+        );
+    }
+}
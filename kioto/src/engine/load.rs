@@ -7,9 +7,14 @@ use reality::{
 use serde::de::DeserializeOwned;
 use std::io::Error;
 
+use super::check_template_fields;
+
 /// Type-alias for a function to load a plugin by toml
 type LoadByToml = fn(&mut State, &str, Labels) -> std::io::Result<Address>;
 
+/// Type-alias for a function to load a plugin by bincode serialized bytes
+type LoadByBincode = fn(&mut State, &[u8], Labels) -> std::io::Result<Address>;
+
 /// Type-alias for a function to load a plugin by arg matches
 type LoadByArgs = fn(&mut State, &ArgMatches, Labels) -> std::io::Result<Address>;
 
@@ -29,6 +34,8 @@ pub enum LoadBy {
     Toml(LoadByToml),
     /// Load plugin by cli arg matches
     Args(LoadByArgs),
+    /// Load plugin by bincode serialized bytes
+    Bincode(LoadByBincode),
 }
 
 /// Enumeration of load plugin input
@@ -37,6 +44,8 @@ pub enum LoadInput {
     Toml(String),
     /// Arg matches
     Args(ArgMatches),
+    /// Binary input
+    Bytes(Vec<u8>),
 }
 
 impl Load {
@@ -56,6 +65,9 @@ impl Load {
             (LoadBy::Args(load_args), LoadInput::Args(input_args)) => {
                 load_args(state, &input_args, labels)
             }
+            (LoadBy::Bincode(load_bincode), LoadInput::Bytes(input_bytes)) => {
+                load_bincode(state, &input_bytes, labels)
+            }
             _ => Err(Error::new(
                 std::io::ErrorKind::InvalidInput,
                 "Could not load input with provided input settings",
@@ -83,7 +95,19 @@ impl Load {
     {
         Self {
             name: P::name(),
-            load: LoadBy::Toml(P::load_by_toml),
+            load: LoadBy::Toml(load_by_toml::<P>),
+        }
+    }
+
+    /// Creates a load resource for a plugin to load by bincode serialized bytes
+    #[inline]
+    pub fn by_bincode<P>() -> Self
+    where
+        P: Plugin + DeserializeOwned,
+    {
+        Self {
+            name: P::name(),
+            load: LoadBy::Bincode(P::load_by_bincode),
         }
     }
 
@@ -107,7 +131,7 @@ impl Load {
     {
         Self {
             name: H::name(),
-            load: LoadBy::Toml(H::load_handler_by_toml),
+            load: LoadBy::Toml(load_handler_by_toml::<H>),
         }
     }
 
@@ -118,12 +142,40 @@ impl Load {
     }
 }
 
+/// Loads a plugin by toml after checking that all template fields in the toml can be deserialized
+///
+/// **Note**: Checking first reports every template field error at once, instead of only the first
+fn load_by_toml<P: Plugin + DeserializeOwned>(
+    state: &mut State,
+    toml: &str,
+    labels: Labels,
+) -> std::io::Result<Address> {
+    check_template_fields::<P>(toml)?;
+    P::load_by_toml(state, toml, labels)
+}
+
+/// Loads a handler by toml after checking that all template fields in the toml can be deserialized
+fn load_handler_by_toml<H: Handler + DeserializeOwned>(
+    state: &mut State,
+    toml: &str,
+    labels: Labels,
+) -> std::io::Result<Address> {
+    check_template_fields::<H>(toml)?;
+    H::load_handler_by_toml(state, toml, labels)
+}
+
 impl From<ArgMatches> for LoadInput {
     fn from(value: ArgMatches) -> Self {
         LoadInput::Args(value)
     }
 }
 
+impl From<Vec<u8>> for LoadInput {
+    fn from(value: Vec<u8>) -> Self {
+        LoadInput::Bytes(value)
+    }
+}
+
 impl<'l> From<toml_edit::DocumentMut> for LoadInput {
     fn from(value: toml_edit::DocumentMut) -> Self {
         LoadInput::Toml(value.to_string())
@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+
+use reality::{repr::Labels, State};
+use serde::{Deserialize, Serialize};
+
+use super::Engine;
+
+/// Portable description of the events pushed on to an engine, see `Engine::to_manifest`
+///
+/// # Example
+/// ```toml
+/// [[events]]
+/// plugin = "kioto/0.1.0/plugins/process/cecb48f8a3c27967"
+/// labels = { team = "core" }
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EngineManifest {
+    /// Events in the order they were pushed on to the engine
+    #[serde(default)]
+    pub events: Vec<EventManifest>,
+}
+
+/// Description of a single event within an engine manifest
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EventManifest {
+    /// Address of the plugin the event calls
+    pub plugin: String,
+    /// Address of the handler set on the event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handler: Option<String>,
+    /// Labels the plugin was loaded with
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Error returned when an event from a manifest could not be rebuilt, see `Engine::from_manifest`
+#[derive(Debug)]
+pub struct ManifestEventError {
+    /// Index of the event in the manifest
+    pub index: usize,
+    /// Address from the manifest that could not be resolved
+    pub address: String,
+    /// Error returned while resolving the address
+    pub error: reality::Error,
+}
+
+impl std::fmt::Display for ManifestEventError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Could not rebuild event {} from `{}`, {:?}",
+            self.index, self.address, self.error
+        )
+    }
+}
+
+impl std::error::Error for ManifestEventError {}
+
+impl Engine {
+    /// Returns a manifest of the events pushed on to this engine
+    ///
+    /// The manifest only references plugins by address, so the plugins must be loaded into the state passed to
+    /// `Engine::from_manifest`, i.e. by restoring a snapshot of this engine's store
+    pub fn to_manifest(&self) -> EngineManifest {
+        EngineManifest {
+            events: self
+                .events
+                .iter()
+                .map(|e| EventManifest {
+                    plugin: e.address().to_string(),
+                    handler: e.handler().map(|h| h.to_string()),
+                    labels: e.labels().map(|l| l.0.clone()).unwrap_or_default(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Rebuilds an engine from a manifest, resolving the address of each event's plugin and handler against `state`
+    ///
+    /// If an event in the manifest has labels, they are set on the rebuilt event in place of the labels the plugin was
+    /// loaded with in `state`
+    ///
+    /// Returns an error for each event whose plugin or handler could not be resolved
+    pub fn from_manifest(
+        state: State,
+        manifest: &EngineManifest,
+    ) -> std::result::Result<Engine, Vec<ManifestEventError>> {
+        let mut engine = Engine::with(state);
+        let mut errors = vec![];
+        for (index, event) in manifest.events.iter().enumerate() {
+            let rebuilt = engine
+                .state
+                .event(&event.plugin)
+                .map_err(|error| ManifestEventError {
+                    index,
+                    address: event.plugin.clone(),
+                    error,
+                })
+                .map(|mut e| {
+                    if !event.labels.is_empty() {
+                        e.with_labels(Labels(event.labels.clone()));
+                    }
+                    e
+                })
+                .and_then(|mut e| match event.handler.as_ref() {
+                    Some(handler) => engine
+                        .state
+                        .event(handler)
+                        .map(|h| h.address().clone())
+                        .and_then(|address| {
+                            let thunk = engine.state.handler(handler)?;
+                            e.set_handler(address, &thunk)?;
+                            Ok(e)
+                        })
+                        .map_err(|error| ManifestEventError {
+                            index,
+                            address: handler.clone(),
+                            error,
+                        }),
+                    None => Ok(e),
+                });
+
+            match rebuilt {
+                Ok(e) => engine.events.push(e),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(engine)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "process"))]
+mod tests {
+    use super::*;
+    use crate::plugins::{utils::ProcessClient, Process};
+
+    #[tokio::test]
+    async fn test_engine_manifest_round_trip() {
+        let mut state = State::new();
+        let echo = state
+            .load_by_toml::<Process>(
+                r#"
+program = "echo"
+args = ["hello"]
+"#,
+                Labels::from(&[("team", "core")][..]),
+            )
+            .unwrap();
+        let cat = state
+            .load_by_toml::<Process>("program = \"cat\"\nargs = []", Labels::default())
+            .unwrap();
+        let client = state.load_handler(ProcessClient::default(), Labels::default());
+
+        let mut engine = Engine::with(state.clone());
+        let mut event = state.event(&echo).unwrap();
+        event.with_handler::<ProcessClient>(client.clone()).unwrap();
+        engine.push(event).unwrap();
+        engine.push(state.event(&cat).unwrap()).unwrap();
+
+        let manifest = engine.to_manifest();
+        assert_eq!(
+            EventManifest {
+                plugin: echo.to_string(),
+                handler: Some(client.to_string()),
+                labels: BTreeMap::from([("team".to_string(), "core".to_string())]),
+            },
+            manifest.events[0]
+        );
+
+        // The manifest can be persisted and rebuilt against the same state
+        let manifest: EngineManifest =
+            toml::from_str(&toml::to_string(&manifest).unwrap()).unwrap();
+        let rebuilt = Engine::from_manifest(state.clone(), &manifest).unwrap();
+        assert_eq!(2, rebuilt.len());
+        assert_eq!(Some(&client), rebuilt.event(0).unwrap().handler());
+        assert_eq!(manifest, rebuilt.to_manifest());
+        let output = rebuilt.event(0).unwrap().clone().returns().await.unwrap();
+        assert_eq!(Some(&b"hello\n"[..]), output.as_bytes().map(|b| &b[..]));
+
+        // Labels from the manifest are set on the rebuilt events
+        let mut relabeled = manifest.clone();
+        relabeled.events[1].labels = BTreeMap::from([("team".to_string(), "edge".to_string())]);
+        let rebuilt = Engine::from_manifest(state.clone(), &relabeled).unwrap();
+        assert_eq!(Some("core"), rebuilt.event(0).unwrap().label("team"));
+        assert_eq!(Some("edge"), rebuilt.event(1).unwrap().label("team"));
+        assert_eq!(relabeled, rebuilt.to_manifest());
+
+        // Addresses that cannot be resolved are returned per event
+        let mut manifest = manifest;
+        manifest.events[1].plugin = String::from("kioto/0.1.0/plugins/process/0000000000000000");
+        let Err(errors) = Engine::from_manifest(State::new(), &manifest) else {
+            panic!("should not rebuild from an empty state");
+        };
+        assert_eq!(2, errors.len());
+        assert_eq!(0, errors[0].index);
+        assert_eq!(echo.to_string(), errors[0].address);
+        assert_eq!(1, errors[1].index);
+        assert!(matches!(errors[1].error, reality::Error::PluginNotFound));
+    }
+}
@@ -1,23 +1,48 @@
 mod env;
 mod load;
+mod manifest;
 mod operation;
+pub use env::check_template_fields;
+pub use env::config_diff;
 pub use env::default_create_env;
 pub use env::BuildMetadata;
+pub use env::BuildSummary;
+pub use env::ConfigSource;
+pub use env::EmbeddedConfigSource;
 pub use env::EngineConfig;
 pub use env::Env;
 pub use env::EnvBuilder;
 pub use env::EventConfig;
+pub use env::FieldChange;
+pub use env::FileSecretResolver;
+#[cfg(feature = "http")]
+pub use env::HttpConfigSource;
 pub use env::LoaderMetadata;
 pub use env::Metadata;
+pub use env::ReadFuture;
+pub use env::ReloadReport;
+pub use env::SecretResolver;
 pub use env::TemplateField;
 pub use env::TemplateMap;
+#[cfg(feature = "watch")]
+pub use env::ReloadEvent;
+#[cfg(feature = "watch")]
+pub use env::WatchHandle;
 pub use load::Load;
 pub use load::LoadBy;
 pub use load::LoadInput;
+pub use manifest::EngineManifest;
+pub use manifest::EventManifest;
+pub use manifest::ManifestEventError;
+pub use operation::EventForward;
 pub use operation::Operation;
+pub use operation::Step;
+pub use operation::StepHandler;
+pub use operation::StepTiming;
 
 use reality::plugin::Event;
 use reality::State;
+use tracing::{debug, error};
 
 /// An engine manages a collection of events and plugin resources
 pub struct Engine {
@@ -37,6 +62,29 @@ impl Engine {
         }
     }
 
+    /// Creates an engine from an env, moving the env's state into the engine
+    ///
+    /// An event is pushed for each plugin loaded by the env's config, in the order returned by `Env::create_all_events`.
+    ///
+    /// Returns the error of the first plugin whose event could not be created
+    #[inline]
+    pub fn from_env(env: Env) -> reality::Result<Self> {
+        let mut events = vec![];
+        for (name, event) in env.create_all_events() {
+            match event {
+                Ok(event) => events.push(event),
+                Err(err) => {
+                    error!(event = name, "Could not create event, {err:?}");
+                    return Err(err);
+                }
+            }
+        }
+        Ok(Engine {
+            state: env.state,
+            events,
+        })
+    }
+
     /// Creates and pushes a plugin event onto the engine
     #[inline]
     pub fn push(&mut self, event: Event) -> reality::Result<()> {
@@ -50,22 +98,95 @@ impl Engine {
         self.events.get(index)
     }
 
+    /// Returns the first event whose address matches `matcher`
+    ///
+    /// The matcher can be a plugin reference, i.e. `kioto/plugins.request`, or the full address of the event
+    #[inline]
+    pub fn find_event(&self, matcher: &str) -> Option<&Event> {
+        self.events.iter().find(|e| e.address().matches(matcher))
+    }
+
+    /// Returns all events whose address matches `matcher`, in the order they were pushed
+    #[inline]
+    pub fn find_events<'a>(&'a self, matcher: &'a str) -> impl Iterator<Item = &'a Event> + 'a {
+        self.events.iter().filter(move |e| e.address().matches(matcher))
+    }
+
+    /// Removes and returns the event at `index`
+    ///
+    /// **Note**: Events after `index` are shifted down by one, so indices returned before removal may no longer refer
+    /// to the same event
+    #[inline]
+    pub fn remove(&mut self, index: usize) -> Option<Event> {
+        if index < self.events.len() {
+            Some(self.events.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Removes all events from the engine, plugin resources in state are not affected
+    #[inline]
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Returns the number of events pushed on to this engine
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns true if no events have been pushed on to this engine
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
     /// Returns a reference to the engine's state
     #[inline]
     pub fn state(&self) -> &State {
         &self.state
     }
+
+    /// Runs every event pushed on to this engine, see `Engine::run_where`
+    #[inline]
+    pub async fn run(&self) -> reality::Result<usize> {
+        self.run_where(|_| true).await
+    }
+
+    /// Runs the events that match `predicate` in the order they were pushed, waiting for each event to complete before
+    /// starting the next
+    ///
+    /// Returns the number of events that were run, or the first error returned by an event. Events after the event that
+    /// failed are not run.
+    ///
+    /// **Note**: Yields to the runtime after each event, so that a long run does not starve other tasks
+    ///
+    /// # Example
+    /// ```ignore
+    /// engine.run_where(|e| e.label("stage") == Some("setup")).await?;
+    /// engine.run_where(|e| e.label("stage") == Some("deploy")).await?;
+    /// ```
+    pub async fn run_where(&self, predicate: impl Fn(&Event) -> bool) -> reality::Result<usize> {
+        let mut count = 0;
+        for event in self.events.iter().filter(|e| predicate(e)) {
+            debug!(address = event.address().to_string(), "engine_run_event");
+            event.clone().start().await?;
+            count += 1;
+            tokio::task::yield_now().await;
+        }
+        Ok(count)
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "http", feature = "process"))]
 mod tests {
-    use bytes::Bytes;
-    use http_body_util::BodyExt;
-    use reality::{plugin::MessageData, repr::Labels};
+    use reality::{repr::Labels, Plugin};
     use toml::toml;
 
     use crate::{
-        engine::{default_create_env, env::EnvBuilder, EventConfig, Metadata, Operation},
+        engine::{default_create_env, env::EnvBuilder, Engine, EventConfig, Metadata, Operation},
         plugins::{utils::{HttpRequestClient, ProcessClient}, Request},
     };
 
@@ -91,6 +212,7 @@ mod tests {
             .create_event(&EventConfig {
                 event: "test".to_string(),
                 handler: None,
+                input: None,
             })
             .unwrap();
         assert_eq!(
@@ -102,6 +224,7 @@ mod tests {
             .create_event(&EventConfig {
                 event: "test2".to_string(),
                 handler: None,
+                input: None,
             })
             .unwrap();
         assert_eq!(
@@ -110,6 +233,83 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_env_loader_create_event_with_input() {
+        let env = EnvBuilder::new("test_event_input", default_create_env);
+        env.build_env("tests/data", ".test")
+            .expect("should be able to build env");
+
+        let loader = env
+            .load_env(".test")
+            .expect("should be able to load test env");
+
+        let event = loader
+            .create_event(&EventConfig {
+                event: "test".to_string(),
+                handler: None,
+                input: Some(toml::toml! { name = "kioto" }.into()),
+            })
+            .unwrap();
+        let input = loader.broker().receive(event.address().commit());
+        assert_eq!(
+            Some("kioto"),
+            input.as_toml().and_then(|t| t.get("name")).and_then(|n| n.as_str())
+        );
+
+        assert!(loader
+            .create_event(&EventConfig {
+                event: "test".to_string(),
+                handler: None,
+                input: Some(toml::Value::Integer(1)),
+            })
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_engine_remove_and_clear_events() {
+        let env = EnvBuilder::new("test_engine_events", default_create_env);
+        env.build_env("tests/data", ".test")
+            .expect("should be able to build env");
+
+        let loader = env
+            .load_env(".test")
+            .expect("should be able to load test env");
+
+        let mut engine = Engine::with(loader.state.clone());
+        assert!(engine.is_empty());
+        for name in ["first", "second"] {
+            let event = loader
+                .create_event(&EventConfig {
+                    event: name.to_string(),
+                    handler: None,
+                    input: None,
+                })
+                .unwrap();
+            engine.push(event).unwrap();
+        }
+        assert_eq!(2, engine.len());
+        let second = engine.event(1).unwrap().address().clone();
+        assert_eq!(
+            engine.event(0).unwrap().address(),
+            engine.find_event("kioto/plugins.request").unwrap().address()
+        );
+        assert_eq!(2, engine.find_events("kioto/plugins.request@0.1.0").count());
+        assert_eq!(
+            &second,
+            engine.find_event(&second.to_string()).unwrap().address()
+        );
+        assert!(engine.find_event("kioto/plugins.process").is_none());
+
+        assert!(engine.remove(2).is_none());
+        engine.remove(0).unwrap();
+        assert_eq!(1, engine.len());
+        assert_eq!(&second, engine.event(0).unwrap().address());
+
+        engine.clear();
+        assert!(engine.is_empty());
+        assert!(engine.event(0).is_none());
+    }
+
     #[tokio::test]
     #[tracing_test::traced_test]
     async fn test_env_loader_test_operation() {
@@ -128,6 +328,7 @@ mod tests {
             .create_event(&EventConfig {
                 event: "run_tests".to_string(),
                 handler: None,
+                input: None,
             })
             .unwrap();
         let event_clone = event.clone();
@@ -141,7 +342,7 @@ mod tests {
             .take_engine()
             .unwrap();
 
-        let event = engine.event(1).unwrap();
+        let event = engine.find_event("kioto/plugins.request").unwrap();
         let resp = event
             .item()
             .clone()
@@ -151,11 +352,187 @@ mod tests {
             .unwrap();
         assert!(resp.status().is_success());
 
-        let event = engine.event(2).unwrap();
+        let event = engine
+            .find_events("kioto/plugins.request")
+            .find(|e| e.label("test").is_some_and(|l| l != "test_label"))
+            .unwrap();
         let label = event.label("test").unwrap();
         assert_eq!("testval", label);
     }
 
+    #[tokio::test]
+    async fn test_operation_parallelism() {
+        let env = EnvBuilder::default_env("test_operation_parallel");
+        env.build_env("tests/data", ".test").unwrap();
+        let env = env
+            .load_env(".test")
+            .expect("should be able to load test env");
+
+        let mut steps = vec![];
+        for name in ["sequential", "parallel"] {
+            let event = env
+                .create_event(&EventConfig {
+                    event: name.to_string(),
+                    handler: None,
+                    input: None,
+                })
+                .unwrap();
+            event.clone().start().await.unwrap();
+            steps.push(
+                event
+                    .item()
+                    .borrow::<Operation>()
+                    .unwrap()
+                    .steps()
+                    .to_vec(),
+            );
+        }
+
+        let (sequential, parallel) = (&steps[0], &steps[1]);
+        assert_eq!(2, sequential.len());
+        assert!(!sequential[0].overlaps(&sequential[1]));
+        assert!(sequential[0].end <= sequential[1].start);
+        assert_eq!(2, parallel.len());
+        assert!(parallel[0].overlaps(&parallel[1]));
+    }
+
+    #[tokio::test]
+    async fn test_operation_cancel() {
+        let env = EnvBuilder::default_env("test_operation_cancel");
+        env.build_env("tests/data", ".test").unwrap();
+        let env = env
+            .load_env(".test")
+            .expect("should be able to load test env");
+        let event = |name: &str| {
+            env.create_event(&EventConfig {
+                event: name.to_string(),
+                handler: None,
+                input: None,
+            })
+            .unwrap()
+        };
+
+        // Cancelling the operation cancels every step that is executing
+        let parallel = event("parallel");
+        let (forked, cancel) = parallel.fork();
+        let started = std::time::Instant::now();
+        let running = tokio::spawn(forked.start());
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        cancel.cancel();
+        assert!(matches!(
+            running.await.unwrap(),
+            Err(reality::Error::PluginCallCancelled { .. })
+        ));
+        assert!(started.elapsed() < std::time::Duration::from_secs(4));
+        assert_eq!(
+            2,
+            parallel.item().borrow::<Operation>().unwrap().steps().len()
+        );
+
+        // A failed step cancels the steps that are executing
+        let abort = event("abort");
+        let started = std::time::Instant::now();
+        assert!(matches!(
+            abort.clone().start().await,
+            Err(reality::Error::PluginCallError { .. })
+        ));
+        assert!(started.elapsed() < std::time::Duration::from_secs(4));
+        assert_eq!(2, abort.item().borrow::<Operation>().unwrap().steps().len());
+    }
+
+    #[tokio::test]
+    async fn test_operation_inline_steps() {
+        let env = EnvBuilder::default_env("test_operation_steps");
+        env.build_env("tests/data", ".test").unwrap();
+        let mut env = env
+            .load_env(".test")
+            .expect("should be able to load test env");
+
+        let event = env
+            .create_event(&EventConfig {
+                event: "steps".to_string(),
+                handler: None,
+                input: None,
+            })
+            .unwrap();
+        event.clone().start().await.unwrap();
+        let mut item = event.item().clone();
+        let operation = item.borrow_mut::<Operation>().unwrap();
+        assert_eq!(2, operation.inline_steps().len());
+        assert_eq!(2, operation.steps().len());
+        let engine = operation.take_engine().unwrap();
+        assert_eq!(2, engine.find_events("kioto/plugins.process").count());
+
+        // Input is sent to the plugin of the step
+        let operation = toml::from_str::<Operation>(
+            r#"
+            [[steps]]
+            plugin = "kioto/plugins.process@0.1.0"
+            settings = { program = "echo", args = [] }
+            input = { name = "kioto" }
+            "#,
+        )
+        .unwrap();
+        let events = operation.load_steps(&mut env).unwrap();
+        let input = env.broker().receive(events[0].address().commit());
+        assert_eq!(
+            Some("kioto"),
+            input.as_toml().and_then(|t| t.get("name")).and_then(|n| n.as_str())
+        );
+
+        let operation = toml::from_str::<Operation>(
+            r#"
+            [[steps]]
+            plugin = "kioto/plugins.unknown@0.1.0"
+            "#,
+        )
+        .unwrap();
+        assert!(operation.load_steps(&mut env).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_operation_forward() {
+        let env = EnvBuilder::default_env("test_operation_forward");
+        env.build_env("tests/data", ".test").unwrap();
+        let mut env = env
+            .load_env(".test")
+            .expect("should be able to load test env");
+
+        // Target of the forwarded output in the state the operation is called from
+        let target = env
+            .load(
+                &crate::plugins::File::name(),
+                r#"path = ".test/forward.txt""#.parse::<toml_edit::DocumentMut>().unwrap(),
+                Labels::default(),
+            )
+            .unwrap();
+
+        let event = env
+            .create_event(&EventConfig {
+                event: "forward".to_string(),
+                handler: None,
+                input: None,
+            })
+            .unwrap();
+        event.start().await.unwrap();
+        assert_eq!(
+            Some(&bytes::Bytes::from("hello")),
+            env.broker().receive(target.commit()).as_bytes()
+        );
+
+        // Forwarding from an event that does not exist fails the operation
+        let operation = toml::from_str::<Operation>(
+            r#"
+            [[forward]]
+            from = "kioto/plugins.request"
+            to = "kioto/0.1.0/plugins/file"
+            "#,
+        )
+        .unwrap();
+        let address = env.state.load(operation, Labels::default());
+        assert!(env.state.call(&address).await.is_err());
+    }
+
     #[tokio::test]
     #[tracing_test::traced_test]
     async fn test_env_loader_test_templates() {
@@ -174,6 +551,7 @@ mod tests {
             .create_event(&EventConfig {
                 event: "test_basic".to_string(),
                 handler: None,
+                input: None,
             })
             .unwrap();
 
@@ -209,54 +587,40 @@ mod tests {
         );
 
         // Create a new client plugin to receive the request from
-        let client = HttpRequestClient::new(|r| {
-            // TODO: Default implementations can be created for this
-            Box::pin(async move {
-                let incoming = r.into_body();
-                let data = serde_json::from_slice::<serde_json::Value>(
-                    &incoming.collect().await.unwrap().to_bytes(),
-                )
-                .unwrap();
-                let data = MessageData::from(data);
-                Ok(data)
-            })
-        });
+        let client = HttpRequestClient::default();
 
         // Load the client plugin into state
         let client_address = env.state.load(client, Labels::default());
 
-        // Broker a message to the request event
-        // The Request plugin will "receive" this message before starting in order to apply the template to itself
-        env.broker()
-            .send(
-                event.address().commit(),
-                serde_json::json! ({
-                    "url": {
-                        "host": "jsonplaceholder.typicode.com",
-                        "path": "posts/1"
-                    }
-                }),
-            )
-            .unwrap();
-
         // TODO: Start builder here?
         // Create a fresh event to apply a a handler on
         let mut event = env
             .create_event(&EventConfig {
                 event: "test_basic".to_string(),
                 handler: None,
+                input: None,
             })
             .unwrap();
 
-        // Assign the client as a handler on the event
+        // Send input to the request event and assign the client as a handler on the event
+        // The Request plugin will "receive" the input before starting in order to apply the template to itself
         event
+            .with_input(serde_json::json! ({
+                "url": {
+                    "host": "jsonplaceholder.typicode.com",
+                    "path": "posts/1"
+                }
+            }))
+            .unwrap()
             .with_handler::<HttpRequestClient>(client_address.clone())
             .unwrap();
 
         // Start the event which will make the request
         // After this returns, retrieve the response from the broker
-       let data =  event.returns().await.unwrap();
-        let data = data.as_json().unwrap();
+        let data = event
+            .returns_typed::<serde_json::Map<String, serde_json::Value>>()
+            .await
+            .unwrap();
         eprintln!("{data:?}");
     }
 
@@ -274,13 +638,7 @@ mod tests {
             .load_env(".test")
             .expect("should be able to load test env");
         // Create a new client plugin to receive the request from
-        let client = ProcessClient::new(|r| {
-            // TODO: Default implementations can be created for this
-            Box::pin(async move {
-                eprintln!("{r:?}");
-                Ok(MessageData::from(Bytes::copy_from_slice(&r.stdout)))
-            })
-        });
+        let client = ProcessClient::default();
         let address = env.state.load_handler(client, Labels::default());
 
         // Create a fresh event to apply a a handler on
@@ -288,10 +646,35 @@ mod tests {
             .create_event(&EventConfig {
                 event: "cargo_help".to_string(),
                 handler: None,
+                input: None,
             })
             .unwrap();
         event.with_handler::<ProcessClient>(address).unwrap();
         let output = event.returns().await.unwrap();
         assert!(output.as_bytes().is_some());
     }
+
+    #[tokio::test]
+    async fn test_engine_run_where() {
+        let mut state = reality::State::new();
+        let mut engine = Engine::with(state.clone());
+        for (program, stage) in [("true", "deploy"), ("false", "setup"), ("true", "deploy")] {
+            let address = state
+                .load_by_toml::<crate::plugins::Process>(
+                    &format!("program = \"{program}\"\nargs = []"),
+                    Labels::from(&[("stage", stage)][..]),
+                )
+                .unwrap();
+            engine.push(state.event(&address).unwrap()).unwrap();
+        }
+
+        // The failing setup event is not run
+        assert_eq!(
+            Ok(2),
+            engine
+                .run_where(|e| e.label("stage") == Some("deploy"))
+                .await
+        );
+        assert!(engine.run().await.is_err());
+    }
 }
@@ -1,12 +1,42 @@
-use crate::plugins::utils::with_cancel;
-use plugin::Bind;
+use plugin::{Address, Bind, Event, Name, WorkSet};
 use reality::*;
+use repr::Labels;
 use runplat_macros::kt_metadata;
 use serde::{Deserialize, Serialize};
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tokio::select;
 
-use super::{Engine, EnvBuilder, EventConfig, Metadata};
+use super::{env::input_data, Engine, Env, EnvBuilder, EventConfig, LoadInput, Metadata};
 
 /// Plugin for executing a list of events
+///
+/// Events can either reference plugins loaded by the env, w/ `events`, or be defined inline w/ `steps`, so that a
+/// single operation file defines its whole workflow,
+///
+/// ```toml
+/// [[steps]]
+/// plugin = "kioto/plugins.request@0.1.0"
+/// settings = { url = "https://example.com" }
+///
+/// [[steps]]
+/// plugin = "kioto/plugins.process@0.1.0"
+/// settings = { program = "echo", args = ["hello"] }
+/// input = { message = "hello" }
+/// ```
+///
+/// Events from `events` are executed before events from `steps`.
+///
+/// The events of an operation are executed in a separate state, so the outputs of events are not visible to the state
+/// the operation was called from. Outputs can be forwarded to plugins in the calling state w/ `forward`,
+///
+/// ```toml
+/// [[forward]]
+/// from = "kioto/plugins.file"
+/// to = "kioto/plugins.process"
+/// ```
 #[kt_metadata(build, loader)]
 #[derive(Plugin, Serialize, Deserialize)]
 #[reality(
@@ -15,10 +45,106 @@ use super::{Engine, EnvBuilder, EventConfig, Metadata};
 )]
 pub struct Operation {
     /// List of event config
+    #[serde(default)]
     events: Vec<EventConfig>,
+    /// List of steps w/ inline plugin settings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    steps: Vec<Step>,
+    /// Maximum number of events executed concurrently
+    ///
+    /// Events are started in order, so the default of 1 executes events sequentially. A value of 0 is treated as 1.
+    ///
+    /// If an event fails no further events are started, events that are already executing are allowed to finish unless
+    /// `abort_on_failure` is set.
+    #[serde(default = "default_parallelism")]
+    parallelism: usize,
+    /// If true, events that are already executing are cancelled once an event fails
+    #[serde(default)]
+    abort_on_failure: bool,
+    /// Outputs of events forwarded to the state the operation was called from, once every event has completed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    forward: Vec<EventForward>,
     /// Engine this sequence is executing
     #[serde(skip)]
     engine: Option<Engine>,
+    /// Timing of each step from the most recent execution
+    #[serde(skip)]
+    timings: Vec<StepTiming>,
+}
+
+/// Step of an operation w/ inline plugin settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Step {
+    /// Plugin reference, i.e. `kioto/plugins.request@0.1.0`
+    pub plugin: String,
+    /// Settings the plugin is loaded w/
+    #[serde(default)]
+    pub settings: toml::Table,
+    /// Input data sent to the step's plugin before the step is executed, see `EventConfig::input`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<toml::Value>,
+    /// Handler applied to the step's event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handler: Option<StepHandler>,
+}
+
+/// Handler of a step w/ inline plugin settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StepHandler {
+    /// Handler plugin reference
+    pub plugin: String,
+    /// Settings the handler is loaded w/
+    #[serde(default)]
+    pub settings: toml::Table,
+}
+
+impl Step {
+    /// Loads the step's plugin, and handler if set, into the env and returns the step's event
+    ///
+    /// Returns an error if a plugin could not be loaded, or if the input is not supported
+    pub fn create_event(&self, env: &mut Env) -> Result<Event> {
+        let address = load_inline(env, &self.plugin, &self.settings)?;
+        let mut event = env.state.event(&address)?;
+        if let Some(handler) = self.handler.as_ref() {
+            let address = load_inline(env, &handler.plugin, &handler.settings)?;
+            let thunk = env.state.handler(&address)?;
+            event.set_handler(address, &thunk)?;
+        }
+        if let Some(input) = input_data(&self.plugin, self.input.as_ref())? {
+            event.with_input(input)?;
+        }
+        Ok(event)
+    }
+}
+
+/// Forwards the output of an event executed by an operation to a plugin in the state the operation was called from
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EventForward {
+    /// Event name from `events`, or a matcher for the address of an event, i.e. `kioto/plugins.request`
+    ///
+    /// If more than one event matches, the output of the first event is forwarded
+    pub from: String,
+    /// Path of the plugin in the calling state the output is sent to, see `Bind::emit_to`
+    pub to: String,
+}
+
+/// Start and end of a step executed by an operation, relative to when the operation started executing steps
+#[derive(Clone, Debug)]
+pub struct StepTiming {
+    /// Address of the step's event
+    pub address: Address,
+    /// Elapsed time before the step started
+    pub start: Duration,
+    /// Elapsed time before the step ended
+    pub end: Duration,
+}
+
+impl StepTiming {
+    /// Returns true if this step was executing at the same time as another step
+    #[inline]
+    pub fn overlaps(&self, other: &StepTiming) -> bool {
+        self.start < other.end && other.start < self.end
+    }
 }
 
 impl Operation {
@@ -27,6 +153,42 @@ impl Operation {
     pub fn take_engine(&mut self) -> Option<Engine> {
         self.engine.take()
     }
+
+    /// Returns the timing of each step from the most recent execution, in the order the steps were configured
+    ///
+    /// **Note**: Steps that were not started because a previous step failed, or because the operation was cancelled,
+    /// are not included
+    #[inline]
+    pub fn steps(&self) -> &[StepTiming] {
+        &self.timings
+    }
+
+    /// Returns the steps w/ inline plugin settings
+    #[inline]
+    pub fn inline_steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Loads the plugins of each inline step into the env and returns the events of the steps, in order
+    ///
+    /// Returns an error if an event could not be created
+    #[inline]
+    pub fn load_steps(&self, env: &mut Env) -> Result<Vec<Event>> {
+        self.steps.iter().map(|s| s.create_event(env)).collect()
+    }
+}
+
+/// Loads a plugin or handler from inline settings into the env
+#[inline]
+fn load_inline(env: &mut Env, plugin: &str, settings: &toml::Table) -> Result<Address> {
+    let name = Name::from_str(plugin)?;
+    let settings = toml::to_string(settings)?;
+    Ok(env.load(&name, LoadInput::Toml(settings), Labels::default())?)
+}
+
+/// Returns the default parallelism of an operation
+fn default_parallelism() -> usize {
+    1
 }
 
 fn execute_operation(mut binding: Bind<Operation>) -> CallResult {
@@ -39,23 +201,102 @@ fn execute_operation(mut binding: Bind<Operation>) -> CallResult {
         .unwrap_or_else(|| ("default".to_string(), std::env::current_dir()));
 
     // Build the engine if it hasn't already been built
-    let loader = EnvBuilder::default_env(env).load_env(root_dir?)?;
-    let mut engine = Engine::with(loader.state.clone());
+    let mut loader = EnvBuilder::default_env(env).load_env(root_dir?)?;
+    let mut events = vec![];
     for e in binding.receiver()?.events.iter() {
-        let event = loader.create_event(e)?;
+        events.push(loader.create_event(e)?);
+    }
+    events.extend(binding.receiver()?.load_steps(&mut loader)?);
+
+    let mut engine = Engine::with(loader.state.clone());
+    for event in events {
         engine.push(event)?;
     }
+
+    // Resolve the commit of each forwarded event before the events are executed
+    let mut forwards = vec![];
+    for forward in binding.receiver()?.forward.iter() {
+        let commit = match loader.config.event(&forward.from, &loader) {
+            Ok((address, _)) => address.commit(),
+            Err(_) => engine
+                .find_event(&forward.from)
+                .map(|e| e.address().commit())
+                .ok_or_else(|| {
+                    binding.plugin_call_error(format!(
+                        "Could not find event `{}` to forward",
+                        forward.from
+                    ))
+                })?,
+        };
+        forwards.push((commit, forward.to.clone()));
+    }
+    let child_broker = loader.state.broker().clone();
     binding.update()?.engine = Some(engine);
-    binding.defer(|i, ct| async move {
-        match i.receiver()?.engine.as_ref() {
-            Some(engine) => {
-                for e in engine.events.iter() {
-                    let (f, _) = e.fork();
-                    with_cancel(ct.clone()).run(f.start()).await??;
+    // Each step is forked from its event and spawned as work in a work set owned by the operation, cancelling the
+    // operation cancels the work set, which cancels the call of every step that is executing
+    binding.defer(|mut i, ct| async move {
+        let events = match i.receiver()?.engine.as_ref() {
+            Some(engine) => engine.events.clone(),
+            None => return Err(reality::Error::PluginCallSkipped),
+        };
+
+        let parallelism = i.receiver()?.parallelism.max(1);
+        let abort_on_failure = i.receiver()?.abort_on_failure;
+        let started = Instant::now();
+        let mut pending = events.iter();
+        let mut running = WorkSet::new();
+        let mut starts = vec![];
+        let mut steps = vec![];
+        let mut result = Ok(());
+        let mut cancelled = false;
+        loop {
+            // Steps are started in order, no further steps are started once a step has failed
+            while result.is_ok() && !ct.is_cancelled() && running.len() < parallelism {
+                let Some(e) = pending.next() else {
+                    break;
+                };
+                let (f, _) = e.fork();
+                starts.push(started.elapsed());
+                running.push(f.spawn());
+                // Yield between steps so that starting many steps does not starve the steps already running
+                tokio::task::yield_now().await;
+            }
+
+            select! {
+                joined = running.join_next() => {
+                    let Some((index, joined)) = joined else {
+                        break;
+                    };
+                    steps.push((index, StepTiming {
+                        address: events[index].address().clone(),
+                        start: starts[index],
+                        end: started.elapsed(),
+                    }));
+                    if joined.is_err() && result.is_ok() && abort_on_failure {
+                        running.cancel_all();
+                    }
+                    result = result.and(joined);
+                },
+                _ = ct.cancelled(), if !cancelled => {
+                    cancelled = true;
+                    running.cancel_all();
                 }
-                Ok(())
             }
-            None => Err(reality::Error::PluginCallSkipped),
         }
+        steps.sort_by_key(|(index, _)| *index);
+        i.update()?.timings = steps.into_iter().map(|(_, timing)| timing).collect();
+        if ct.is_cancelled() {
+            return Err(i.plugin_call_cancelled());
+        }
+        result?;
+
+        // Outputs are only forwarded once every event has completed successfully
+        for (commit, to) in forwards {
+            let data = child_broker.receive(commit);
+            if !data.is_empty() {
+                i.emit_to(to, data)?;
+            }
+        }
+        Ok(())
     })
 }
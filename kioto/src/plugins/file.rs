@@ -0,0 +1,254 @@
+use std::path::{Path, PathBuf};
+
+use bytes::{Bytes, BytesMut};
+use reality::{plugin::MessageData, *};
+use runplat_macros::kt_metadata;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use super::utils::with_cancel;
+
+/// Size of each chunk read or written, cancellation is checked between chunks
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Mode of the file plugin
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileMode {
+    /// Reads the file and sends the contents to the target
+    #[default]
+    Read,
+    /// Writes received message data to the file, replacing any existing contents
+    Write,
+    /// Appends received message data to the file
+    Append,
+}
+
+/// Plugin for reading and writing files
+///
+/// In `read` mode, the file contents are sent as `MessageData::Bytes` to the plugin at `target`, or to this plugin's
+/// commit id if `target` is not set.
+///
+/// In `write` or `append` mode, the plugin writes the message data it received before it was called. JSON and TOML
+/// message data is serialized before it is written, and a store item is written if its resource is a `String`.
+///
+/// **Note**: Received streams are ignored, since a stream cannot be read until the plugin is called
+///
+/// **Note**: In dry-run mode the file is still read, but received message data is not written
+#[kt_metadata(loader)]
+#[derive(Clone, Serialize, Deserialize, Resource)]
+pub struct File {
+    /// Path of the file
+    path: PathBuf,
+    /// File mode
+    #[serde(default)]
+    mode: FileMode,
+    /// Path of the plugin to send file contents to in read mode
+    target: Option<String>,
+    /// Message data received to write
+    #[serde(skip)]
+    data: Option<Bytes>,
+}
+
+impl Plugin for File {
+    fn kind(&self) -> plugin::PluginKind {
+        match self.mode {
+            FileMode::Read => plugin::PluginKind::Source,
+            FileMode::Write | FileMode::Append => plugin::PluginKind::Sink,
+        }
+    }
+
+    fn receive(&self, data: MessageData) -> Option<Self> {
+        if self.mode == FileMode::Read {
+            return None;
+        }
+
+        let data = match data {
+            MessageData::Bytes(bytes) => bytes,
+            MessageData::Json(json) => serde_json::to_vec(&json).ok()?.into(),
+            MessageData::Toml(toml) => toml::to_string(&toml).ok()?.into(),
+            MessageData::Item(item) => match item.borrow::<String>() {
+                Some(text) => Bytes::copy_from_slice(text.as_bytes()),
+                None => {
+                    debug!("Ignoring received item, the resource is not a string");
+                    return None;
+                }
+            },
+            MessageData::Stream(_) => {
+                debug!("Ignoring received stream, streams cannot be written");
+                return None;
+            }
+            MessageData::Empty => {
+                debug!("Ignoring received empty message data");
+                return None;
+            }
+        };
+
+        let mut next = self.clone();
+        debug!("Received {} bytes to write to {:?}", data.len(), self.path);
+        next.data = Some(data);
+        Some(next)
+    }
+
+    fn call(bind: plugin::Bind<Self>) -> CallResult {
+        bind.defer(|binding, ct| async move {
+            let p = binding.receiver()?;
+            match p.mode {
+                FileMode::Read => {
+                    let data = read_chunked(&p.path, ct).await?;
+                    match p.target.as_ref() {
+                        Some(target) => binding.emit_to(target, data),
+                        None => binding.emit(data),
+                    }
+                }
+                FileMode::Write | FileMode::Append => match p.data.as_ref() {
+                    Some(data) if binding.is_dry_run() => {
+                        debug!(
+                            "Dry run, skipping write of {} bytes to {:?}",
+                            data.len(),
+                            p.path
+                        );
+                        Ok(())
+                    }
+                    Some(data) => {
+                        write_chunked(&p.path, data, p.mode == FileMode::Append, ct).await
+                    }
+                    None => Err(binding.plugin_call_error("No message data was received to write")),
+                },
+            }
+        })
+    }
+
+    fn version() -> Version {
+        Version::new(0, 1, 0)
+    }
+}
+
+impl Content for File {
+    fn state_uuid(&self) -> uuid::Uuid {
+        BincodeContent::new(self).unwrap().state_uuid()
+    }
+}
+
+/// Reads a file in chunks, returns an error if cancelled before the file is read
+async fn read_chunked(path: &Path, ct: CancellationToken) -> reality::Result<Bytes> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut data = BytesMut::new();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        let read = with_cancel(ct.clone()).run(file.read(&mut chunk)).await??;
+        if read == 0 {
+            break;
+        }
+        data.extend_from_slice(&chunk[..read]);
+    }
+    Ok(data.freeze())
+}
+
+/// Writes data to a file in chunks, returns an error if cancelled before all data is written
+async fn write_chunked(
+    path: &Path,
+    data: &[u8],
+    append: bool,
+    ct: CancellationToken,
+) -> reality::Result<()> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .await?;
+    for chunk in data.chunks(CHUNK_SIZE) {
+        with_cancel(ct.clone()).run(file.write_all(chunk)).await??;
+    }
+    with_cancel(ct).run(file.flush()).await??;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reality::repr::Labels;
+
+    #[tokio::test]
+    async fn test_file_plugin() {
+        let dir = PathBuf::from(".test").join("test_file_plugin");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("output.txt");
+
+        let mut state = State::new();
+        let writer = state
+            .load_by_toml::<File>(
+                &format!("path = {path:?}\nmode = \"write\""),
+                Labels::default(),
+            )
+            .unwrap();
+        let appender = state
+            .load_by_toml::<File>(
+                &format!("path = {path:?}\nmode = \"append\""),
+                Labels::default(),
+            )
+            .unwrap();
+        let reader = state
+            .load_by_toml::<File>(&format!("path = {path:?}"), Labels::default())
+            .unwrap();
+
+        // Fails w/o received message data
+        assert!(state.call(&writer).await.is_err());
+
+        state
+            .broker()
+            .send(writer.commit(), Bytes::from_static(b"hello"))
+            .unwrap();
+        state.call(&writer).await.unwrap();
+        state
+            .broker()
+            .send(appender.commit(), Bytes::from_static(b" world"))
+            .unwrap();
+        state.call(&appender).await.unwrap();
+        assert_eq!("hello world", std::fs::read_to_string(&path).unwrap());
+
+        // Items w/ a string resource are written
+        let mut store = runir::Store::new();
+        let handle = store.put(String::from("!")).commit();
+        state
+            .broker()
+            .send(
+                appender.commit(),
+                store.item(handle.commit()).unwrap().clone(),
+            )
+            .unwrap();
+        state.call(&appender).await.unwrap();
+        assert_eq!("hello world!", std::fs::read_to_string(&path).unwrap());
+
+        state.call(&reader).await.unwrap();
+        let data = state.broker().receive(reader.commit());
+        assert_eq!(Some(&Bytes::from_static(b"hello world!")), data.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_file_plugin_dry_run() {
+        let dir = PathBuf::from(".test").join("test_file_plugin_dry_run");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("output.txt");
+
+        let mut state = State::new();
+        let writer = state
+            .load_by_toml::<File>(
+                &format!("path = {path:?}\nmode = \"write\""),
+                Labels::from(&[(plugin::DRY_RUN_LABEL, "true")][..]),
+            )
+            .unwrap();
+        state
+            .broker()
+            .send(writer.commit(), Bytes::from_static(b"hello"))
+            .unwrap();
+        state.call(&writer).await.unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -1,8 +1,22 @@
+mod file;
 mod repl;
+#[cfg(feature = "process")]
 mod process;
+#[cfg(feature = "http")]
 mod request;
 
 pub mod utils;
+pub use file::File;
+pub use file::FileMode;
+#[cfg(feature = "process")]
 pub use process::Process;
+#[cfg(feature = "http")]
 pub use request::Request;
+#[cfg(feature = "http")]
 pub use request::RequestArgs;
+pub use repl::inspect;
+pub use repl::inspect_command;
+pub use repl::inspect_debug;
+pub use repl::Repl;
+pub use repl::ReplEval;
+pub use repl::ReplInterface;
@@ -1,27 +1,66 @@
-use std::{path::PathBuf, process::Output};
+use std::{
+    path::PathBuf,
+    process::{ExitStatus, Output, Stdio},
+};
 
-use reality::*;
+use bytes::Bytes;
+use reality::{plugin::MessageData, *};
 use runplat_macros::kt_metadata;
 use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
 use tracing::debug;
 
 use crate::engine::Metadata;
 
-use super::utils::with_cancel;
+use super::utils::{with_cancel, TemplateField};
 
 /// Plugin for starting a process
+///
+/// By default, message data received before the plugin is called is applied as a template. If `stdin` is set, the
+/// message data is instead written to the process's stdin, which is closed once all of the data is written. The output
+/// is read while the data is written, so a process that writes output before reading all of its input does not block.
+/// JSON and TOML message data is serialized before it is written.
+///
+/// The program is started directly w/ `args`, so arguments are never parsed by a shell. Each argument can be a mustache
+/// template, which is rendered from received JSON or TOML message data, i.e. `args = ["--name", "{{{name}}}"]`. Use
+/// triple braces so that the rendered value is not HTML escaped.
+///
+/// Setting `shell` opts in to starting the program through a shell, i.e. `sh -c <program> <args>`. The program is
+/// passed to the shell as a command line and the arguments are quoted and appended to it. This is the unsafe choice,
+/// since the command line is interpreted by the shell, and should only be used when shell features are required.
+///
+/// If the process exits w/ a non-zero code that is not in `allowed_codes`, the call returns an error w/ the code and
+/// the captured stderr. Set `fail_on_nonzero = false` to keep the output so that it is forwarded regardless.
+///
+/// **Note**: In dry-run mode the process is not started, and the output is set to an empty successful output
 #[kt_metadata(loader)]
-#[derive(Serialize, Deserialize, Resource)]
+#[derive(Clone, Serialize, Deserialize, Resource)]
 pub struct Process {
     /// Program to start a process for
     program: String,
-    /// Program arguments
-    args: Vec<String>,
+    /// Program arguments, each argument can be a template rendered from received message data
+    #[serde(default)]
+    args: Vec<TemplateField<String>>,
+    /// If set, the shell used to start the program, i.e. `sh`, see the `Process` docs before opting in
+    shell: Option<String>,
     /// Env variables
     #[serde(default)]
     env: Vec<[String; 2]>,
     /// Bin dir to find the program from
     bin_dir: Option<PathBuf>,
+    /// If true, received message data is written to stdin instead of being applied as a template
+    #[serde(default)]
+    stdin: bool,
+    /// If true, the call returns an error when the process exits unsuccessfully, otherwise the output is kept so that
+    /// it can be forwarded regardless of the exit code
+    #[serde(default = "default_fail_on_nonzero")]
+    fail_on_nonzero: bool,
+    /// Non-zero exit codes that are treated as success
+    #[serde(default)]
+    allowed_codes: Vec<i32>,
+    /// Message data received to write to stdin
+    #[serde(skip)]
+    input: Option<Bytes>,
     /// Output of the process
     #[serde(skip)]
     output: Option<Output>
@@ -33,11 +72,82 @@ impl Process {
     pub fn take_output(&mut self) -> Option<Output> {
         self.output.take()
     }
+
+    /// Returns a copy of this process w/ templated arguments rendered from message data
+    ///
+    /// Returns None if no arguments are templates, or if an argument could not be rendered
+    fn render_args(&self, data: &MessageData) -> Option<Self> {
+        if !self.args.iter().any(|a| a.is_template()) {
+            return None;
+        }
+
+        let mut next = self.clone();
+        for (i, arg) in next.args.iter_mut().enumerate() {
+            let rendered = match data {
+                MessageData::Json(json) => arg.render(&format!("args[{i}]"), json),
+                MessageData::Toml(toml) => arg.render(&format!("args[{i}]"), toml),
+                _ => return None,
+            };
+            if let Err(err) = rendered {
+                debug!("Could not render process argument, {err}");
+                return None;
+            }
+        }
+        Some(next)
+    }
+
+    /// Returns the rendered program arguments
+    ///
+    /// Returns an error w/ the index of the first argument that is a template that has not been rendered
+    fn rendered_args(&self) -> std::result::Result<Vec<&str>, String> {
+        self.args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| {
+                arg.as_inner()
+                    .map(|a| a.as_str())
+                    .ok_or_else(|| format!("Template argument `args[{i}]` has not been rendered"))
+            })
+            .collect()
+    }
+
+    /// Returns true if the exit status counts as success, either because the process exited successfully or because
+    /// the exit code is one of the allowed codes
+    #[inline]
+    fn is_success(&self, status: &ExitStatus) -> bool {
+        status.success()
+            || status
+                .code()
+                .is_some_and(|code| self.allowed_codes.contains(&code))
+    }
+}
+
+/// By default, a process exiting w/ a non-zero code is an error
+fn default_fail_on_nonzero() -> bool {
+    true
 }
 
 impl Plugin for Process {
-    fn receive(&self, data: plugin::MessageData) -> Option<Self> {
-        self.apply_template(data).ok().inspect(|_| debug!("Applying template to process"))
+    fn receive(&self, data: MessageData) -> Option<Self> {
+        if !self.stdin {
+            if let Some(next) = self.render_args(&data) {
+                debug!("Rendered process arguments");
+                return Some(next);
+            }
+            return self.apply_template(data).ok().inspect(|_| debug!("Applying template to process"));
+        }
+
+        let data = match data {
+            MessageData::Bytes(bytes) => bytes,
+            MessageData::Json(json) => serde_json::to_vec(&json).ok()?.into(),
+            MessageData::Toml(toml) => toml::to_string(&toml).ok()?.into(),
+            _ => return None,
+        };
+
+        let mut next = self.clone();
+        debug!("Received {} bytes to write to process stdin", data.len());
+        next.input = Some(data);
+        Some(next)
     }
 
     fn call(bind: plugin::Bind<Self>) -> CallResult {
@@ -46,38 +156,92 @@ impl Plugin for Process {
             return bind.skip();
         }
 
+        if bind.is_dry_run() {
+            debug!("Dry run, skipping process");
+            return bind.defer(|mut binding, _| async move {
+                binding.update()?.output = Some(Output {
+                    status: Default::default(),
+                    stdout: vec![],
+                    stderr: vec![],
+                });
+                Ok(())
+            });
+        }
+
         bind.defer(|mut binding, ct| async move {
             let p = binding.receiver()?;
-            let mut command = if let Some(bin_dir) = p.bin_dir.as_ref() {
-                tokio::process::Command::new(bin_dir.join(&p.program))
-            } else {
-                tokio::process::Command::new(&p.program)
+            let program = match p.bin_dir.as_ref() {
+                Some(bin_dir) => bin_dir.join(&p.program),
+                None => PathBuf::from(&p.program),
             };
+            let args = p
+                .rendered_args()
+                .map_err(|e| binding.plugin_call_error(e))?;
 
-            let checked = shlex::try_join(p.args.iter().map(|s| s.as_str()))
-                .map_err(|e| binding.plugin_call_error(e.to_string()))?;
-            if let Some(args) = shlex::split(&checked) {
+            let mut command = if let Some(shell) = p.shell.as_ref() {
+                // **Note**: Only the arguments are quoted, the program is interpreted by the shell as-is
+                let quoted =
+                    shlex::try_join(args).map_err(|e| binding.plugin_call_error(e.to_string()))?;
+                let line = format!("{} {quoted}", program.to_string_lossy());
+                debug!(shell, "process_shell_invocation");
+                let mut command = tokio::process::Command::new(shell);
+                command.arg("-c").arg(line.trim_end());
+                command
+            } else {
+                let mut command = tokio::process::Command::new(program);
                 command.args(args);
-            }
+                command
+            };
 
             for [k, v] in p.env.iter() {
                 debug!("Setting env variable {k}");
                 command.env(k, v);
             }
 
+            // The child is killed if the call is cancelled before the process exits
+            command
+                .stdin(if p.input.is_some() { Stdio::piped() } else { Stdio::null() })
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true);
+            let input = p.input.clone();
             let output = with_cancel(ct)
-                .run(command.output())
+                .run(async move {
+                    let mut child = command.spawn()?;
+                    let stdin = child.stdin.take();
+                    let write = async move {
+                        if let (Some(input), Some(mut stdin)) = (input, stdin) {
+                            debug!("Writing {} bytes to process stdin", input.len());
+                            match stdin.write_all(&input).await {
+                                // The process exited or closed stdin w/o reading all of the input
+                                Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => {}
+                                result => result?,
+                            }
+                            // Dropping stdin closes it so that the process receives EOF
+                            drop(stdin);
+                        }
+                        Ok::<_, std::io::Error>(())
+                    };
+                    // **Note**: The output must be read while stdin is written, otherwise a process that fills its
+                    // output pipes before reading all of its input never exits
+                    let (written, output) = tokio::join!(write, child.wait_with_output());
+                    written?;
+                    output
+                })
                 .await??;
 
-            let status = output.status;
-            binding.update()?.output = Some(output);
-            if status.success() {
+            let p = binding.receiver()?;
+            if !p.fail_on_nonzero || p.is_success(&output.status) {
+                binding.update()?.output = Some(output);
                 Ok(())
             } else {
-                Err(binding.plugin_call_error(format!(
-                    "process exited unsuccessfully, status code: {}",
-                    status.code().unwrap_or(1)
-                )))
+                let message = format!(
+                    "process exited unsuccessfully, status code: {}, stderr: {}",
+                    output.status.code().unwrap_or(1),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                binding.update()?.output = Some(output);
+                Err(binding.plugin_call_error(message))
             }
         })
     }
@@ -92,3 +256,171 @@ impl Content for Process {
         BincodeContent::new(self).unwrap().state_uuid()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::plugins::utils::ProcessClient;
+    use reality::repr::Labels;
+
+    #[tokio::test]
+    async fn test_process_plugin_stdin() {
+        let mut state = State::new();
+        let process = state
+            .load_by_toml::<Process>(
+                "program = \"cat\"\nargs = []\nstdin = true",
+                Labels::default(),
+            )
+            .unwrap();
+        let client = state.load_handler(ProcessClient::default(), Labels::default());
+
+        let mut event = state.event(&process).unwrap();
+        event
+            .with_input(Bytes::from_static(b"hello world"))
+            .unwrap()
+            .with_handler::<ProcessClient>(client)
+            .unwrap();
+        let output = event.returns().await.unwrap();
+        assert_eq!(Some(&Bytes::from_static(b"hello world")), output.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_process_plugin_stdin_larger_than_pipe_buffer() {
+        let mut state = State::new();
+        let process = state
+            .load_by_toml::<Process>(
+                "program = \"cat\"\nargs = []\nstdin = true",
+                Labels::default(),
+            )
+            .unwrap();
+        let client = state.load_handler(ProcessClient::default(), Labels::default());
+
+        // Larger than the pipe buffers, so `cat` blocks on writing stdout until the output is read
+        let input = Bytes::from(vec![b'a'; 1024 * 1024]);
+        let mut event = state.event(&process).unwrap();
+        event
+            .with_input(input.clone())
+            .unwrap()
+            .with_handler::<ProcessClient>(client)
+            .unwrap()
+            .with_timeout(Duration::from_secs(10));
+        let output = event.returns().await.unwrap();
+        assert_eq!(Some(&input), output.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_process_plugin_exit_codes() {
+        let mut state = State::new();
+        let program = "program = \"sh\"\nargs = [\"-c\", \"echo out; echo err >&2; exit 3\"]";
+        let failing = state
+            .load_by_toml::<Process>(program, Labels::default())
+            .unwrap();
+        match state.call(&failing).await {
+            Err(Error::PluginCallError { message, .. }) => {
+                assert!(message.contains("status code: 3"));
+                assert!(message.contains("stderr: err"));
+            }
+            result => panic!("expected a plugin call error, got {result:?}"),
+        }
+
+        let forwarded = state
+            .load_by_toml::<Process>(
+                &format!("{program}\nfail_on_nonzero = false"),
+                Labels::default(),
+            )
+            .unwrap();
+        let client = state.load_handler(ProcessClient::default(), Labels::default());
+        let mut event = state.event(&forwarded).unwrap();
+        event.with_handler::<ProcessClient>(client).unwrap();
+        let output = event.returns().await.unwrap();
+        assert_eq!(Some(&Bytes::from_static(b"out\n")), output.as_bytes());
+
+        let allowed = state
+            .load_by_toml::<Process>(
+                &format!("{program}\nallowed_codes = [3]"),
+                Labels::default(),
+            )
+            .unwrap();
+        state.call(&allowed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_process_plugin_args_not_shell_parsed() {
+        let mut state = State::new();
+        let process = state
+            .load_by_toml::<Process>(
+                "program = \"echo\"\nargs = [\"$HOME; echo injected\", \"{{{name}}}\"]",
+                Labels::default(),
+            )
+            .unwrap();
+        let client = state.load_handler(ProcessClient::default(), Labels::default());
+
+        let mut event = state.event(&process).unwrap();
+        event
+            .with_input(serde_json::json!({ "name": "a 'quoted' name" }))
+            .unwrap()
+            .with_handler::<ProcessClient>(client)
+            .unwrap();
+        let output = event.returns().await.unwrap();
+        assert_eq!(
+            Some(&Bytes::from_static(
+                b"$HOME; echo injected a 'quoted' name\n"
+            )),
+            output.as_bytes()
+        );
+
+        // A template argument that was not rendered is an error
+        let unrendered = state
+            .load_by_toml::<Process>(
+                "program = \"echo\"\nargs = [\"{{name}}\"]",
+                Labels::default(),
+            )
+            .unwrap();
+        match state.call(&unrendered).await {
+            Err(Error::PluginCallError { message, .. }) => assert!(message.contains("`args[0]`")),
+            result => panic!("expected a plugin call error, got {result:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_plugin_shell() {
+        let mut state = State::new();
+        let process = state
+            .load_by_toml::<Process>(
+                "program = \"printf '%s-'\"\nargs = [\"; echo injected\", \"$HOME\"]\nshell = \"sh\"",
+                Labels::default(),
+            )
+            .unwrap();
+        let client = state.load_handler(ProcessClient::default(), Labels::default());
+
+        let mut event = state.event(&process).unwrap();
+        event.with_handler::<ProcessClient>(client).unwrap();
+        let output = event.returns().await.unwrap();
+        assert_eq!(
+            Some(&Bytes::from_static(b"; echo injected-$HOME-")),
+            output.as_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_plugin_stdin_cancelled() {
+        let mut state = State::new();
+        let process = state
+            .load_by_toml::<Process>(
+                "program = \"sleep\"\nargs = [\"10\"]\nstdin = true",
+                Labels::default(),
+            )
+            .unwrap();
+
+        let mut event = state.event(&process).unwrap();
+        event
+            .with_input(Bytes::from_static(b"ignored"))
+            .unwrap()
+            .with_timeout(Duration::from_millis(100));
+        let started = Instant::now();
+        assert!(event.start().await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
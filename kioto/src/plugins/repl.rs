@@ -1,8 +1,11 @@
 use clap::ArgMatches;
 use reality::{
-    plugin::{Bind, Handler},
-    CallResult, Content, Plugin, Repr, Resource, Uuid,
+    plugin::{Bind, CancelReason, Handler, Thunk},
+    repr::Labels,
+    CallResult, Content, Plugin, Repr, Resource, State, Uuid,
 };
+use serde::Serialize;
+use std::fmt::Debug;
 
 use super::utils::with_cancel;
 
@@ -26,8 +29,7 @@ impl<T: Plugin + ReplEval> Plugin for Repl<T> {
                     let (repl, target_bind) = _target_repl.clone();
                     // READ
                     let read_bind = target_bind.clone();
-                    let read = target_bind.runtime().clone();
-                    let read = read.spawn_blocking(move || {
+                    let read = target_bind.blocking(move || {
                         if let Some(Ok(line)) = std::io::stdin().lines().next() {
                             // EVAL
                             if let Some(args) = shlex::split(&line) {
@@ -35,7 +37,9 @@ impl<T: Plugin + ReplEval> Plugin for Repl<T> {
                                 (repl.eval)(matches, &read_bind)?;
                                 Ok(true)
                             } else {
-                                Err(reality::Error::PluginCallCancelled)
+                                Err(reality::Error::PluginCallCancelled {
+                                    reason: CancelReason::User,
+                                })
                             }
                         } else {
                             Ok(false)
@@ -43,16 +47,15 @@ impl<T: Plugin + ReplEval> Plugin for Repl<T> {
                     });
 
                     let result = with_cancel(ct)
-                        .run(
-                            async move {
-                                let should_eval = read.await?;
-                                if should_eval? {
-                                    T::call(target_bind)?.await
-                                } else {
-                                    Err(reality::Error::PluginCallCancelled)
-                                }
-                            },
-                        )
+                        .run(async move {
+                            if read.await? {
+                                T::call(target_bind)?.await
+                            } else {
+                                Err(reality::Error::PluginCallCancelled {
+                                    reason: CancelReason::User,
+                                })
+                            }
+                        })
                         .await?;
 
                     // LOOP -- TODO, better error handling
@@ -118,6 +121,12 @@ impl<T: Plugin + ReplEval> ReplInterface<T> {
     }
 }
 
+impl<T: Plugin + ReplEval> Default for ReplInterface<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub trait ReplEval: Plugin {
     /// Command that configures the repl
     fn command() -> clap::Command;
@@ -126,6 +135,65 @@ pub trait ReplEval: Plugin {
     fn eval(next: ArgMatches, call: &Bind<Self>) -> reality::Result<()>;
 }
 
+/// Returns the `inspect <path>` command which can be added as a subcommand by `ReplEval::command`
+///
+/// Use `inspect` to evaluate the matches for this command
+#[inline]
+pub fn inspect_command() -> clap::Command {
+    clap::Command::new("inspect")
+        .about("Prints the serialized state and attributes of a plugin")
+        .arg(clap::Arg::new("path").required(true))
+}
+
+/// Finds the plugin at `path` and returns its attributes (name, labels, thunk) and its serialized form as TOML
+///
+/// **Note**: The plugin is only serialized if it is of type `T`, otherwise only attributes are returned. Use
+/// `inspect_debug` for plugins that do not implement `Serialize`.
+///
+/// Returns `Error::PluginNotFound` if a plugin could not be found at `path`
+pub fn inspect<T: Plugin + Serialize>(state: &State, path: &str) -> reality::Result<String> {
+    inspect_with::<T>(state, path, |plugin| Ok(toml::to_string_pretty(plugin)?))
+}
+
+/// Finds the plugin at `path` and returns its attributes (name, labels, thunk) and its `Debug` form, see `inspect`
+///
+/// Returns `Error::PluginNotFound` if a plugin could not be found at `path`
+pub fn inspect_debug<T: Plugin + Debug>(state: &State, path: &str) -> reality::Result<String> {
+    inspect_with::<T>(state, path, |plugin| Ok(format!("{plugin:#?}\n")))
+}
+
+/// Finds the plugin at `path` and returns its attributes followed by the view of the plugin returned by `view`
+fn inspect_with<T: Plugin>(
+    state: &State,
+    path: &str,
+    view: impl FnOnce(&T) -> reality::Result<String>,
+) -> reality::Result<String> {
+    let item = state
+        .find_plugin(path)
+        .ok_or(reality::Error::PluginNotFound)?;
+    let attributes = item.attributes();
+
+    let mut output = format!("# {path}\n");
+    if let Some(thunk) = attributes.get::<Thunk>() {
+        output.push_str(&format!("# name = {}\n", thunk.name()));
+        output.push_str(&format!("# thunk = {}\n", thunk.name().full_plugin_ref()));
+    }
+    if let Some(labels) = attributes.get::<Labels>() {
+        for (k, v) in labels.iter() {
+            output.push_str(&format!("# label.{k} = {v}\n"));
+        }
+    }
+
+    match item.borrow::<T>() {
+        Some(plugin) => output.push_str(&view(plugin)?),
+        None => output.push_str(&format!(
+            "# plugin is not of type {}\n",
+            std::any::type_name::<T>()
+        )),
+    }
+    Ok(output)
+}
+
 impl<T: Plugin> Resource for ReplInterface<T> {}
 impl<T: Plugin> Repr for ReplInterface<T> {}
 impl<T: Plugin> Content for ReplInterface<T> {
@@ -136,10 +204,13 @@ impl<T: Plugin> Content for ReplInterface<T> {
 
 #[cfg(test)]
 mod tests {
-    use super::ReplEval;
+    use super::{inspect, inspect_command, inspect_debug, ReplEval};
     use crate::plugins::repl::Repl;
     use clap::{Arg, ArgAction};
-    use reality::{repr::Labels, CallResult, Content, Plugin, Resource, State, Uuid};
+    use reality::{
+        plugin::CancelReason, repr::Labels, CallResult, Content, Plugin, Resource, State, Uuid,
+    };
+    use serde::Serialize;
     use tokio::io::AsyncWriteExt;
 
     #[ignore = "would block"]
@@ -159,7 +230,40 @@ mod tests {
         event.start().await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_repl_inspect() {
+        let mut state = State::new();
+        let address = state.load(
+            Echo {
+                message: String::from("hello world"),
+            },
+            Labels::default(),
+        );
+
+        let output = inspect::<Echo>(&state, &address.to_string()).unwrap();
+        assert!(output.contains(&format!("# name = {}", Echo::name())));
+        assert!(output.contains(r#"message = "hello world""#));
+
+        assert_eq!(
+            reality::Error::PluginNotFound,
+            inspect::<Echo>(&state, "missing/plugin").unwrap_err()
+        );
+
+        let output = inspect_debug::<Echo>(&state, &address.to_string()).unwrap();
+        assert!(output.contains(&format!("# name = {}", Echo::name())));
+        assert!(output.contains(r#"message: "hello world""#));
+
+        let matches = inspect_command()
+            .try_get_matches_from(["inspect", "echo/plugins.echo"])
+            .unwrap();
+        assert_eq!(
+            Some(&String::from("echo/plugins.echo")),
+            matches.get_one::<String>("path")
+        );
+    }
+
     ///
+    #[derive(Debug, Serialize)]
     struct Echo {
         message: String,
     }
@@ -196,6 +300,7 @@ mod tests {
                             .value_delimiter(' '),
                     ),
                 )
+                .subcommand(inspect_command())
                 .subcommand(clap::Command::new("exit"))
                 .multicall(true)
         }
@@ -212,16 +317,28 @@ mod tests {
                                 message.collect::<Vec<_>>().join(" ");
                             Ok(())
                         } else {
-                            Err(reality::Error::PluginCallCancelled)
+                            Err(reality::Error::PluginCallCancelled {
+                                reason: CancelReason::User,
+                            })
+                        }
+                    }
+                    ("inspect", matches) => {
+                        if let Some(path) = matches.get_one::<String>("path") {
+                            eprintln!("{}", inspect::<Echo>(call.state(), path)?);
                         }
+                        Ok(())
                     }
-                    ("exit", _) => Err(reality::Error::PluginCallCancelled),
+                    ("exit", _) => Err(reality::Error::PluginCallCancelled {
+                        reason: CancelReason::User,
+                    }),
                     _ => {
                         Echo::command().print_help().unwrap();
                         Ok(())
                     }
                 },
-                None => Err(reality::Error::PluginCallCancelled),
+                None => Err(reality::Error::PluginCallCancelled {
+                    reason: CancelReason::User,
+                }),
             }
         }
     }
@@ -1,15 +1,33 @@
-use super::utils::{with_cancel, PluginCommands, TemplateField};
+use super::utils::{
+    connect_proxy, connect_tunnel, with_cancel, CookieJar, LimitedBody, MultipartBody,
+    MultipartPart, Paginate, PluginCommands, ProxyConfig, ResponseLimit, Retry, TemplateField,
+    TunnelStream, DEFAULT_MAX_RESPONSE_BYTES,
+};
 use crate::{engine::Metadata, kt_metadata};
 use bytes::{Bytes, BytesMut};
 use clap::Args;
-use http_body_util::combinators::BoxBody;
-use hyper::{body::Incoming, header, Response};
+use http_body_util::{combinators::BoxBody, BodyExt};
+use hyper::{body::Incoming, header, HeaderMap, Response};
 use hyper_util::rt::{TokioExecutor, TokioIo};
-use reality::{plugin::Bind, BincodeContent, CallResult, Content, Plugin, Resource, Uuid, Version};
+use reality::{
+    plugin::{Bind, MessageData, PluginKind},
+    BincodeContent, CallResult, Content, Plugin, Resource, Uuid, Version,
+};
 use serde::{Deserialize, Serialize};
-use std::{future::Future, path::PathBuf, pin::Pin, sync::OnceLock, task::Poll};
+use std::{
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    task::Poll,
+    time::{Duration, Instant},
+};
 use tokio::{net::TcpStream, select};
-use tracing::{debug, error, trace, warn};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, trace, warn};
 use url::Url;
 
 /// Type-alias for the default result type returned by this plugin's plumbing
@@ -18,9 +36,29 @@ type Result<T> = std::io::Result<T>;
 /// Type-alias for the default http client
 type DefaultClient = ClientHelper<Body>;
 
+/// Default number of times to retry establishing a connection
+const DEFAULT_CONNECT_RETRIES: u32 = 2;
+
+/// Delay before the first connection retry, doubled for each following retry
+const CONNECT_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Maximum delay between connection retries
+const MAX_CONNECT_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Maximum time to wait for a retained connection to be ready to send a follow-up request
+///
+/// If the connection is not ready in time, e.g. the previous response body has not been read, a new connection is used
+const KEEP_ALIVE_READY_TIMEOUT: Duration = Duration::from_millis(100);
+
 /// Type-alias for the default request builder
 type RequestBuilder = hyper::http::request::Builder;
 
+/// Type-alias for the handle used to send requests on an http/1.1 connection
+type Http1Sender<B> = hyper::client::conn::http1::SendRequest<B>;
+
+/// Type-alias for a connection retained for the key of its authority
+type RetainedConnection<B> = (String, Http1Sender<B>);
+
 /// Type-alias for a hyper body
 type Body = http_body_util::combinators::BoxBody<Bytes, std::io::Error>;
 
@@ -45,6 +83,21 @@ pub struct RequestArgs {
     /// --header 'accept=application/json'
     #[clap(short = 'H', long)]
     header: Vec<String>,
+    /// Query parameters to append to the url
+    ///
+    /// Can be passed multiple times,
+    ///
+    /// # Example
+    /// --query 'page=2'
+    #[clap(long)]
+    query: Vec<String>,
+    /// Sets the method of the request, any valid HTTP method token can be used
+    ///
+    /// # Example
+    /// -X HEAD
+    /// --request PURGE
+    #[clap(short = 'X', long = "request", conflicts_with_all = ["post", "put", "patch", "delete"])]
+    method: Option<String>,
     /// Sets the method to a POST request
     #[clap(long, action)]
     post: bool,
@@ -66,6 +119,9 @@ pub struct RequestArgs {
     /// Will use http2
     #[clap(long = "http2")]
     use_http2: bool,
+    /// Percent-decodes header values before they are set on the request
+    #[clap(long, action)]
+    decode_headers: bool,
     /// Url to send the request to
     #[clap(long, short, required = true)]
     url: Url,
@@ -78,10 +134,17 @@ pub struct RequestArgs {
 }
 
 impl Plugin for RequestArgs {
+    fn kind(&self) -> PluginKind {
+        PluginKind::Source
+    }
+
     fn call(bind: Bind<Self>) -> CallResult {
         let plugin = bind.receiver()?;
         if plugin.request.is_none() {
             bind.skip()
+        } else if bind.is_dry_run() {
+            debug!("Dry run, skipping request");
+            bind.defer(|_, _| async { Ok(()) })
         } else {
             bind.defer(|mut i, ct| async move {
                 let binding = i.clone();
@@ -92,8 +155,11 @@ impl Plugin for RequestArgs {
                         .await
                         .map_err(|e| binding.plugin_call_error(e.to_string()))?;
 
+                    let client = req
+                        .client()
+                        .map_err(|e| binding.plugin_call_error(e.to_string()))?;
                     let o = with_cancel(ct)
-                        .run(req.client()(request))
+                        .run(client(request))
                         .await?;
                     match o {
                         Ok(resp) => {
@@ -121,29 +187,13 @@ impl Plugin for RequestArgs {
             .expect("should be a version because cargo will complain first")
     }
 
+    fn validate(&self) -> reality::Result<()> {
+        validate_body_source(self.json.as_ref(), self.file.as_ref(), &[])?;
+        validate_method(self.method.as_deref())
+    }
+
     fn load(mut put: reality::runir::store::Put<'_, Self>) -> reality::runir::store::Put<'_, Self> {
-        let request = {
-            let args = put.resource();
-            let mut request = Request::new(args.url.clone());
-            if args.delete {
-                request.method = Some("DELETE".to_string());
-            } else if args.patch {
-                request.method = Some("PATCH".to_string());
-            } else if args.post {
-                request.method = Some("POST".to_string());
-            } else if args.put {
-                request.method = Some("PUT".to_string());
-            }
-            request.use_http2 = args.use_http2;
-            request.headers = args
-                .header
-                .iter()
-                .map(|h| h.trim().trim_matches(['\'', '"']).to_string())
-                .collect();
-            request.file = args.file.clone();
-            request.json = args.json.clone();
-            request
-        };
+        let request = Request::from(put.resource());
         put.resource_mut().request = Some(request);
         put
     }
@@ -175,6 +225,39 @@ impl RequestArgs {
     }
 }
 
+impl From<&RequestArgs> for Request {
+    /// Builds a request from parsed cli args, w/o loading the args into a state
+    fn from(args: &RequestArgs) -> Self {
+        let mut request = Request::new(args.url.clone());
+        if let Some(method) = args.method.as_ref() {
+            request.method = Some(method.to_string());
+        } else if args.delete {
+            request.method = Some("DELETE".to_string());
+        } else if args.patch {
+            request.method = Some("PATCH".to_string());
+        } else if args.post {
+            request.method = Some("POST".to_string());
+        } else if args.put {
+            request.method = Some("PUT".to_string());
+        }
+        request.use_http2 = args.use_http2;
+        request.decode_headers = args.decode_headers;
+        request.headers = args
+            .header
+            .iter()
+            .map(|h| h.trim().trim_matches(['\'', '"']).to_string())
+            .collect();
+        request.query = args
+            .query
+            .iter()
+            .map(|q| q.trim().trim_matches(['\'', '"']).to_string())
+            .collect();
+        request.file = args.file.clone();
+        request.json = args.json.clone();
+        request
+    }
+}
+
 impl Resource for RequestArgs {}
 impl Content for RequestArgs {
     fn state_uuid(&self) -> Uuid {
@@ -183,6 +266,8 @@ impl Content for RequestArgs {
 }
 
 /// Plugin to execute a request
+///
+/// **Note**: In dry-run mode the request is not sent and a response is not set
 #[kt_metadata(build, loader)]
 #[derive(Serialize, Deserialize)]
 pub struct Request {
@@ -195,14 +280,82 @@ pub struct Request {
     file: Option<PathBuf>,
     /// Json string to use as the body
     json: Option<String>,
+    /// Parts to send as a `multipart/form-data` body, file parts are streamed from disk
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    multipart: Vec<MultipartPart>,
     /// HTTP method to execute
     method: Option<String>,
     /// Header parameters
     #[serde(default)]
     headers: Vec<String>,
+    /// Query parameters to append to the url, formatted as `key=value`
+    ///
+    /// Parameters are percent-encoded and appended after any query already set on the url
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    query: Vec<String>,
+    /// If true, header values will be percent-decoded before they are set on the request
+    #[serde(default)]
+    decode_headers: bool,
+    /// Number of times to retry establishing a connection before the request fails, defaults to 2
+    ///
+    /// **Note**: Only the TCP connect and TLS handshake are retried, a request is never sent more than once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    connect_retries: Option<u32>,
+    /// If set, a request that receives a `429` or `503` response is sent again, see `Retry`
+    ///
+    /// **Note**: Waiting for the next attempt is cancelled if the call is cancelled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry: Option<Retry>,
+    /// If set, the request is sent over this unix domain socket instead of connecting to the url's host
+    ///
+    /// **Note**: The url is still used for the request line and `Host` header, and TLS is not used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unix_socket: Option<PathBuf>,
+    /// Proxy to use for `http` urls, defaults to the `HTTP_PROXY` environment variable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    http_proxy: Option<String>,
+    /// Proxy to use for `https` urls, defaults to the `HTTPS_PROXY` environment variable
+    ///
+    /// **Note**: The connection to the host is tunneled through the proxy w/ a `CONNECT` request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    https_proxy: Option<String>,
+    /// Comma-separated list of hosts that should not use a proxy, defaults to the `NO_PROXY` environment variable
+    ///
+    /// A host matches if it is equal to or a subdomain of an entry, and `*` matches every host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    no_proxy: Option<String>,
+    /// If set, the next page of each response is requested until there is no next page, or the page limit is reached
+    ///
+    /// **Note**: Each page must have a json body, the bodies are collected as a json array instead of setting a response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    paginate: Option<Paginate>,
+    /// Maximum number of bytes read from a response body, defaults to 16 MiB, `0` disables the limit
+    ///
+    /// The limit is enforced while the body is read, see `ResponseLimit::apply`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_response_bytes: Option<usize>,
+    /// If true, a response body is truncated at `max_response_bytes` instead of returning an error
+    #[serde(default)]
+    truncate_response: bool,
+    /// If true, cookies set by responses are stored in a cookie jar and sent w/ the following requests of this plugin
+    ///
+    /// **Note**: The cookie jar is kept by the loaded plugin, so cookies are sent across calls and pages
+    #[serde(default)]
+    cookies: bool,
+    /// Cookies stored from the responses this request received
+    #[serde(skip)]
+    cookie_jar: CookieJar,
+    /// Connection retained after a keep-alive response, reused by the next request to the same authority
+    ///
+    /// **Note**: The connection is kept by the loaded plugin, so it is reused across calls and pages
+    #[serde(skip)]
+    keep_alive: KeepAlive<Body>,
     /// Response this request received
     #[serde(skip)]
     response: Option<Response<Incoming>>,
+    /// Json bodies of the pages this request received when paginating
+    #[serde(skip)]
+    pages: Option<MessageData>,
 }
 
 /// Bytes Body
@@ -215,46 +368,61 @@ pub struct StringBody(String);
 pub struct EmptyBody;
 
 impl Plugin for Request {
+    fn kind(&self) -> PluginKind {
+        PluginKind::Source
+    }
+
     fn receive(&self, data: reality::plugin::MessageData) -> Option<Self> {
         self.apply_template(data).ok().inspect(|_| debug!("Applying template to request"))
     }
 
+    fn validate(&self) -> reality::Result<()> {
+        validate_body_source(self.json.as_ref(), self.file.as_ref(), &self.multipart)?;
+        validate_method(self.method.as_deref())
+    }
+
     fn call(binding: reality::plugin::Bind<Self>) -> CallResult {
         let plugin = binding.receiver()?;
-        if plugin.response.is_some() {
+        if plugin.response.is_some() || plugin.pages.is_some() {
             debug!("Skipping request, response has not been removed");
             binding.skip()
+        } else if binding.is_dry_run() {
+            debug!("Dry run, skipping request");
+            binding.defer(|_, _| async { Ok(()) })
         } else {
             debug!("Request does not have a response, sending request");
             binding.defer(move |mut b, ct| async move {
-                let (client, request) = Request::prepare(&b).await?;
-                let req_fut = (client)(request);
-                let ct_fut = ct.cancelled();
-                select! {
-                    res = req_fut => {
-                        match res {
-                            Ok(resp) => {
-                                let plugin = b.update()?;
-                                if plugin.response.is_none() {
-                                    plugin.response = Some(resp);
-                                    Ok(())
-                                } else {
-                                    Err(b.plugin_call_error("Response was already set and has not been handled"))
-                                }
-                            },
-                            Err(e) => {
-                                Err(b.plugin_call_error(format!("Could not complete sending request {e}")))
-                            },
-                        }
-                    },
-                    _ = ct_fut => {
-                        Err(reality::Error::PluginCallCancelled)
-                    }
+                if let Some(paginate) = b.receiver()?.paginate.clone() {
+                    let pages = Request::paginate(&b, &paginate, &ct).await?;
+                    let plugin = b.update()?;
+                    return if plugin.pages.is_none() {
+                        plugin.pages = Some(pages);
+                        Ok(())
+                    } else {
+                        Err(b.plugin_call_error("Pages were already set and have not been handled"))
+                    };
+                }
+
+                let url = Request::url_with_query(&b)?;
+                let mut resp = Request::send(&b, &url, &ct).await?;
+                if let Some(limit) = b.receiver()?.response_limit() {
+                    resp.extensions_mut().insert(limit);
+                }
+                let plugin = b.update()?;
+                if plugin.response.is_none() {
+                    plugin.response = Some(resp);
+                    Ok(())
+                } else {
+                    Err(b.plugin_call_error("Response was already set and has not been handled"))
                 }
             })
         }
     }
 
+    fn default_timeout() -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+
     fn version() -> Version {
         env!("CARGO_PKG_VERSION")
             .parse()
@@ -269,10 +437,75 @@ impl Request {
     }
 
     /// Takes the response from the request args
+    ///
+    /// **Note**: The body of the response is not limited unless it is read w/ `ResponseLimit::apply`
     pub fn take_response(&mut self) -> Option<Response<Incoming>> {
         self.response.take()
     }
 
+    /// Returns the limit on the number of bytes read from a response body, or None if the limit is disabled
+    #[inline]
+    pub fn response_limit(&self) -> Option<ResponseLimit> {
+        match self
+            .max_response_bytes
+            .unwrap_or(DEFAULT_MAX_RESPONSE_BYTES)
+        {
+            0 => None,
+            max_bytes => Some(ResponseLimit {
+                max_bytes,
+                truncate: self.truncate_response,
+            }),
+        }
+    }
+
+    /// Takes the json bodies of the pages received when paginating, as a `MessageData::Json` array
+    pub fn take_pages(&mut self) -> Option<MessageData> {
+        self.pages.take()
+    }
+
+    /// Returns the cookie jar of this request
+    ///
+    /// **Note**: Cookies are only stored and sent if `cookies` is enabled
+    #[inline]
+    pub fn cookie_jar(&self) -> &CookieJar {
+        &self.cookie_jar
+    }
+
+    /// Stores the cookies set by a response to a request for `url`, if cookies are enabled
+    #[inline]
+    fn store_cookies(&self, url: &Url, headers: &HeaderMap) {
+        if self.cookies {
+            self.cookie_jar.store(url, headers);
+        }
+    }
+
+    /// Sends a `GET` request to a url w/ the default client and returns the response body
+    ///
+    /// Returns an error if the request could not be sent, or if the response status is not successful
+    pub(crate) async fn fetch(url: Url) -> Result<Bytes> {
+        let request = Request::new(url);
+        let req = request.create_request().await?;
+        let resp = request.client()?(req).await?;
+        let status = resp.status();
+        if !status.is_success() {
+            let kind = if status == hyper::StatusCode::NOT_FOUND {
+                std::io::ErrorKind::NotFound
+            } else {
+                std::io::ErrorKind::Other
+            };
+            return Err(std::io::Error::new(
+                kind,
+                format!("Could not fetch {}, status: {status}", request.url()),
+            ));
+        }
+
+        Ok(LimitedBody::new(resp.into_body(), request.response_limit())
+            .collect()
+            .await
+            .map_err(std::io::Error::other)?
+            .to_bytes())
+    }
+
     /// Creates a new request for url
     fn new(url: Url) -> Self {
         Self {
@@ -280,88 +513,257 @@ impl Request {
             use_http2: false,
             file: None,
             json: None,
+            multipart: vec![],
             method: None,
             headers: vec![],
+            query: vec![],
+            decode_headers: false,
+            connect_retries: None,
+            retry: None,
+            unix_socket: None,
+            http_proxy: None,
+            https_proxy: None,
+            no_proxy: None,
+            paginate: None,
+            max_response_bytes: None,
+            truncate_response: false,
+            cookies: false,
+            cookie_jar: CookieJar::default(),
+            keep_alive: KeepAlive::new(),
             response: None,
+            pages: None,
             _kt_build: None,
             _kt_loader: None,
         }
     }
 
-    /// Prepares the request to be called
+    /// Returns the url of the request w/ the query parameters of the request appended
+    ///
+    /// Returns an error if the url has not been set
+    fn url_with_query(binding: &Bind<Self>) -> reality::Result<Url> {
+        let plugin = binding.receiver()?;
+        plugin
+            .url
+            .try_as_inner()
+            .map(|u| plugin.with_query(u))
+            .map_err(|_| binding.plugin_call_error("Request url has not been set"))
+    }
+
+    /// Sends the request to `url` w/ a client that reuses the connection retained by this plugin
     ///
-    /// Returns an error if the binding doesn't match the plugin type, or if the body could not be set on the request
-    async fn prepare(
+    /// If `retry` is set and the response is retryable, the request is sent again after the delay returned by
+    /// `Retry::delay`. Returns an error if the request could not be sent, or if the call is cancelled before a response is
+    /// received.
+    async fn send(
         binding: &Bind<Self>,
-    ) -> reality::Result<(DefaultClient, hyper::Request<Body>)> {
+        url: &Url,
+        cancel: &CancellationToken,
+    ) -> reality::Result<Response<Incoming>> {
         let plugin = binding.receiver()?;
-        let request = plugin
-            .create_request()
-            .await
-            .map_err(|e| binding.plugin_call_error(e.to_string()))?;
-        Ok((plugin.client(), request))
+        let mut attempt = 0;
+        loop {
+            let request = plugin
+                .create_request_for(url)
+                .await
+                .map_err(|e| binding.plugin_call_error(e.to_string()))?;
+            let client = plugin
+                .client_with_keep_alive(&plugin.keep_alive)
+                .map_err(|e| binding.plugin_call_error(e.to_string()))?;
+
+            let access = AccessLog::start(&request);
+            let resp = select! {
+                resp = client(request) => match resp {
+                    Ok(resp) => resp,
+                    Err(e) => {
+                        access.failed("error");
+                        return Err(binding.plugin_call_error(format!("Could not complete sending request {e}")));
+                    }
+                },
+                _ = cancel.cancelled() => {
+                    access.failed("cancelled");
+                    return Err(binding.plugin_call_cancelled());
+                }
+            };
+            access.response(&resp);
+            plugin.store_cookies(url, resp.headers());
+
+            let Some(delay) = plugin
+                .retry
+                .as_ref()
+                .and_then(|r| r.delay(attempt, resp.status(), resp.headers()))
+            else {
+                return Ok(resp);
+            };
+            warn!(
+                status = resp.status().as_u16(),
+                attempt,
+                delay_ms = delay.as_millis() as u64,
+                "retrying_request"
+            );
+            drop(resp);
+            select! {
+                _ = tokio::time::sleep(delay) => {
+                    attempt += 1;
+                },
+                _ = cancel.cancelled() => {
+                    return Err(binding.plugin_call_cancelled());
+                }
+            }
+        }
+    }
+
+    /// Sends the request and follows the next page of each response, until there is no next page or the page limit
+    /// is reached
+    ///
+    /// Returns the json body of each page as a `MessageData::Json` array, returns an error if a page could not be
+    /// fetched, or if the call is cancelled before the last page is received
+    async fn paginate(
+        binding: &Bind<Self>,
+        paginate: &Paginate,
+        cancel: &CancellationToken,
+    ) -> reality::Result<MessageData> {
+        let plugin = binding.receiver()?;
+        let mut url = Request::url_with_query(binding)?;
+
+        let mut pages = vec![];
+        while pages.len() < paginate.limit() {
+            let resp = Request::send(binding, &url, cancel).await?;
+            let (headers, body) = select! {
+                page = read_page(resp, plugin.response_limit()) => {
+                    page.map_err(|e| binding.plugin_call_error(format!("Could not fetch page {url}: {e}")))?
+                },
+                _ = cancel.cancelled() => {
+                    return Err(binding.plugin_call_cancelled());
+                }
+            };
+
+            let next = paginate.next_page(&url, &headers, &body);
+            pages.push(body);
+            match next {
+                Some(next) => {
+                    debug!("Requesting next page {next}");
+                    url = next;
+                }
+                None => break,
+            }
+        }
+        Ok(serde_json::Value::Array(pages).into())
     }
 
     /// Creates a new default client
+    ///
+    /// Returns an error if the proxy settings could not be parsed
+    #[inline]
+    fn client(&self) -> Result<DefaultClient> {
+        Ok(https(self.client_options()?, None))
+    }
+
+    /// Creates a new default client that reuses the connection retained by `keep_alive`
+    ///
+    /// If the response indicates keep-alive, the connection is retained by `keep_alive` for a follow-up request
+    ///
+    /// Returns an error if the proxy settings could not be parsed
+    #[inline]
+    pub(crate) fn client_with_keep_alive(&self, keep_alive: &KeepAlive<Body>) -> Result<DefaultClient> {
+        Ok(https(self.client_options()?, Some(keep_alive.clone())))
+    }
+
+    /// Returns the options for connecting the client
+    ///
+    /// Returns an error if the proxy settings could not be parsed
     #[inline]
-    fn client(&self) -> DefaultClient {
-        https(self.use_http2)
+    fn client_options(&self) -> Result<ClientOptions> {
+        Ok(ClientOptions {
+            use_http_2: self.use_http2,
+            connect_retries: self.connect_retries.unwrap_or(DEFAULT_CONNECT_RETRIES),
+            unix_socket: self.unix_socket.clone(),
+            proxy: self.proxy()?,
+        })
+    }
+
+    /// Resolves the proxy settings for this request
+    #[inline]
+    fn proxy(&self) -> Result<ProxyConfig> {
+        ProxyConfig::resolve(
+            self.http_proxy.as_deref(),
+            self.https_proxy.as_deref(),
+            self.no_proxy.as_deref(),
+        )
     }
 
     /// Creates the http request
+    ///
+    /// Returns an error if the request could not be built, or if a file used for the body could not be read
     #[inline]
-    async fn create_request(&self) -> hyper::http::Result<hyper::Request<Body>> {
+    async fn create_request(&self) -> Result<hyper::Request<Body>> {
         let url = self.url.try_as_inner().map_err(|_| {
-            hyper::http::Error::from(
-                hyper::http::uri::Builder::new()
-                    .build()
-                    .expect_err("should be an error"),
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Request url has not been set",
             )
         })?;
 
-        let url_authority = url.authority();
+        self.create_request_for(&self.with_query(url)).await
+    }
+
+    /// Creates the http request for `url`, the query parameters of this request are not appended to `url`
+    ///
+    /// Returns an error if the request could not be built, or if a file used for the body could not be read
+    #[inline]
+    async fn create_request_for(&self, url: &Url) -> Result<hyper::Request<Body>> {
         let mut builder = RequestBuilder::new()
-            .uri(self.url.to_string())
-            .header(header::HOST, url_authority);
+            .uri(url.as_str())
+            .header(header::HOST, url.authority());
 
         if let Some(method) = self.method.as_ref() {
-            match method.to_uppercase().as_str() {
-                "PUT" => {
-                    builder = builder.method("PUT");
-                }
-                "POST" => {
-                    builder = builder.method("POST");
-                }
-                "PATCH" => {
-                    builder = builder.method("PATCH");
-                }
-                "DELETE" => {
-                    builder = builder.method("DELETE");
-                }
-                _ => {}
+            builder = builder.method(parse_method(method)?);
+        }
+
+        if self.cookies {
+            if let Some(cookies) = self.cookie_jar.header_for(url) {
+                builder = builder.header(header::COOKIE, cookies);
             }
         }
 
         self.finish_build(self.set_headers(builder)).await
     }
 
+    /// Returns the url w/ the query parameters of this request appended to the existing query
+    #[inline]
+    fn with_query(&self, url: &Url) -> Url {
+        let mut url = url.clone();
+        if !self.query.is_empty() {
+            let mut pairs = url.query_pairs_mut();
+            for param in self.query.iter() {
+                match param.split_once('=') {
+                    Some((key, value)) => pairs.append_pair(key.trim(), value.trim()),
+                    None => pairs.append_key_only(param.trim()),
+                };
+            }
+        }
+        url
+    }
+
     /// Parse and set the headers for the request
     #[inline]
     fn set_headers(&self, mut builder: RequestBuilder) -> RequestBuilder {
         let headers = self.headers.join(";;");
-        for (header, v) in reality::runir::util::scan_for_headers(&headers) {
-            builder = builder.header(header.to_lowercase(), v.join(","));
+        if self.decode_headers {
+            for (header, v) in reality::runir::util::scan_for_headers_decoded(&headers) {
+                builder = builder.header(header.to_lowercase(), v.join(","));
+            }
+        } else {
+            for (header, v) in reality::runir::util::scan_for_headers(&headers) {
+                builder = builder.header(header.to_lowercase(), v.join(","));
+            }
         }
         builder
     }
 
     /// Finish building the request
     #[inline]
-    async fn finish_build(
-        &self,
-        builder: RequestBuilder,
-    ) -> hyper::http::Result<hyper::Request<Body>> {
-        if let Some(json) = self.json.as_ref() {
+    async fn finish_build(&self, builder: RequestBuilder) -> Result<hyper::Request<Body>> {
+        let request = if let Some(json) = self.json.as_ref() {
             let body = StringBody::from(json.to_string()).into_boxed_body();
             builder
                 .header(hyper::header::CONTENT_LENGTH, json.len())
@@ -382,14 +784,21 @@ impl Request {
                     );
                 }
             }
-            let body = tokio::fs::read(path).await.unwrap();
+            let body = tokio::fs::read(path).await?;
             let body = BytesMut::from_iter(&body).freeze();
             builder
                 .header(hyper::header::CONTENT_LENGTH, body.len())
                 .body(BytesBody::from(body).into_boxed_body())
+        } else if !self.multipart.is_empty() {
+            let body = MultipartBody::new(&self.multipart).await?;
+            builder
+                .header(hyper::header::CONTENT_LENGTH, body.len())
+                .header(hyper::header::CONTENT_TYPE, body.content_type())
+                .body(body.into_boxed_body())
         } else {
             builder.body(EmptyBody.into_boxed_body())
-        }
+        };
+        request.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))
     }
 }
 
@@ -401,8 +810,322 @@ impl Content for Request {
     }
 }
 
+/// Reads the response headers and json body of a page
+///
+/// Returns an error if the response status is not successful, if the body exceeds the limit, or if the body is not json
+async fn read_page(
+    resp: Response<Incoming>,
+    limit: Option<ResponseLimit>,
+) -> Result<(HeaderMap, serde_json::Value)> {
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(std::io::Error::other(format!("status: {status}")));
+    }
+
+    let (parts, body) = resp.into_parts();
+    let body = LimitedBody::new(body, limit)
+        .collect()
+        .await
+        .map_err(std::io::Error::other)?
+        .to_bytes();
+    Ok((parts.headers, serde_json::from_slice(&body)?))
+}
+
+/// Structured access log of a single request sent by the request plugin
+///
+/// A single `info` event is emitted w/ the `kioto::access` target once the outcome of the request is known. The duration
+/// is measured from when the request is sent until the response headers are received.
+///
+/// **Note**: The query and credentials of the url are not logged, since either can contain secrets
+struct AccessLog {
+    /// Method of the request
+    method: String,
+    /// Url of the request w/o the query or credentials
+    url: String,
+    /// Size of the request body, if known
+    request_bytes: Option<u64>,
+    /// When the request was sent
+    start: Instant,
+    /// Set once the outcome of the request has been logged
+    logged: AtomicBool,
+}
+
+impl AccessLog {
+    /// Starts the access log for a request that is about to be sent
+    #[inline]
+    fn start(request: &hyper::Request<Body>) -> Self {
+        let uri = request.uri();
+        let port = uri.port().map(|p| format!(":{p}")).unwrap_or_default();
+        Self {
+            method: request.method().to_string(),
+            url: format!(
+                "{}://{}{port}{}",
+                uri.scheme_str().unwrap_or("http"),
+                uri.host().unwrap_or_default(),
+                uri.path()
+            ),
+            request_bytes: content_length(request.headers())
+                .or(hyper::body::Body::size_hint(request.body()).exact()),
+            start: Instant::now(),
+            logged: AtomicBool::new(false),
+        }
+    }
+
+    /// Logs the status and the size of the body of a response that was received
+    #[inline]
+    fn response<B>(&self, resp: &Response<B>) {
+        self.log(
+            "ok",
+            Some(resp.status().as_u16()),
+            content_length(resp.headers()),
+        );
+    }
+
+    /// Logs that a response was not received, i.e. `error` or `cancelled`
+    #[inline]
+    fn failed(&self, outcome: &'static str) {
+        self.log(outcome, None, None);
+    }
+
+    /// Logs the outcome of the request, if it has not already been logged
+    fn log(&self, outcome: &'static str, status: Option<u16>, response_bytes: Option<u64>) {
+        if self.logged.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        info!(
+            target: "kioto::access",
+            method = self.method,
+            url = self.url,
+            status,
+            duration_ms = self.start.elapsed().as_millis() as u64,
+            request_bytes = self.request_bytes,
+            response_bytes,
+            outcome,
+            "request"
+        );
+    }
+}
+
+/// Returns the value of the `Content-Length` header
+#[inline]
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|l| l.to_str().ok())
+        .and_then(|l| l.parse().ok())
+}
+
+/// Validates that at most one source is set for the request body
+#[inline]
+fn validate_body_source(
+    json: Option<&String>,
+    file: Option<&PathBuf>,
+    multipart: &[MultipartPart],
+) -> reality::Result<()> {
+    let sources = [json.is_some(), file.is_some(), !multipart.is_empty()];
+    if sources.into_iter().filter(|s| *s).count() > 1 {
+        Err(reality::Error::InvalidPluginConfig {
+            name: Request::name(),
+            message: "Only one of `json`, `file` or `multipart` can be set as the request body"
+                .to_string(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Parses an HTTP method, the method is case-insensitive and can be any valid method token, i.e. `HEAD` or `PURGE`
+///
+/// Returns an error if the method is not a valid token
+#[inline]
+fn parse_method(method: &str) -> Result<hyper::Method> {
+    hyper::Method::from_bytes(method.trim().to_uppercase().as_bytes()).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid HTTP method `{method}`"),
+        )
+    })
+}
+
+/// Validates that the method of the request is a valid HTTP method, if set
+#[inline]
+fn validate_method(method: Option<&str>) -> reality::Result<()> {
+    match method.map(parse_method) {
+        Some(Err(err)) => Err(reality::Error::InvalidPluginConfig {
+            name: Request::name(),
+            message: err.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Establishes a TLS connection, retrying w/ exponential backoff if the connection could not be established
+///
+/// If a proxy is set, the TLS connection is established through a tunnel opened by the proxy
+///
+/// **Note**: Only establishing the underlying connection is retried, certificate and handshake failures are returned
+/// immediately since they will not succeed on another attempt
+async fn connect_tls(
+    cx: &tokio_native_tls::TlsConnector,
+    authority: &str,
+    addr: &str,
+    proxy: Option<&Url>,
+    retries: u32,
+) -> Result<tokio_native_tls::TlsStream<TunnelStream>> {
+    let tcp = connect_with_retry(addr, retries, || async move {
+        match proxy {
+            Some(proxy) => connect_tunnel(proxy, addr).await,
+            None => TcpStream::connect(addr).await.map(TunnelStream::from),
+        }
+    })
+    .await?;
+
+    cx.connect(authority, tcp)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))
+}
+
+/// Calls `connect` until a connection is established, retrying transient errors up to `retries` times w/ exponential
+/// backoff
+async fn connect_with_retry<T, F, Fut>(addr: &str, retries: u32, mut connect: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match connect().await {
+            Ok(stream) => return Ok(stream),
+            Err(err) if attempt < retries && is_transient(&err) => {
+                let backoff = connect_backoff(attempt);
+                warn!("Could not connect to {addr}, retrying in {backoff:?}: {err}");
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Returns the delay before retrying a connection, doubled for each attempt up to `MAX_CONNECT_RETRY_BACKOFF`
+#[inline]
+fn connect_backoff(attempt: u32) -> Duration {
+    CONNECT_RETRY_BACKOFF
+        .checked_mul(2u32.saturating_pow(attempt))
+        .map_or(MAX_CONNECT_RETRY_BACKOFF, |b| {
+            b.min(MAX_CONNECT_RETRY_BACKOFF)
+        })
+}
+
+/// Returns true if an error establishing a connection may not occur on another attempt
+#[inline]
+fn is_transient(err: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    matches!(
+        err.kind(),
+        ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::AddrNotAvailable
+            | ErrorKind::TimedOut
+            | ErrorKind::Interrupted
+            | ErrorKind::WouldBlock
+            | ErrorKind::UnexpectedEof
+    )
+}
+
+/// Options for connecting the client created by `https`
+#[derive(Clone)]
+struct ClientOptions {
+    /// If true, uses http2 when sending the request
+    use_http_2: bool,
+    /// Number of times to retry establishing a connection
+    connect_retries: u32,
+    /// If set, connects to this unix domain socket instead of the host of the request uri
+    unix_socket: Option<PathBuf>,
+    /// Proxy settings used to connect to the host of the request uri
+    proxy: ProxyConfig,
+}
+
+/// Idle http/1.1 connection retained after a keep-alive response
+///
+/// Kept by a request plugin and shared by the clients created for its sequential requests, so that a follow-up
+/// request to the same authority can reuse the connection instead of establishing a new one
+pub(crate) struct KeepAlive<B> {
+    /// Key of the connection's authority and the handle used to send a request on the connection
+    conn: Arc<Mutex<Option<RetainedConnection<B>>>>,
+}
+
+impl<B> KeepAlive<B> {
+    /// Creates a new empty keep-alive handle
+    pub(crate) fn new() -> Self {
+        Self {
+            conn: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Takes the retained connection if it was established for `key`
+    ///
+    /// A connection retained for a different key is dropped
+    fn take(&self, key: &str) -> Option<Http1Sender<B>> {
+        let (retained, sender) = self.conn.lock().ok()?.take()?;
+        if retained == key && !sender.is_closed() {
+            Some(sender)
+        } else {
+            None
+        }
+    }
+
+    /// Retains a connection for `key`, replacing any previously retained connection
+    fn retain(&self, key: String, sender: Http1Sender<B>) {
+        if let Ok(mut conn) = self.conn.lock() {
+            *conn = Some((key, sender));
+        }
+    }
+}
+
+impl<B> Default for KeepAlive<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B> Clone for KeepAlive<B> {
+    fn clone(&self) -> Self {
+        Self {
+            conn: self.conn.clone(),
+        }
+    }
+}
+
+/// Returns true if the connection a response was received on can be used for another request
+///
+/// `HTTP/1.1` connections are persistent unless the response has `Connection: close`, and `HTTP/1.0` connections
+/// must opt in w/ `Connection: keep-alive`
+#[inline]
+fn is_keep_alive<B>(resp: &Response<B>) -> bool {
+    let connection = resp
+        .headers()
+        .get(header::CONNECTION)
+        .and_then(|c| c.to_str().ok())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    let has = |token: &str| connection.split(',').any(|c| c.trim() == token);
+    match resp.version() {
+        hyper::Version::HTTP_11 => !has("close"),
+        hyper::Version::HTTP_10 => has("keep-alive"),
+        _ => false,
+    }
+}
+
 /// Creates a client helper monad that can be used to send an https request
-fn https<B>(use_http_2: bool) -> ClientHelper<B>
+///
+/// Establishing the connection is retried up to `connect_retries` times before the request fails
+///
+/// If `keep_alive` is set, an http/1.1 connection retained for the request's authority is reused, and the connection
+/// is retained after the response if the response indicates keep-alive
+fn https<B>(options: ClientOptions, keep_alive: Option<KeepAlive<B>>) -> ClientHelper<B>
 where
     B: hyper::body::Body + Unpin + Send + Sync + 'static,
     B::Data: Send,
@@ -433,70 +1156,76 @@ where
         Box<dyn Future<Output = Result<Response<Incoming>>> + Send + 'static>,
     > {
         Box::pin(async move {
+            let keep_alive = match keep_alive.filter(|_| !options.use_http_2) {
+                Some(keep_alive) => {
+                    let uri = req.uri();
+                    let key = match options.unix_socket.as_ref() {
+                        Some(path) => format!("unix:{}", path.display()),
+                        None => format!(
+                            "{}://{}",
+                            uri.scheme_str().unwrap_or("https"),
+                            uri.authority().map(|a| a.as_str()).unwrap_or_default()
+                        ),
+                    };
+
+                    if let Some(mut sender) = keep_alive.take(&key) {
+                        match tokio::time::timeout(KEEP_ALIVE_READY_TIMEOUT, sender.ready()).await {
+                            Ok(Ok(())) => {
+                                debug!("Reusing connection to {key}");
+                                return send_http1(sender, req, Some((keep_alive, key))).await;
+                            }
+                            _ => {
+                                debug!("Retained connection to {key} is not ready, connecting");
+                            }
+                        }
+                    }
+                    Some((keep_alive, key))
+                }
+                None => None,
+            };
+
             let permit = tcp_socket_semaphore
                 .acquire()
                 .await
                 .map_err(|e| std::io::Error::new(std::io::ErrorKind::WouldBlock, e.to_string()))?;
 
+            if let Some(path) = options.unix_socket.as_ref() {
+                let stream = connect_unix(path).await?;
+                return send_request(stream, req, options.use_http_2, permit, keep_alive).await;
+            }
+
             let uri = req.uri();
 
             if let (Some(authority), Some(host), port) =
                 (uri.authority(), uri.host(), uri.port_u16())
             {
+                let scheme = uri.scheme_str().unwrap_or("https");
+                let proxy = options.proxy.proxy_for(scheme, host);
+
+                // Plain http requests are sent to the proxy w/ the absolute uri as the request target
+                if let (Some(proxy), "http") = (proxy, scheme) {
+                    let stream =
+                        connect_with_retry(proxy.as_str(), options.connect_retries, || {
+                            connect_proxy(proxy)
+                        })
+                        .await?;
+                    return send_request(stream, req, false, permit, keep_alive).await;
+                }
+
                 let port = port.unwrap_or(443);
 
                 let addr = format!("{host}:{port}");
 
-                let tcp = TcpStream::connect(addr).await?;
-
-                let stream = cx.connect(authority.as_str(), tcp).await.map_err(|e| {
-                    std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string())
-                })?;
-
-                let rt = TokioIo::new(stream);
-
-                if !use_http_2 {
-                    let (mut s, conn) = hyper::client::conn::http1::handshake::<_, B>(rt)
-                        .await
-                        .map_err(|e| {
-                            std::io::Error::new(
-                                std::io::ErrorKind::ConnectionRefused,
-                                e.to_string(),
-                            )
-                        })?;
-                    tokio::spawn(async move {
-                        let _ = permit;
-                        if let Err(err) = conn.await {
-                            error!("Connection error {err}");
-                        }
-                        trace!("Connection is closing");
-                    });
-
-                    Ok(s.send_request(req).await.map_err(|e| {
-                        std::io::Error::new(std::io::ErrorKind::ConnectionAborted, e.to_string())
-                    })?)
-                } else {
-                    let (mut s, conn) = hyper::client::conn::http2::handshake::<_, _, B>(
-                        TokioExecutor::default(),
-                        rt,
-                    )
-                    .await
-                    .map_err(|e| {
-                        std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string())
-                    })?;
-
-                    tokio::spawn(async move {
-                        let _ = permit;
-                        if let Err(err) = conn.await {
-                            error!("Connection error {err}");
-                        }
-                        trace!("Connection is closing");
-                    });
+                let stream = connect_tls(
+                    &cx,
+                    authority.as_str(),
+                    &addr,
+                    proxy,
+                    options.connect_retries,
+                )
+                .await?;
 
-                    Ok(s.send_request(req).await.map_err(|e| {
-                        std::io::Error::new(std::io::ErrorKind::ConnectionAborted, e.to_string())
-                    })?)
-                }
+                send_request(stream, req, options.use_http_2, permit, keep_alive).await
             } else {
                 Err::<hyper::Response<Incoming>, std::io::Error>(std::io::Error::new(
                     std::io::ErrorKind::InvalidInput,
@@ -509,6 +1238,100 @@ where
     Box::new(monad)
 }
 
+/// Connects to a unix domain socket
+#[cfg(unix)]
+async fn connect_unix(path: &std::path::Path) -> Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(path).await
+}
+
+/// Connects to a unix domain socket
+#[cfg(not(unix))]
+async fn connect_unix(_: &std::path::Path) -> Result<TcpStream> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Unix domain sockets are not supported on this platform",
+    ))
+}
+
+/// Performs the http handshake over an established connection and sends the request
+///
+/// The connection is driven on a spawned task, which holds the socket permit until the connection closes
+///
+/// If `keep_alive` is set, an http/1.1 connection is retained for the key if the response indicates keep-alive
+async fn send_request<S, B>(
+    stream: S,
+    req: hyper::Request<B>,
+    use_http_2: bool,
+    permit: tokio::sync::SemaphorePermit<'static>,
+    keep_alive: Option<(KeepAlive<B>, String)>,
+) -> Result<Response<Incoming>>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+    B: hyper::body::Body + Unpin + Send + Sync + 'static,
+    B::Data: Send,
+    B::Error: std::error::Error + Send + Sync,
+{
+    let rt = TokioIo::new(stream);
+
+    if !use_http_2 {
+        let (s, conn) = hyper::client::conn::http1::handshake::<_, B>(rt)
+            .await
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string()))?;
+        tokio::spawn(async move {
+            let _ = permit;
+            if let Err(err) = conn.await {
+                error!("Connection error {err}");
+            }
+            trace!("Connection is closing");
+        });
+
+        send_http1(s, req, keep_alive).await
+    } else {
+        let (mut s, conn) =
+            hyper::client::conn::http2::handshake::<_, _, B>(TokioExecutor::default(), rt)
+                .await
+                .map_err(|e| {
+                    std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string())
+                })?;
+
+        tokio::spawn(async move {
+            let _ = permit;
+            if let Err(err) = conn.await {
+                error!("Connection error {err}");
+            }
+            trace!("Connection is closing");
+        });
+
+        Ok(s.send_request(req).await.map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::ConnectionAborted, e.to_string())
+        })?)
+    }
+}
+
+/// Sends a request over an http/1.1 connection
+///
+/// If `keep_alive` is set, the connection is retained for the key if the response indicates keep-alive
+async fn send_http1<B>(
+    mut sender: Http1Sender<B>,
+    req: hyper::Request<B>,
+    keep_alive: Option<(KeepAlive<B>, String)>,
+) -> Result<Response<Incoming>>
+where
+    B: hyper::body::Body + Send + 'static,
+{
+    let resp = sender.send_request(req).await.map_err(|e| {
+        std::io::Error::new(std::io::ErrorKind::ConnectionAborted, e.to_string())
+    })?;
+
+    if let Some((keep_alive, key)) = keep_alive {
+        if is_keep_alive(&resp) {
+            trace!("Retaining connection to {key}");
+            keep_alive.retain(key, sender);
+        }
+    }
+    Ok(resp)
+}
+
 /// Trait for converting into a boxed body
 trait IntoBoxedBody {
     fn into_boxed_body(self) -> Body;
@@ -586,7 +1409,7 @@ impl From<EmptyBody> for Body {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use std::ffi::OsString;
 
     use clap::{CommandFactory, Parser, Subcommand};
@@ -597,12 +1420,621 @@ mod tests {
 
     use super::*;
 
+    /// Binds a unix socket at `.test/<name>/server.sock` and spawns a server that writes each response in order
+    ///
+    /// Each response is written once a request is read, and requests are served on the same connection until a response
+    /// w/ a `connection: close` header is written. The server returns each request it read.
+    #[cfg(unix)]
+    pub(crate) fn unix_server(
+        name: &str,
+        responses: Vec<String>,
+    ) -> (PathBuf, tokio::task::JoinHandle<Vec<String>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir = PathBuf::from(".test").join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("server.sock");
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move {
+            let mut received = vec![];
+            let mut connection = None;
+            for response in responses {
+                if connection.is_none() {
+                    connection = Some(listener.accept().await.unwrap().0);
+                }
+                let stream = connection.as_mut().unwrap();
+                let mut buf = vec![0; 1024];
+                let read = stream.read(&mut buf).await.unwrap();
+                assert!(read > 0, "connection was closed before the next request");
+                received.push(String::from_utf8_lossy(&buf[..read]).to_string());
+                // **Note**: The client can stop reading before the response is written, i.e. if the response is too large
+                let _ = stream.write_all(response.as_bytes()).await;
+                if response.contains("\r\nconnection: close\r\n") {
+                    connection = None;
+                }
+            }
+            received
+        });
+        (path, server)
+    }
+
     #[test]
     fn test_request_plugin_name() {
         let name = Request::name();
         assert_eq!("kioto/plugins.request", name.plugin_ref());
     }
 
+    #[tokio::test]
+    async fn test_request_plugin_validate_body_source() {
+        let mut state = State::new();
+
+        let err = state
+            .load_by_toml::<Request>(
+                r#"
+url = "https://jsonplaceholder.typicode.com/posts"
+json = "{}"
+file = "body.json"
+"#,
+                Labels::default(),
+            )
+            .expect_err("should not load a request w/ conflicting body sources");
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        assert!(state.addresses().is_empty());
+    }
+
+    #[test]
+    fn test_parse_method() {
+        assert_eq!(hyper::Method::HEAD, parse_method("head").unwrap());
+        assert_eq!(hyper::Method::OPTIONS, parse_method("OPTIONS").unwrap());
+        assert_eq!("PURGE", parse_method("purge").unwrap().as_str());
+        assert!(parse_method("GET /").is_err());
+        assert!(parse_method("").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request_plugin_validate_method() {
+        let mut state = State::new();
+        let err = state
+            .load_by_toml::<Request>(
+                r#"
+url = "https://jsonplaceholder.typicode.com/posts"
+method = "NOT A METHOD"
+"#,
+                Labels::default(),
+            )
+            .expect_err("should not load a request w/ an invalid method");
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        assert!(matches!(
+            validate_method(Some("NOT A METHOD")),
+            Err(reality::Error::InvalidPluginConfig { .. })
+        ));
+
+        let address = state
+            .load_by_toml::<Request>(
+                r#"
+url = "https://jsonplaceholder.typicode.com/posts"
+method = "HEAD"
+"#,
+                Labels::default(),
+            )
+            .unwrap();
+        let request = state
+            .find_plugin(&address)
+            .and_then(|i| i.borrow::<Request>())
+            .unwrap()
+            .create_request()
+            .await
+            .unwrap();
+        assert_eq!(hyper::Method::HEAD, request.method());
+    }
+
+    #[tokio::test]
+    async fn test_request_plugin_connect_retries() {
+        use tokio::io::AsyncWriteExt;
+
+        // Reserve a port and close it so that connecting is refused
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        let cx = tokio_native_tls::TlsConnector::from(
+            tokio_native_tls::native_tls::TlsConnector::new().unwrap(),
+        );
+        let start = std::time::Instant::now();
+        let err = connect_tls(&cx, &addr, &addr, None, 2).await.unwrap_err();
+        assert_eq!(std::io::ErrorKind::ConnectionRefused, err.kind());
+        assert!(start.elapsed() >= CONNECT_RETRY_BACKOFF * 3);
+
+        assert_eq!(CONNECT_RETRY_BACKOFF * 4, connect_backoff(2));
+        assert_eq!(MAX_CONNECT_RETRY_BACKOFF, connect_backoff(32));
+        assert_eq!(MAX_CONNECT_RETRY_BACKOFF, connect_backoff(u32::MAX));
+
+        // Handshake failures are not retried
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n")
+                .await
+                .unwrap();
+            listener
+        });
+        let start = std::time::Instant::now();
+        connect_tls(&cx, &addr, &addr, None, 5).await.unwrap_err();
+        assert!(start.elapsed() < CONNECT_RETRY_BACKOFF);
+        let listener = server.await.unwrap();
+        assert!(
+            tokio::time::timeout(CONNECT_RETRY_BACKOFF * 2, listener.accept())
+                .await
+                .is_err()
+        );
+
+        let request: Request = toml::from_str(
+            r#"
+url = "https://example.com"
+connect_retries = 5
+"#,
+        )
+        .unwrap();
+        assert_eq!(Some(5), request.connect_retries);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_request_plugin_unix_socket() {
+        let (path, server) = unix_server(
+            "test_request_unix_socket",
+            vec!["HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok".to_string()],
+        );
+
+        let mut request = Request::new("http://localhost/status".parse().unwrap());
+        request.unix_socket = Some(path);
+        let req = request.create_request().await.unwrap();
+        let resp = request.client().unwrap()(req).await.unwrap();
+        assert_eq!(200, resp.status().as_u16());
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&b"ok"[..], &body[..]);
+
+        let received = server.await.unwrap();
+        assert!(received[0].contains("host: localhost"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_request_plugin_cookies() {
+        let (path, server) = unix_server(
+            "test_request_cookies",
+            ["set-cookie: session=abc; Path=/\r\n", ""]
+                .map(|set_cookie| {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nconnection: close\r\n{set_cookie}content-length: 2\r\n\r\nok"
+                    )
+                })
+                .to_vec(),
+        );
+
+        let mut state = State::new();
+        let address = state
+            .load_by_toml::<Request>(
+                &format!(
+                    "url = \"http://localhost/login\"\ncookies = true\nunix_socket = \"{}\"",
+                    path.display()
+                ),
+                Labels::default(),
+            )
+            .unwrap();
+        for _ in 0..2 {
+            state.call(&address).await.unwrap();
+            let mut item = state.find_plugin(&address).unwrap().clone();
+            let request = item.borrow_mut::<Request>().unwrap();
+            assert!(request.take_response().is_some());
+        }
+
+        let received = server.await.unwrap();
+        assert!(!received[0].contains("cookie:"));
+        assert!(received[1].contains("cookie: session=abc\r\n"));
+        let item = state.find_plugin(&address).unwrap();
+        assert_eq!(1, item.borrow::<Request>().unwrap().cookie_jar().len());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_request_plugin_retry_after() {
+        let (path, server) = unix_server(
+            "test_request_retry_after",
+            [
+                "503 Service Unavailable\r\nretry-after: 1",
+                "429 Too Many Requests\r\nretry-after: 86400",
+                "200 OK",
+                "503 Service Unavailable\r\nretry-after: 60",
+            ]
+            .map(|status| {
+                format!("HTTP/1.1 {status}\r\nconnection: close\r\ncontent-length: 2\r\n\r\nok")
+            })
+            .to_vec(),
+        );
+
+        let mut state = State::new();
+        let address = state
+            .load_by_toml::<Request>(
+                &format!(
+                    "url = \"http://localhost/\"\nunix_socket = \"{}\"\n[retry]\nbackoff_ms = 10000\nmax_retry_after_ms = 1500",
+                    path.display()
+                ),
+                Labels::default(),
+            )
+            .unwrap();
+
+        // The delay of the Retry-After header is used instead of the backoff, and is clamped to the maximum
+        let started = Instant::now();
+        state.call(&address).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(2500));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let mut item = state.find_plugin(&address).unwrap().clone();
+        let request = item.borrow_mut::<Request>().unwrap();
+        assert!(request.take_response().unwrap().status().is_success());
+
+        // Waiting for the next attempt is cancelled w/ the call
+        let address = state
+            .load_by_toml::<Request>(
+                &format!(
+                    "url = \"http://localhost/retry\"\nunix_socket = \"{}\"\n[retry]\nattempts = 1",
+                    path.display()
+                ),
+                Labels::default(),
+            )
+            .unwrap();
+        let (call, cancel) = state.spawn(&address).unwrap();
+        let started = Instant::now();
+        let call = tokio::spawn(call);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        cancel.cancel();
+        assert!(matches!(
+            call.await.unwrap(),
+            Err(reality::Error::PluginCallCancelled { .. })
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        server.await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_request_plugin_access_log() {
+        let (path, server) = unix_server(
+            "test_request_access_log",
+            vec!["HTTP/1.1 201 Created\r\ncontent-length: 2\r\n\r\nok".to_string()],
+        );
+
+        let mut state = State::new();
+        let address = state
+            .load_by_toml::<Request>(
+                &format!(
+                    "url = \"http://localhost/items?token=secret\"\nmethod = \"POST\"\njson = '{{}}'\nunix_socket = \"{}\"",
+                    path.display()
+                ),
+                Labels::default(),
+            )
+            .unwrap();
+        state.call(&address).await.unwrap();
+        server.await.unwrap();
+
+        assert!(logs_contain("method=\"POST\""));
+        assert!(logs_contain("url=\"http://localhost/items\""));
+        assert!(logs_contain("status=201"));
+        assert!(logs_contain("request_bytes=2"));
+        assert!(logs_contain("response_bytes=2"));
+        assert!(logs_contain("outcome=\"ok\""));
+        assert!(!logs_contain("secret"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_request_plugin_max_response_bytes() {
+        use crate::plugins::utils::HttpRequestClient;
+
+        let (path, server) = unix_server(
+            "test_request_max_response_bytes",
+            vec![
+                "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 5\r\n\r\nkioto"
+                    .to_string();
+                3
+            ],
+        );
+
+        let mut state = State::new();
+        let client = state.load_handler(HttpRequestClient::default(), Labels::default());
+        let mut load = |settings: &str| {
+            state
+                .load_by_toml::<Request>(
+                    &format!(
+                        "url = \"http://localhost/\"\nunix_socket = \"{}\"\n{settings}",
+                        path.display()
+                    ),
+                    Labels::default(),
+                )
+                .unwrap()
+        };
+        let limited = load("max_response_bytes = 4");
+        let truncated = load("max_response_bytes = 4\ntruncate_response = true");
+        let unlimited = load("max_response_bytes = 0");
+
+        assert!(state
+            .call_with_handler::<HttpRequestClient>(&limited, client.clone())
+            .await
+            .is_err());
+        let data = state
+            .call_with_handler::<HttpRequestClient>(&truncated, client.clone())
+            .await
+            .unwrap();
+        assert_eq!(Some(&Bytes::from("kiot")), data.as_bytes());
+        let data = state
+            .call_with_handler::<HttpRequestClient>(&unlimited, client)
+            .await
+            .unwrap();
+        assert_eq!(Some(&Bytes::from("kioto")), data.as_bytes());
+        server.await.unwrap();
+
+        let request = Request::new("http://localhost/".parse().unwrap());
+        assert_eq!(
+            Some(ResponseLimit {
+                max_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+                truncate: false
+            }),
+            request.response_limit()
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_request_plugin_keep_alive() {
+        // Both requests must be served on the first accepted connection
+        let (path, server) = unix_server(
+            "test_request_keep_alive",
+            ["p1", "p2"]
+                .map(|body| format!("HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n{body}"))
+                .to_vec(),
+        );
+
+        let keep_alive = KeepAlive::new();
+        let mut bodies = vec![];
+        for page in ["/items?page=1", "/items?page=2"] {
+            let mut request = Request::new(format!("http://localhost{page}").parse().unwrap());
+            request.unix_socket = Some(path.clone());
+            let req = request.create_request().await.unwrap();
+            let resp = request.client_with_keep_alive(&keep_alive).unwrap()(req).await.unwrap();
+            bodies.push(resp.into_body().collect().await.unwrap().to_bytes());
+        }
+        assert_eq!(vec![Bytes::from("p1"), Bytes::from("p2")], bodies);
+
+        let received = server.await.unwrap();
+        assert!(received[0].contains("/items?page=1 HTTP/1.1\r\n"));
+        assert!(received[1].contains("/items?page=2 HTTP/1.1\r\n"));
+
+        let resp = Response::builder()
+            .header(header::CONNECTION, "close")
+            .body(())
+            .unwrap();
+        assert!(!is_keep_alive(&resp));
+        let resp = Response::builder()
+            .version(hyper::Version::HTTP_10)
+            .header(header::CONNECTION, "Keep-Alive")
+            .body(())
+            .unwrap();
+        assert!(is_keep_alive(&resp));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_request_plugin_keep_alive_across_calls() {
+        // The request of each call must be served on the first accepted connection
+        let (path, server) = unix_server(
+            "test_request_keep_alive_across_calls",
+            vec!["HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok".to_string(); 2],
+        );
+
+        let mut state = State::new();
+        let address = state
+            .load_by_toml::<Request>(
+                &format!(
+                    "url = \"http://localhost/items\"\nunix_socket = \"{}\"",
+                    path.display()
+                ),
+                Labels::default(),
+            )
+            .unwrap();
+        for _ in 0..2 {
+            state.call(&address).await.unwrap();
+            let mut item = state.find_plugin(&address).unwrap().clone();
+            let request = item.borrow_mut::<Request>().unwrap();
+            let resp = request.take_response().unwrap();
+            assert_eq!(200, resp.status().as_u16());
+        }
+        server.await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_request_plugin_paginate() {
+        let (path, server) = unix_server(
+            "test_request_paginate",
+            [
+                r#"{"items":[1],"next":"/items?page=2"}"#,
+                r#"{"items":[2],"next":"/items?page=3"}"#,
+                r#"{"items":[3],"next":null}"#,
+            ]
+            .map(|body| {
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                    body.len()
+                )
+            })
+            .to_vec(),
+        );
+
+        let mut state = State::new();
+        let request = state
+            .load_by_toml::<Request>(
+                &format!(
+                    r#"
+url = "http://localhost/items"
+query = ["page=1"]
+unix_socket = "{}"
+
+[paginate]
+next = "/next"
+"#,
+                    path.display()
+                ),
+                Labels::default(),
+            )
+            .unwrap();
+        state.call(&request).await.unwrap();
+        server.await.unwrap();
+
+        let mut plugin = state.find_plugin(&request).unwrap().clone();
+        let request = plugin.borrow_mut::<Request>().unwrap();
+        assert!(request.take_response().is_none());
+        let pages = request.take_pages().expect("should have pages");
+        let pages = pages.as_json().and_then(|j| j.get("[]")).unwrap();
+        assert_eq!(
+            &serde_json::json!([[1], [2], [3]]),
+            &serde_json::Value::Array(
+                pages
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|p| p["items"].clone())
+                    .collect()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_plugin_http_proxy() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 1024];
+            let read = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..read]).to_string()
+        });
+
+        let mut request = Request::new("http://runplat.dev/status".parse().unwrap());
+        request.http_proxy = Some(proxy);
+        request.no_proxy = Some("localhost".to_string());
+        let req = request.create_request().await.unwrap();
+        let resp = request.client().unwrap()(req).await.unwrap();
+        assert_eq!(200, resp.status().as_u16());
+
+        let received = server.await.unwrap();
+        assert!(received.starts_with("GET http://runplat.dev/status HTTP/1.1\r\n"));
+
+        request.http_proxy = Some("https://proxy.internal".to_string());
+        assert!(request.client().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request_plugin_multipart() {
+        let dir = PathBuf::from(".test").join("test_request_multipart");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("avatar.png");
+        std::fs::write(&path, [0x89, b'P', b'N', b'G']).unwrap();
+
+        let mut request = Request::new("http://localhost/upload".parse().unwrap());
+        request.method = Some("POST".to_string());
+        request.multipart = vec![
+            MultipartPart::Text {
+                name: "description".to_string(),
+                value: "avatar".to_string(),
+            },
+            MultipartPart::File {
+                name: "avatar".to_string(),
+                file: path,
+                filename: None,
+                content_type: Some("image/png".to_string()),
+            },
+        ];
+        let req = request.create_request().await.unwrap();
+        let content_type = req.headers()[header::CONTENT_TYPE].to_str().unwrap().to_string();
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .expect("should be a multipart content type")
+            .to_string();
+        let content_length = req.headers()[header::CONTENT_LENGTH].clone();
+
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body.len().to_string(), content_length.to_str().unwrap());
+        let expected = [
+            format!("--{boundary}\r\nContent-Disposition: form-data; name=\"avatar\"; filename=\"avatar.png\"\r\nContent-Type: image/png\r\n\r\n").into_bytes(),
+            vec![0x89, b'P', b'N', b'G'],
+            format!("\r\n--{boundary}--\r\n").into_bytes(),
+        ]
+        .concat();
+        assert!(body.ends_with(&expected));
+
+        request.json = Some("{}".to_string());
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_request_plugin_decode_headers() {
+        let mut request = Request::new("https://example.com".parse().unwrap());
+        request.headers = vec![
+            "accept=text/plain".to_string(),
+            "x-custom=hello%20world".to_string(),
+        ];
+
+        let builder = request.set_headers(RequestBuilder::new());
+        assert_eq!("hello%20world", builder.headers_ref().unwrap()["x-custom"]);
+
+        request.decode_headers = true;
+        let builder = request.set_headers(RequestBuilder::new());
+        assert_eq!("hello world", builder.headers_ref().unwrap()["x-custom"]);
+    }
+
+    #[tokio::test]
+    async fn test_request_plugin_query() {
+        let request: Request = toml::from_str(
+            r#"
+url = "https://example.com/search?sort=asc"
+query = ["q=hello world", "tag=a&b", "verbose"]
+"#,
+        )
+        .unwrap();
+
+        let req = request.create_request().await.unwrap();
+        assert_eq!(
+            "https://example.com/search?sort=asc&q=hello+world&tag=a%26b&verbose",
+            req.uri().to_string()
+        );
+        assert_eq!("example.com", req.headers()[header::HOST]);
+
+        let TestSubcommands::Test(args) = TestParser::try_parse_from([
+            "testparser",
+            "test",
+            "--url",
+            "https://example.com/search",
+            "--query",
+            "'page=2'",
+            "run",
+        ])
+        .unwrap()
+        .command;
+        let request = Request::from(&args);
+        let req = request.create_request().await.unwrap();
+        assert_eq!("https://example.com/search?page=2", req.uri().to_string());
+    }
+
     #[tokio::test]
     async fn test_request_plugin_call() {
         let mut state = State::new();
@@ -748,6 +2180,28 @@ headers = [
         args.take_request().expect("should have loaded a request")
     }
 
+    #[test]
+    fn test_request_from_request_args() {
+        let TestSubcommands::Test(args) = TestParser::try_parse_from([
+            "testparser",
+            "test",
+            "--url",
+            "https://jsonplaceholder.typicode.com/posts",
+            "--post",
+            "--header",
+            "'accept: application/json'",
+            "run",
+        ])
+        .unwrap()
+        .command;
+
+        let req = Request::from(&args);
+        assert_eq!("https://jsonplaceholder.typicode.com/posts", req.url());
+        assert_eq!(Some("POST"), req.method.as_deref());
+        assert_eq!(vec!["accept: application/json"], req.headers);
+        assert!(req.response.is_none());
+    }
+
     #[tokio::test]
     async fn test_request_args_parse_args_plugin_loading_single_arg() {
         let req = test_mock_request_args([
@@ -862,6 +2316,20 @@ headers = [
         ()
     }
 
+    #[tokio::test]
+    async fn test_request_args_parse_args_plugin_loading_request_method() {
+        let req = test_mock_request_args([
+            "testparser",
+            "test",
+            "-X",
+            "PURGE",
+            "--url",
+            "https://jsonplaceholder.typicode.com/posts",
+            "run",
+        ]);
+        assert_eq!("PURGE", req.method.clone().unwrap());
+    }
+
     #[tokio::test]
     async fn test_request_args_parse_args_plugin_loading_headers() {
         let req = test_mock_request_args([
@@ -0,0 +1,106 @@
+use std::future::Future;
+
+use futures_util::{stream, StreamExt};
+use tokio_util::sync::CancellationToken;
+
+use super::with_cancel;
+
+/// Runs `f` over each item w/ at most `concurrency` futures in flight and returns the results in the order of `items`
+///
+/// Items are only pulled from `items` once a slot is available, so a slow `f` applies backpressure to the iterator
+/// instead of buffering every item. Once `cancel` is cancelled, in-flight and remaining items complete w/ a
+/// `PluginCallCancelled` error.
+///
+/// **Note**: A `concurrency` of `0` is treated as `1`
+///
+/// # Example
+/// ```ignore
+/// bind.defer(|_, ct| async move {
+///     let responses = bounded_map(urls, 8, ct, |url| async move { fetch(url).await }).await;
+///     ...
+/// })
+/// ```
+pub async fn bounded_map<I, F, Fut, O>(
+    items: I,
+    concurrency: usize,
+    cancel: CancellationToken,
+    mut f: F,
+) -> Vec<reality::Result<O>>
+where
+    I: IntoIterator,
+    F: FnMut(I::Item) -> Fut,
+    Fut: Future<Output = reality::Result<O>>,
+{
+    stream::iter(items)
+        .map(|item| {
+            let fut = f(item);
+            let cancel = cancel.clone();
+            async move {
+                with_cancel(cancel)
+                    .run(fut)
+                    .await
+                    .and_then(std::convert::identity)
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn test_bounded_map() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let results = bounded_map(0..10u64, 3, CancellationToken::new(), |i| {
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                // Later items finish first to check that results keep the order of the items
+                tokio::time::sleep(Duration::from_millis(20 - i)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                if i == 5 {
+                    Err(reality::Error::PluginNotFound)
+                } else {
+                    Ok(i * 2)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(10, results.len());
+        assert_eq!(3, max_in_flight.load(Ordering::SeqCst));
+        assert!(results[5].is_err());
+        for (i, result) in results.iter().enumerate().filter(|(i, _)| *i != 5) {
+            assert_eq!(i as u64 * 2, *result.as_ref().unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bounded_map_cancelled() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let results = bounded_map(0..4, 0, cancel, |i| async move {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(i)
+        })
+        .await;
+
+        assert_eq!(4, results.len());
+        assert!(results
+            .iter()
+            .all(|r| matches!(r, Err(reality::Error::PluginCallCancelled { .. }))));
+    }
+}
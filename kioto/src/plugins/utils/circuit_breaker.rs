@@ -0,0 +1,416 @@
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use reality::{
+    plugin::{Bind, Call, CallOutcome, Handler, Work},
+    CallResult, Content, Plugin, Resource, Uuid, Version,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::debug;
+
+/// Capacity of the circuit transition channel
+const TRANSITION_CAPACITY: usize = 64;
+
+/// State of a circuit breaker
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls are passed through to the target plugin
+    Closed,
+    /// Calls fail fast w/o calling the target plugin
+    Open,
+    /// The cooldown has elapsed and the next call is a trial, which closes the circuit if successful. Other calls fail
+    /// fast while the trial call is in progress.
+    HalfOpen,
+}
+
+/// Event published when the circuit of a circuit breaker changes state
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitTransition {
+    /// Name of the circuit breaker plugin
+    pub name: String,
+    /// State the circuit transitioned from
+    pub from: CircuitState,
+    /// State the circuit transitioned to
+    pub to: CircuitState,
+}
+
+/// Handler plugin that wraps calls to a target plugin and stops calling the target after consecutive failures
+///
+/// After `failure_threshold` consecutive failures the circuit opens and calls fail fast w/ a plugin call error. Once
+/// `cooldown_ms` has elapsed the circuit half-opens and the next call is passed through as a trial. Only one trial call
+/// is in progress at a time, other calls fail fast until it completes. If the trial call succeeds the circuit closes,
+/// otherwise the circuit opens again.
+///
+/// **Note**: State transitions are published as a `CircuitTransition` to receivers returned by `subscribe`, and are
+/// emitted as `circuit_breaker_transition` tracing events
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CircuitBreaker<T> {
+    /// Number of consecutive failures before the circuit opens
+    #[serde(default = "default_failure_threshold")]
+    failure_threshold: u32,
+    /// Milliseconds to wait before an open circuit allows a trial call
+    #[serde(default = "default_cooldown_ms")]
+    cooldown_ms: u64,
+    /// Failure tracking state
+    #[serde(skip)]
+    breaker: Arc<Mutex<Breaker>>,
+    /// Target plugin type
+    #[serde(skip)]
+    _target: PhantomData<T>,
+}
+
+/// Failure tracking state of a circuit breaker
+struct Breaker {
+    /// Current state of the circuit
+    state: CircuitState,
+    /// Number of consecutive failures
+    failures: u32,
+    /// Instant the circuit was last opened
+    opened_at: Option<Instant>,
+    /// True while a trial call is in progress in the half-open state
+    probing: bool,
+    /// Sender used to publish state transitions
+    transitions: broadcast::Sender<CircuitTransition>,
+}
+
+impl Default for Breaker {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            failures: 0,
+            opened_at: None,
+            probing: false,
+            transitions: broadcast::channel(TRANSITION_CAPACITY).0,
+        }
+    }
+}
+
+impl Breaker {
+    /// Transitions the circuit to a new state and publishes the transition to subscribers
+    fn transition(&mut self, name: &str, to: CircuitState) {
+        if self.state != to {
+            debug!(name, from = ?self.state, to = ?to, "circuit_breaker_transition");
+            // **Note**: Sending only fails if there are no subscribers
+            let _ = self.transitions.send(CircuitTransition {
+                name: name.to_string(),
+                from: self.state,
+                to,
+            });
+            self.state = to;
+        }
+    }
+}
+
+impl<T> CircuitBreaker<T> {
+    /// Creates a new circuit breaker
+    #[inline]
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown_ms: cooldown.as_millis() as u64,
+            breaker: Arc::new(Mutex::new(Breaker::default())),
+            _target: PhantomData,
+        }
+    }
+
+    /// Returns the current state of the circuit
+    #[inline]
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Returns the number of consecutive failures
+    #[inline]
+    pub fn failures(&self) -> u32 {
+        self.lock().failures
+    }
+
+    /// Returns a receiver for state transitions of the circuit
+    ///
+    /// **Note**: Clones of this circuit breaker share the same circuit, so a receiver can be subscribed from the plugin
+    /// before it is loaded
+    #[inline]
+    pub fn subscribe(&self) -> broadcast::Receiver<CircuitTransition> {
+        self.lock().transitions.subscribe()
+    }
+
+    /// Locks the failure tracking state
+    #[inline]
+    fn lock(&self) -> std::sync::MutexGuard<'_, Breaker> {
+        match self.breaker.lock() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        }
+    }
+}
+
+impl<T: Plugin> CircuitBreaker<T> {
+    /// Returns whether a call can be passed through to the target plugin, and if so whether the call is a trial call
+    ///
+    /// If the circuit is open and the cooldown has elapsed, the circuit half-opens. Returns None if the circuit is open,
+    /// or if the circuit is half-open and a trial call is already in progress.
+    fn try_acquire(&self) -> Option<bool> {
+        let mut breaker = self.lock();
+        match breaker.state {
+            CircuitState::Closed => Some(false),
+            CircuitState::HalfOpen if breaker.probing => None,
+            CircuitState::HalfOpen => {
+                breaker.probing = true;
+                Some(true)
+            }
+            CircuitState::Open => {
+                let cooldown = Duration::from_millis(self.cooldown_ms);
+                if breaker.opened_at.is_some_and(|o| o.elapsed() >= cooldown) {
+                    breaker.transition(&Self::name().to_string(), CircuitState::HalfOpen);
+                    breaker.probing = true;
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Records the outcome of a call to the target plugin, `probe` is true if the call was a trial call
+    ///
+    /// Skipped and cancelled calls are not counted as failures, if a trial call is skipped or cancelled the next call is
+    /// a trial call
+    fn record(&self, outcome: CallOutcome, probe: bool) {
+        let name = Self::name().to_string();
+        let mut breaker = self.lock();
+        if probe {
+            breaker.probing = false;
+        }
+        match outcome {
+            CallOutcome::Ok => {
+                breaker.failures = 0;
+                breaker.transition(&name, CircuitState::Closed);
+            }
+            CallOutcome::Error => {
+                breaker.failures += 1;
+                if breaker.state == CircuitState::HalfOpen
+                    || breaker.failures >= self.failure_threshold
+                {
+                    breaker.opened_at = Some(Instant::now());
+                    breaker.transition(&name, CircuitState::Open);
+                }
+            }
+            CallOutcome::Cancelled | CallOutcome::Skipped => {}
+        }
+    }
+}
+
+impl<T> Clone for CircuitBreaker<T> {
+    fn clone(&self) -> Self {
+        Self {
+            failure_threshold: self.failure_threshold,
+            cooldown_ms: self.cooldown_ms,
+            breaker: self.breaker.clone(),
+            _target: PhantomData,
+        }
+    }
+}
+
+impl<T: Plugin> Plugin for CircuitBreaker<T> {
+    fn call(bind: Bind<Self>) -> CallResult {
+        bind.skip()
+    }
+
+    fn version() -> Version {
+        Version::parse(env!("CARGO_PKG_VERSION"))
+            .expect("should be successful because cargo would not compile")
+    }
+}
+
+impl<T: Plugin> Handler for CircuitBreaker<T> {
+    type Target = T;
+
+    fn handle(_: Bind<Self::Target>, _: Bind<Self>) -> reality::Result<()> {
+        Ok(())
+    }
+
+    /// Decides whether to call the target plugin before the target's work is started, and records the outcome
+    /// of the call after the work completes
+    fn wrap_thunk(call: Call) -> reality::Result<Work> {
+        let breaker = call
+            .handler()
+            .and_then(|a| call.state().find_plugin(a))
+            .or_else(|| call.state().find_plugin(Self::name().path()))
+            .and_then(|h| h.borrow::<Self>())
+            .cloned();
+
+        match breaker {
+            Some(breaker) => {
+                let Some(probe) = breaker.try_acquire() else {
+                    return Err(reality::Error::PluginCallError {
+                        name: Self::name(),
+                        message: "Circuit is open".to_string(),
+                    });
+                };
+
+                let bound = match T::thunk(call.clone()) {
+                    Ok(work) => call.bind::<T>().map(|bind| (work, bind)),
+                    Err(err) => Err(err),
+                };
+                let (work, bind) = match bound {
+                    Ok(bound) => bound,
+                    Err(err) => {
+                        breaker.record(CallOutcome::of(&Err(err.clone())), probe);
+                        return Err(err);
+                    }
+                };
+
+                bind.defer(move |_, _| async move {
+                    let result = work.await;
+                    breaker.record(CallOutcome::of(&result), probe);
+                    result
+                })
+            }
+            None => T::thunk(call),
+        }
+    }
+}
+
+impl<T: Plugin> Resource for CircuitBreaker<T> {}
+impl<T> Content for CircuitBreaker<T> {
+    fn state_uuid(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Default number of consecutive failures before the circuit opens
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+/// Default cooldown before an open circuit allows a trial call
+fn default_cooldown_ms() -> u64 {
+    30_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reality::{repr::Labels, State};
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    struct Flaky {
+        fail: Arc<AtomicBool>,
+        slow: Arc<AtomicBool>,
+        calls: Arc<AtomicU32>,
+    }
+
+    impl Plugin for Flaky {
+        fn call(bind: Bind<Self>) -> CallResult {
+            bind.work(|p, _| {
+                p.calls.fetch_add(1, Ordering::SeqCst);
+                let fail = p.fail.load(Ordering::SeqCst);
+                let slow = p.slow.load(Ordering::SeqCst);
+                async move {
+                    if slow {
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                    }
+                    if fail {
+                        Err(reality::Error::PluginCallError {
+                            name: Flaky::name(),
+                            message: "flaky".to_string(),
+                        })
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+        }
+
+        fn version() -> Version {
+            Version::new(0, 1, 0)
+        }
+    }
+
+    impl Resource for Flaky {}
+    impl Content for Flaky {
+        fn state_uuid(&self) -> Uuid {
+            Uuid::new_v4()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker() {
+        let fail = Arc::new(AtomicBool::new(true));
+        let slow = Arc::new(AtomicBool::new(false));
+        let calls = Arc::new(AtomicU32::new(0));
+        let mut state = State::new();
+        let target = state.load(
+            Flaky {
+                fail: fail.clone(),
+                slow: slow.clone(),
+                calls: calls.clone(),
+            },
+            Labels::default(),
+        );
+        let breaker = CircuitBreaker::<Flaky>::new(2, Duration::from_millis(50));
+        let mut transitions = breaker.subscribe();
+        let handler = state.load_handler(breaker, Labels::default());
+
+        let call = || async {
+            let mut event = state.event(&target).unwrap();
+            event
+                .with_handler::<CircuitBreaker<Flaky>>(handler.clone())
+                .unwrap();
+            event.start().await
+        };
+        let circuit = || {
+            state
+                .find_plugin(&handler)
+                .and_then(|h| h.borrow::<CircuitBreaker<Flaky>>())
+                .map(|b| b.state())
+        };
+
+        assert!(call().await.is_err());
+        assert_eq!(Some(CircuitState::Closed), circuit());
+        assert!(call().await.is_err());
+        assert_eq!(Some(CircuitState::Open), circuit());
+
+        // Fails fast w/o calling the target
+        assert!(call().await.is_err());
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+
+        // Only one trial call is passed through after the cooldown, a failed trial opens the circuit again
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        slow.store(true, Ordering::SeqCst);
+        let (trial, rejected) = tokio::join!(call(), call());
+        assert!(trial.is_err());
+        assert!(rejected.is_err());
+        assert_eq!(3, calls.load(Ordering::SeqCst));
+        assert_eq!(Some(CircuitState::Open), circuit());
+
+        // Trial call after the cooldown closes the circuit
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        slow.store(false, Ordering::SeqCst);
+        fail.store(false, Ordering::SeqCst);
+        call().await.unwrap();
+        assert_eq!(Some(CircuitState::Closed), circuit());
+        assert_eq!(4, calls.load(Ordering::SeqCst));
+
+        let mut received = vec![];
+        while let Ok(transition) = transitions.try_recv() {
+            assert_eq!(CircuitBreaker::<Flaky>::name().to_string(), transition.name);
+            received.push((transition.from, transition.to));
+        }
+        assert_eq!(
+            vec![
+                (CircuitState::Closed, CircuitState::Open),
+                (CircuitState::Open, CircuitState::HalfOpen),
+                (CircuitState::HalfOpen, CircuitState::Open),
+                (CircuitState::Open, CircuitState::HalfOpen),
+                (CircuitState::HalfOpen, CircuitState::Closed),
+            ],
+            received
+        );
+    }
+}
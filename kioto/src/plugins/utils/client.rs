@@ -1,17 +1,32 @@
-use std::{future::Future, pin::Pin, process::Output};
+use std::{future::Future, pin::Pin, sync::Arc};
 
-use crate::plugins::{Process, Request};
-use hyper::body::Incoming;
-use reality::{plugin::{Handler, MessageData}, Content, Plugin, Resource, Uuid, Version};
 use super::with_cancel;
+#[cfg(feature = "http")]
+use super::ResponseLimit;
+#[cfg(feature = "process")]
+use crate::plugins::Process;
+#[cfg(feature = "http")]
+use crate::plugins::Request;
+#[cfg(feature = "process")]
+use bytes::Bytes;
+#[cfg(feature = "http")]
+use hyper::body::Incoming;
+#[cfg(any(feature = "http", feature = "process"))]
+use reality::plugin::Handler;
+use reality::{plugin::MessageData, Content, Plugin, Resource, Uuid, Version};
+#[cfg(feature = "process")]
+use std::process::Output;
 
 /// Type-alias for a process client
+#[cfg(feature = "process")]
 pub type ProcessClient = Client<Output>;
 
 /// Type-alias for an http request client
+#[cfg(feature = "http")]
 pub type HttpRequestClient = Client<IncomingResponse>;
 
 /// Type-alias for an incoming response from an http request
+#[cfg(feature = "http")]
 pub type IncomingResponse = hyper::Response<Incoming>;
 
 /// Type-alias for a client return function
@@ -38,6 +53,91 @@ impl<R> Client<R> {
     ) -> Self {
         Self { returns: Box::new(next), result: None }
     }
+
+    /// Returns a client that transforms the message data returned by this client w/ `f`
+    ///
+    /// `f` is not called if this client returns an error
+    #[inline]
+    pub fn map(self, f: impl Fn(MessageData) -> MessageData + Send + Sync + 'static) -> Self
+    where
+        R: 'static,
+    {
+        let f = Arc::new(f);
+        self.and_then(move |data| {
+            let f = f.clone();
+            async move { Ok(f(data)) }
+        })
+    }
+
+    /// Returns a client that passes the message data returned by this client to `f`, returning the output of `f`
+    ///
+    /// `f` is not called if this client returns an error, and an error returned by `f` is returned by the client
+    #[inline]
+    pub fn and_then<F>(self, f: impl Fn(MessageData) -> F + Send + Sync + 'static) -> Self
+    where
+        R: 'static,
+        F: Future<Output = reality::Result<MessageData>> + Send + 'static,
+    {
+        let returns = self.returns;
+        let f = Arc::new(f);
+        Self {
+            returns: Box::new(move |r| {
+                let data = returns(r);
+                let f = f.clone();
+                Box::pin(async move { f(data.await?).await })
+            }),
+            result: self.result,
+        }
+    }
+}
+
+#[cfg(feature = "process")]
+impl Default for ProcessClient {
+    /// Creates a client that returns the process's stdout as `MessageData::Bytes`
+    ///
+    /// If the process did not write to stdout, `MessageData::Empty` is returned
+    fn default() -> Self {
+        Self::new(|output| {
+            Box::pin(async move {
+                if output.stdout.is_empty() {
+                    Ok(MessageData::Empty)
+                } else {
+                    Ok(MessageData::Bytes(Bytes::from(output.stdout)))
+                }
+            })
+        })
+    }
+}
+
+#[cfg(feature = "http")]
+impl Default for HttpRequestClient {
+    /// Creates a client that collects the response body and returns it as `MessageData::Json` if the response has a
+    /// json content type, otherwise as `MessageData::Bytes`
+    ///
+    /// If the response does not have a body, `MessageData::Empty` is returned. The body is read w/ the limit set by the
+    /// request plugin, see `ResponseLimit::apply`
+    ///
+    /// **Note**: A json body that is not an object or array is returned as `MessageData::Empty`, see
+    /// `From<serde_json::Value> for MessageData`
+    fn default() -> Self {
+        Self::new(|response| Box::pin(MessageData::from_response(ResponseLimit::apply(response))))
+    }
+}
+
+#[cfg(feature = "http")]
+impl HttpRequestClient {
+    /// Creates a client that collects the response into a `MessageData::Json` envelope w/ the status, headers and body
+    /// of the response, see `MessageData::from_response_envelope`
+    ///
+    /// The body is read w/ the limit set by the request plugin, see `ResponseLimit::apply`
+    #[inline]
+    pub fn envelope() -> Self {
+        Self::new(|response| {
+            Box::pin(MessageData::from_response_envelope(ResponseLimit::apply(
+                response,
+            )))
+        })
+    }
 }
 
 impl<R: Send + Sync + 'static> Plugin for Client<R> {
@@ -47,8 +147,7 @@ impl<R: Send + Sync + 'static> Plugin for Client<R> {
                 let returns = with_cancel(ct)
                     .run((binding.receiver()?.returns)(r))
                     .await??;
-                let reply_to = binding.item().commit();
-                binding.broker().send(reply_to, returns)
+                binding.emit(returns)
             } else {
                 Err(reality::Error::PluginCallSkipped)
             }
@@ -60,6 +159,7 @@ impl<R: Send + Sync + 'static> Plugin for Client<R> {
     }
 }
 
+#[cfg(feature = "http")]
 impl Handler for Client<IncomingResponse> {
     type Target = Request;
 
@@ -72,6 +172,7 @@ impl Handler for Client<IncomingResponse> {
     }
 }
 
+#[cfg(feature = "process")]
 impl Handler for Client<std::process::Output> {
     type Target = Process;
 
@@ -90,3 +191,94 @@ impl<R> Content for Client<R> {
         Uuid::new_v4()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    #[tokio::test]
+    async fn test_client_map_and_then() {
+        let client = Client::<String>::new(|r| {
+            Box::pin(async move { Ok(serde_json::json!({ "name": r }).into()) })
+        })
+        .and_then(|data| async move {
+            let name = data
+                .as_json()
+                .and_then(|j| j.get("name"))
+                .and_then(|n| n.as_str())
+                .map(str::to_string)
+                .ok_or(reality::Error::PluginCallSkipped)?;
+            Ok(MessageData::Bytes(Bytes::from(name)))
+        })
+        .map(|data| match data.as_bytes() {
+            Some(bytes) => MessageData::Bytes(bytes.slice(..2)),
+            None => data,
+        });
+
+        let data = (client.returns)(String::from("kioto")).await.unwrap();
+        assert_eq!(Some(&Bytes::from("ki")), data.as_bytes());
+
+        let client = Client::<String>::new(|_| Box::pin(async move { Ok(MessageData::Empty) }))
+            .and_then(|_| async move { Err(reality::Error::PluginCallSkipped) })
+            .map(|_| panic!("should not be called after an error"));
+        assert_eq!(
+            Err(reality::Error::PluginCallSkipped),
+            (client.returns)(String::new()).await.map(|_| ())
+        );
+    }
+
+    #[cfg(all(unix, feature = "http"))]
+    #[tokio::test]
+    async fn test_http_request_client_default() {
+        use crate::plugins::request::tests::unix_server;
+        use reality::{repr::Labels, State};
+
+        let responses = [
+            ("application/json; charset=utf-8", r#"{"id":1}"#),
+            ("text/plain", "ok"),
+            ("application/json", ""),
+        ];
+        let (path, server) = unix_server(
+            "test_http_request_client_default",
+            responses
+                .map(|(content_type, body)| {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\n\r\n{body}",
+                        body.len()
+                    )
+                })
+                .to_vec(),
+        );
+
+        let mut state = State::new();
+        let request = state
+            .load_by_toml::<Request>(
+                &format!(
+                    "url = \"http://localhost/\"\nunix_socket = \"{}\"",
+                    path.display()
+                ),
+                Labels::default(),
+            )
+            .unwrap();
+        let client = state.load_handler(HttpRequestClient::default(), Labels::default());
+
+        let mut returns = vec![];
+        for _ in responses {
+            returns.push(
+                state
+                    .call_with_handler::<HttpRequestClient>(&request, client.clone())
+                    .await
+                    .unwrap(),
+            );
+        }
+        server.await.unwrap();
+
+        assert_eq!(
+            Some(&serde_json::json!(1)),
+            returns[0].as_json().and_then(|j| j.get("id"))
+        );
+        assert_eq!(Some(&Bytes::from("ok")), returns[1].as_bytes());
+        assert!(returns[2].is_empty());
+    }
+}
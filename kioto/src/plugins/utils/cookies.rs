@@ -0,0 +1,359 @@
+use hyper::{header, HeaderMap};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+use url::Url;
+
+/// Jar of cookies received from `Set-Cookie` response headers, which are replayed on requests to matching urls
+///
+/// Cloning the jar shares the cookies w/ the clone.
+///
+/// **Note**: Cookies are matched by domain, path, and the `Secure` attribute, other attributes are ignored
+#[derive(Clone, Debug, Default)]
+pub struct CookieJar {
+    cookies: Arc<Mutex<Vec<Cookie>>>,
+}
+
+/// Cookie stored in a cookie jar
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Cookie {
+    name: String,
+    value: String,
+    /// Domain of the cookie, w/o a leading `.`
+    domain: String,
+    /// True if the cookie did not set a `Domain` attribute, and should only be sent to the host that set it
+    host_only: bool,
+    path: String,
+    secure: bool,
+    expires: Option<SystemTime>,
+}
+
+impl CookieJar {
+    /// Stores the cookies set by the `Set-Cookie` headers of a response to a request for `url`
+    ///
+    /// A cookie replaces a stored cookie w/ the same name, domain, and path. A cookie that has expired removes the
+    /// stored cookie instead. Cookies w/ a domain that does not match the url are ignored.
+    pub fn store(&self, url: &Url, headers: &HeaderMap) {
+        let Some(host) = url.host_str() else {
+            return;
+        };
+
+        let mut cookies = self.lock();
+        let now = SystemTime::now();
+        for cookie in headers
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .filter_map(|v| Cookie::parse(v, host, url.path(), now))
+        {
+            cookies.retain(|c| {
+                c.name != cookie.name || c.domain != cookie.domain || c.path != cookie.path
+            });
+            if !cookie.is_expired(now) {
+                cookies.push(cookie);
+            }
+        }
+    }
+
+    /// Returns the value of the `Cookie` header to send w/ a request for `url`, or None if no cookies match
+    ///
+    /// Cookies w/ longer paths are listed first
+    pub fn header_for(&self, url: &Url) -> Option<String> {
+        let host = url.host_str()?;
+        let now = SystemTime::now();
+        let mut cookies = self.lock();
+        cookies.retain(|c| !c.is_expired(now));
+
+        let mut matching = cookies
+            .iter()
+            .filter(|c| c.matches(host, url.path(), url.scheme() == "https"))
+            .collect::<Vec<_>>();
+        matching.sort_by_key(|c| std::cmp::Reverse(c.path.len()));
+
+        let header = matching
+            .iter()
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        (!header.is_empty()).then_some(header)
+    }
+
+    /// Returns the number of cookies in the jar
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns true if the jar does not have any cookies
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Removes all cookies from the jar
+    #[inline]
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    #[inline]
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Cookie>> {
+        match self.cookies.lock() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        }
+    }
+}
+
+impl Cookie {
+    /// Returns true if the cookie has an expiry that is not after `now`
+    #[inline]
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.is_some_and(|e| e <= now)
+    }
+
+    /// Parses the value of a `Set-Cookie` header received from `host` for a request to `request_path`
+    ///
+    /// Returns None if the header is malformed, or if the `Domain` attribute does not match the host
+    fn parse(value: &str, host: &str, request_path: &str, now: SystemTime) -> Option<Self> {
+        let mut attributes = value.split(';');
+        let (name, value) = attributes.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Cookie {
+            name: name.to_string(),
+            value: value.trim().trim_matches('"').to_string(),
+            domain: host.to_lowercase(),
+            host_only: true,
+            path: default_path(request_path),
+            secure: false,
+            expires: None,
+        };
+
+        let mut max_age = None;
+        for attribute in attributes {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_lowercase();
+                    if !domain_matches(&cookie.domain, &domain) {
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "secure" => cookie.secure = true,
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "expires" => {
+                    if let Some(expires) = parse_http_date(value) {
+                        cookie.expires = Some(expires);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Max-Age takes precedence over Expires, a Max-Age past the range of `SystemTime` never expires
+        match max_age {
+            Some(age) if age <= 0 => cookie.expires = Some(SystemTime::UNIX_EPOCH),
+            Some(age) => cookie.expires = now.checked_add(Duration::from_secs(age as u64)),
+            None => {}
+        }
+        Some(cookie)
+    }
+
+    /// Returns true if the cookie should be sent w/ a request to `host` and `path`
+    fn matches(&self, host: &str, path: &str, secure: bool) -> bool {
+        let host = host.to_lowercase();
+        let domain = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(&host, &self.domain)
+        };
+        domain && path_matches(path, &self.path) && (secure || !self.secure)
+    }
+}
+
+/// Returns true if `host` is equal to or a subdomain of `domain`
+#[inline]
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Returns true if `path` is equal to or nested under `cookie_path`
+#[inline]
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || path
+            .strip_prefix(cookie_path)
+            .is_some_and(|rest| cookie_path.ends_with('/') || rest.starts_with('/'))
+}
+
+/// Returns the default path of a cookie, i.e. the directory of the request path
+#[inline]
+fn default_path(request_path: &str) -> String {
+    match request_path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(idx) => request_path[..idx].to_string(),
+    }
+}
+
+/// Parses an http date, i.e. `Wed, 21 Oct 2015 07:28:00 GMT`
+///
+/// Returns None if the date is not in this format, or if the date is out of range
+pub(crate) fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let (_, date) = value.split_once(',')?;
+    let mut parts = date.split_whitespace();
+    let day = parts
+        .next()?
+        .parse::<i64>()
+        .ok()
+        .filter(|d| (1..=31).contains(d))?;
+    let month = match parts.next()?.to_lowercase().as_str() {
+        "jan" => 1,
+        "feb" => 2,
+        "mar" => 3,
+        "apr" => 4,
+        "may" => 5,
+        "jun" => 6,
+        "jul" => 7,
+        "aug" => 8,
+        "sep" => 9,
+        "oct" => 10,
+        "nov" => 11,
+        "dec" => 12,
+        _ => return None,
+    };
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let mut time = parts.next()?.split(':').map(|t| t.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+    if hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+
+    // Days since the unix epoch of the civil date
+    let y = if month <= 2 {
+        year.checked_sub(1)?
+    } else {
+        year
+    };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era
+        .checked_mul(146097)?
+        .checked_add(doe - 719468)
+        .and_then(|d| u64::try_from(d).ok())?;
+
+    let secs = days
+        .checked_mul(86400)?
+        .checked_add(hours * 3600 + minutes * 60 + seconds)?;
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn test_cookie_jar() {
+        let jar = CookieJar::default();
+        let url = Url::parse("https://api.example.com/auth/login").unwrap();
+        let mut headers = HeaderMap::new();
+        for cookie in [
+            "session=abc; Path=/; Secure; HttpOnly",
+            "scoped=1",
+            "shared=2; Domain=.example.com; Path=/",
+            "other=3; Domain=other.com",
+            "expired=4; Max-Age=0",
+        ] {
+            headers.append(header::SET_COOKIE, HeaderValue::from_static(cookie));
+        }
+        jar.store(&url, &headers);
+        assert_eq!(3, jar.len());
+
+        let header = |url: &str| jar.header_for(&Url::parse(url).unwrap());
+        assert_eq!(
+            Some("scoped=1; session=abc; shared=2".to_string()),
+            header("https://api.example.com/auth/refresh")
+        );
+        assert_eq!(
+            Some("session=abc; shared=2".to_string()),
+            header("https://api.example.com/authx")
+        );
+        assert_eq!(
+            Some("shared=2".to_string()),
+            header("http://www.example.com/")
+        );
+        assert_eq!(None, header("https://example.org/"));
+
+        // A cookie w/ the same name, domain, and path replaces the stored cookie, an expired cookie removes it
+        let mut headers = HeaderMap::new();
+        headers.append(
+            header::SET_COOKIE,
+            HeaderValue::from_static("session=def; Path=/"),
+        );
+        headers.append(
+            header::SET_COOKIE,
+            HeaderValue::from_static(
+                "shared=; Domain=example.com; Path=/; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+            ),
+        );
+        jar.store(&url, &headers);
+        assert_eq!(
+            Some("session=def".to_string()),
+            header("http://api.example.com/")
+        );
+
+        jar.clear();
+        assert!(jar.is_empty());
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        assert_eq!(
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1445412480)),
+            parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT")
+        );
+        assert_eq!(None, parse_http_date("not a date"));
+        assert_eq!(
+            None,
+            parse_http_date("Wed, 21 Oct 9223372036854775807 07:28:00 GMT")
+        );
+        assert_eq!(
+            None,
+            parse_http_date("Wed, 21 Oct 2015 18446744073709551615:28:00 GMT")
+        );
+        assert_eq!(None, parse_http_date("Wed, 99 Oct 2015 07:28:00 GMT"));
+
+        let now = SystemTime::UNIX_EPOCH;
+        let cookie = Cookie::parse("a=1; Max-Age=9223372036854775807", "example.com", "/", now);
+        assert!(!cookie.unwrap().is_expired(now));
+        let cookie = Cookie::parse(
+            "a=1; Max-Age=9223372036854775807",
+            "example.com",
+            "/",
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1445412480),
+        );
+        assert!(!cookie.unwrap().is_expired(now));
+        let cookie = Cookie::parse(
+            "a=1; Expires=Wed, 21 Oct 9223372036854775807 07:28:00 GMT",
+            "example.com",
+            "/",
+            now,
+        );
+        assert_eq!(None, cookie.unwrap().expires);
+    }
+}
@@ -0,0 +1,252 @@
+use std::{marker::PhantomData, path::PathBuf};
+
+use reality::{
+    plugin::{Bind, Call, Handler, MessageData, Work},
+    BincodeContent, CallResult, Content, Plugin, Resource, Uuid, Version,
+};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Handler plugin that forwards the output of a target plugin `A` to the input of plugin `B` and then calls `B`
+///
+/// After `A` completes, any message data sent to `A`'s commit id is forwarded to `B`. If `A` did not send any output,
+/// the state of `A` is serialized to a TOML table and forwarded instead.
+///
+/// `B` is resolved from the plugin path set by `to`, or from `B`'s plugin name if `to` is not set.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Forward<A, B> {
+    /// Path of the plugin to forward to
+    to: Option<String>,
+    /// Source and destination plugin types
+    #[serde(skip)]
+    _plugins: PhantomData<(A, B)>,
+}
+
+impl<A, B> Forward<A, B> {
+    /// Creates a new forward handler that forwards to the plugin at `to`
+    #[inline]
+    pub fn new(to: impl Into<String>) -> Self {
+        Self {
+            to: Some(to.into()),
+            _plugins: PhantomData,
+        }
+    }
+}
+
+impl<A: Plugin + Serialize, B: Plugin> Forward<A, B> {
+    /// Returns the path of the plugin to forward to
+    #[inline]
+    fn destination(&self) -> PathBuf {
+        self.to
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| B::name().path().clone())
+    }
+
+    /// Returns an error w/ a message describing why forwarding failed
+    #[inline]
+    fn forward_error(message: impl Into<String>) -> reality::Error {
+        reality::Error::PluginCallError {
+            name: Self::name(),
+            message: message.into(),
+        }
+    }
+}
+
+impl<A: Plugin + Serialize, B: Plugin> Plugin for Forward<A, B> {
+    fn call(bind: Bind<Self>) -> CallResult {
+        bind.skip()
+    }
+
+    fn version() -> Version {
+        Version::parse(env!("CARGO_PKG_VERSION"))
+            .expect("should be successful because cargo would not compile")
+    }
+}
+
+impl<A: Plugin + Serialize, B: Plugin> Handler for Forward<A, B> {
+    type Target = A;
+
+    fn handle(_: Bind<Self::Target>, _: Bind<Self>) -> reality::Result<()> {
+        Ok(())
+    }
+
+    /// Calls the target plugin, and after the target's work completes forwards the output and calls the destination
+    fn wrap_thunk(call: Call) -> reality::Result<Work> {
+        let dest = call
+            .handler()
+            .and_then(|a| call.state().find_plugin(a))
+            .or_else(|| call.state().find_plugin(Self::name().path()))
+            .and_then(|h| h.borrow::<Self>())
+            .map(|f| f.destination());
+
+        match dest {
+            Some(dest) => {
+                let work = A::thunk(call.clone())?;
+                let bind = call.bind::<A>()?;
+                let source = bind.item().clone();
+                let state = bind.state().clone();
+                bind.defer(|_, _| async move {
+                    work.await?;
+
+                    let output = match state.broker().receive(source.commit()) {
+                        MessageData::Empty => source
+                            .borrow::<A>()
+                            .ok_or_else(|| {
+                                Self::forward_error("Source plugin could not be borrowed")
+                            })
+                            .and_then(|a| {
+                                toml::Table::try_from(a).map_err(|e| {
+                                    Self::forward_error(format!(
+                                        "Could not serialize `{}` to forward: {e}",
+                                        A::name()
+                                    ))
+                                })
+                            })?
+                            .into(),
+                        output => output,
+                    };
+
+                    let target = state
+                        .find_plugin(&dest)
+                        .filter(|i| i.is_type::<B>())
+                        .ok_or_else(|| {
+                            Self::forward_error(format!(
+                                "Could not find `{}` at `{}` to forward to",
+                                B::name(),
+                                dest.display()
+                            ))
+                        })?
+                        .commit();
+
+                    debug!(
+                        from = A::name().to_string(),
+                        to = B::name().to_string(),
+                        "Forwarding output"
+                    );
+                    state.broker().send(target, output)?;
+                    state.event(dest)?.start().await
+                })
+            }
+            None => A::thunk(call),
+        }
+    }
+}
+
+impl<A: Plugin + Serialize, B: Plugin> Resource for Forward<A, B> {}
+impl<A, B> Content for Forward<A, B> {
+    fn state_uuid(&self) -> Uuid {
+        BincodeContent::new(self).unwrap().state_uuid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reality::{repr::Labels, State};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Serialize)]
+    struct Source {
+        value: u32,
+    }
+
+    impl Plugin for Source {
+        fn call(bind: Bind<Self>) -> CallResult {
+            bind.work(|_, _| async { Ok(()) })
+        }
+
+        fn version() -> Version {
+            Version::new(0, 1, 0)
+        }
+    }
+
+    impl Resource for Source {}
+    impl Content for Source {
+        fn state_uuid(&self) -> Uuid {
+            BincodeContent::new(self).unwrap().state_uuid()
+        }
+    }
+
+    struct Sink {
+        received: Arc<Mutex<Option<toml::Table>>>,
+        called: Arc<Mutex<bool>>,
+    }
+
+    impl Plugin for Sink {
+        fn receive(&self, data: MessageData) -> Option<Self> {
+            if let MessageData::Toml(table) = data {
+                *self.received.lock().unwrap() = Some(table);
+            }
+            None
+        }
+
+        fn call(bind: Bind<Self>) -> CallResult {
+            bind.work(|p, _| {
+                *p.called.lock().unwrap() = true;
+                async { Ok(()) }
+            })
+        }
+
+        fn version() -> Version {
+            Version::new(0, 1, 0)
+        }
+    }
+
+    impl Resource for Sink {}
+    impl Content for Sink {
+        fn state_uuid(&self) -> Uuid {
+            Uuid::new_v4()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forward() {
+        let received = Arc::new(Mutex::new(None));
+        let called = Arc::new(Mutex::new(false));
+        let mut state = State::new();
+        let source = state.load(Source { value: 7 }, Labels::default());
+        let sink = state.load(
+            Sink {
+                received: received.clone(),
+                called: called.clone(),
+            },
+            Labels::default(),
+        );
+        let handler = state.load_handler(
+            Forward::<Source, Sink>::new(sink.to_string()),
+            Labels::default(),
+        );
+
+        let mut event = state.event(&source).unwrap();
+        event
+            .with_handler::<Forward<Source, Sink>>(handler.clone())
+            .unwrap();
+        event.start().await.unwrap();
+        assert!(*called.lock().unwrap());
+        assert_eq!(
+            Some(7),
+            received
+                .lock()
+                .unwrap()
+                .as_ref()
+                .and_then(|t| t.get("value"))
+                .and_then(|v| v.as_integer())
+        );
+
+        // Fails w/ a descriptive error if the destination does not exist
+        let missing = state.load_handler(
+            Forward::<Source, Sink>::new("missing/plugins.sink"),
+            Labels::default(),
+        );
+        let mut event = state.event(&source).unwrap();
+        event
+            .with_handler::<Forward<Source, Sink>>(missing)
+            .unwrap();
+        let err = event.start().await.unwrap_err();
+        assert!(
+            matches!(err, reality::Error::PluginCallError { message, .. } if message.contains("missing/plugins.sink"))
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use std::{pin::Pin, task::Poll};
+
+use bytes::Bytes;
+use hyper::{
+    body::{Body, Frame, SizeHint},
+    Response,
+};
+
+/// Default maximum number of bytes read from a response body
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Maximum number of bytes read from a response body
+///
+/// The request plugin sets this as an extension on the responses it receives, see `ResponseLimit::apply`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResponseLimit {
+    /// Maximum number of bytes to read
+    pub max_bytes: usize,
+    /// If true, the body is truncated at the limit instead of returning an error
+    pub truncate: bool,
+}
+
+impl ResponseLimit {
+    /// Wraps the body of a response w/ the limit set as an extension on the response
+    ///
+    /// If the response does not have a limit, the body is read w/o a limit
+    #[inline]
+    pub fn apply<B>(response: Response<B>) -> Response<LimitedBody<B>> {
+        let limit = response.extensions().get::<ResponseLimit>().copied();
+        response.map(|body| LimitedBody::new(body, limit))
+    }
+}
+
+/// Body that stops reading from an inner body once a limit is reached
+///
+/// The limit is enforced as frames are read, so the body is never buffered past the limit
+pub struct LimitedBody<B> {
+    /// Body being read
+    inner: B,
+    /// Limit to enforce, if any
+    limit: Option<ResponseLimit>,
+    /// Number of bytes read so far
+    read: usize,
+    /// Set once the body was truncated
+    truncated: bool,
+}
+
+impl<B> LimitedBody<B> {
+    /// Creates a new limited body
+    #[inline]
+    pub fn new(inner: B, limit: Option<ResponseLimit>) -> Self {
+        Self {
+            inner,
+            limit,
+            read: 0,
+            truncated: false,
+        }
+    }
+}
+
+impl<B> Body for LimitedBody<B>
+where
+    B: Body<Data = Bytes> + Unpin,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Data = Bytes;
+
+    type Error = std::io::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<std::result::Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        if this.truncated {
+            return Poll::Ready(None);
+        }
+
+        let frame = match Pin::new(&mut this.inner).poll_frame(cx) {
+            Poll::Ready(Some(Ok(frame))) => frame,
+            Poll::Ready(Some(Err(err))) => {
+                return Poll::Ready(Some(Err(std::io::Error::other(err))))
+            }
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let (Some(limit), Some(data)) = (this.limit, frame.data_ref()) else {
+            return Poll::Ready(Some(Ok(frame)));
+        };
+
+        let remaining = limit.max_bytes.saturating_sub(this.read);
+        if data.len() <= remaining {
+            this.read += data.len();
+            Poll::Ready(Some(Ok(frame)))
+        } else if limit.truncate {
+            this.read = limit.max_bytes;
+            this.truncated = true;
+            Poll::Ready(Some(Ok(Frame::data(data.slice(..remaining)))))
+        } else {
+            Poll::Ready(Some(Err(std::io::Error::other(format!(
+                "Response body exceeds the maximum of {} bytes",
+                limit.max_bytes
+            )))))
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.truncated || self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let hint = self.inner.size_hint();
+        match self.limit {
+            Some(limit) if limit.truncate => {
+                let max = limit.max_bytes.saturating_sub(self.read) as u64;
+                let mut limited = SizeHint::new();
+                limited.set_lower(hint.lower().min(max));
+                limited.set_upper(hint.upper().map_or(max, |u| u.min(max)));
+                limited
+            }
+            _ => hint,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::{BodyExt, Full};
+
+    /// Returns a response w/ a limit and body
+    fn response(limit: Option<ResponseLimit>, body: &'static [u8]) -> Response<Full<Bytes>> {
+        let mut response = Response::new(Full::new(Bytes::from_static(body)));
+        if let Some(limit) = limit {
+            response.extensions_mut().insert(limit);
+        }
+        response
+    }
+
+    #[tokio::test]
+    async fn test_response_limit() {
+        let limit = ResponseLimit {
+            max_bytes: 4,
+            truncate: false,
+        };
+        let body = ResponseLimit::apply(response(Some(limit), b"kioto")).into_body();
+        assert!(body.collect().await.is_err());
+
+        let body = ResponseLimit::apply(response(Some(limit), b"kiot")).into_body();
+        assert_eq!(
+            Bytes::from("kiot"),
+            body.collect().await.unwrap().to_bytes()
+        );
+
+        let limit = ResponseLimit {
+            max_bytes: 2,
+            truncate: true,
+        };
+        let body = ResponseLimit::apply(response(Some(limit), b"kioto")).into_body();
+        assert_eq!(2, body.size_hint().exact().unwrap());
+        assert_eq!(Bytes::from("ki"), body.collect().await.unwrap().to_bytes());
+
+        let body = ResponseLimit::apply(response(None, b"kioto")).into_body();
+        assert_eq!(
+            Bytes::from("kioto"),
+            body.collect().await.unwrap().to_bytes()
+        );
+    }
+}
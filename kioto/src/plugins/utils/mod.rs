@@ -1,7 +1,61 @@
+mod bounded;
+mod circuit_breaker;
 mod client;
+#[cfg(feature = "http")]
+mod cookies;
+mod forward;
+#[cfg(feature = "http")]
+mod limit;
+#[cfg(feature = "http")]
+mod multipart;
+#[cfg(feature = "http")]
+mod paginate;
+#[cfg(feature = "http")]
+mod proxy;
+#[cfg(feature = "http")]
+mod retry;
+#[cfg(feature = "http")]
+mod sse;
+pub use bounded::bounded_map;
+pub use circuit_breaker::CircuitBreaker;
+pub use circuit_breaker::CircuitState;
+pub use circuit_breaker::CircuitTransition;
 pub use client::Client;
+#[cfg(feature = "http")]
 pub use client::HttpRequestClient;
+#[cfg(feature = "process")]
 pub use client::ProcessClient;
+#[cfg(feature = "http")]
+pub use cookies::CookieJar;
+pub use forward::Forward;
+#[cfg(feature = "http")]
+pub use limit::LimitedBody;
+#[cfg(feature = "http")]
+pub use limit::ResponseLimit;
+#[cfg(feature = "http")]
+pub use limit::DEFAULT_MAX_RESPONSE_BYTES;
+#[cfg(feature = "http")]
+pub use multipart::MultipartBody;
+#[cfg(feature = "http")]
+pub use multipart::MultipartPart;
+#[cfg(feature = "http")]
+pub use paginate::Paginate;
+#[cfg(feature = "http")]
+pub(crate) use proxy::connect_proxy;
+#[cfg(feature = "http")]
+pub(crate) use proxy::connect_tunnel;
+#[cfg(feature = "http")]
+pub use proxy::ProxyConfig;
+#[cfg(feature = "http")]
+pub(crate) use proxy::TunnelStream;
+#[cfg(feature = "http")]
+pub use retry::Retry;
+#[cfg(feature = "http")]
+pub use sse::body_stream;
+#[cfg(feature = "http")]
+pub use sse::sse;
+#[cfg(feature = "http")]
+pub use sse::SseEvent;
 
 use std::future::Future;
 use clap::{Args, Subcommand};
@@ -65,7 +119,9 @@ impl TaskCancelWrapper {
                 Ok(o)
             },
             _ = self.cancel.cancelled() => {
-                Err(reality::Error::PluginCallCancelled.into())
+                Err(reality::Error::PluginCallCancelled {
+                    reason: reality::plugin::CancelReason::Unknown,
+                })
             }
         }
     }
@@ -86,7 +142,9 @@ impl TaskCancelWrapper {
                 on_complete(o)
             },
             _ = self.cancel.cancelled() => {
-                Err(reality::Error::PluginCallCancelled.into())
+                Err(reality::Error::PluginCallCancelled {
+                    reason: reality::plugin::CancelReason::Unknown,
+                })
             }
         }
     }
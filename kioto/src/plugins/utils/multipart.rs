@@ -0,0 +1,302 @@
+use bytes::Bytes;
+use hyper::body::Frame;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, path::PathBuf, pin::Pin, task::Poll};
+use tokio::{
+    fs::File,
+    io::{AsyncRead, ReadBuf},
+};
+
+/// Size of the buffer used to read file parts
+const FILE_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Content type of a file part if one is not configured
+const DEFAULT_FILE_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Part of a `multipart/form-data` body
+///
+/// # Example
+/// ```toml
+/// multipart = [
+///     { name = "description", value = "Profile picture" },
+///     { name = "avatar", file = "avatar.png", content_type = "image/png" },
+/// ]
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub enum MultipartPart {
+    /// Text field
+    Text {
+        /// Name of the form field
+        name: String,
+        /// Value of the form field
+        value: String,
+    },
+    /// File field, the file is streamed when the body is sent
+    File {
+        /// Name of the form field
+        name: String,
+        /// Path of the file to upload
+        file: PathBuf,
+        /// Filename sent w/ the part, defaults to the file name of `file`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        filename: Option<String>,
+        /// Content type of the part, defaults to `application/octet-stream`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content_type: Option<String>,
+    },
+}
+
+impl MultipartPart {
+    /// Returns the header block of this part, including the leading boundary delimiter
+    fn header(&self, boundary: &str) -> String {
+        match self {
+            MultipartPart::Text { name, .. } => format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                escape(name)
+            ),
+            MultipartPart::File {
+                name,
+                file,
+                filename,
+                content_type,
+            } => {
+                let filename = filename.clone().unwrap_or_else(|| {
+                    file.file_name()
+                        .map(|f| f.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                });
+                format!(
+                    "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                    escape(name),
+                    escape(&filename),
+                    content_type.as_deref().unwrap_or(DEFAULT_FILE_CONTENT_TYPE)
+                )
+            }
+        }
+    }
+}
+
+/// Body that encodes a list of parts as `multipart/form-data`
+///
+/// Files are opened when the body is created, but their content is read in chunks while the body is being sent
+pub struct MultipartBody {
+    /// Boundary separating each part
+    boundary: String,
+    /// Remaining segments of the body
+    segments: VecDeque<Segment>,
+    /// Total length of the body in bytes
+    len: u64,
+    /// Buffer file parts are read into
+    chunk: Box<[u8]>,
+}
+
+/// Segment of a multipart body
+enum Segment {
+    /// Bytes that are sent as is
+    Bytes(Bytes),
+    /// File that is read until the end
+    File(File),
+}
+
+impl MultipartBody {
+    /// Creates a new body from a list of parts w/ a generated boundary
+    ///
+    /// Returns an error if a file part could not be opened, or if the content type of a file part contains a line break
+    pub async fn new(parts: &[MultipartPart]) -> std::io::Result<Self> {
+        let boundary = format!("kioto-boundary-{}", reality::Uuid::new_v4().simple());
+
+        let mut segments = VecDeque::new();
+        let mut len = 0;
+        let mut push = |segment: Segment, segment_len: u64| {
+            segments.push_back(segment);
+            len += segment_len;
+        };
+        for part in parts {
+            if let MultipartPart::File {
+                name,
+                content_type: Some(content_type),
+                ..
+            } = part
+            {
+                if content_type.contains(['\r', '\n']) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Content type of multipart file `{name}` must not contain a line break"
+                        ),
+                    ));
+                }
+            }
+
+            let header = Bytes::from(part.header(&boundary));
+            let header_len = header.len() as u64;
+            push(Segment::Bytes(header), header_len);
+            match part {
+                MultipartPart::Text { value, .. } => {
+                    push(
+                        Segment::Bytes(Bytes::from(format!("{value}\r\n"))),
+                        value.len() as u64 + 2,
+                    );
+                }
+                MultipartPart::File { file, .. } => {
+                    let file = File::open(file).await?;
+                    let file_len = file.metadata().await?.len();
+                    push(Segment::File(file), file_len);
+                    push(Segment::Bytes(Bytes::from_static(b"\r\n")), 2);
+                }
+            }
+        }
+        let end = Bytes::from(format!("--{boundary}--\r\n"));
+        let end_len = end.len() as u64;
+        push(Segment::Bytes(end), end_len);
+
+        Ok(Self {
+            boundary,
+            segments,
+            len,
+            chunk: vec![0; FILE_CHUNK_SIZE].into_boxed_slice(),
+        })
+    }
+
+    /// Returns the value of the `Content-Type` header for this body
+    #[inline]
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Returns the total length of this body in bytes
+    ///
+    /// **Note**: The length of file parts is read when the body is created, so a file that changes before it is sent
+    /// will cause the body to differ from this length
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if this body does not contain any bytes
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl hyper::body::Body for MultipartBody {
+    type Data = Bytes;
+
+    type Error = std::io::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<std::result::Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        loop {
+            match this.segments.front_mut() {
+                Some(Segment::Bytes(bytes)) => {
+                    let bytes = std::mem::take(bytes);
+                    this.segments.pop_front();
+                    return Poll::Ready(Some(Ok(Frame::data(bytes))));
+                }
+                Some(Segment::File(file)) => {
+                    let mut buf = ReadBuf::new(&mut this.chunk);
+                    match Pin::new(file).poll_read(cx, &mut buf) {
+                        Poll::Ready(Ok(())) if buf.filled().is_empty() => {
+                            this.segments.pop_front();
+                        }
+                        Poll::Ready(Ok(())) => {
+                            return Poll::Ready(Some(Ok(Frame::data(Bytes::copy_from_slice(
+                                buf.filled(),
+                            )))));
+                        }
+                        Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    fn size_hint(&self) -> hyper::body::SizeHint {
+        hyper::body::SizeHint::with_exact(self.len)
+    }
+}
+
+/// Escapes a value used in a quoted `Content-Disposition` parameter
+fn escape(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    #[tokio::test]
+    async fn test_multipart_body() {
+        let dir = PathBuf::from(".test").join("test_multipart_body");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("upload.txt");
+        std::fs::write(&path, "file content").unwrap();
+
+        let parts: Vec<MultipartPart> = toml::from_str::<toml::Table>(&format!(
+            r#"
+multipart = [
+    {{ name = "description", value = "upload" }},
+    {{ name = "upload", file = "{}", content_type = "text/plain" }},
+]
+"#,
+            path.display()
+        ))
+        .unwrap()["multipart"]
+            .clone()
+            .try_into()
+            .unwrap();
+
+        let body = MultipartBody::new(&parts).await.unwrap();
+        let boundary = body.boundary.clone();
+        assert_eq!(
+            format!("multipart/form-data; boundary={boundary}"),
+            body.content_type()
+        );
+        let len = body.len();
+        let bytes = body.collect().await.unwrap().to_bytes();
+        assert_eq!(len, bytes.len() as u64);
+        assert_eq!(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"description\"\r\n\r\nupload\r\n\
+                 --{boundary}\r\nContent-Disposition: form-data; name=\"upload\"; filename=\"upload.txt\"\r\nContent-Type: text/plain\r\n\r\nfile content\r\n\
+                 --{boundary}--\r\n"
+            ),
+            String::from_utf8_lossy(&bytes)
+        );
+
+        assert!(MultipartBody::new(&[MultipartPart::File {
+            name: "missing".to_string(),
+            file: dir.join("missing.txt"),
+            filename: None,
+            content_type: None,
+        }])
+        .await
+        .is_err());
+
+        let err = MultipartBody::new(&[MultipartPart::File {
+            name: "upload".to_string(),
+            file: path,
+            filename: None,
+            content_type: Some("text/plain\r\nX-Injected: true".to_string()),
+        }])
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+    }
+}
@@ -0,0 +1,125 @@
+use hyper::{header, HeaderMap};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Maximum number of pages to request if a limit is not configured
+const DEFAULT_PAGE_LIMIT: usize = 10;
+
+/// Settings for following the pages of a paginated response
+///
+/// # Example
+/// ```toml
+/// [paginate]
+/// next = "/links/next"
+/// limit = 5
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Paginate {
+    /// JSON pointer to the url of the next page in the response body
+    ///
+    /// If not set, the url of the `Link` header w/ `rel="next"` is used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
+    /// Maximum number of pages to request, defaults to 10
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    limit: Option<usize>,
+}
+
+impl Paginate {
+    /// Returns the maximum number of pages to request
+    #[inline]
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_LIMIT)
+    }
+
+    /// Returns the url of the page following the `current` page, or None if this is the last page
+    ///
+    /// A relative url is resolved against the url of the current page
+    pub fn next_page(
+        &self,
+        current: &Url,
+        headers: &HeaderMap,
+        body: &serde_json::Value,
+    ) -> Option<Url> {
+        let next = match self.next.as_deref() {
+            Some(pointer) => body.pointer(pointer).and_then(|n| n.as_str()),
+            None => headers
+                .get_all(header::LINK)
+                .iter()
+                .filter_map(|l| l.to_str().ok())
+                .find_map(next_link),
+        };
+
+        next.filter(|n| !n.is_empty())
+            .and_then(|n| current.join(n).ok())
+    }
+}
+
+/// Returns the url of the link w/ `rel="next"` from the value of a `Link` header
+#[inline]
+fn next_link(value: &str) -> Option<&str> {
+    value.split(',').find_map(|link| {
+        let mut params = link.split(';');
+        let url = params
+            .next()?
+            .trim()
+            .strip_prefix('<')?
+            .strip_suffix('>')?;
+        params
+            .filter_map(|p| p.split_once('='))
+            .filter(|(name, _)| name.trim().eq_ignore_ascii_case("rel"))
+            .any(|(_, rel)| {
+                rel.trim()
+                    .trim_matches('"')
+                    .split_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("next"))
+            })
+            .then_some(url)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate_next_page() {
+        let current: Url = "https://example.com/items?page=1".parse().unwrap();
+
+        let paginate: Paginate = toml::from_str(r#"next = "/links/next""#).unwrap();
+        let body = serde_json::json!({ "links": { "next": "/items?page=2" } });
+        assert_eq!(
+            Some("https://example.com/items?page=2"),
+            paginate
+                .next_page(&current, &HeaderMap::new(), &body)
+                .as_ref()
+                .map(Url::as_str)
+        );
+        let body = serde_json::json!({ "links": { "next": null } });
+        assert!(paginate.next_page(&current, &HeaderMap::new(), &body).is_none());
+        assert_eq!(DEFAULT_PAGE_LIMIT, paginate.limit());
+
+        let paginate = Paginate::default();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::LINK,
+            r#"<https://example.com/items?page=1>; rel="prev", <https://example.com/items?page=3>; rel="next""#
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            Some("https://example.com/items?page=3"),
+            paginate
+                .next_page(&current, &headers, &serde_json::Value::Null)
+                .as_ref()
+                .map(Url::as_str)
+        );
+    }
+
+    #[test]
+    fn test_next_link() {
+        assert_eq!(Some("/b"), next_link(r#"</a>; rel=first, </b>; rel="next last""#));
+        assert_eq!(None, next_link(r#"</a>; rel="prev""#));
+        assert_eq!(None, next_link("/a; rel=next"));
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    net::TcpStream,
+};
+use tracing::debug;
+use url::Url;
+
+/// Maximum size of a proxy's response to a `CONNECT` request
+const MAX_CONNECT_RESPONSE: usize = 8 * 1024;
+
+/// Proxy settings used to connect to a host
+///
+/// Each setting falls back to the standard environment variable if it is not configured, i.e. `HTTP_PROXY`,
+/// `HTTPS_PROXY`, and `NO_PROXY` (or their lowercase equivalents).
+///
+/// **Note**: Only `http://` proxies are supported, and proxy credentials are not sent
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProxyConfig {
+    /// Proxy for `http` targets
+    http: Option<Url>,
+    /// Proxy for `https` targets
+    https: Option<Url>,
+    /// Hosts that should be connected to directly
+    no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// Resolves proxy settings from configured values, falling back to environment variables
+    ///
+    /// Returns an error if a proxy url could not be parsed
+    pub fn resolve(
+        http: Option<&str>,
+        https: Option<&str>,
+        no_proxy: Option<&str>,
+    ) -> std::io::Result<Self> {
+        let http = http.map(str::to_string).or_else(|| env_var("HTTP_PROXY"));
+        let https = https.map(str::to_string).or_else(|| env_var("HTTPS_PROXY"));
+        let no_proxy = no_proxy
+            .map(str::to_string)
+            .or_else(|| env_var("NO_PROXY"))
+            .unwrap_or_default();
+
+        Ok(Self {
+            http: http.as_deref().map(parse_proxy).transpose()?,
+            https: https.as_deref().map(parse_proxy).transpose()?,
+            no_proxy: no_proxy
+                .split(',')
+                .map(|h| h.trim().trim_start_matches('.').to_lowercase())
+                .filter(|h| !h.is_empty())
+                .collect(),
+        })
+    }
+
+    /// Returns the proxy to use to connect to a host w/ a url scheme
+    ///
+    /// Returns None if the host should be connected to directly
+    pub fn proxy_for(&self, scheme: &str, host: &str) -> Option<&Url> {
+        let host = host.to_lowercase();
+        let bypass = self.no_proxy.iter().any(|pattern| {
+            pattern == "*"
+                || host == *pattern
+                || host
+                    .strip_suffix(pattern.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        });
+
+        match scheme {
+            _ if bypass => None,
+            "http" => self.http.as_ref(),
+            "https" => self.https.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Connects to a proxy
+pub async fn connect_proxy(proxy: &Url) -> std::io::Result<TcpStream> {
+    let addr = proxy_addr(proxy)?;
+    debug!("Connecting to proxy {addr}");
+    TcpStream::connect(addr).await
+}
+
+/// Connects to a proxy and opens a tunnel to `authority` w/ a `CONNECT` request
+///
+/// Returns an error if the proxy does not respond w/ a successful status
+pub async fn connect_tunnel(proxy: &Url, authority: &str) -> std::io::Result<TunnelStream> {
+    let mut stream = connect_proxy(proxy).await?;
+    stream
+        .write_all(format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n\r\n").as_bytes())
+        .await?;
+
+    let mut response = Vec::new();
+    let mut buf = [0; 1024];
+    let end = loop {
+        if let Some(end) = response.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+
+        if response.len() > MAX_CONNECT_RESPONSE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Proxy response to the tunnel request for {authority} is too large"),
+            ));
+        }
+
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionAborted,
+                format!(
+                    "Proxy closed the connection before a tunnel to {authority} was established"
+                ),
+            ));
+        }
+        response.extend_from_slice(&buf[..read]);
+    };
+
+    // Bytes after the response header were sent by the target, and are read before reading from the connection
+    let buffered = response.split_off(end);
+    let status = String::from_utf8_lossy(&response);
+    let status = status.lines().next().unwrap_or_default();
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(TunnelStream {
+            buffered,
+            pos: 0,
+            stream,
+        }),
+        // The proxy could not reach the target, which may succeed on another attempt
+        Some(code) if code.starts_with('5') => Err(std::io::Error::new(
+            std::io::ErrorKind::ConnectionRefused,
+            format!("Proxy could not open a tunnel to {authority}: {status}"),
+        )),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("Proxy refused to open a tunnel to {authority}: {status}"),
+        )),
+    }
+}
+
+/// Connection to a host, established directly or through a proxy's tunnel
+///
+/// Bytes received from the host while the tunnel was being established are read before reading from the connection
+#[derive(Debug)]
+pub struct TunnelStream {
+    /// Bytes received after the proxy's response to the `CONNECT` request
+    buffered: Vec<u8>,
+    /// Position of the next buffered byte to read
+    pos: usize,
+    /// Connection to the host
+    stream: TcpStream,
+}
+
+impl From<TcpStream> for TunnelStream {
+    fn from(stream: TcpStream) -> Self {
+        Self {
+            buffered: vec![],
+            pos: 0,
+            stream,
+        }
+    }
+}
+
+impl AsyncRead for TunnelStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if self.pos < self.buffered.len() {
+            let len = buf.remaining().min(self.buffered.len() - self.pos);
+            buf.put_slice(&self.buffered[self.pos..self.pos + len]);
+            self.pos += len;
+            return Poll::Ready(Ok(()));
+        }
+
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TunnelStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+/// Returns the address of a proxy
+fn proxy_addr(proxy: &Url) -> std::io::Result<String> {
+    match proxy.host_str() {
+        Some(host) => Ok(format!(
+            "{host}:{}",
+            proxy.port_or_known_default().unwrap_or(80)
+        )),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Proxy url `{proxy}` does not have a host"),
+        )),
+    }
+}
+
+/// Parses a proxy url, a url w/o a scheme is treated as an `http://` url
+fn parse_proxy(proxy: &str) -> std::io::Result<Url> {
+    let proxy = if proxy.contains("://") {
+        Url::parse(proxy)
+    } else {
+        Url::parse(&format!("http://{proxy}"))
+    }
+    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+
+    if proxy.scheme() == "http" {
+        Ok(proxy)
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Unsupported proxy scheme `{}`", proxy.scheme()),
+        ))
+    }
+}
+
+/// Returns the value of an environment variable, or the value of its lowercase equivalent
+fn env_var(key: &str) -> Option<String> {
+    std::env::var(key)
+        .or_else(|_| std::env::var(key.to_lowercase()))
+        .ok()
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_proxy_config() {
+        let config = ProxyConfig::resolve(
+            Some("proxy.internal:3128"),
+            Some("http://secure.internal:3129"),
+            Some("localhost, .example.com"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some("http://proxy.internal:3128/"),
+            config.proxy_for("http", "runplat.dev").map(Url::as_str)
+        );
+        assert_eq!(
+            Some("http://secure.internal:3129/"),
+            config.proxy_for("https", "runplat.dev").map(Url::as_str)
+        );
+        assert!(config.proxy_for("https", "localhost").is_none());
+        assert!(config.proxy_for("https", "api.example.com").is_none());
+        assert!(config.proxy_for("https", "example.com").is_none());
+        assert!(config.proxy_for("https", "notexample.com").is_some());
+
+        assert!(ProxyConfig::resolve(Some("https://proxy.internal"), None, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_proxy_connect_tunnel() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let server = tokio::spawn(async move {
+            for response in [
+                "HTTP/1.1 200 Connection established\r\n\r\nhello",
+                "HTTP/1.1 403 Forbidden\r\n\r\n",
+                "HTTP/1.1 502 Bad Gateway\r\n\r\n",
+            ] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 1024];
+                let read = stream.read(&mut buf).await.unwrap();
+                assert!(buf[..read].starts_with(b"CONNECT runplat.dev:443 HTTP/1.1\r\n"));
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        // Bytes sent w/ the proxy's response are read from the tunnel
+        let mut tunnel = connect_tunnel(&proxy, "runplat.dev:443").await.unwrap();
+        let mut hello = [0; 5];
+        tunnel.read_exact(&mut hello).await.unwrap();
+        assert_eq!(b"hello", &hello);
+
+        let err = connect_tunnel(&proxy, "runplat.dev:443").await.unwrap_err();
+        assert_eq!(std::io::ErrorKind::PermissionDenied, err.kind());
+        let err = connect_tunnel(&proxy, "runplat.dev:443").await.unwrap_err();
+        assert_eq!(std::io::ErrorKind::ConnectionRefused, err.kind());
+        server.await.unwrap();
+    }
+}
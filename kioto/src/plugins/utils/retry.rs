@@ -0,0 +1,160 @@
+use super::cookies::parse_http_date;
+use hyper::{header, HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+/// Number of times to retry a request if a limit is not configured
+const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+
+/// Delay before each retry if a backoff is not configured
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 1000;
+
+/// Maximum delay honored from a `Retry-After` header if a maximum is not configured
+const DEFAULT_MAX_RETRY_AFTER_MS: u64 = 60_000;
+
+/// Settings for retrying a request that received a `429 Too Many Requests` or `503 Service Unavailable` response
+///
+/// If the response has a `Retry-After` header, the request is retried after the delay indicated by the header,
+/// clamped to `max_retry_after_ms`, otherwise the request is retried after `backoff_ms`.
+///
+/// # Example
+/// ```toml
+/// [retry]
+/// attempts = 5
+/// backoff_ms = 500
+/// max_retry_after_ms = 30000
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Retry {
+    /// Maximum number of times to retry the request, defaults to 3
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    attempts: Option<u32>,
+    /// Milliseconds to wait before each retry if the response does not have a `Retry-After` header, defaults to 1000
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backoff_ms: Option<u64>,
+    /// Maximum number of milliseconds to wait for a `Retry-After` header, defaults to 60000
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_retry_after_ms: Option<u64>,
+}
+
+impl Retry {
+    /// Returns the maximum number of times to retry the request
+    #[inline]
+    pub fn attempts(&self) -> u32 {
+        self.attempts.unwrap_or(DEFAULT_RETRY_ATTEMPTS)
+    }
+
+    /// Returns the delay before retrying a request that received a response w/ `status` and `headers`, or None if the
+    /// request should not be retried
+    ///
+    /// `attempt` is the number of times the request has already been retried
+    pub fn delay(&self, attempt: u32, status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+        if attempt >= self.attempts()
+            || !matches!(
+                status,
+                StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+            )
+        {
+            return None;
+        }
+
+        let max = Duration::from_millis(
+            self.max_retry_after_ms
+                .unwrap_or(DEFAULT_MAX_RETRY_AFTER_MS),
+        );
+        match headers
+            .get(header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_retry_after(v, SystemTime::now()))
+        {
+            Some(retry_after) => Some(retry_after.min(max)),
+            None => Some(Duration::from_millis(
+                self.backoff_ms.unwrap_or(DEFAULT_RETRY_BACKOFF_MS),
+            )),
+        }
+    }
+}
+
+/// Parses the value of a `Retry-After` header, either a number of seconds or an http date
+///
+/// An http date in the past is parsed as a delay of zero, returns None if the value is in neither format
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            parse_http_date(value).map(|date| date.duration_since(now).unwrap_or(Duration::ZERO))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn test_retry_delay() {
+        let retry = toml::from_str::<Retry>(
+            r#"
+            attempts = 2
+            backoff_ms = 250
+            max_retry_after_ms = 5000
+            "#,
+        )
+        .unwrap();
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            Some(Duration::from_millis(250)),
+            retry.delay(0, StatusCode::SERVICE_UNAVAILABLE, &headers)
+        );
+        assert_eq!(
+            None,
+            retry.delay(0, StatusCode::INTERNAL_SERVER_ERROR, &headers)
+        );
+        assert_eq!(
+            None,
+            retry.delay(2, StatusCode::TOO_MANY_REQUESTS, &headers)
+        );
+
+        headers.insert(header::RETRY_AFTER, HeaderValue::from_static("2"));
+        assert_eq!(
+            Some(Duration::from_secs(2)),
+            retry.delay(1, StatusCode::TOO_MANY_REQUESTS, &headers)
+        );
+
+        // A large Retry-After is clamped to the maximum
+        headers.insert(header::RETRY_AFTER, HeaderValue::from_static("86400"));
+        assert_eq!(
+            Some(Duration::from_secs(5)),
+            retry.delay(1, StatusCode::TOO_MANY_REQUESTS, &headers)
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1445412480);
+        assert_eq!(
+            Some(Duration::from_secs(120)),
+            parse_retry_after(" 120 ", now)
+        );
+        assert_eq!(
+            Some(Duration::from_secs(60)),
+            parse_retry_after("Wed, 21 Oct 2015 07:29:00 GMT", now)
+        );
+        assert_eq!(
+            Some(Duration::ZERO),
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now)
+        );
+        assert_eq!(None, parse_retry_after("soon", now));
+        assert_eq!(
+            None,
+            parse_retry_after("Wed, 21 Oct 9223372036854775807 07:29:00 GMT", now)
+        );
+        assert_eq!(
+            None,
+            parse_retry_after("Wed, 21 Oct 2015 18446744073709551615:29:00 GMT", now)
+        );
+    }
+}
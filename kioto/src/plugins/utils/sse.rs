@@ -0,0 +1,194 @@
+use bytes::Bytes;
+use futures_util::{stream, Stream, StreamExt, TryStreamExt};
+use http_body_util::BodyExt;
+use reality::plugin::{MessageData, MessageStream};
+use serde::Serialize;
+
+use super::client::IncomingResponse;
+
+/// Event parsed from a `text/event-stream` body
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SseEvent {
+    /// Event type, set by the `event` field
+    pub event: Option<String>,
+    /// Event data, multiple `data` fields are joined w/ a newline
+    pub data: String,
+    /// Last event id, set by the `id` field of this or a previous event
+    pub id: Option<String>,
+}
+
+impl From<SseEvent> for MessageData {
+    fn from(value: SseEvent) -> Self {
+        match serde_json::to_value(value) {
+            Ok(value) => value.into(),
+            Err(_) => MessageData::Empty,
+        }
+    }
+}
+
+/// Parses a stream of bytes from a `text/event-stream` body into a stream of events
+///
+/// Events are separated by a blank line. Comments and fields other than `event`, `data` and `id` are ignored.
+pub fn sse(
+    body: impl Stream<Item = reality::Result<Bytes>> + Send + 'static,
+) -> impl Stream<Item = reality::Result<SseEvent>> + Send + 'static {
+    body.scan(SseParser::default(), |parser, chunk| {
+        let events = match chunk {
+            Ok(chunk) => parser.push(&chunk).into_iter().map(Ok).collect::<Vec<_>>(),
+            Err(err) => vec![Err(err)],
+        };
+        std::future::ready(Some(stream::iter(events)))
+    })
+    .flatten()
+}
+
+/// Converts the body of an incoming response into a message stream
+///
+/// The returned stream can be passed to `sse` in order to parse events from the response
+pub fn body_stream(response: IncomingResponse) -> MessageStream {
+    Box::pin(
+        response
+            .into_body()
+            .into_data_stream()
+            .map_err(std::io::Error::other)
+            .map_err(reality::Error::from),
+    )
+}
+
+/// Incremental parser for the `text/event-stream` framing rules
+#[derive(Default)]
+struct SseParser {
+    /// Bytes of the current line which have not been terminated
+    line: Vec<u8>,
+    /// True if the previous chunk ended w/ a carriage return, so a leading line feed should be skipped
+    skip_lf: bool,
+    /// Event currently being built
+    event: SseEvent,
+    /// True if a data field has been set on the current event
+    has_data: bool,
+    /// Last event id, which is kept across events until another `id` field is received
+    last_id: Option<String>,
+}
+
+impl SseParser {
+    /// Pushes a chunk of bytes to the parser, returning any events that were completed
+    fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = vec![];
+        for b in chunk.iter().copied() {
+            if std::mem::take(&mut self.skip_lf) && b == b'\n' {
+                continue;
+            }
+
+            match b {
+                b'\r' | b'\n' => {
+                    self.skip_lf = b == b'\r';
+                    let line = std::mem::take(&mut self.line);
+                    if let Some(event) = self.line(&String::from_utf8_lossy(&line)) {
+                        events.push(event);
+                    }
+                }
+                _ => self.line.push(b),
+            }
+        }
+        events
+    }
+
+    /// Processes a complete line, returns an event if the line dispatched the current event
+    fn line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            let mut event = std::mem::take(&mut self.event);
+            if !std::mem::take(&mut self.has_data) {
+                return None;
+            }
+            if event.data.ends_with('\n') {
+                event.data.pop();
+            }
+            event.id = self.last_id.clone();
+            return Some(event);
+        }
+
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event.event = Some(value.to_string()),
+            "data" => {
+                self.has_data = true;
+                self.event.data.push_str(value);
+                self.event.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.last_id = Some(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sse_parse() {
+        let chunks = [
+            ": comment\r\n",
+            "event: update\r\ndata: hello\r",
+            "\ndata:world\r\nid: 1\r\n\r\n",
+            "data: second\n\n",
+            "event: empty\n\n",
+            "id: 2\ndata: third\n\n",
+            "id\ndata: fourth\n\n",
+            "data: trailing",
+        ]
+        .map(|c| Ok(Bytes::from_static(c.as_bytes())));
+
+        let events = sse(stream::iter(chunks))
+            .map(|e| e.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            vec![
+                SseEvent {
+                    event: Some("update".to_string()),
+                    data: "hello\nworld".to_string(),
+                    id: Some("1".to_string()),
+                },
+                SseEvent {
+                    event: None,
+                    data: "second".to_string(),
+                    id: Some("1".to_string()),
+                },
+                SseEvent {
+                    event: None,
+                    data: "third".to_string(),
+                    id: Some("2".to_string()),
+                },
+                SseEvent {
+                    event: None,
+                    data: "fourth".to_string(),
+                    id: Some(String::new()),
+                },
+            ],
+            events
+        );
+    }
+
+    #[test]
+    fn test_sse_event_message_data() {
+        let data = MessageData::from(SseEvent {
+            event: Some("update".to_string()),
+            data: "hello".to_string(),
+            id: None,
+        });
+        let json = data.as_json().expect("should be json");
+        assert_eq!("hello", json["data"]);
+        assert_eq!("update", json["event"]);
+    }
+}
@@ -9,15 +9,14 @@ pub struct BincodeContent {
 
 impl BincodeContent {
     /// Creates a new Bincode Content
+    ///
+    /// The serializer output is fed directly into the digest, so the serialized resource is never held in memory
     pub fn new<S: Serialize>(c: &S) -> std::io::Result<Self> {
-        match bincode::serialize(c) {
-            Ok(b) => {
-                let mut crc = crate::content::crc().digest();
-                crc.update(&b);
-                Ok(Self {
-                    state_uuid: uuid::Uuid::from_u64_pair(crc.finalize(), 0),
-                })
-            }
+        let mut crc = crate::content::crc().digest();
+        match bincode::serialize_into(DigestWriter(|b: &[u8]| crc.update(b)), c) {
+            Ok(()) => Ok(Self {
+                state_uuid: uuid::Uuid::from_u64_pair(crc.finalize(), 0),
+            }),
             Err(e) => Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidInput,
                 e.to_string(),
@@ -26,6 +25,20 @@ impl BincodeContent {
     }
 }
 
+/// Writer that passes each written buffer to a digest update function
+struct DigestWriter<F: FnMut(&[u8])>(F);
+
+impl<F: FnMut(&[u8])> std::io::Write for DigestWriter<F> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        (self.0)(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 impl<S: Serialize> From<&S> for BincodeContent {
     fn from(value: &S) -> Self {
         Self::new(value).expect("should be able to create")
@@ -37,3 +50,31 @@ impl runir::Content for BincodeContent {
         self.state_uuid.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::BincodeContent;
+    use runir::Content;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Large {
+        name: String,
+        data: Vec<u64>,
+    }
+
+    #[test]
+    fn test_bincode_content_matches_buffered_digest() {
+        let large = Large {
+            name: String::from("large"),
+            data: (0..100_000).collect(),
+        };
+
+        let mut crc = crate::content::crc().digest();
+        crc.update(&bincode::serialize(&large).unwrap());
+        assert_eq!(
+            uuid::Uuid::from_u64_pair(crc.finalize(), 0),
+            BincodeContent::new(&large).unwrap().state_uuid()
+        );
+    }
+}
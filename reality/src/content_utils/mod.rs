@@ -5,4 +5,5 @@ mod nil;
 pub use nil::NilContent;
 
 mod random;
+pub use random::ephemeral;
 pub use random::RandomContent;
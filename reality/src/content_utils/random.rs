@@ -15,3 +15,13 @@ impl runir::Content for RandomContent {
         Uuid::new_v4()
     }
 }
+
+/// Generates a fresh state_uuid each time it is called, for use w/ `#[reality(content_with = reality::ephemeral)]`
+///
+/// A plugin using `BincodeContent` is identified by its serialized state, so identical plugins share the same commit.
+/// `NilContent` always returns the nil uuid, so every instance of the plugin shares the same commit. `RandomContent`
+/// and `ephemeral` return a new uuid each time the resource is put into state, however use `State::load_ephemeral`
+/// to guarantee that each loaded instance of a plugin is distinct regardless of its content.
+pub fn ephemeral<S>(_: &S) -> Uuid {
+    Uuid::new_v4()
+}
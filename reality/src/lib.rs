@@ -11,7 +11,13 @@ pub mod plugin;
 pub use plugin::Plugin;
 pub use plugin::State;
 
+pub mod prelude;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
 mod content_utils;
+pub use content_utils::ephemeral;
 pub use content_utils::BincodeContent;
 pub use content_utils::NilContent;
 pub use content_utils::RandomContent;
@@ -29,6 +35,9 @@ pub use runir::*;
 /// Re-export semver::Version
 pub use semver::Version;
 
+/// Re-export semver::VersionReq
+pub use semver::VersionReq;
+
 /// Re-export uuid::Uuid
 pub use uuid;
 pub use uuid::Uuid;
@@ -53,6 +62,14 @@ pub enum Error {
     },
     /// Error returned when a plugin could not be loaded from a path
     LoadPluginError,
+    /// Error returned when a plugin was produced under a framework that is incompatible w/ the current framework, see
+    /// `FrameworkCheck`
+    IncompatibleFramework {
+        /// Framework the plugin was produced under, i.e. `reality@0.1.0`
+        framework: String,
+        /// Current framework
+        current: String,
+    },
     /// Error returned when a `Name` could not be parsed
     IncompletePluginName,
     /// Error returned when previous request data exists for a handle
@@ -60,20 +77,58 @@ pub enum Error {
     /// Error returned when trying to write request data, expecting the entry to
     /// be empty, but replacing an existing entry
     WriteRequestRaceCondition,
+    /// Error returned when sending a message to a broker in queue mode and the queue for the destination is full
+    BrokerQueueFull,
+    /// Error returned when sending a message that exceeds the max message size of a broker
+    MessageTooLarge {
+        /// Size of the message in bytes
+        size: usize,
+        /// Max message size of the broker in bytes
+        limit: usize,
+    },
     /// Error when a plugin cannot be found in the current state
     PluginNotFound,
+    /// Error returned when an alias collides w/ the short path or address of a loaded plugin
+    PluginAliasConflict {
+        /// Alias that was rejected
+        alias: String,
+    },
     /// Error returned when casting a dynamic pointer to a plugin
     PluginMismatch,
+    /// Error returned when a plugin's state may be inconsistent because a previous call panicked while mutating it
+    PoisonedState,
     /// Error returned when the trying to add a handler to a plugin event
     /// and the the handler's target does not match the type of backing the
     /// event
     PluginHandlerTargetMismatch,
     /// Error returned when a plugin handlercall is skipped by the plugin
     PluginHandlerCallSkipped,
+    /// Error returned when a typed output was expected from an event, but the event did not return any message data
+    EmptyHandlerOutput,
+    /// Error returned when the message data returned by an event could not be deserialized into the expected type
+    HandlerOutputMismatch {
+        /// Reason the message data could not be deserialized
+        message: String,
+    },
     /// Error returned when a plugin call is cancelled
-    PluginCallCancelled,
+    PluginCallCancelled {
+        /// Reason the call was cancelled
+        reason: plugin::CancelReason,
+    },
     /// Error returned when a plugin call is skipped by the plugin
     PluginCallSkipped,
+    /// Error returned when a call exceeds the resource budget assigned to its event
+    ResourceBudgetExceeded {
+        /// Limit that was exceeded
+        limit: plugin::ResourceLimit,
+    },
+    /// Error returned by `Plugin::validate` when a plugin's config is invalid
+    InvalidPluginConfig {
+        /// Name of the plugin w/ the invalid config
+        name: plugin::Name,
+        /// Reason the config is invalid
+        message: String,
+    },
     /// Custom error returned by the implementation of the plugin
     PluginCallError {
         /// Name of the plugin where the error occured
@@ -82,12 +137,29 @@ pub enum Error {
     },
 }
 
+/// Enum of serialization formats supported by this library
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum SerializationFormat {
     Toml,
     Json,
 }
 
+impl SerializationFormat {
+    /// Detects the format of some source content
+    ///
+    /// If the first non-whitespace character is `{` or `[` the content is assumed to be JSON, otherwise TOML.
+    ///
+    /// **Note**: A TOML document that starts w/ a table header, i.e. `[table]`, will be detected as JSON, `State::load_auto`
+    /// falls back to the other format in this case
+    #[inline]
+    pub fn detect(src: &str) -> Self {
+        match src.trim_start().chars().next() {
+            Some('{') | Some('[') => SerializationFormat::Json,
+            _ => SerializationFormat::Toml,
+        }
+    }
+}
+
 impl From<tokio::task::JoinError> for Error {
     fn from(e: tokio::task::JoinError) -> Self {
         Self::TaskError {
@@ -135,7 +207,7 @@ impl From<serde_json::Error> for Error {
 #[cfg(test)]
 pub(crate) mod tests {
     use crate::*;
-    use plugin::{Bind, Call, Handler, Plugin, State, Work};
+    use plugin::{Bind, Call, Handler, MessageData, Plugin, State, TypedHandler, Work};
     use repr::Labels;
     use runir::Resource;
     use runplat_macros::Plugin;
@@ -168,6 +240,10 @@ pub(crate) mod tests {
         fn version() -> semver::Version {
             semver::Version::new(0, 1, 0)
         }
+
+        fn load(put: store::Put<'_, Self>) -> store::Put<'_, Self> {
+            put.attr(repr::Tags::from(&["smoke-test"][..]))
+        }
     }
     impl Resource for TomlPlugin {}
 
@@ -193,298 +269,2623 @@ pub(crate) mod tests {
     }
 
     #[tokio::test]
-    async fn test_plugin_replacement() {
+    async fn test_plugin_load_async() {
+        #[derive(Serialize)]
+        struct Warmed {
+            fail: bool,
+        }
+
+        impl Resource for Warmed {}
+        impl Content for Warmed {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for Warmed {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                bind.skip()
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+
+            async fn load_async(
+                put: runir::store::Put<'_, Self>,
+            ) -> Result<runir::store::Put<'_, Self>> {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                if put.resource().fail {
+                    Err(Error::LoadPluginError)
+                } else {
+                    Ok(put.attr(repr::Tags::from(&["warmed"][..])))
+                }
+            }
+        }
+
         let mut state = State::new();
-        state.load(
-            TestPlugin {
-                skip: false,
-                called: Arc::new(OnceLock::new()),
-                call_mut: false,
-            },
-            Labels::default(),
+        let address = state
+            .load_async(Warmed { fail: false }, Labels::default())
+            .await
+            .expect("should load after async setup");
+        assert!(state.tags_of(&address).unwrap().contains("warmed"));
+
+        assert_eq!(
+            Err(Error::LoadPluginError),
+            state
+                .load_async(Warmed { fail: true }, Labels::default())
+                .await
         );
+        assert_eq!(1, state.addresses().len());
+
+        // Plugins that do not override `load_async` are loaded w/ `load`
+        let address = state
+            .load_async(
+                TomlPlugin {
+                    name: String::from("async"),
+                },
+                Labels::default(),
+            )
+            .await
+            .unwrap();
+        assert!(state.tags_of(&address).unwrap().contains("smoke-test"));
+    }
 
-        state.load(
-            TestPlugin {
-                skip: true,
-                called: Arc::new(OnceLock::new()),
-                call_mut: false,
-            },
-            Labels::default(),
+    #[tokio::test]
+    async fn test_plugin_load_auto() {
+        assert_eq!(
+            SerializationFormat::Json,
+            SerializationFormat::detect("  {\"name\": \"json\"}")
         );
-        let addresses = state.addresses();
-        addresses.get(0).expect("should have address");
-        addresses.get(1).expect("should have address");
-        assert_eq!(2, state.addresses().len());
+        assert_eq!(
+            SerializationFormat::Toml,
+            SerializationFormat::detect("name = \"toml\"")
+        );
+
+        let mut state = State::new();
+        let json = state
+            .load_auto::<TomlPlugin>(r#"{ "name": "json" }"#, Labels::default())
+            .expect("should load json");
+        let toml = state
+            .load_auto::<TomlPlugin>(r#"name = "toml""#, Labels::default())
+            .expect("should load toml");
+        assert_ne!(json.commit(), toml.commit());
+
+        state
+            .load_by_format::<TomlPlugin>(
+                r#"name = "explicit""#,
+                SerializationFormat::Toml,
+                Labels::default(),
+            )
+            .expect("should load toml");
+
+        let err = state
+            .load_auto::<TomlPlugin>("{ name: ", Labels::default())
+            .expect_err("should not parse");
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+        assert!(err.to_string().contains("Json"));
+        assert!(err.to_string().contains("Toml"));
     }
 
     #[tokio::test]
-    async fn test_plugin_work_cancel() {
+    async fn test_plugin_load_by_reader() {
         let mut state = State::new();
-        state.load(
-            TestPlugin {
-                skip: false,
-                called: Arc::new(OnceLock::new()),
-                call_mut: false,
-            },
-            Labels::default(),
+        let toml = state
+            .load_by_toml_reader::<TomlPlugin>(&b"name = \"reader\""[..], Labels::default())
+            .expect("should load toml");
+        let json = state
+            .load_by_json_reader::<TomlPlugin>(&br#"{ "name": "reader" }"#[..], Labels::default())
+            .expect("should load json");
+        assert_eq!(toml.commit(), json.commit());
+
+        let err = state
+            .load_by_json_reader::<TomlPlugin>(&b"{ name: "[..], Labels::default())
+            .expect_err("should not parse");
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[tokio::test]
+    async fn test_plugin_load_by_bincode() {
+        let mut state = State::new();
+        let plugin = TomlPlugin {
+            name: String::from("bincode"),
+        };
+        let bytes = bincode::serialize(&plugin).unwrap();
+        let address = state
+            .load_by_bincode::<TomlPlugin>(&bytes, Labels::default())
+            .expect("should load bincode");
+        assert_eq!(
+            state.load(plugin, Labels::default()).commit(),
+            address.commit()
         );
 
-        let path = TestPlugin::name();
-        let (f, cancel) = state.spawn(path.path()).expect("should spawn");
+        let err = state
+            .load_by_bincode::<TomlPlugin>(&[0xff], Labels::default())
+            .expect_err("should not deserialize");
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+    }
 
-        cancel.cancel();
+    #[tokio::test]
+    async fn test_type_registry_load() {
+        let mut registry = plugin::TypeRegistry::new();
+        registry.register::<TomlPlugin>();
+        assert!(registry.is_registered(&TomlPlugin::name()));
+        // A name w/o the version matches the registered type
+        let name = plugin::name::parse_name(&TomlPlugin::name().plugin_ref()).unwrap();
+        assert!(registry.is_registered(&name));
+
+        let mut state = State::new();
+        let plugin = TomlPlugin {
+            name: String::from("registry"),
+        };
+        let bytes = bincode::serialize(&plugin).unwrap();
+        let address = registry
+            .load(&mut state, &TomlPlugin::name(), &bytes, Labels::default())
+            .expect("should reconstruct the plugin");
+        let item = state.find_plugin(&address).expect("should be loaded");
+        assert_eq!("registry", item.borrow::<TomlPlugin>().unwrap().name);
+
+        let err = registry
+            .load(&mut state, &TestPlugin::name(), &bytes, Labels::default())
+            .expect_err("should not be registered");
+        assert_eq!(std::io::ErrorKind::NotFound, err.kind());
+    }
+
+    #[tokio::test]
+    async fn test_state_reload() {
+        let mut state = State::new();
+        let address = state
+            .load_by_toml::<TomlPlugin>(r#"name = "before""#, Labels::default())
+            .unwrap();
+
+        state
+            .reload::<TomlPlugin>(&address, r#"name = "after""#)
+            .expect("should reload");
+        let item = state
+            .find_plugin(&address)
+            .expect("should keep the address");
+        assert_eq!("after", item.borrow::<TomlPlugin>().unwrap().name);
+        assert_eq!(vec![address.clone()], state.addresses());
+
+        assert!(matches!(
+            state.reload::<TomlPlugin>(&address, "name = 0"),
+            Err(Error::SerializationError { .. })
+        ));
         assert_eq!(
-            Error::PluginCallCancelled,
-            f.await.expect_err("should be cancelled")
+            Err(Error::PluginNotFound),
+            state.reload::<TomlPlugin>(
+                &plugin::Address {
+                    name: TomlPlugin::name(),
+                    commit: 0,
+                },
+                r#"name = "missing""#
+            )
         );
     }
 
     #[tokio::test]
-    async fn test_join_error_conversion() {
-        let handle = tokio::runtime::Handle::current();
+    async fn test_state_stats() {
+        let mut state = State::new();
+        assert_eq!(plugin::StateStats::default(), state.stats());
 
-        let jh = handle.spawn(async {});
-        jh.abort();
-        assert_eq!(
-            Error::TaskError {
-                is_panic: false,
-                is_cancel: true
+        state.load(
+            TomlPlugin {
+                name: String::from("stats"),
             },
-            Error::from(jh.await.expect_err("should be an error"))
+            Labels::default(),
         );
+        let stats = state.stats();
+        assert_eq!(1, stats.plugin_count);
+        assert_eq!(2, stats.plugin_paths);
+        assert_eq!(1, stats.store.item_count);
+        assert!(stats.store.journal_entries > 0);
+    }
 
-        let jh = handle.spawn(async { panic!() });
-        assert_eq!(
-            Error::TaskError {
-                is_panic: true,
-                is_cancel: false
+    #[tokio::test]
+    async fn test_state_plugin_count_and_conflicts() {
+        let mut state = State::new();
+        let first = state.load(
+            TomlPlugin {
+                name: String::from("first"),
             },
-            Error::from(jh.await.expect_err("should be an error"))
+            Labels::default(),
+        );
+        state.load(
+            TomlPlugin {
+                name: String::from("first"),
+            },
+            Labels::default(),
+        );
+        assert_eq!(1, state.plugin_count());
+        assert!(state.detect_conflicts().is_empty());
+
+        let second = state.load(
+            TomlPlugin {
+                name: String::from("second"),
+            },
+            Labels::default(),
+        );
+        assert_eq!(2, state.plugin_count());
+        assert_eq!(
+            vec![(
+                TomlPlugin::name().path().clone(),
+                vec![first.commit(), second.commit()]
+            )],
+            state.detect_conflicts()
         );
     }
 
     #[tokio::test]
-    async fn test_plugin_mismatch() {
+    async fn test_plugin_find_by_label() {
         let mut state = State::new();
+        let a = state.load(
+            TomlPlugin {
+                name: String::from("a"),
+            },
+            Labels::from(&[("group", "smoke"), ("env", "dev")][..]),
+        );
+        let b = state.load(
+            TomlPlugin {
+                name: String::from("b"),
+            },
+            Labels::from(&[("group", "smoke")][..]),
+        );
+        let handler = state.load_handler(
+            AsyncHandler { handled: false },
+            Labels::from(&[("group", "smoke")][..]),
+        );
+        // Loading an identical plugin again does not duplicate the entry
         state.load(
-            TestPlugin {
-                skip: false,
-                called: Arc::new(OnceLock::new()),
-                call_mut: false,
+            TomlPlugin {
+                name: String::from("b"),
             },
-            Labels::default(),
+            Labels::from(&[("group", "smoke")][..]),
         );
 
-        let plugin = state.find_plugin(TestPlugin::name().path()).unwrap();
-        let call = Call {
-            state: state.clone(),
-            item: plugin.clone(),
-            fork_fn: TestPlugin::fork,
-            cancel: CancellationToken::new(),
-            runtime: tokio::runtime::Handle::current(),
-            handler: None,
-        };
+        let mut found = state
+            .find_by_label("group", "smoke")
+            .iter()
+            .map(|a| a.commit())
+            .collect::<Vec<_>>();
+        found.sort();
+        let mut expected = vec![a.commit(), b.commit(), handler.commit()];
+        expected.sort();
+        assert_eq!(expected, found);
+
+        let found = state.find_by_label("env", "dev");
+        assert_eq!(1, found.len());
+        assert_eq!(a.to_string(), found[0].to_string());
+        assert!(state.find_by_label("env", "prod").is_empty());
+        assert!(state.find_by_label("missing", "smoke").is_empty());
+
+        // A deep clone has a copy of the index
+        assert_eq!(3, state.deep_clone().find_by_label("group", "smoke").len());
+    }
 
-        assert_eq!(
-            Error::PluginMismatch,
-            call.bind::<NotTestPlugin>()
-                .expect_err("should have an error")
+    #[tokio::test]
+    async fn test_state_alias_and_unload() {
+        let mut state = State::new();
+        let a = state.load(
+            TomlPlugin {
+                name: String::from("a"),
+            },
+            Labels::from(&[("group", "smoke")][..]),
+        );
+        let b = state.load(
+            TomlPlugin {
+                name: String::from("b"),
+            },
+            Labels::default(),
         );
 
-        // Tests internal error handling
-        let call = Call {
-            state: state.clone(),
-            item: plugin.clone(),
-            fork_fn: TestPlugin::fork,
-            cancel: CancellationToken::new(),
-            runtime: tokio::runtime::Handle::current(),
-            handler: None,
-        };
-        let mut bound = call.bind::<TestPlugin>().expect("should bind");
-        bound.receiver().expect("should return a plugin");
-        bound.update().expect("should return a plugin");
+        state.alias(&a, "my-alias").unwrap();
+        assert_eq!(a.commit(), state.event("my-alias").unwrap().address().commit());
+        assert!(state.is_loaded("my-alias"));
+        // Aliases are not returned as additional addresses
+        assert_eq!(2, state.addresses().len());
 
-        // Tests internal error handling
-        let call = Call {
-            state: state.clone(),
-            item: plugin.clone(),
-            fork_fn: TestPlugin::fork,
-            cancel: CancellationToken::new(),
-            runtime: tokio::runtime::Handle::current(),
-            handler: None,
-        };
-        let mut bind = Bind::<NotTestPlugin> {
-            call,
-            receiver: None,
-            _bound: std::marker::PhantomData,
-        };
+        // An alias can be remapped, but cannot replace a short path or address
+        state.alias(&b, "my-alias").unwrap();
+        assert_eq!(b.commit(), state.event("my-alias").unwrap().address().commit());
         assert_eq!(
-            Error::PluginMismatch,
-            bind.receiver().expect_err("should have an error")
+            Err(Error::PluginAliasConflict {
+                alias: a.to_string()
+            }),
+            state.alias(&b, &a.to_string())
         );
         assert_eq!(
-            Error::PluginMismatch,
-            bind.update().expect_err("should have an error")
+            Err(Error::PluginNotFound),
+            state.alias(
+                &plugin::Address {
+                    name: TomlPlugin::name(),
+                    commit: 0
+                },
+                "other"
+            )
         );
-    }
 
-    #[tokio::test]
-    async fn test_plugin_could_not_find_plugin() {
-        let state = State::new();
+        state.alias(&a, "other").unwrap();
+        state.unload(&a).unwrap();
+        assert!(state.event(&a).is_err());
+        assert!(state.event("other").is_err());
+        assert!(state.find_by_label("group", "smoke").is_empty());
+        assert_eq!(1, state.addresses().len());
+        // The short path was remapped to `b` when it was loaded, so it still resolves
         assert_eq!(
-            Error::PluginNotFound,
-            state
-                .call("doesnt-exist")
-                .await
-                .expect_err("should return an error")
+            b.commit(),
+            state.event(TomlPlugin::name().path()).unwrap().address().commit()
         );
+        assert_eq!(Err(Error::PluginNotFound), state.unload(&a));
+
+        state.unload(&b).unwrap();
+        assert!(state.event("my-alias").is_err());
+        assert!(state.event(TomlPlugin::name().path()).is_err());
     }
 
-    #[tokio::test]
-    #[tracing_test::traced_test]
-    async fn test_plugin_call() {
-        let called = Arc::new(OnceLock::new());
-        let mut state = State::init().await;
-        state.load(
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_state_concurrent_access() {
+        let mut state = State::new();
+        let address = state.load(
             TestPlugin {
                 skip: false,
-                called: called.clone(),
+                called: Arc::new(OnceLock::new()),
                 call_mut: false,
             },
             Labels::default(),
         );
 
-        let path = TestPlugin::name();
-        let _ = state.call(path.path()).await.unwrap();
-        assert!(called.get().unwrap());
-        ()
+        let mut tasks = vec![];
+        // Loads into clones of the state, which share the plugins map w/ each other
+        for i in 0..4 {
+            let mut loader = state.clone();
+            let address = address.clone();
+            tasks.push(tokio::task::spawn_blocking(move || {
+                for j in 0..100 {
+                    let loaded = loader.load(
+                        TomlPlugin {
+                            name: format!("{i}-{j}"),
+                        },
+                        Labels::from(&[("loader", i.to_string().as_str())][..]),
+                    );
+                    assert!(loader.find_plugin(&loaded).is_some());
+                    assert!(loader
+                        .find_plugin(&address)
+                        .and_then(|i| i.borrow::<TestPlugin>())
+                        .is_some());
+                }
+            }));
+        }
+        // Calls and borrows the plugin while the other tasks load, w/o mutating it
+        for _ in 0..4 {
+            let caller = state.clone();
+            let address = address.clone();
+            tasks.push(tokio::spawn(async move {
+                for _ in 0..100 {
+                    caller.call(&address).await.unwrap();
+                    let item = caller.find_plugin(&address).cloned().unwrap();
+                    assert!(item.borrow::<TestPlugin>().is_some());
+                    let _ = caller.addresses();
+                    let _ = caller.find_by_label("loader", "0");
+                }
+            }));
+        }
+
+        tokio::time::timeout(Duration::from_secs(10), futures_util::future::join_all(tasks))
+            .await
+            .expect("should not deadlock")
+            .into_iter()
+            .for_each(|r| r.unwrap());
+        // Each clone loaded into the shared plugins map
+        assert_eq!(401, state.plugin_count());
     }
 
     #[tokio::test]
-    #[tracing_test::traced_test]
-    async fn test_plugin_call_work_mut() {
-        let called = Arc::new(OnceLock::new());
-        let mut state = State::init().await;
-        state.load(
+    async fn test_plugin_find_by_tag() {
+        let mut state = State::new();
+        let toml = state.load(
+            TomlPlugin {
+                name: String::from("hello world"),
+            },
+            Labels::default(),
+        );
+        let test = state.load(
             TestPlugin {
                 skip: false,
-                called: called.clone(),
-                call_mut: true,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
             },
             Labels::default(),
         );
 
-        let path = TestPlugin::name();
-        let _ = state.call(path.path()).await.unwrap();
-        assert!(called.get().unwrap());
+        let tagged = state.find_by_tag("smoke-test");
+        assert_eq!(1, tagged.len());
+        assert_eq!(toml.commit(), tagged[0].commit());
+        assert!(state.find_by_tag("other").is_empty());
 
-        let plugin = state.find_plugin(path.path()).unwrap();
-        let plugin = plugin.borrow::<TestPlugin>().unwrap();
-        assert!(!plugin.call_mut);
-        ()
+        assert!(state.tags_of(&toml).unwrap().contains("smoke-test"));
+        assert!(state.tags_of(&test).is_none());
     }
 
     #[tokio::test]
-    #[tracing_test::traced_test]
-    async fn test_plugin_call_skip() {
-        let called = Arc::new(OnceLock::new());
-        let mut state = State::init().await;
+    async fn test_plugin_replacement() {
+        let mut state = State::new();
         state.load(
             TestPlugin {
-                skip: true,
-                called: called.clone(),
+                skip: false,
+                called: Arc::new(OnceLock::new()),
                 call_mut: false,
             },
             Labels::default(),
         );
 
-        let path = TestPlugin::name();
-        assert_eq!(
-            Error::PluginCallSkipped,
-            state
-                .call(path.path())
-                .await
-                .expect_err("should return an error")
+        state.load(
+            TestPlugin {
+                skip: true,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+        let addresses = state.addresses();
+        addresses.get(0).expect("should have address");
+        addresses.get(1).expect("should have address");
+        assert_eq!(2, state.addresses().len());
+    }
+
+    #[tokio::test]
+    async fn test_state_share_and_deep_clone() {
+        let mut state = State::new();
+        let address = state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        let mut deep = state.deep_clone();
+        deep.load(NotTestPlugin, Labels::default());
+        assert_eq!(1, state.addresses().len());
+        assert_eq!(2, deep.addresses().len());
+        assert!(deep.find_plugin(&address).is_some());
+
+        deep.broker()
+            .send(address.commit(), bytes::Bytes::from_static(b"a"))
+            .unwrap();
+        assert!(state.broker().receive(address.commit()).is_empty());
+
+        deep.close();
+        assert!(!state.cancel.is_cancelled());
+
+        let shared = state.share();
+        shared
+            .broker()
+            .send(address.commit(), bytes::Bytes::from_static(b"b"))
+            .unwrap();
+        assert!(state.broker().receive(address.commit()).is_bytes());
+        shared.close();
+        assert!(state.cancel.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_state_call_hooks() {
+        use plugin::{Address, CallHook};
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Recorder {
+            veto: bool,
+            calls: Mutex<Vec<(String, bool)>>,
+        }
+
+        impl CallHook for Recorder {
+            fn before(&self, address: &Address) -> Result<()> {
+                self.calls
+                    .lock()
+                    .unwrap()
+                    .push((format!("before {}", address.name()), true));
+                if self.veto {
+                    Err(Error::PluginCallSkipped)
+                } else {
+                    Ok(())
+                }
+            }
+
+            fn after(&self, address: &Address, result: &Result<()>) {
+                self.calls
+                    .lock()
+                    .unwrap()
+                    .push((format!("after {}", address.name()), result.is_ok()));
+            }
+        }
+
+        let called = Arc::new(OnceLock::new());
+        let mut state = State::new();
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: called.clone(),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+        let hook = Arc::new(Recorder::default());
+        state.add_call_hook(hook.clone());
+
+        state.call(TestPlugin::name().path()).await.unwrap();
+        assert!(called.get().is_some());
+        let name = TestPlugin::name();
+        assert_eq!(
+            vec![
+                (format!("before {name}"), true),
+                (format!("after {name}"), true)
+            ],
+            *hook.calls.lock().unwrap()
+        );
+
+        let veto = Arc::new(Recorder {
+            veto: true,
+            ..Default::default()
+        });
+        state.add_call_hook(veto.clone());
+        assert_eq!(
+            Error::PluginCallSkipped,
+            state.call(TestPlugin::name().path()).await.unwrap_err()
+        );
+        assert_eq!(
+            vec![
+                (format!("before {name}"), true),
+                (format!("after {name}"), false)
+            ],
+            *veto.calls.lock().unwrap()
+        );
+        assert_eq!(4, hook.calls.lock().unwrap().len());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_state_result_cache() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Serialize)]
+        struct CachedPlugin {
+            name: String,
+            #[serde(skip)]
+            calls: Arc<AtomicUsize>,
+        }
+
+        impl Resource for CachedPlugin {}
+        impl Content for CachedPlugin {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for CachedPlugin {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                let name = bind.receiver()?.name.clone();
+                if name != "empty" {
+                    bind.emit(bytes::Bytes::from(name))?;
+                }
+                bind.work(|p, _| {
+                    p.calls.fetch_add(1, Ordering::SeqCst);
+                    async { Ok(()) }
+                })
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+
+            fn cacheable() -> bool {
+                true
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut state = State::new();
+        let a = state.load(
+            CachedPlugin {
+                name: "a".to_string(),
+                calls: calls.clone(),
+            },
+            Labels::default(),
+        );
+        let b = state.load(
+            CachedPlugin {
+                name: "b".to_string(),
+                calls: calls.clone(),
+            },
+            Labels::default(),
+        );
+
+        // Results are not cached until the cache is enabled
+        state.event(&a).unwrap().returns().await.unwrap();
+        state.event(&a).unwrap().returns().await.unwrap();
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+
+        state.enable_result_cache(Duration::from_millis(100), 1);
+        state.event(&a).unwrap().returns().await.unwrap();
+        let cached = state.event(&a).unwrap().returns().await.unwrap();
+        assert_eq!(3, calls.load(Ordering::SeqCst));
+        assert_eq!(Some(&bytes::Bytes::from("a")), cached.as_bytes());
+
+        // A different config is a different key, and evicts the oldest result once the cache is full
+        state.event(&b).unwrap().returns().await.unwrap();
+        state.event(&b).unwrap().returns().await.unwrap();
+        state.event(&a).unwrap().returns().await.unwrap();
+        assert_eq!(5, calls.load(Ordering::SeqCst));
+
+        state.clear_result_cache();
+        state.event(&a).unwrap().returns().await.unwrap();
+        assert_eq!(6, calls.load(Ordering::SeqCst));
+
+        tokio::time::advance(Duration::from_millis(99)).await;
+        state.event(&a).unwrap().returns().await.unwrap();
+        assert_eq!(6, calls.load(Ordering::SeqCst));
+
+        tokio::time::advance(Duration::from_millis(1)).await;
+        state.event(&a).unwrap().returns().await.unwrap();
+        assert_eq!(7, calls.load(Ordering::SeqCst));
+
+        // Calls that do not return a result bypass the cache
+        state.call(&a).await.unwrap();
+        assert_eq!(8, calls.load(Ordering::SeqCst));
+
+        state.set_dry_run(true);
+        state.event(&a).unwrap().returns().await.unwrap();
+        assert_eq!(9, calls.load(Ordering::SeqCst));
+        state.set_dry_run(false);
+
+        // Calls that did not return any message data are not cached
+        let empty = state.load(
+            CachedPlugin {
+                name: "empty".to_string(),
+                calls: calls.clone(),
+            },
+            Labels::default(),
+        );
+        state.event(&empty).unwrap().returns().await.unwrap();
+        state.event(&empty).unwrap().returns().await.unwrap();
+        assert_eq!(11, calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_state_await_idle() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Serialize)]
+        struct BackgroundPlugin {
+            #[serde(skip)]
+            done: Arc<AtomicUsize>,
+        }
+
+        impl Resource for BackgroundPlugin {}
+        impl Content for BackgroundPlugin {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for BackgroundPlugin {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                let done = bind.item().borrow::<Self>().unwrap().done.clone();
+                bind.spawn(async move {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    done.fetch_add(1, Ordering::SeqCst);
+                })?;
+                bind.work(|_, _| async { Ok(()) })
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+        }
+
+        let done = Arc::new(AtomicUsize::new(0));
+        let mut state = State::new();
+        let address = state.load(BackgroundPlugin { done: done.clone() }, Labels::default());
+        assert!(state.await_idle(None).await);
+
+        // The call returns before the task it spawned completes
+        state.event(&address).unwrap().start().await.unwrap();
+        assert_eq!(1, state.in_flight());
+        assert!(!state.await_idle(Some(Duration::from_millis(1))).await);
+
+        assert!(state.await_idle(Some(Duration::from_secs(1))).await);
+        assert_eq!(0, state.in_flight());
+        assert_eq!(1, done.load(Ordering::SeqCst));
+
+        // A deep clone does not wait on work spawned from the original state
+        state.event(&address).unwrap().start().await.unwrap();
+        assert!(state.deep_clone().await_idle(None).await);
+        assert!(state.await_idle(None).await);
+        assert_eq!(2, done.load(Ordering::SeqCst));
+
+        // Shutdown waits for work in flight and returns the messages that were not received
+        state.event(&address).unwrap().start().await.unwrap();
+        state
+            .broker()
+            .send(address.commit(), bytes::Bytes::from("a"))
+            .unwrap();
+        let undelivered = state.shutdown(None).await;
+        assert_eq!(3, done.load(Ordering::SeqCst));
+        assert_eq!(1, undelivered.len());
+        assert_eq!(address.commit(), undelivered[0].0);
+        assert!(state.broker().receive(address.commit()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_state_addresses_w_qualified_name() {
+        mod nested {
+            use super::*;
+
+            #[derive(Serialize)]
+            pub struct Qualified;
+
+            impl Resource for Qualified {}
+            impl Content for Qualified {
+                fn state_uuid(&self) -> uuid::Uuid {
+                    BincodeContent::new(self).unwrap().state_uuid()
+                }
+            }
+            impl Plugin for Qualified {
+                fn call(bind: Bind<Self>) -> Result<Work> {
+                    bind.skip()
+                }
+
+                fn version() -> Version {
+                    Version::new(0, 1, 0)
+                }
+            }
+        }
+
+        let mut state = State::new();
+        let address = state.load(nested::Qualified, Labels::default());
+        assert!(address.name().qualifiers().count() > 0);
+        assert_eq!(vec![address], state.addresses());
+    }
+
+    #[tokio::test]
+    async fn test_plugin_work_cancel() {
+        let mut state = State::new();
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        let path = TestPlugin::name();
+        let (f, cancel) = state.spawn(path.path()).expect("should spawn");
+
+        cancel.cancel();
+        assert_eq!(
+            Error::PluginCallCancelled {
+                reason: plugin::CancelReason::Unknown
+            },
+            f.await.expect_err("should be cancelled")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plugin_work_set() {
+        let mut state = State::new();
+        let address = state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+        let work = |sleep: u64, fail: bool| {
+            let event = state.event(&address).unwrap();
+            event
+                .call
+                .bind::<TestPlugin>()
+                .unwrap()
+                .work(move |_, _| async move {
+                    tokio::time::sleep(Duration::from_millis(sleep)).await;
+                    if fail {
+                        Err(Error::PluginCallSkipped)
+                    } else {
+                        Ok(())
+                    }
+                })
+                .unwrap()
+        };
+
+        let set = [work(10, false), work(0, true)]
+            .into_iter()
+            .collect::<plugin::WorkSet>();
+        assert_eq!(vec![&TestPlugin::name(); 2], set.names());
+        assert_eq!(
+            vec![Ok(()), Err(Error::PluginCallSkipped)],
+            set.join_all().await
+        );
+
+        let event = state.event(&address).unwrap();
+        let cancel = event.call.cancel.clone();
+        let slow = event
+            .call
+            .bind::<TestPlugin>()
+            .unwrap()
+            .work(|_, _| async {
+                tokio::time::sleep(Duration::from_millis(1000)).await;
+                Ok(())
+            })
+            .unwrap();
+        let set = [slow, work(0, false)]
+            .into_iter()
+            .collect::<plugin::WorkSet>();
+        assert_eq!(Ok(()), set.any().await);
+        assert!(cancel.is_cancelled());
+
+        let mut set = plugin::WorkSet::new();
+        set.extend([work(1000, false), work(1000, false)]);
+        set.cancel_all();
+        assert_eq!(
+            vec![
+                Err(Error::PluginCallCancelled {
+                    reason: plugin::CancelReason::User
+                });
+                2
+            ],
+            set.join_all().await
+        );
+        assert_eq!(vec![Ok(())], Work::join_all([work(0, false)]).await);
+    }
+
+    #[tokio::test]
+    async fn test_join_error_conversion() {
+        let handle = tokio::runtime::Handle::current();
+
+        let jh = handle.spawn(async {});
+        jh.abort();
+        assert_eq!(
+            Error::TaskError {
+                is_panic: false,
+                is_cancel: true
+            },
+            Error::from(jh.await.expect_err("should be an error"))
+        );
+
+        let jh = handle.spawn(async { panic!() });
+        assert_eq!(
+            Error::TaskError {
+                is_panic: true,
+                is_cancel: false
+            },
+            Error::from(jh.await.expect_err("should be an error"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plugin_call_panic() {
+        #[derive(Serialize)]
+        struct PanicPlugin;
+
+        impl Resource for PanicPlugin {}
+        impl Content for PanicPlugin {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for PanicPlugin {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                bind.work(|_, _| async { panic!("boom {}", 1) })
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+        }
+
+        let mut state = State::new();
+        let address = state.load(PanicPlugin, Labels::default());
+        match state.call(&address).await {
+            Err(Error::PluginCallError { name, message }) => {
+                assert_eq!(PanicPlugin::name(), name);
+                assert_eq!("plugin call panicked: boom 1", message);
+            }
+            result => panic!("unexpected result {result:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plugin_kind() {
+        #[derive(Serialize)]
+        struct SinkPlugin {
+            sink: bool,
+        }
+
+        impl Resource for SinkPlugin {}
+        impl Content for SinkPlugin {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for SinkPlugin {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                bind.skip()
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+
+            fn kind(&self) -> plugin::PluginKind {
+                if self.sink {
+                    plugin::PluginKind::Sink
+                } else {
+                    plugin::PluginKind::Source
+                }
+            }
+        }
+
+        let mut state = State::new();
+        let sink = state.load(SinkPlugin { sink: true }, Labels::default());
+        let source = state.load(SinkPlugin { sink: false }, Labels::default());
+        let transform = state.load(
+            TomlPlugin {
+                name: String::from("a"),
+            },
+            Labels::default(),
+        );
+        let handler = state.load_handler(AsyncHandler { handled: false }, Labels::default());
+
+        assert_eq!(Some(plugin::PluginKind::Sink), state.kind_of(&sink));
+        assert_eq!(Some(plugin::PluginKind::Source), state.kind_of(&source));
+        assert_eq!(
+            Some(plugin::PluginKind::Transform),
+            state.kind_of(&transform)
+        );
+        assert_eq!(Some(plugin::PluginKind::Handler), state.kind_of(&handler));
+        assert_eq!(None, state.kind_of("missing"));
+
+        let graph = state.export_graph();
+        let node = graph
+            .nodes
+            .iter()
+            .find(|n| n.address.commit() == sink.commit())
+            .unwrap();
+        assert_eq!(plugin::PluginKind::Sink, node.plugin_kind);
+    }
+
+    #[tokio::test]
+    async fn test_bind_try_update_poisoned() {
+        #[derive(Serialize)]
+        struct TornPlugin {
+            started: bool,
+        }
+
+        impl Resource for TornPlugin {}
+        impl Content for TornPlugin {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for TornPlugin {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                bind.defer(|mut bind, _| async move {
+                    let plugin = bind.try_update()?;
+                    if !plugin.started {
+                        plugin.started = true;
+                        panic!("torn");
+                    }
+                    Ok(())
+                })
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+        }
+
+        let mut state = State::new();
+        let address = state.load(TornPlugin { started: false }, Labels::default());
+        let event = state.event(&address).unwrap();
+        assert!(matches!(
+            event.clone().start().await,
+            Err(Error::PluginCallError { .. })
+        ));
+
+        // The panic poisoned the plugin's state, so the next call does not observe a partial update
+        assert!(event.call.item.is_poisoned());
+        assert_eq!(Err(Error::PoisonedState), event.clone().start().await);
+
+        // Events share the plugin's state w/ the loaded plugin
+        assert_eq!(Err(Error::PoisonedState), state.call(&address).await);
+    }
+
+    #[tokio::test]
+    async fn test_state_resolve_req() {
+        macro_rules! versioned {
+            ($module:ident, $major:literal, $minor:literal) => {
+                mod $module {
+                    use super::*;
+
+                    #[derive(Serialize)]
+                    pub struct Versioned;
+
+                    impl Resource for Versioned {}
+                    impl Content for Versioned {
+                        fn state_uuid(&self) -> uuid::Uuid {
+                            BincodeContent::new(self).unwrap().state_uuid()
+                        }
+                    }
+                    impl Plugin for Versioned {
+                        fn call(bind: Bind<Self>) -> Result<Work> {
+                            bind.skip()
+                        }
+
+                        fn version() -> Version {
+                            Version::new($major, $minor, 0)
+                        }
+                    }
+                }
+            };
+        }
+        versioned!(v1, 0, 1);
+        versioned!(v2, 0, 2);
+        versioned!(v3, 0, 3);
+
+        let mut state = State::new();
+        let v1 = state.load(v1::Versioned, Labels::default());
+        let v2 = state.load(v2::Versioned, Labels::default());
+        let v3 = state.load(v3::Versioned, Labels::default());
+
+        let req = |req: &str| req.parse::<VersionReq>().unwrap();
+        assert_eq!(
+            Some(v2.clone()),
+            state.resolve_req("reality/tests.versioned", &req(">=0.1, <0.3"))
+        );
+        assert_eq!(
+            Some(v3),
+            state.resolve_req("reality/tests.versioned@0.1.0", &req(">=0.1"))
+        );
+        assert_eq!(
+            Some(v1),
+            state.resolve_req("reality/tests.versioned", &req("=0.1.0"))
+        );
+        assert_eq!(
+            None,
+            state.resolve_req("reality/tests.versioned", &req("^1"))
+        );
+        assert_eq!(None, state.resolve_req("reality/tests.other", &req("*")));
+        assert_eq!(None, state.resolve_req("versioned", &req("*")));
+    }
+
+    #[tokio::test]
+    async fn test_bind_scratch() {
+        #[derive(Serialize)]
+        struct Scratchy;
+
+        impl Resource for Scratchy {}
+        impl Content for Scratchy {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for Scratchy {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                assert_eq!(None, bind.scratch::<Vec<u32>>());
+                bind.scratch_mut(|v: &mut Vec<u32>| v.push(1));
+
+                let fork = bind.fork_shared();
+                assert_eq!(None, fork.scratch::<Vec<u32>>());
+                fork.scratch_mut(|v: &mut Vec<u32>| v.push(3));
+
+                bind.defer(|b, _| async move {
+                    b.scratch_mut(|v: &mut Vec<u32>| v.push(2));
+                    b.emit(serde_json::json!({ "scratch": b.scratch::<Vec<u32>>() }))
+                })
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+        }
+
+        let mut state = State::new();
+        let address = state.load(Scratchy, Labels::default());
+        for _ in 0..2 {
+            let scratch = state
+                .event(&address)
+                .unwrap()
+                .returns_typed::<serde_json::Value>()
+                .await
+                .unwrap();
+            assert_eq!(serde_json::json!({ "scratch": [1, 2] }), scratch);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bind_blocking() {
+        #[derive(Serialize)]
+        struct Blocky;
+
+        impl Resource for Blocky {}
+        impl Content for Blocky {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for Blocky {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                bind.defer(|b, _| async move {
+                    let value = b.blocking(|| Ok(42)).await?;
+
+                    // Cancelling the call returns before the blocking work completes
+                    let fork = b.fork_shared();
+                    let slow = fork.blocking(|| {
+                        std::thread::sleep(Duration::from_millis(500));
+                        Ok(0)
+                    });
+                    fork.call.cancel_with(plugin::CancelReason::User);
+                    let cancelled = matches!(
+                        slow.await,
+                        Err(Error::PluginCallCancelled {
+                            reason: plugin::CancelReason::User
+                        })
+                    );
+                    b.emit(serde_json::json!({ "value": value, "cancelled": cancelled }))
+                })
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+        }
+
+        let mut state = State::new();
+        let address = state.load(Blocky, Labels::default());
+        let now = std::time::Instant::now();
+        let returns = state
+            .event(&address)
+            .unwrap()
+            .returns_typed::<serde_json::Value>()
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::json!({ "value": 42, "cancelled": true }),
+            returns
+        );
+        assert!(now.elapsed() < Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_state_check_framework() {
+        #[derive(Serialize)]
+        struct Outdated;
+
+        impl Resource for Outdated {}
+        impl Content for Outdated {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for Outdated {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                bind.skip()
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+
+            fn framework() -> (&'static str, &'static str) {
+                ("reality", "9.0.0")
+            }
+        }
+
+        let mut state = State::new();
+        assert_eq!(("reality", "9.0.0"), Outdated::name().framework());
+        assert_eq!(Ok(()), state.check_framework(plugin::FRAMEWORK));
+        assert_eq!(
+            Ok(()),
+            state.check_framework((plugin::FRAMEWORK.0, "0.1.99"))
+        );
+
+        // Incompatible frameworks are loaded w/ a warning by default
+        assert!(state.try_load(Outdated, Labels::default()).is_ok());
+        assert!(logs_contain("incompatible_framework"));
+
+        state.set_framework_check(plugin::FrameworkCheck::Deny);
+        assert_eq!(
+            Err(Error::IncompatibleFramework {
+                framework: "reality@9.0.0".to_string(),
+                current: format!("reality@{}", env!("CARGO_PKG_VERSION")),
+            }),
+            state.try_load(Outdated, Labels::default())
+        );
+        assert!(state
+            .check_framework(("other", plugin::FRAMEWORK.1))
+            .is_err());
+
+        state.set_framework_check(plugin::FrameworkCheck::Ignore);
+        assert!(state.try_load(Outdated, Labels::default()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_state_try_load_invalid_config() {
+        #[derive(Serialize, Deserialize)]
+        struct Invalid {
+            limit: u64,
+        }
+
+        impl Resource for Invalid {}
+        impl Content for Invalid {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for Invalid {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                bind.skip()
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+
+            fn validate(&self) -> Result<()> {
+                if self.limit == 0 {
+                    Err(Error::InvalidPluginConfig {
+                        name: Self::name(),
+                        message: "`limit` must be greater than 0".to_string(),
+                    })
+                } else {
+                    Ok(())
+                }
+            }
+        }
+
+        let mut state = State::new();
+        assert_eq!(
+            Err(Error::InvalidPluginConfig {
+                name: Invalid::name(),
+                message: "`limit` must be greater than 0".to_string(),
+            }),
+            state.try_load(Invalid { limit: 0 }, Labels::default())
+        );
+
+        let err = state
+            .load_by_toml::<Invalid>("limit = 0", Labels::default())
+            .unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        assert_eq!(
+            format!("{}: `limit` must be greater than 0", Invalid::name()),
+            err.to_string()
+        );
+        assert!(state.addresses().is_empty());
+
+        assert!(state
+            .try_load(Invalid { limit: 1 }, Labels::default())
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_state_check_dependencies() {
+        #[derive(Serialize)]
+        struct Dependent;
+
+        impl Resource for Dependent {}
+        impl Content for Dependent {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for Dependent {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                bind.skip()
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+
+            fn dependencies() -> Vec<plugin::Name> {
+                vec![TestPlugin::name(), TomlPlugin::name()]
+            }
+        }
+
+        let mut state = State::new();
+        let address = state.load(Dependent, Labels::default());
+        assert_eq!(
+            Err(vec![TestPlugin::name(), TomlPlugin::name()]),
+            state.check_dependencies(&address)
+        );
+
+        state.load(
+            TomlPlugin {
+                name: String::from("a"),
+            },
+            Labels::default(),
+        );
+        assert_eq!(
+            Err(vec![TestPlugin::name()]),
+            state.check_dependencies(&address)
+        );
+
+        let test_plugin = state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+        assert_eq!(Ok(()), state.check_dependencies(&address));
+        assert_eq!(Ok(()), state.check_dependencies(&test_plugin));
+
+        state.unload(&address).unwrap();
+        assert_eq!(
+            Err(vec![Dependent::name()]),
+            state.check_dependencies(&address)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_state_evict_expired() {
+        #[derive(Serialize)]
+        struct Expiring {
+            id: usize,
+        }
+
+        impl Resource for Expiring {}
+        impl Content for Expiring {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for Expiring {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                bind.defer(|_, _| async {
+                    tokio::time::sleep(Duration::from_millis(150)).await;
+                    Ok(())
+                })
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+
+            fn load(put: runir::store::Put<'_, Self>) -> runir::store::Put<'_, Self> {
+                put.ttl(Duration::from_millis(50))
+            }
+        }
+
+        let mut state = State::new();
+        let address = state.load(Expiring { id: 0 }, Labels::default());
+        let kept = state.load(
+            TomlPlugin {
+                name: String::from("kept"),
+            },
+            Labels::default(),
+        );
+        let (call, _) = state.spawn(&address).unwrap();
+        let call = tokio::spawn(call);
+        tokio::time::sleep(Duration::from_millis(80)).await;
+
+        // The plugin has expired, but is not evicted while the call is in flight
+        assert!(state.evict_expired().is_empty());
+        assert!(state.event(&address).is_ok());
+
+        call.await.unwrap().unwrap();
+        assert_eq!(vec![address.commit()], state.evict_expired());
+        assert!(state.event(&address).is_err());
+        assert!(!state.store().contains(address.commit()));
+        assert!(state.event(&kept).is_ok());
+
+        // The background task evicts expired plugins from the store until the state is closed, including plugins loaded
+        // after the task was started
+        let address = state.load(Expiring { id: 1 }, Labels::default());
+        let eviction = state.start_eviction(Duration::from_millis(10));
+        let loaded = state.load(Expiring { id: 2 }, Labels::default());
+        assert!(state.is_loaded(&address));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        for address in [address, loaded] {
+            assert!(!state.is_loaded(&address));
+            assert!(!state.store().contains(address.commit()));
+        }
+        state.close();
+        eviction.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_plugin_mismatch() {
+        let mut state = State::new();
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        let plugin = state.find_plugin(TestPlugin::name().path()).unwrap();
+        let call = Call {
+            state: state.clone(),
+            item: plugin.clone(),
+            fork_fn: TestPlugin::fork,
+            cancel: CancellationToken::new(),
+            cause: Default::default(),
+            runtime: tokio::runtime::Handle::current(),
+            handler: None,
+            budget: None,
+            timeout: None,
+            scratch: Default::default(),
+        };
+
+        assert_eq!(
+            Error::PluginMismatch,
+            call.bind::<NotTestPlugin>()
+                .expect_err("should have an error")
+        );
+
+        // Tests internal error handling
+        let call = Call {
+            state: state.clone(),
+            item: plugin.clone(),
+            fork_fn: TestPlugin::fork,
+            cancel: CancellationToken::new(),
+            cause: Default::default(),
+            runtime: tokio::runtime::Handle::current(),
+            handler: None,
+            budget: None,
+            timeout: None,
+            scratch: Default::default(),
+        };
+        let mut bound = call.bind::<TestPlugin>().expect("should bind");
+        bound.plugin().expect("should return a plugin");
+        bound.plugin_mut().expect("should return a plugin");
+        bound.receiver().expect("should return a plugin");
+        bound.update().expect("should return a plugin");
+
+        // Tests internal error handling
+        let call = Call {
+            state: state.clone(),
+            item: plugin.clone(),
+            fork_fn: TestPlugin::fork,
+            cancel: CancellationToken::new(),
+            cause: Default::default(),
+            runtime: tokio::runtime::Handle::current(),
+            handler: None,
+            budget: None,
+            timeout: None,
+            scratch: Default::default(),
+        };
+        let mut bind = Bind::<NotTestPlugin> {
+            call,
+            receiver: None,
+            _bound: std::marker::PhantomData,
+        };
+        assert_eq!(
+            Error::PluginMismatch,
+            bind.receiver().expect_err("should have an error")
+        );
+        assert_eq!(
+            Error::PluginMismatch,
+            bind.update().expect_err("should have an error")
+        );
+        assert_eq!(
+            Error::PluginMismatch,
+            bind.plugin().expect_err("should have an error")
+        );
+        assert_eq!(
+            Error::PluginMismatch,
+            bind.plugin_mut().expect_err("should have an error")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plugin_could_not_find_plugin() {
+        let state = State::new();
+        assert_eq!(
+            Error::PluginNotFound,
+            state
+                .call("doesnt-exist")
+                .await
+                .expect_err("should return an error")
+        );
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_plugin_call() {
+        let called = Arc::new(OnceLock::new());
+        let mut state = State::init().await;
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: called.clone(),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        let path = TestPlugin::name();
+        let _ = state.call(path.path()).await.unwrap();
+        assert!(called.get().unwrap());
+        ()
+    }
+
+    #[tokio::test]
+    async fn test_plugin_call_from_bind_state() {
+        let called = Arc::new(OnceLock::new());
+        let mut state = State::init().await;
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: called.clone(),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+        state.load(ComposePlugin, Labels::default());
+
+        state.call(ComposePlugin::name().path()).await.unwrap();
+        assert!(called.get().unwrap());
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_plugin_call_work_mut() {
+        let called = Arc::new(OnceLock::new());
+        let mut state = State::init().await;
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: called.clone(),
+                call_mut: true,
+            },
+            Labels::default(),
+        );
+
+        let path = TestPlugin::name();
+        let _ = state.call(path.path()).await.unwrap();
+        assert!(called.get().unwrap());
+
+        let plugin = state.find_plugin(path.path()).unwrap();
+        let plugin = plugin.borrow::<TestPlugin>().unwrap();
+        assert!(!plugin.call_mut);
+        ()
+    }
+
+    #[tokio::test]
+    async fn test_plugin_bind_labels() {
+        let mut state = State::new();
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::from(&[("env", "prod")][..]),
+        );
+
+        let event = state.event(TestPlugin::name().path()).unwrap();
+        let bind = event.call.bind::<TestPlugin>().unwrap();
+        assert_eq!(Some("prod".to_string()), bind.label("env"));
+        assert_eq!(None, bind.label("region"));
+        assert_eq!(1, bind.labels().len());
+
+        let labels = state.labels_of(TestPlugin::name().path()).unwrap();
+        assert_eq!(Some(&"prod".to_string()), labels.get("env"));
+        assert!(state.labels_of("missing").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_plugin_bind_is_dry_run() {
+        let test_plugin = || TestPlugin {
+            skip: false,
+            called: Arc::new(OnceLock::new()),
+            call_mut: false,
+        };
+
+        let mut state = State::new();
+        let address = state.load(test_plugin(), Labels::default());
+        let bind = |state: &State| {
+            state
+                .event(&address)
+                .unwrap()
+                .call
+                .bind::<TestPlugin>()
+                .unwrap()
+        };
+        assert!(!bind(&state).is_dry_run());
+
+        state.set_dry_run(true);
+        assert!(bind(&state).is_dry_run());
+
+        let mut state = State::new();
+        let address = state.load(
+            test_plugin(),
+            Labels::from(&[(plugin::DRY_RUN_LABEL, "true")][..]),
+        );
+        let bind = state
+            .event(&address)
+            .unwrap()
+            .call
+            .bind::<TestPlugin>()
+            .unwrap();
+        assert!(bind.is_dry_run());
+    }
+
+    #[tokio::test]
+    async fn test_plugin_call_fork_isolated() {
+        let mut state = State::new();
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: true,
+            },
+            Labels::default(),
+        );
+
+        let event = state.event(TestPlugin::name().path()).unwrap();
+        let mut forked = event.call.fork_isolated::<TestPlugin>().unwrap();
+        forked.item.borrow_mut::<TestPlugin>().unwrap().call_mut = false;
+        assert!(event.item().borrow::<TestPlugin>().unwrap().call_mut);
+        assert_eq!(event.item().commit(), forked.item.commit());
+
+        let mut shared = event.call.fork();
+        shared.item.borrow_mut::<TestPlugin>().unwrap().call_mut = false;
+        assert!(!event.item().borrow::<TestPlugin>().unwrap().call_mut);
+
+        assert!(matches!(
+            event.call.fork_isolated::<NotTestPlugin>(),
+            Err(Error::PluginMismatch)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_bind_fork() {
+        let mut state = State::new();
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: true,
+            },
+            Labels::default(),
+        );
+
+        let event = state.event(TestPlugin::name().path()).unwrap();
+        let bind = event.call.clone().bind::<TestPlugin>().unwrap();
+
+        // Changes to a fork are not visible to the parent binding
+        let mut forked = bind.fork().unwrap();
+        forked.update().unwrap().call_mut = false;
+        assert!(bind.receiver().unwrap().call_mut);
+
+        // Cancelling a fork does not cancel the parent binding
+        assert!(!forked.is_cancelled());
+        forked.call.cancel_with(plugin::CancelReason::User);
+        assert!(forked.is_cancelled());
+        assert!(!bind.is_cancelled());
+
+        // Changes to a shared fork are visible to the parent binding
+        let mut shared = bind.fork_shared();
+        shared.update().unwrap().call_mut = false;
+        assert!(!bind.receiver().unwrap().call_mut);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_bind_runtime_metrics() {
+        let mut state = State::new();
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        let event = state.event(TestPlugin::name().path()).unwrap();
+        let bind = event.call.clone().bind::<TestPlugin>().unwrap();
+        let metrics = bind.runtime_metrics();
+        assert_eq!(2, metrics.workers);
+
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let task = bind.spawn(rx).unwrap();
+        assert!(bind.runtime_metrics().alive_tasks >= 1);
+        tx.send(()).unwrap();
+        task.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_plugin_call_metrics() {
+        let mut state = State::new();
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        let path = TestPlugin::name();
+        state.call(path.path()).await.unwrap();
+        state.call(path.path()).await.unwrap();
+
+        let snapshot = state.metrics_snapshot();
+        let stats = snapshot
+            .get(&TestPlugin::name())
+            .expect("should have stats");
+        assert_eq!(2, stats.calls);
+        assert_eq!(2, stats.ok);
+        assert_eq!(0, stats.errors);
+        assert!(stats.max_duration <= stats.total_duration);
+    }
+
+    #[tokio::test]
+    async fn test_plugin_call_run_mut() {
+        let mut state = State::new();
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: true,
+            },
+            Labels::default(),
+        );
+
+        let plugin = state.find_plugin(TestPlugin::name().path()).unwrap();
+        let call = Call {
+            state: state.clone(),
+            item: plugin.clone(),
+            fork_fn: TestPlugin::fork,
+            cancel: CancellationToken::new(),
+            cause: Default::default(),
+            runtime: tokio::runtime::Handle::current(),
+            handler: None,
+            budget: None,
+            timeout: None,
+            scratch: Default::default(),
+        };
+
+        call.bind::<TestPlugin>()
+            .unwrap()
+            .run_mut(|test, _| {
+                test.call_mut = false;
+                async { Ok(()) }
+            })
+            .unwrap()
+            .await
+            .unwrap();
+
+        let plugin = state.find_plugin(TestPlugin::name().path()).unwrap();
+        assert!(!plugin.borrow::<TestPlugin>().unwrap().call_mut);
+    }
+
+    #[tokio::test]
+    async fn test_plugin_call_budget() {
+        let mut state = State::new();
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: true,
+            },
+            Labels::default(),
+        );
+
+        let mut event = state.event(TestPlugin::name().path()).unwrap();
+        let cancel = event.call.cancel.clone();
+        event.with_budget(
+            plugin::ResourceBudget::new().with_max_duration(Duration::from_millis(50)),
+        );
+        assert_eq!(
+            Error::ResourceBudgetExceeded {
+                limit: plugin::ResourceLimit::Duration(Duration::from_millis(50))
+            },
+            event.start().await.expect_err("should exceed the budget")
+        );
+        assert!(cancel.is_cancelled());
+
+        let mut event = state.event(TestPlugin::name().path()).unwrap();
+        event.with_budget(plugin::ResourceBudget::new().with_max_spawned_tasks(2));
+        let bind = event.call.bind::<TestPlugin>().unwrap();
+        let forked = bind.clone();
+        bind.spawn(async {}).expect("should spawn").await.unwrap();
+        forked.spawn(async {}).expect("should spawn").await.unwrap();
+        assert_eq!(
+            Error::ResourceBudgetExceeded {
+                limit: plugin::ResourceLimit::SpawnedTasks(2)
+            },
+            bind.spawn(async {}).expect_err("should exceed the budget")
+        );
+
+        // Setting a timeout keeps the tasks already spawned against the budget
+        let mut event = state.event(TestPlugin::name().path()).unwrap();
+        event.with_budget(plugin::ResourceBudget::new().with_max_spawned_tasks(1));
+        let bind = event.call.clone().bind::<TestPlugin>().unwrap();
+        bind.spawn(async {}).expect("should spawn").await.unwrap();
+        event.with_timeout(Duration::from_secs(5));
+        let bind = event.call.bind::<TestPlugin>().unwrap();
+        assert_eq!(
+            Error::ResourceBudgetExceeded {
+                limit: plugin::ResourceLimit::SpawnedTasks(1)
+            },
+            bind.spawn(async {}).expect_err("should exceed the budget")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plugin_default_timeout() {
+        let mut state = State::new();
+        let address = state.load(SlowPlugin, Labels::default());
+
+        assert_eq!(
+            Error::PluginCallCancelled {
+                reason: plugin::CancelReason::Timeout
+            },
+            state.call(&address).await.expect_err("should time out")
+        );
+
+        // The caller's timeout overrides the plugin's default
+        let mut event = state.event(&address).unwrap();
+        event.with_timeout(Duration::from_secs(5));
+        event
+            .start()
+            .await
+            .expect("should complete before the timeout");
+
+        // An explicit timeout is not reported as an exceeded budget
+        let mut event = state.event(&address).unwrap();
+        event.with_timeout(Duration::from_millis(10));
+        assert_eq!(
+            Err(Error::PluginCallCancelled {
+                reason: plugin::CancelReason::Timeout
+            }),
+            event.start().await
+        );
+
+        // A budget w/ a shorter max duration than the timeout still applies
+        let mut event = state.event(&address).unwrap();
+        event
+            .with_budget(plugin::ResourceBudget::new().with_max_duration(Duration::from_millis(10)))
+            .with_timeout(Duration::from_secs(5));
+        assert_eq!(
+            Err(Error::ResourceBudgetExceeded {
+                limit: plugin::ResourceLimit::Duration(Duration::from_millis(10))
+            }),
+            event.start().await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plugin_call_cancel_reason() {
+        let mut state = State::new();
+        let address = state.load(CancelAwarePlugin, Labels::default());
+
+        let event = state.event(&address).unwrap();
+        let call = event.call.clone();
+        let started = tokio::spawn(event.start());
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        call.cancel_with(plugin::CancelReason::Timeout);
+        call.cancel_with(plugin::CancelReason::User);
+        assert_eq!(plugin::CancelReason::Timeout, call.cancel_reason());
+        assert_eq!(
+            Err(Error::PluginCallCancelled {
+                reason: plugin::CancelReason::Timeout
+            }),
+            started.await.unwrap()
+        );
+        assert!(plugin::CancelReason::Timeout.is_retryable());
+
+        state.close();
+        assert_eq!(
+            Error::PluginCallCancelled {
+                reason: plugin::CancelReason::Shutdown
+            },
+            state.call(&address).await.unwrap_err()
+        );
+        assert!(!plugin::CancelReason::Shutdown.is_retryable());
+        assert!(!plugin::CancelReason::ResourceBudget.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_plugin_call_timeout_is_retryable() {
+        let mut state = State::new();
+        let address = state.load(CancelAwarePlugin, Labels::default());
+
+        let mut event = state.event(&address).unwrap();
+        event.with_timeout(Duration::from_millis(10));
+        match event.start().await {
+            Err(Error::PluginCallCancelled { reason }) => {
+                assert_eq!(plugin::CancelReason::Timeout, reason);
+                assert!(reason.is_retryable());
+            }
+            result => panic!("expected the call to be cancelled w/ a timeout, got {result:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_state_global_deadline() {
+        let mut state = State::new();
+        let address = state.load(CancelAwarePlugin, Labels::default());
+
+        state.set_global_deadline(std::time::Instant::now() + Duration::from_millis(50));
+        assert_eq!(
+            Error::PluginCallCancelled {
+                reason: plugin::CancelReason::GlobalDeadline
+            },
+            state.call(&address).await.unwrap_err()
+        );
+        assert!(!plugin::CancelReason::GlobalDeadline.is_retryable());
+
+        // Closing before the deadline stops the timer
+        let state = State::new();
+        state.set_global_deadline(std::time::Instant::now() + Duration::from_millis(50));
+        state.close();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(plugin::CancelReason::Shutdown, state.cause.reason());
+    }
+
+    #[tokio::test]
+    async fn test_plugin_exec_timed() {
+        let mut state = State::new();
+        let address = state.load(SlowPlugin, Labels::default());
+
+        let mut event = state.event(&address).unwrap();
+        event.with_timeout(Duration::from_secs(5));
+        let elapsed = event.start_timed().await.unwrap();
+        assert!(elapsed >= Duration::from_millis(200));
+
+        let event = state.event(&address).unwrap();
+        assert_eq!(
+            Error::PluginCallCancelled {
+                reason: plugin::CancelReason::Timeout
+            },
+            event
+                .thunk
+                .exec_with_timeout(event.call.clone(), Duration::from_millis(10))
+                .await
+                .expect_err("should time out")
+        );
+
+        // A shorter max duration from the event's budget is reported as exceeding the budget
+        let mut event = state.event(&address).unwrap();
+        event.with_budget(
+            plugin::ResourceBudget::new().with_max_duration(Duration::from_millis(10)),
+        );
+        assert_eq!(
+            Error::ResourceBudgetExceeded {
+                limit: plugin::ResourceLimit::Duration(Duration::from_millis(10))
+            },
+            event
+                .thunk
+                .exec_with_timeout(event.call.clone(), Duration::from_secs(5))
+                .await
+                .expect_err("should exceed the budget")
+        );
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_plugin_call_skip() {
+        let called = Arc::new(OnceLock::new());
+        let mut state = State::init().await;
+        state.load(
+            TestPlugin {
+                skip: true,
+                called: called.clone(),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        let path = TestPlugin::name();
+        assert_eq!(
+            Error::PluginCallSkipped,
+            state
+                .call(path.path())
+                .await
+                .expect_err("should return an error")
+        );
+        ()
+    }
+
+    #[test]
+    fn test_plugin_name() {
+        let name = TestPlugin::name();
+        assert_eq!(
+            format!("reality/{}/tests/testplugin", env!("CARGO_PKG_VERSION")).as_str(),
+            name.path().as_os_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plugin_call_by_path() {
+        let called = Arc::new(OnceLock::new());
+        let mut state = State::init().await;
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: called.clone(),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        let _ = state.call("reality/0.1.0/tests/testplugin").await.unwrap();
+        assert!(called.get().unwrap());
+        ()
+    }
+
+    #[test]
+    fn test_state_with_handle() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let called = Arc::new(OnceLock::new());
+        let mut state = State::with(rt.handle().clone());
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: called.clone(),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        rt.block_on(async move {
+            let path = TestPlugin::name();
+            let _ = state.call(path.path()).await.unwrap();
+        });
+
+        assert!(called.get().unwrap());
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_state_with_event_handler() {
+        let called = Arc::new(OnceLock::new());
+        let mut state = State::init().await;
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: called.clone(),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+        let handler = state.load(TestPluginHandler { test_plugin: None }, Labels::default());
+
+        let mut event = state.event("reality/0.1.0/tests/testplugin").unwrap();
+        event.with_handler::<TestPluginHandler>(handler).unwrap();
+        event.start().await.unwrap();
+
+        let event = state
+            .event("reality/0.1.0/tests/testpluginhandler")
+            .unwrap();
+        let handler = event.call.item.borrow::<TestPluginHandler>().unwrap();
+        assert!(handler.test_plugin.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_event_with_input() {
+        let mut state = State::new();
+        let address = state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        let mut event = state.event(&address).unwrap();
+        event.with_input(bytes::Bytes::from("input")).unwrap();
+        assert_eq!(
+            Some(Error::PreviousUnhandledRequest),
+            event.with_input(bytes::Bytes::from("again")).err()
+        );
+        assert_eq!(
+            Some(&bytes::Bytes::from("input")),
+            state.broker().receive(address.commit()).as_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_state_max_message_bytes() {
+        let mut state = State::new();
+        state.set_max_message_bytes(4);
+        let address = state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        let mut event = state.event(&address).unwrap();
+        assert_eq!(
+            Some(Error::MessageTooLarge { size: 5, limit: 4 }),
+            event.with_input(bytes::Bytes::from("input")).err()
+        );
+        assert!(state.broker().receive(address.commit()).is_empty());
+
+        state.enable_message_queue(2);
+        assert_eq!(4, state.broker().max_message_bytes());
+        let mut event = state.event(&address).unwrap();
+        event.with_input(bytes::Bytes::from("in")).unwrap();
+        assert_eq!(
+            Some(Error::MessageTooLarge { size: 5, limit: 4 }),
+            event.with_input(bytes::Bytes::from("input")).err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bind_emit() {
+        #[derive(Serialize)]
+        struct EmitPlugin {
+            target: Option<String>,
+        }
+
+        impl Resource for EmitPlugin {}
+        impl Content for EmitPlugin {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for EmitPlugin {
+            fn call(bind: Bind<Self>) -> Result<Work> {
+                match bind.receiver()?.target.clone() {
+                    Some(target) => bind.emit_to(target, bytes::Bytes::from("emitted"))?,
+                    None => {
+                        bind.emit(bytes::Bytes::from("emitted"))?;
+                        assert_eq!(
+                            Some(Error::PreviousUnhandledRequest),
+                            bind.emit(bytes::Bytes::from("again")).err()
+                        );
+                    }
+                }
+                bind.work(|_, _| async { Ok(()) })
+            }
+
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+        }
+
+        let mut state = State::new();
+        let source = state.load(EmitPlugin { target: None }, Labels::default());
+        let returns = state.event(&source).unwrap().returns().await.unwrap();
+        assert_eq!(Some(&bytes::Bytes::from("emitted")), returns.as_bytes());
+        let returns = state.call_returns(&source).await.unwrap();
+        assert_eq!(Some(&bytes::Bytes::from("emitted")), returns.as_bytes());
+        assert_eq!(
+            Some(Error::PluginNotFound),
+            state
+                .call_returns("reality/0.1.0/tests/missing")
+                .await
+                .err()
+        );
+
+        let forward = state.load(
+            EmitPlugin {
+                target: Some(source.to_string()),
+            },
+            Labels::default(),
+        );
+        let returns = state.event(&forward).unwrap().returns().await.unwrap();
+        assert!(returns.as_bytes().is_none());
+        assert_eq!(
+            Some(&bytes::Bytes::from("emitted")),
+            state.broker().receive(source.commit()).as_bytes()
+        );
+
+        let missing = state.load(
+            EmitPlugin {
+                target: Some("reality/0.1.0/tests/missing".to_string()),
+            },
+            Labels::default(),
+        );
+        assert_eq!(
+            Some(Error::PluginNotFound),
+            state.event(&missing).unwrap().returns().await.err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_state_load_handler_dedup() {
+        let mut state = State::new();
+        let first = state.load_handler(AsyncHandler { handled: false }, Labels::default());
+        let mut item = state.find_plugin(&first).unwrap().clone();
+        item.borrow_mut::<AsyncHandler>().unwrap().handled = true;
+        let stats = state.stats();
+
+        // Reloading a handler w/ the same content keeps the existing handler
+        let reloaded = state.load_handler(AsyncHandler { handled: false }, Labels::default());
+        assert_eq!(first, reloaded);
+        assert_eq!(stats, state.stats());
+        let item = state.find_plugin(&reloaded).unwrap();
+        assert!(item.borrow::<AsyncHandler>().unwrap().handled);
+
+        // The short path is not remapped to a handler loaded later
+        let other = state.load_handler(AsyncHandler { handled: true }, Labels::default());
+        assert_ne!(first, other);
+        let short = state.find_plugin(AsyncHandler::name().path()).unwrap();
+        assert_eq!(first.commit(), short.commit());
+        assert!(state.find_plugin(&other).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_state_with_async_event_handler() {
+        let mut state = State::init().await;
+        state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+        let handler = state.load(AsyncHandler { handled: false }, Labels::default());
+
+        let mut event = state.event("reality/0.1.0/tests/testplugin").unwrap();
+        event.with_handler::<AsyncHandler>(handler.clone()).unwrap();
+        event.start().await.unwrap();
+
+        let event = state.event(&handler).unwrap();
+        assert!(event.call.item.borrow::<AsyncHandler>().unwrap().handled);
+    }
+
+    #[tokio::test]
+    async fn test_state_call_with_handler() {
+        #[derive(Serialize)]
+        struct EchoHandler;
+
+        impl Resource for EchoHandler {}
+        impl Content for EchoHandler {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for EchoHandler {
+            fn call(bind: Bind<Self>) -> Result<plugin::Work> {
+                bind.work(|_, _| async { Ok(()) })
+            }
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+        }
+        impl Handler for EchoHandler {
+            type Target = TestPlugin;
+
+            fn handle(_: Bind<Self::Target>, handler: Bind<Self>) -> Result<()> {
+                handler.emit(bytes::Bytes::from_static(b"echo"))
+            }
+        }
+
+        let mut state = State::new();
+        let target = state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+        let handler = state.load_handler(EchoHandler, Labels::default());
+        let other = state.load_handler(AsyncHandler { handled: false }, Labels::default());
+
+        let returns = state
+            .call_with_handler::<EchoHandler>(&target, handler.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            Some(&bytes::Bytes::from_static(b"echo")),
+            returns.as_bytes()
+        );
+
+        // The handler is validated before the plugin is called
+        assert_eq!(
+            Some(Error::PluginMismatch),
+            state
+                .call_with_handler::<EchoHandler>(&target, other)
+                .await
+                .err()
+        );
+        assert_eq!(
+            Some(Error::PluginMismatch),
+            state
+                .call_with_handler::<EchoHandler>(&handler, handler.clone())
+                .await
+                .err()
+        );
+        assert_eq!(
+            Some(Error::PluginNotFound),
+            state
+                .call_with_handler::<EchoHandler>("missing", handler)
+                .await
+                .err()
         );
-        ()
     }
 
-    #[test]
-    fn test_plugin_name() {
-        let name = TestPlugin::name();
+    #[tokio::test]
+    async fn test_event_returns_typed() {
+        #[derive(Serialize)]
+        struct ReplyHandler {
+            reply: Option<String>,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Reply {
+            value: String,
+        }
+
+        impl Resource for ReplyHandler {}
+        impl Content for ReplyHandler {
+            fn state_uuid(&self) -> uuid::Uuid {
+                BincodeContent::new(self).unwrap().state_uuid()
+            }
+        }
+        impl Plugin for ReplyHandler {
+            fn call(bind: Bind<Self>) -> Result<plugin::Work> {
+                bind.work(|_, _| async { Ok(()) })
+            }
+            fn version() -> Version {
+                Version::new(0, 1, 0)
+            }
+        }
+        impl Handler for ReplyHandler {
+            type Target = TestPlugin;
+
+            fn handle(_: Bind<Self::Target>, handler: Bind<Self>) -> Result<()> {
+                match handler.receiver()?.reply.as_deref() {
+                    Some(reply) => handler.emit(serde_json::from_str::<serde_json::Value>(reply)?),
+                    None => Ok(()),
+                }
+            }
+        }
+        impl TypedHandler for ReplyHandler {
+            type Output = Reply;
+        }
+
+        let mut state = State::new();
+        let target = state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+        let event = |reply: Option<&str>| {
+            let mut state = state.clone();
+            let handler = state.load_handler(
+                ReplyHandler {
+                    reply: reply.map(str::to_string),
+                },
+                Labels::default(),
+            );
+            let mut event = state.event(&target).unwrap();
+            event.with_handler::<ReplyHandler>(handler).unwrap();
+            event
+        };
+
         assert_eq!(
-            format!("reality/{}/tests/testplugin", env!("CARGO_PKG_VERSION")).as_str(),
-            name.path().as_os_str()
+            Reply {
+                value: "typed".to_string()
+            },
+            event(Some(r#"{ "value": "typed" }"#))
+                .returns_output::<ReplyHandler>()
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            Some(Error::EmptyHandlerOutput),
+            event(None).returns_typed::<Reply>().await.err()
         );
+        assert!(matches!(
+            event(Some(r#"{ "other": 1 }"#))
+                .returns_typed::<Reply>()
+                .await,
+            Err(Error::HandlerOutputMismatch { .. })
+        ));
+
+        assert_eq!(
+            Reply {
+                value: "toml".to_string()
+            },
+            MessageData::Toml(toml::toml! { value = "toml" })
+                .deserialize::<Reply>()
+                .unwrap()
+        );
+        assert!(matches!(
+            MessageData::Bytes(bytes::Bytes::from_static(b"not json")).deserialize::<Reply>(),
+            Err(Error::HandlerOutputMismatch { .. })
+        ));
     }
 
     #[tokio::test]
-    async fn test_plugin_call_by_path() {
-        let called = Arc::new(OnceLock::new());
-        let mut state = State::init().await;
-        state.load(
+    async fn test_state_export_graph() {
+        let mut state = State::new();
+        let plugin = state.load(
             TestPlugin {
                 skip: false,
-                called: called.clone(),
+                called: Arc::new(OnceLock::new()),
                 call_mut: false,
             },
-            Labels::default(),
+            Labels::from(&[("event", "test")][..]),
         );
+        let handler = state.load_handler(TestPluginHandler { test_plugin: None }, Labels::default());
 
-        let _ = state.call("reality/0.1.0/tests/testplugin").await.unwrap();
-        assert!(called.get().unwrap());
-        ()
+        let graph = state.export_graph();
+        assert_eq!(2, graph.nodes.len());
+        assert_eq!(1, graph.edges.len());
+        assert_eq!(
+            Some(plugin.commit()),
+            graph.edges[0].target.as_ref().map(|t| t.commit())
+        );
+        assert_eq!(handler.commit(), graph.edges[0].handler.commit());
+        assert_eq!(0, graph.unresolved().count());
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph reality {"));
+        assert!(dot.contains(&format!("\"{handler}\" -> \"{plugin}\";")));
+        assert!(dot.contains("event=test"));
     }
 
-    #[test]
-    fn test_state_with_handle() {
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .build()
-            .unwrap();
-        let called = Arc::new(OnceLock::new());
-        let mut state = State::with(rt.handle().clone());
-        state.load(
+    #[tokio::test]
+    async fn test_state_describe_all() {
+        let mut state = State::new();
+        let plugin = state.load(
             TestPlugin {
                 skip: false,
-                called: called.clone(),
+                called: Arc::new(OnceLock::new()),
                 call_mut: false,
             },
-            Labels::default(),
+            Labels::from(&[("event", "test")][..]),
+        );
+        let handler =
+            state.load_handler(TestPluginHandler { test_plugin: None }, Labels::default());
+
+        let inventory = state.describe_all();
+        assert_eq!(2, inventory.plugins.len());
+        let described = inventory.find(&plugin.to_string()).unwrap();
+        assert_eq!(plugin.name().plugin_ref(), described.name.as_str());
+        assert_eq!(Version::new(0, 1, 0), described.version);
+        assert_eq!(
+            Some("test"),
+            described.labels.get("event").map(|l| l.as_str())
         );
+        assert!(described.has_handler());
+        assert_eq!(vec![handler.clone()], described.handlers);
 
-        rt.block_on(async move {
-            let path = TestPlugin::name();
-            let _ = state.call(path.path()).await.unwrap();
-        });
+        let described = inventory.find(&handler.to_string()).unwrap();
+        assert_eq!(plugin::PluginKind::Handler, described.kind);
+        assert!(!described.has_handler());
 
-        assert!(called.get().unwrap());
+        let json =
+            serde_json::from_str::<serde_json::Value>(&inventory.to_json().unwrap()).unwrap();
+        assert_eq!(2, json["plugins"].as_array().unwrap().len());
     }
 
     #[tokio::test]
-    #[tracing_test::traced_test]
-    async fn test_state_with_event_handler() {
-        let called = Arc::new(OnceLock::new());
-        let mut state = State::init().await;
+    async fn test_state_validate_handlers() {
+        let mut state = State::new();
         state.load(
             TestPlugin {
                 skip: false,
-                called: called.clone(),
+                called: Arc::new(OnceLock::new()),
                 call_mut: false,
             },
             Labels::default(),
         );
-        let handler = state.load(TestPluginHandler { test_plugin: None }, Labels::default());
+        state.load_handler(TestPluginHandler { test_plugin: None }, Labels::default());
+        assert_eq!(Ok(()), state.validate_handlers());
+
+        let ping = state.load_handler(PingHandler, Labels::default());
+        let pong = state.load_handler(PongHandler, Labels::default());
+        let cycles = state.validate_handlers().expect_err("should find a cycle");
+        assert_eq!(1, cycles.len());
+        assert_eq!(2, cycles[0].addresses.len());
+        assert!(cycles[0].addresses.contains(&ping));
+        assert!(cycles[0].addresses.contains(&pong));
+        let names = cycles[0].names();
+        assert!(names.contains(&&PingHandler::name()));
+        assert!(names.contains(&&PongHandler::name()));
+        assert_eq!(3, cycles[0].to_string().split(" -> ").count());
+
+        let dot = state.graph_to_dot();
+        assert!(dot.contains(&format!("\"{ping}\" -> \"{pong}\";")));
+        assert!(dot.contains(&format!("\"{pong}\" -> \"{ping}\";")));
+    }
 
-        let mut event = state.event("reality/0.1.0/tests/testplugin").unwrap();
-        event.with_handler::<TestPluginHandler>(handler).unwrap();
-        event.start().await.unwrap();
+    #[tokio::test]
+    async fn test_plugin_observable() {
+        let mut state = State::new();
+        let address = state.load(ProgressPlugin, Labels::default());
 
-        let event = state
-            .event("reality/0.1.0/tests/testpluginhandler")
+        let mut event = state.observe(&address).expect("should find the plugin");
+        event.timeout(Duration::from_secs(1));
+        state.call(&address).await.unwrap();
+
+        let current = event.wait();
+        assert_eq!(2, current.version);
+        assert_eq!(100, current.progress);
+        assert_eq!("done", current.message);
+    }
+
+    #[tokio::test]
+    async fn test_state_with_broker() {
+        let listener = State::new();
+        let mut worker =
+            State::with_broker(tokio::runtime::Handle::current(), listener.broker().clone());
+        let address = worker.load(
+            TomlPlugin {
+                name: String::from("worker"),
+            },
+            Labels::default(),
+        );
+
+        listener
+            .broker()
+            .send(address.commit(), bytes::Bytes::from_static(b"hello"))
             .unwrap();
-        let handler = event.call.item.borrow::<TestPluginHandler>().unwrap();
-        assert!(handler.test_plugin.is_some());
+        let received = worker.broker().receive(address.commit());
+        assert_eq!(
+            Some(&bytes::Bytes::from_static(b"hello")),
+            received.as_bytes()
+        );
+    }
+
+    #[cfg(feature = "signal")]
+    #[tokio::test]
+    async fn test_state_signal_handler() {
+        let state = State::new();
+        state.install_signal_handler().unwrap();
+        state.install_signal_handler().unwrap();
+        assert!(!state.cancel.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_address_as_map_key() {
+        let mut state = State::new();
+        let toml = state.load(
+            TomlPlugin {
+                name: String::from("hello world"),
+            },
+            Labels::default(),
+        );
+        let test = state.load(
+            TestPlugin {
+                skip: false,
+                called: Arc::new(OnceLock::new()),
+                call_mut: false,
+            },
+            Labels::default(),
+        );
+
+        let mut calls = std::collections::HashMap::new();
+        calls.insert(toml.clone(), 1);
+        calls.insert(test.clone(), 2);
+        for address in state.addresses() {
+            assert!(calls.contains_key(&address));
+        }
+
+        let ordered = state
+            .addresses()
+            .into_iter()
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(2, ordered.len());
+        assert_eq!(
+            toml.commit().min(test.commit()),
+            ordered.first().unwrap().commit()
+        );
+        assert_ne!(toml, test);
     }
 
     #[test]
@@ -544,6 +2945,183 @@ pub(crate) mod tests {
         }
     }
 
+    #[derive(Serialize)]
+    struct AsyncHandler {
+        handled: bool,
+    }
+
+    impl Resource for AsyncHandler {}
+    impl Content for AsyncHandler {
+        fn state_uuid(&self) -> uuid::Uuid {
+            BincodeContent::new(self).unwrap().state_uuid()
+        }
+    }
+    impl Plugin for AsyncHandler {
+        fn call(bind: Bind<Self>) -> Result<plugin::Work> {
+            bind.work(|_, _| async { Ok(()) })
+        }
+        fn version() -> Version {
+            Version::new(0, 1, 0)
+        }
+    }
+
+    impl Handler for AsyncHandler {
+        type Target = TestPlugin;
+
+        fn handle(_: Bind<Self::Target>, _: Bind<Self>) -> Result<()> {
+            Err(Error::PluginHandlerCallSkipped)
+        }
+
+        async fn handle_async(_: Bind<Self::Target>, mut handler: Bind<Self>) -> Result<()> {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            handler.update()?.handled = true;
+            Ok(())
+        }
+    }
+
+    #[derive(Serialize)]
+    struct PingHandler;
+
+    #[derive(Serialize)]
+    struct PongHandler;
+
+    impl Resource for PingHandler {}
+    impl Resource for PongHandler {}
+    impl Content for PingHandler {
+        fn state_uuid(&self) -> uuid::Uuid {
+            uuid::Uuid::from_u64_pair(1, 0)
+        }
+    }
+    impl Content for PongHandler {
+        fn state_uuid(&self) -> uuid::Uuid {
+            uuid::Uuid::from_u64_pair(2, 0)
+        }
+    }
+    impl Plugin for PingHandler {
+        fn call(bind: Bind<Self>) -> Result<plugin::Work> {
+            bind.work(|_, _| async { Ok(()) })
+        }
+        fn version() -> Version {
+            Version::new(0, 1, 0)
+        }
+    }
+    impl Plugin for PongHandler {
+        fn call(bind: Bind<Self>) -> Result<plugin::Work> {
+            bind.work(|_, _| async { Ok(()) })
+        }
+        fn version() -> Version {
+            Version::new(0, 1, 0)
+        }
+    }
+    impl Handler for PingHandler {
+        type Target = PongHandler;
+
+        fn handle(_: Bind<Self::Target>, _: Bind<Self>) -> Result<()> {
+            Ok(())
+        }
+    }
+    impl Handler for PongHandler {
+        type Target = PingHandler;
+
+        fn handle(_: Bind<Self::Target>, _: Bind<Self>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Serialize)]
+    struct ProgressPlugin;
+
+    impl Resource for ProgressPlugin {}
+    impl Content for ProgressPlugin {
+        fn state_uuid(&self) -> uuid::Uuid {
+            BincodeContent::new(self).unwrap().state_uuid()
+        }
+    }
+    impl Plugin for ProgressPlugin {
+        fn call(mut bind: Bind<Self>) -> Result<Work> {
+            let mut observable = bind.observable();
+            bind.work(move |_, _| {
+                observable.notify_change_with_progress(100);
+                observable.notify_change_with_message("done");
+                async { Ok(()) }
+            })
+        }
+
+        fn version() -> Version {
+            Version::new(0, 1, 0)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct ComposePlugin;
+
+    impl Resource for ComposePlugin {}
+    impl Content for ComposePlugin {
+        fn state_uuid(&self) -> uuid::Uuid {
+            BincodeContent::new(self).unwrap().state_uuid()
+        }
+    }
+    impl Plugin for ComposePlugin {
+        fn call(bind: Bind<Self>) -> Result<Work> {
+            let state = bind.state().clone();
+            bind.defer(
+                move |_, _| async move { state.event(TestPlugin::name().path())?.start().await },
+            )
+        }
+
+        fn version() -> Version {
+            Version::new(0, 1, 0)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct CancelAwarePlugin;
+
+    impl Resource for CancelAwarePlugin {}
+    impl Content for CancelAwarePlugin {
+        fn state_uuid(&self) -> uuid::Uuid {
+            BincodeContent::new(self).unwrap().state_uuid()
+        }
+    }
+    impl Plugin for CancelAwarePlugin {
+        fn call(bind: Bind<Self>) -> Result<Work> {
+            bind.defer(|b, ct| async move {
+                ct.cancelled().await;
+                Err(b.plugin_call_cancelled())
+            })
+        }
+
+        fn version() -> Version {
+            Version::new(0, 1, 0)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct SlowPlugin;
+
+    impl Resource for SlowPlugin {}
+    impl Content for SlowPlugin {
+        fn state_uuid(&self) -> uuid::Uuid {
+            BincodeContent::new(self).unwrap().state_uuid()
+        }
+    }
+    impl Plugin for SlowPlugin {
+        fn call(bind: Bind<Self>) -> Result<Work> {
+            bind.work(|_, _| async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                Ok(())
+            })
+        }
+
+        fn default_timeout() -> Option<Duration> {
+            Some(Duration::from_millis(50))
+        }
+
+        fn version() -> Version {
+            Version::new(0, 1, 0)
+        }
+    }
+
     #[derive(Clone, Serialize)]
     pub struct TestPlugin {
         skip: bool,
@@ -603,9 +3181,56 @@ pub(crate) mod tests {
         Uuid::new_v4()
     }
 
+    #[derive(Plugin, Serialize)]
+    #[reality(call = call_test_derive_nil, content_from = NilContent)]
+    struct TestDeriveNil;
+
+    fn call_test_derive_nil(bind: Bind<TestDeriveNil>) -> CallResult {
+        bind.work(|_, _| async { Ok(()) })
+    }
+
+    #[derive(Plugin, Serialize)]
+    #[derive(runplat_macros::Resource)]
+    #[reality(call = call_test_derive_skip, skip_resource, skip_content)]
+    struct TestDeriveSkip;
+
+    impl Content for TestDeriveSkip {
+        fn state_uuid(&self) -> uuid::Uuid {
+            Uuid::from_u64_pair(7, 0)
+        }
+    }
+
+    fn call_test_derive_skip(bind: Bind<TestDeriveSkip>) -> CallResult {
+        bind.work(|_, _| async { Ok(()) })
+    }
+
     #[tokio::test]
     async fn test_test_derive() {
         let mut state = State::new();
         let _ = state.load(TestDerive, Labels::default());
+
+        let address = state.load(TestDeriveSkip, Labels::default());
+        assert_eq!(Uuid::from_u64_pair(7, 0), TestDeriveSkip.state_uuid());
+        state.call(&address).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_state_load_ephemeral() {
+        let mut state = State::new();
+        assert_ne!(ephemeral(&TestDerive), ephemeral(&TestDerive));
+
+        // Plugins w/ NilContent share a commit when loaded normally
+        let a = state.load(TestDeriveNil, Labels::default());
+        let b = state.load(TestDeriveNil, Labels::default());
+        assert_eq!(a, b);
+
+        let c = state.load_ephemeral(TestDeriveNil, Labels::default());
+        let d = state.load_ephemeral(TestDeriveNil, Labels::default());
+        assert_ne!(a, c);
+        assert_ne!(c, d);
+        assert_eq!(3, state.addresses().len());
+        let event = state.event(TestDeriveNil::name().path()).unwrap();
+        assert_eq!(d.commit(), event.address().commit());
+        state.call(&c).await.unwrap();
     }
 }
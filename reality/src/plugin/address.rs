@@ -5,7 +5,10 @@ use serde::Serialize;
 use super::Name;
 
 /// Full address to a plugin which includes both the name and the commit the plugin is stored at
-#[derive(Clone, Debug, Serialize)]
+///
+/// Addresses are compared and hashed by commit, w/ the name used as a tiebreak, since the commit uniquely identifies
+/// the loaded plugin
+#[derive(Clone, Debug, Serialize, PartialEq, Eq, Hash)]
 pub struct Address {
     /// Plugin name this address points to
     pub(crate) name: Name,
@@ -19,6 +22,32 @@ impl Address {
     pub fn commit(&self) -> u64 {
         self.commit
     }
+
+    /// Name of the plugin this address points to
+    #[inline]
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    /// Returns true if the matcher matches the plugin name or is this address in path format
+    #[inline]
+    pub fn matches(&self, matcher: &str) -> bool {
+        self.name.matches(matcher) || self.to_string() == matcher
+    }
+}
+
+impl Ord for Address {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.commit
+            .cmp(&other.commit)
+            .then_with(|| self.name.cmp(&other.name))
+    }
+}
+
+impl PartialOrd for Address {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl std::fmt::Display for Address {
@@ -0,0 +1,99 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{Error, Result};
+
+/// Resource limits that can be attached to an event
+///
+/// **Note**: The budget is shared by all forks of the event's call, so spawned tasks are counted per event
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourceBudget {
+    /// Maximum duration the event is allowed to run before it is cancelled
+    pub max_duration: Option<Duration>,
+    /// Maximum number of tasks that can be spawned w/ `Bind::spawn`
+    pub max_spawned_tasks: Option<usize>,
+}
+
+impl ResourceBudget {
+    /// Returns a new empty budget
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum duration of the event
+    #[inline]
+    pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Sets the maximum number of tasks the event can spawn
+    #[inline]
+    pub fn with_max_spawned_tasks(mut self, max_spawned_tasks: usize) -> Self {
+        self.max_spawned_tasks = Some(max_spawned_tasks);
+        self
+    }
+}
+
+/// Resource limit that was exceeded by a call
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum ResourceLimit {
+    /// The call ran longer than the max duration
+    Duration(Duration),
+    /// The call tried to spawn more than the max number of tasks
+    SpawnedTasks(usize),
+}
+
+/// Tracks resource usage of a call against a budget
+#[derive(Clone)]
+pub(crate) struct BudgetTracker {
+    /// Budget being enforced
+    budget: ResourceBudget,
+    /// Number of tasks spawned so far
+    spawned: Arc<AtomicUsize>,
+}
+
+impl BudgetTracker {
+    /// Returns a new tracker for a budget
+    #[inline]
+    pub(crate) fn new(budget: ResourceBudget) -> Self {
+        Self {
+            budget,
+            spawned: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Returns the max duration of the budget
+    #[inline]
+    pub(crate) fn max_duration(&self) -> Option<Duration> {
+        self.budget.max_duration
+    }
+
+    /// Reserves a spawned task from the budget
+    ///
+    /// Returns an error if the max number of spawned tasks has been reached
+    #[inline]
+    pub(crate) fn reserve_task(&self) -> Result<()> {
+        match self.budget.max_spawned_tasks {
+            Some(max) => self
+                .spawned
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                    (n < max).then_some(n + 1)
+                })
+                .map(|_| ())
+                .map_err(|_| Error::ResourceBudgetExceeded {
+                    limit: ResourceLimit::SpawnedTasks(max),
+                }),
+            None => {
+                self.spawned.fetch_add(1, Ordering::AcqRel);
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use super::MessageData;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+/// Type-alias for the key of a cached result, the state uuid of the plugin and the commit of the handler if one was set
+pub(crate) type CacheKey = (Uuid, Option<u64>);
+
+/// Type-alias for a shared map of cached results
+type CacheMap = Arc<RwLock<HashMap<CacheKey, CacheEntry>>>;
+
+/// Cached result of a plugin call
+struct CacheEntry {
+    /// When the result was cached
+    inserted: Instant,
+    /// Message data returned by the call
+    data: MessageData,
+}
+
+/// Cache of the results returned by calls to cacheable plugins, see `Plugin::cacheable`
+///
+/// Results are keyed by the plugin's `state_uuid`, so calls to plugins w/ identical config share a result
+#[derive(Clone)]
+pub(crate) struct ResultCache {
+    /// How long a result is returned from the cache after it is inserted
+    ttl: Duration,
+    /// Maximum number of cached results
+    max_entries: usize,
+    /// Cached results
+    entries: CacheMap,
+}
+
+impl ResultCache {
+    /// Creates a new empty result cache
+    #[inline]
+    pub(crate) fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries,
+            entries: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Returns a new empty result cache w/ the same settings as this cache
+    #[inline]
+    pub(crate) fn detached(&self) -> Self {
+        Self::new(self.ttl, self.max_entries)
+    }
+
+    /// Returns a copy of the cached result for a key, if the result has not expired
+    #[inline]
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<MessageData> {
+        let entries = match self.entries.read() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        entries
+            .get(key)
+            .filter(|e| e.inserted.elapsed() < self.ttl)
+            .and_then(|e| e.data.try_clone())
+    }
+
+    /// Caches a copy of a result for a key
+    ///
+    /// If the cache is full, expired results are removed first and then the oldest result is evicted
+    ///
+    /// **Note**: Only message data the call returned is cached, `MessageData::Empty` is never cached so that the plugin
+    /// is called again. `MessageData::Stream` cannot be copied and is never cached either.
+    #[inline]
+    pub(crate) fn insert(&self, key: CacheKey, data: &MessageData) {
+        if matches!(data, MessageData::Empty) || self.max_entries == 0 {
+            return;
+        }
+        let Some(data) = data.try_clone() else {
+            return;
+        };
+
+        let mut entries = match self.entries.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        if !entries.contains_key(&key) && entries.len() >= self.max_entries {
+            entries.retain(|_, e| e.inserted.elapsed() < self.ttl);
+            if entries.len() >= self.max_entries {
+                if let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, e)| e.inserted)
+                    .map(|(k, _)| *k)
+                {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(
+            key,
+            CacheEntry {
+                inserted: Instant::now(),
+                data,
+            },
+        );
+    }
+
+    /// Removes all cached results
+    #[inline]
+    pub(crate) fn clear(&self) {
+        let mut entries = match self.entries.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        entries.clear();
+    }
+}
@@ -1,9 +1,20 @@
-use super::{Address, Broker, ForkFn, Plugin, State, Work};
+use super::{
+    budget::BudgetTracker, Address, Broker, CancelCause, CancelReason, ForkFn, MessageData, Plugin,
+    RuntimeMetrics, Scratch, State, Work,
+};
 use crate::{Error, Result};
-use runir::store::Item;
-use std::{fmt::Debug, future::Future, marker::PhantomData, sync::Arc};
+use runir::{
+    repr::Labels,
+    store::{Item, Observable},
+};
+use std::{
+    fmt::Debug, future::Future, marker::PhantomData, path::PathBuf, sync::Arc, time::Duration,
+};
 use tokio_util::sync::CancellationToken;
 
+/// Label that enables dry-run mode for a plugin when set to `true`, see `Bind::is_dry_run`
+pub const DRY_RUN_LABEL: &str = "dry-run";
+
 /// Contains state of a plugin invocation call
 ///
 /// Serves as the context when a plugin is called
@@ -17,10 +28,18 @@ pub struct Call {
     pub(crate) fork_fn: ForkFn,
     /// Child cancellation token that can be used to cancel this call
     pub(crate) cancel: CancellationToken,
+    /// Reason the cancel token was cancelled
+    pub(crate) cause: CancelCause,
     /// Handle to the backing runtime
     pub(crate) runtime: tokio::runtime::Handle,
     /// Address of the handler
     pub(crate) handler: Option<Address>,
+    /// Resource budget assigned by the event, shared by all forks of this call
+    pub(crate) budget: Option<BudgetTracker>,
+    /// Explicit timeout assigned by the event, overrides the plugin\'s default timeout
+    pub(crate) timeout: Option<Duration>,
+    /// Scratch state that lives for the duration of this call, forks of this call get an independent scratch state
+    pub(crate) scratch: Scratch,
 }
 
 impl Call {
@@ -60,8 +79,37 @@ impl Call {
             item: (self.fork_fn)(&self.item),
             fork_fn: self.fork_fn.clone(),
             cancel: self.cancel.child_token(),
+            cause: self.cause.child(),
             runtime: self.runtime.clone(),
             handler: self.handler.clone(),
+            budget: self.budget.clone(),
+            timeout: self.timeout,
+            scratch: Scratch::default(),
+        }
+    }
+
+    /// Creates an isolated fork of this call
+    ///
+    /// Will call `P::fork_isolated(item)` so that the forked call does not share plugin state w/ this call
+    ///
+    /// Returns an error if the plugin does not match the current item in this context
+    #[inline]
+    pub fn fork_isolated<P: Plugin + Clone>(&self) -> Result<Call> {
+        if self.item.is_type::<P>() {
+            Ok(Call {
+                state: self.state.clone(),
+                item: P::fork_isolated(&self.item),
+                fork_fn: self.fork_fn,
+                cancel: self.cancel.child_token(),
+                cause: self.cause.child(),
+                runtime: self.runtime.clone(),
+                handler: self.handler.clone(),
+                budget: self.budget.clone(),
+                timeout: self.timeout,
+                scratch: Scratch::default(),
+            })
+        } else {
+            Err(Error::PluginMismatch)
         }
     }
 
@@ -76,6 +124,33 @@ impl Call {
     pub fn handler(&self) -> Option<&Address> {
         self.handler.as_ref()
     }
+
+    /// Returns the state this call originated from
+    #[inline]
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Returns the scratch state of this call
+    #[inline]
+    pub fn scratch(&self) -> &Scratch {
+        &self.scratch
+    }
+
+    /// Cancels this call w/ a reason
+    ///
+    /// **Note**: If the call was already cancelled, the original reason is kept
+    #[inline]
+    pub fn cancel_with(&self, reason: CancelReason) {
+        self.cause.set(reason);
+        self.cancel.cancel();
+    }
+
+    /// Returns the reason this call was cancelled, or `CancelReason::Unknown` if a reason was not set
+    #[inline]
+    pub fn cancel_reason(&self) -> CancelReason {
+        self.cause.reason()
+    }
 }
 
 /// Represents the binding between a plugin and it's associated Call
@@ -93,14 +168,14 @@ pub struct Bind<P: Plugin> {
 }
 
 impl<P: Plugin> Bind<P> {
-    /// Returns a reference to the plugin's "receiver"
+    /// Returns a reference to the plugin
     ///
-    /// A receiver is always immutable, so if the receiver field on the binding is set, that version of
-    /// plugin state will be returned instead of the base item
+    /// The plugin is always immutable through this reference, so if a receiver override is set on the binding, that
+    /// version of plugin state will be returned instead of the base item
     ///
     /// Returns an error if the current call context does not match the target plugin
     #[inline]
-    pub fn receiver<'a: 'b, 'b>(&'a self) -> Result<&'b P> {
+    pub fn plugin<'a: 'b, 'b>(&'a self) -> Result<&'b P> {
         match self.receiver.as_deref().or(self.call.item.borrow::<P>()) {
             Some(p) => Ok(p),
             None => Err(Error::PluginMismatch),
@@ -109,33 +184,215 @@ impl<P: Plugin> Bind<P> {
 
     /// Returns a mutable reference to the plugin in order to update the plugin's state
     ///
+    /// **Note**: A receiver override is not returned, changes are always made to the base item
+    ///
     /// Returns an error if the current call context does not match the target plugin
     #[inline]
-    pub fn update(&mut self) -> Result<&mut P> {
+    pub fn plugin_mut(&mut self) -> Result<&mut P> {
         match self.call.item.borrow_mut::<P>() {
             Some(p) => Ok(p),
             None => Err(Error::PluginMismatch),
         }
     }
 
+    /// Returns a reference to the plugin's "receiver"
+    ///
+    /// **Note**: Alias of `Bind::plugin`
+    #[inline]
+    pub fn receiver<'a: 'b, 'b>(&'a self) -> Result<&'b P> {
+        self.plugin()
+    }
+
+    /// Returns a mutable reference to the plugin in order to update the plugin's state
+    ///
+    /// **Note**: Alias of `Bind::plugin_mut`
+    #[inline]
+    pub fn update(&mut self) -> Result<&mut P> {
+        self.plugin_mut()
+    }
+
+    /// Returns a mutable reference to the plugin in order to update the plugin's state, if the plugin's state is
+    /// consistent
+    ///
+    /// Unlike `plugin_mut`, returns `Error::PoisonedState` if a previous call panicked while mutating the plugin, so that
+    /// the plugin can fail safe instead of operating on a possibly inconsistent state. Returns `Error::PluginMismatch`
+    /// if the current call context does not match the target plugin.
+    #[inline]
+    pub fn try_update(&mut self) -> Result<&mut P> {
+        if !self.call.item.is_type::<P>() {
+            Err(Error::PluginMismatch)
+        } else if self.call.item.is_poisoned() {
+            Err(Error::PoisonedState)
+        } else {
+            self.update()
+        }
+    }
+
+    /// Returns an observable for the bound plugin, which can be used to publish progress while the plugin is working
+    ///
+    /// Observers can receive changes w/ the event returned by `State::observe`
+    #[inline]
+    pub fn observable(&mut self) -> Observable {
+        let event = self.call.state.observation_event(&self.call.item);
+        self.call.item.observe_with(event)
+    }
+
+    /// Returns a fork of this binding w/ a copy of the plugin that can be handed to spawned sub-work
+    ///
+    /// The fork has a child cancel token, so cancelling the fork does not cancel this binding. The item is forked w/
+    /// `Plugin::fork_isolated`, so changes made w/ `update` on the fork are not visible to this binding, use
+    /// `fork_shared` to fork w/o copying the plugin
+    ///
+    /// Returns an error if the plugin does not match the item of this binding
+    #[inline]
+    pub fn fork(&self) -> Result<Bind<P>>
+    where
+        P: Clone,
+    {
+        Ok(Bind {
+            call: self.call.fork_isolated::<P>()?,
+            receiver: self.receiver.clone(),
+            _bound: PhantomData,
+        })
+    }
+
+    /// Returns a fork of this binding that shares the plugin's state w/ this binding, see `Call::fork`
+    ///
+    /// The fork has a child cancel token, so cancelling the fork does not cancel this binding. The item is forked w/
+    /// `Plugin::fork`, so by default changes made w/ `update` on the fork are visible to this binding
+    #[inline]
+    pub fn fork_shared(&self) -> Bind<P> {
+        Bind {
+            call: self.call.fork(),
+            receiver: self.receiver.clone(),
+            _bound: PhantomData,
+        }
+    }
+
     /// Returns message broker
     #[inline]
     pub fn broker(&self) -> &Broker {
         self.call.state.broker()
     }
 
+    /// Publishes data to the bound item's commit, so that it can be picked up by `Event::returns`
+    ///
+    /// Returns `Error::PreviousUnhandledRequest` if data is already pending for the item, unless the state's broker
+    /// is in queue mode, see `Broker::send`
+    #[inline]
+    pub fn emit(&self, data: impl Into<MessageData>) -> Result<()> {
+        self.broker().send(self.call.item.commit(), data)
+    }
+
+    /// Publishes data to the commit of another plugin loaded in the same state
+    ///
+    /// Returns `Error::PluginNotFound` if the target plugin is not loaded, otherwise follows the same semantics as
+    /// `Bind::emit`
+    #[inline]
+    pub fn emit_to(&self, target: impl Into<PathBuf>, data: impl Into<MessageData>) -> Result<()> {
+        let dest = self
+            .call
+            .state
+            .find_plugin(target)
+            .map(|i| i.commit())
+            .ok_or(Error::PluginNotFound)?;
+        self.broker().send(dest, data)
+    }
+
+    /// Returns the state this call originated from
+    ///
+    /// Plugins can use the state to find or start events for other plugins loaded in the same state
+    #[inline]
+    pub fn state(&self) -> &State {
+        &self.call.state
+    }
+
     /// Returns the current tokio handle
     #[inline]
     pub fn runtime(&self) -> &tokio::runtime::Handle {
         &self.call.runtime
     }
 
+    /// Returns a snapshot of the metrics of the tokio runtime this call is running on
+    ///
+    /// Plugins that spawn work can use this to size their own concurrency, i.e. spawning fewer tasks while many tasks
+    /// are already alive on the runtime
+    #[inline]
+    pub fn runtime_metrics(&self) -> RuntimeMetrics {
+        RuntimeMetrics::of(&self.call.runtime)
+    }
+
+    /// Returns a clone of the value of type `T` in the scratch state of this call, or None if a value was not set
+    ///
+    /// Scratch state can be used to accumulate results across the deferred work of a call w/o updating the plugin
+    #[inline]
+    pub fn scratch<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        self.call.scratch.get::<T>()
+    }
+
+    /// Calls `f` w/ a mutable reference to the value of type `T` in the scratch state of this call, inserting the
+    /// default value if a value was not set
+    ///
+    /// **Note**: Forks of this binding do not share scratch state w/ this binding
+    #[inline]
+    pub fn scratch_mut<T: Default + Send + Sync + 'static, R>(
+        &self,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> R {
+        self.call.scratch.with_mut(f)
+    }
+
     /// Returns the item bound to this call
     #[inline]
     pub fn item(&self) -> &Item {
         &self.call.item
     }
 
+    /// Returns the value of a label the plugin was loaded with
+    ///
+    /// Returns None if the label was not set
+    #[inline]
+    pub fn label(&self, key: &str) -> Option<String> {
+        self.call
+            .item
+            .attributes()
+            .get::<Labels>()
+            .and_then(|l| l.get(key).cloned())
+    }
+
+    /// Returns the labels the plugin was loaded with
+    #[inline]
+    pub fn labels(&self) -> Arc<Labels> {
+        self.call
+            .item
+            .attributes()
+            .get::<Labels>()
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the plugin should skip its side effects, i.e. sending a request, starting a process, or
+    /// writing a file
+    ///
+    /// A call is in dry-run mode if the plugin was loaded w/ the `dry-run = "true"` label, or if dry-run mode was
+    /// enabled on the state w/ `State::set_dry_run`. A plugin in dry-run mode should return success w/o performing
+    /// the side effect, so that operations can be validated end-to-end.
+    #[inline]
+    pub fn is_dry_run(&self) -> bool {
+        self.labels()
+            .get_parsed::<bool>(DRY_RUN_LABEL)
+            .unwrap_or(false)
+            || self.call.state.is_dry_run()
+    }
+
+    /// Returns true if cancellation has been requested for this call
+    ///
+    /// Plugin work that runs in synchronous chunks, i.e. a loop reading lines or pages, can check this between
+    /// iterations to return early w/ `Bind::plugin_call_cancelled` instead of awaiting the cancellation token
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.call.cancel.is_cancelled()
+    }
+
     /// Defers access to the item for later by executing with the binding instead
     #[inline]
     pub fn defer<F>(
@@ -148,12 +405,19 @@ impl<P: Plugin> Bind<P> {
         let binding = self.clone();
         let handle = self.call.runtime.clone();
         let cancel_clone = self.call.cancel.clone();
+        let cause = self.call.cause.clone();
         let cancel = self.call.cancel;
+        let in_flight = self.call.state.in_flight.register(self.call.item.commit());
+        let poison = self.call.item.poison_guard();
         Ok(Work {
-            task: handle
-                .clone()
-                .spawn(async move { exec(binding, cancel_clone).await }),
+            task: handle.clone().spawn(async move {
+                let _in_flight = in_flight;
+                let _poison = poison;
+                exec(binding, cancel_clone).await
+            }),
             cancel,
+            cause,
+            name: P::name(),
         })
     }
 
@@ -171,12 +435,53 @@ impl<P: Plugin> Bind<P> {
         let mut call = self.clone();
         let handle = self.call.runtime.clone();
         let cancel_clone = self.call.cancel.clone();
+        let cause = self.call.cause.clone();
         let cancel = self.call.cancel;
+        let in_flight = self.call.state.in_flight.register(self.call.item.commit());
+        let poison = self.call.item.poison_guard();
+        Ok(Work {
+            task: handle.clone().spawn(async move {
+                let _in_flight = in_flight;
+                let _poison = poison;
+                exec(call.update()?, cancel_clone).await
+            }),
+            cancel,
+            cause,
+            name: P::name(),
+        })
+    }
+
+    /// Consumes the call context and spawns work w/ mutable access to the plugin, w/o cloning the binding
+    ///
+    /// Unlike `work_mut`, the call is moved into the spawned task and the plugin is borrowed directly from the
+    /// bound item. Prefer this in hot paths where the plugin only needs `&mut self`.
+    #[inline]
+    pub fn run_mut<F>(
+        self,
+        exec: impl FnOnce(&mut P, CancellationToken) -> F + Send + 'static,
+    ) -> Result<Work>
+    where
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        let mut call = self.call;
+        let handle = call.runtime.clone();
+        let cancel = call.cancel.clone();
+        let cause = call.cause.clone();
+        let in_flight = call.state.in_flight.register(call.item.commit());
+        let poison = call.item.poison_guard();
         Ok(Work {
-            task: handle
-                .clone()
-                .spawn(async move { exec(call.update()?, cancel_clone).await }),
+            task: handle.spawn(async move {
+                let _in_flight = in_flight;
+                let _poison = poison;
+                let cancel = call.cancel.clone();
+                match call.item.borrow_mut::<P>() {
+                    Some(p) => exec(p, cancel).await,
+                    None => Err(Error::PluginMismatch),
+                }
+            }),
             cancel,
+            cause,
+            name: P::name(),
         })
     }
 
@@ -194,15 +499,74 @@ impl<P: Plugin> Bind<P> {
         let call = self.clone();
         let handle = self.call.runtime.clone();
         let cancel_clone = self.call.cancel.clone();
+        let cause = self.call.cause.clone();
         let cancel = self.call.cancel;
+        let in_flight = self.call.state.in_flight.register(self.call.item.commit());
         Ok(Work {
-            task: handle
-                .clone()
-                .spawn(async move { exec(call.receiver()?, cancel_clone).await }),
+            task: handle.clone().spawn(async move {
+                let _in_flight = in_flight;
+                exec(call.receiver()?, cancel_clone).await
+            }),
             cancel,
+            cause,
+            name: P::name(),
         })
     }
 
+    /// Spawns a background task on the current runtime
+    ///
+    /// The task is tracked as in-flight work of the state until it completes, see `State::await_idle`
+    ///
+    /// If the event was assigned a resource budget w/ a max number of spawned tasks, returns
+    /// `Error::ResourceBudgetExceeded` once the limit has been reached
+    #[inline]
+    pub fn spawn<F>(&self, task: F) -> Result<tokio::task::JoinHandle<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        if let Some(budget) = self.call.budget.as_ref() {
+            budget.reserve_task()?;
+        }
+        let in_flight = self.call.state.in_flight.register(self.call.item.commit());
+        Ok(self.call.runtime.spawn(async move {
+            let _in_flight = in_flight;
+            task.await
+        }))
+    }
+
+    /// Runs blocking work such as file reads or FFI calls on the blocking thread pool of the current runtime
+    ///
+    /// Returns `Error::PluginCallCancelled` if the call is cancelled before the work completes. The work is tracked as
+    /// in-flight work of the state until it completes, see `State::await_idle`
+    ///
+    /// **Note**: Blocking work cannot be interrupted, so if the call is cancelled the work keeps running in the
+    /// background and its result is discarded
+    #[inline]
+    pub fn blocking<T>(
+        &self,
+        f: impl FnOnce() -> Result<T> + Send + 'static,
+    ) -> impl Future<Output = Result<T>> + Send + 'static
+    where
+        T: Send + 'static,
+    {
+        let cancel = self.call.cancel.clone();
+        let cause = self.call.cause.clone();
+        let in_flight = self.call.state.in_flight.register(self.call.item.commit());
+        let task = self.call.runtime.spawn_blocking(move || {
+            let _in_flight = in_flight;
+            f()
+        });
+        async move {
+            tokio::select! {
+                result = task => result?,
+                _ = cancel.cancelled() => Err(Error::PluginCallCancelled {
+                    reason: cause.reason(),
+                }),
+            }
+        }
+    }
+
     /// Convenience helper for calling returns `Err(Error::PluginCallSkipped)`
     #[inline]
     pub fn skip(self) -> crate::Result<Work> {
@@ -218,10 +582,12 @@ impl<P: Plugin> Bind<P> {
         }
     }
 
-    /// Convenience helper for returning a plugin call cancelled error
+    /// Convenience helper for returning a plugin call cancelled error w/ the reason the call was cancelled
     #[inline]
     pub fn plugin_call_cancelled(&self) -> crate::Error {
-        crate::Error::PluginCallCancelled
+        crate::Error::PluginCallCancelled {
+            reason: self.call.cancel_reason(),
+        }
     }
 }
 
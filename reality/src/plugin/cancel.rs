@@ -0,0 +1,68 @@
+use std::sync::{Arc, OnceLock};
+
+/// Enumeration of reasons a plugin call can be cancelled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CancelReason {
+    /// The call was cancelled externally w/o a reason, i.e. by cancelling a token returned by `Event::fork`
+    #[default]
+    Unknown,
+    /// The call was cancelled by the caller, i.e. w/ `Work::cancel`
+    User,
+    /// The call did not complete before its timeout elapsed
+    Timeout,
+    /// The state the call originated from was closed
+    Shutdown,
+    /// The call exceeded the resource budget assigned to its event
+    ResourceBudget,
+    /// The deadline set for the state the call originated from elapsed, see `State::set_global_deadline`
+    GlobalDeadline,
+}
+
+impl CancelReason {
+    /// Returns true if a call cancelled for this reason can be retried
+    ///
+    /// A call that exceeded its resource budget is not retryable, since it would exceed the same budget again
+    #[inline]
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, CancelReason::Timeout)
+    }
+}
+
+/// Records why a cancellation token was cancelled
+///
+/// A cause created w/ `child` falls back to the reason of its parent, which mirrors how cancelling a parent
+/// cancellation token cancels its child tokens
+#[derive(Clone, Default)]
+pub(crate) struct CancelCause {
+    /// Reason set by the path that triggered cancellation, the first reason set wins
+    reason: Arc<OnceLock<CancelReason>>,
+    /// Cause of the parent token
+    parent: Option<Arc<CancelCause>>,
+}
+
+impl CancelCause {
+    /// Returns a new cause for a child token
+    #[inline]
+    pub(crate) fn child(&self) -> CancelCause {
+        CancelCause {
+            reason: Arc::new(OnceLock::new()),
+            parent: Some(Arc::new(self.clone())),
+        }
+    }
+
+    /// Sets the reason for cancellation if a reason has not already been set
+    #[inline]
+    pub(crate) fn set(&self, reason: CancelReason) {
+        let _ = self.reason.set(reason);
+    }
+
+    /// Returns the reason for cancellation, or `CancelReason::Unknown` if no reason was set
+    #[inline]
+    pub(crate) fn reason(&self) -> CancelReason {
+        match (self.reason.get(), self.parent.as_ref()) {
+            (Some(reason), _) => *reason,
+            (None, Some(parent)) => parent.reason(),
+            (None, None) => CancelReason::Unknown,
+        }
+    }
+}
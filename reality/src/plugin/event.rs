@@ -1,8 +1,12 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use super::{thunk::HandlerThunk, Address, Call, Handler, MessageData, Thunk};
+use super::{
+    budget::BudgetTracker, cache::CacheKey, thunk::HandlerThunk, Address, Call, Handler,
+    MessageData, ResourceBudget, Thunk, TypedHandler, Work, DRY_RUN_LABEL,
+};
 use crate::{Error, Result};
 use runir::{repr::Labels, store::Item};
+use serde::de::DeserializeOwned;
 use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
@@ -78,6 +82,39 @@ impl Event {
         }
     }
 
+    /// Sends input data to the plugin of this event, which is received w/ `Plugin::receive` when the event is started
+    ///
+    /// Returns `Error::PreviousUnhandledRequest` if input is already pending for the plugin, unless the state's broker
+    /// is in queue mode, see `Broker::send`
+    #[inline]
+    pub fn with_input(&mut self, data: impl Into<MessageData>) -> Result<&mut Self> {
+        self.call.state.broker().send(self.address.commit(), data)?;
+        Ok(self)
+    }
+
+    /// Assigns a resource budget to this event
+    ///
+    /// If the event runs longer than `max_duration`, the call is cancelled and returns `Error::ResourceBudgetExceeded`.
+    /// Calls to `Bind::spawn` beyond `max_spawned_tasks` are rejected w/ the same error.
+    #[inline]
+    pub fn with_budget(&mut self, budget: ResourceBudget) -> &mut Self {
+        self.call.budget = Some(BudgetTracker::new(budget));
+        self
+    }
+
+    /// Sets the max duration of this event, overriding the plugin's default timeout
+    ///
+    /// If the event runs longer than `timeout`, the call is cancelled and returns `Error::PluginCallCancelled` w/
+    /// `CancelReason::Timeout`.
+    ///
+    /// **Note**: The timeout is kept separate from the resource budget, if the budget has a shorter `max_duration` the
+    /// call returns `Error::ResourceBudgetExceeded` instead.
+    #[inline]
+    pub fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.call.timeout = Some(timeout);
+        self
+    }
+
     /// Consumes and starts the event
     #[inline]
     pub async fn start(self) -> Result<()> {
@@ -89,22 +126,101 @@ impl Event {
         }
     }
 
+    /// Consumes and starts the event in a background task, returning work that can be joined or cancelled
+    ///
+    /// Cancelling the work cancels this event's call, see `Work::cancel`
+    #[inline]
+    pub fn spawn(self) -> Work {
+        let cancel = self.call.cancel.clone();
+        let cause = self.call.cause.clone();
+        let name = self.address.name.clone();
+        let runtime = self.call.runtime.clone();
+        Work {
+            task: runtime.spawn(self.start()),
+            cancel,
+            cause,
+            name,
+        }
+    }
+
+    /// Consumes and starts the event, returning how long the event took to complete
+    #[inline]
+    pub async fn start_timed(self) -> Result<Duration> {
+        if let Some(handler) = self.handler {
+            handler.exec_timed(self.call).await
+        } else {
+            debug!(address = self.address().to_string(), "event_start");
+            self.thunk.exec_timed(self.call).await
+        }
+    }
+
     /// Consumes and starts the event, if the event was assigned a handler, returns
-    /// any messages received by the handler
+    /// any messages received by the handler, otherwise returns any messages the plugin published w/ `Bind::emit`
+    ///
+    /// If result caching is enabled on the state and the plugin is cacheable, a cached result is returned w/o calling
+    /// the plugin, see `State::enable_result_cache`
+    ///
+    /// **Note**: If the handler returned a `MessageData::Stream`, the stream is returned unchanged and has not been polled
     #[inline]
     pub async fn returns(self) -> Result<MessageData> {
-        if let Some(handler) = self.handler {
+        let cache = self.cache_key().and_then(|key| {
+            self.call.state.cache.clone().map(|cache| (cache, key))
+        });
+        if let Some(cached) = cache.as_ref().and_then(|(cache, key)| cache.get(key)) {
+            debug!(address = self.address().to_string(), "event_cached");
+            return Ok(cached);
+        }
+
+        let returns = if let Some(handler) = self.handler {
             let handler_info = self.call.handler().cloned();
             let broker = self.call.state.broker().clone();
             handler.exec(self.call).await?;
-            let returns = handler_info
+            handler_info
                 .map(|h| broker.receive(h.commit()))
-                .unwrap_or_default();
-            Ok(returns)
+                .unwrap_or_default()
         } else {
             debug!(address = self.address().to_string(), "event_start");
+            let broker = self.call.state.broker().clone();
+            let commit = self.address.commit();
             self.thunk.exec(self.call).await?;
-            Ok(MessageData::Empty)
+            broker.receive(commit)
+        };
+
+        if let Some((cache, key)) = cache {
+            cache.insert(key, &returns);
+        }
+        Ok(returns)
+    }
+
+    /// Consumes and starts the event, deserializing the returned message data into `T`, see `Event::returns`
+    ///
+    /// Returns `Error::EmptyHandlerOutput` if no message data was returned, and `Error::HandlerOutputMismatch` if the
+    /// message data could not be deserialized into `T`
+    #[inline]
+    pub async fn returns_typed<T: DeserializeOwned>(self) -> Result<T> {
+        self.returns().await?.deserialize()
+    }
+
+    /// Consumes and starts the event, deserializing the returned message data into the output type declared by `H`
+    #[inline]
+    pub async fn returns_output<H: TypedHandler>(self) -> Result<H::Output> {
+        self.returns_typed().await
+    }
+
+    /// Returns the key the result of this event is cached by, or None if the result should not be cached
+    #[inline]
+    fn cache_key(&self) -> Option<CacheKey> {
+        let dry_run = self.call.state.is_dry_run()
+            || self
+                .label(DRY_RUN_LABEL)
+                .and_then(|l| l.parse::<bool>().ok())
+                .unwrap_or(false);
+        if dry_run {
+            None
+        } else {
+            self.thunk
+                .cache_key(&self.call.item)
+                .map(|uuid| (uuid, self.call.handler().map(|h| h.commit())))
         }
     }
 
@@ -114,6 +230,27 @@ impl Event {
         &self.call.item
     }
 
+    /// Returns the address of the handler set on this event
+    #[inline]
+    pub fn handler(&self) -> Option<&Address> {
+        self.call.handler()
+    }
+
+    /// Returns the labels the plugin of this event was loaded with
+    #[inline]
+    pub fn labels(&self) -> Option<&Labels> {
+        self.labels.as_deref()
+    }
+
+    /// Sets the labels of this event, replacing the labels the plugin was loaded with
+    ///
+    /// **Note**: The labels are only set on this event, the labels the plugin is indexed by in the state are unchanged
+    #[inline]
+    pub fn with_labels(&mut self, labels: Labels) -> &mut Self {
+        self.labels = Some(Arc::new(labels));
+        self
+    }
+
     /// Returns the value of a label
     #[inline]
     pub fn label(&self, label: &str) -> Option<&str> {
@@ -0,0 +1,36 @@
+use semver::Version;
+
+/// Framework of this library, i.e. the crate name and version, see `Plugin::framework`
+pub const FRAMEWORK: (&str, &str) = (env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+
+/// Strictness of the check a state applies to the framework a plugin was produced under, see
+/// `State::set_framework_check`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FrameworkCheck {
+    /// Plugins produced under an incompatible framework are loaded w/o a warning
+    Ignore,
+    /// A warning is emitted when a plugin produced under an incompatible framework is loaded
+    #[default]
+    Warn,
+    /// Loading a plugin produced under an incompatible framework returns `Error::IncompatibleFramework`
+    Deny,
+}
+
+/// Returns true if a plugin produced under `framework` can be loaded by the current framework
+///
+/// Versions are compatible if the major version is the same, or the minor version for `0.x` versions. A version that
+/// cannot be parsed is incompatible.
+pub(crate) fn is_compatible(framework: (&str, &str)) -> bool {
+    let (name, version) = framework;
+    if name != FRAMEWORK.0 {
+        return false;
+    }
+
+    match (Version::parse(version), Version::parse(FRAMEWORK.1)) {
+        (Ok(version), Ok(current)) if current.major == 0 => {
+            version.major == 0 && version.minor == current.minor
+        }
+        (Ok(version), Ok(current)) => version.major == current.major,
+        _ => false,
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use serde::Serialize;
+
+use super::{Address, Name, PluginKind};
+
+/// Kind of plugin a graph node represents
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+pub enum NodeKind {
+    /// Node is a plugin
+    Plugin,
+    /// Node is a handler plugin, loaded w/ a handler thunk
+    Handler,
+}
+
+/// Node within a plugin graph
+#[derive(Clone, Debug, Serialize)]
+pub struct GraphNode {
+    /// Address of the plugin this node represents
+    pub address: Address,
+    /// Kind of plugin this node represents
+    pub kind: NodeKind,
+    /// Kind of data flow the plugin participates in, see `Plugin::kind`
+    pub plugin_kind: PluginKind,
+    /// Labels the plugin was loaded with
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Edge between a handler and a plugin it targets
+#[derive(Clone, Debug, Serialize)]
+pub struct GraphEdge {
+    /// Address of the handler
+    pub handler: Address,
+    /// Type name of the handler's target
+    pub target_name: &'static str,
+    /// Address of the loaded plugin matching the target type
+    ///
+    /// **Note**: If this is `None`, then no plugin matching the target type has been loaded into state
+    pub target: Option<Address>,
+}
+
+/// Inspectable graph of the plugins and handler relationships loaded into state
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Graph {
+    /// Plugin nodes
+    pub nodes: Vec<GraphNode>,
+    /// Handler to target edges
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Cycle of handlers where each handler targets the next, and the last handler targets the first
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct HandlerCycle {
+    /// Addresses of the handlers in the cycle
+    pub addresses: Vec<Address>,
+}
+
+impl HandlerCycle {
+    /// Returns the names of the plugins in this cycle, in the order each handler targets the next
+    #[inline]
+    pub fn names(&self) -> Vec<&Name> {
+        self.addresses.iter().map(Address::name).collect()
+    }
+}
+
+impl std::fmt::Display for HandlerCycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for name in self.names() {
+            write!(f, "{name} -> ")?;
+        }
+        match self.addresses.first() {
+            Some(first) => write!(f, "{}", first.name()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Graph {
+    /// Returns an iterator over edges whose handler target could not be found in state
+    #[inline]
+    pub fn unresolved(&self) -> impl Iterator<Item = &GraphEdge> {
+        self.edges.iter().filter(|e| e.target.is_none())
+    }
+
+    /// Returns cycles formed by handler to target edges
+    ///
+    /// Each cycle is reported starting from the first handler of the cycle that was reached
+    pub fn cycles(&self) -> Vec<HandlerCycle> {
+        let mut targets = BTreeMap::<&Address, Vec<&Address>>::new();
+        for edge in self.edges.iter() {
+            if let Some(target) = edge.target.as_ref() {
+                targets.entry(&edge.handler).or_default().push(target);
+            }
+        }
+
+        let mut visited = BTreeSet::new();
+        let mut cycles = vec![];
+        for handler in targets.keys() {
+            find_cycles(handler, &targets, &mut vec![], &mut visited, &mut cycles);
+        }
+        cycles
+    }
+
+    /// Serializes this graph into the DOT format
+    ///
+    /// Handler nodes are drawn as boxes, and unresolved handler targets are drawn as dashed edges to a placeholder node
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph reality {\n");
+        for node in self.nodes.iter() {
+            let mut label = node.address.name.to_string();
+            for (k, v) in node.labels.iter() {
+                let _ = write!(label, "\\n{k}={v}");
+            }
+            let shape = match node.kind {
+                NodeKind::Plugin => "ellipse",
+                NodeKind::Handler => "box",
+            };
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\", shape={shape}];",
+                node.address,
+                escape(&label)
+            );
+        }
+
+        for edge in self.edges.iter() {
+            match edge.target.as_ref() {
+                Some(target) => {
+                    let _ = writeln!(dot, "    \"{}\" -> \"{target}\";", edge.handler);
+                }
+                None => {
+                    let _ = writeln!(
+                        dot,
+                        "    \"{}\" -> \"{}\" [style=dashed];",
+                        edge.handler,
+                        escape(edge.target_name)
+                    );
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Walks handler to target edges depth-first from `node`, recording a cycle when a handler on the current path is
+/// reached again
+fn find_cycles<'a>(
+    node: &'a Address,
+    targets: &BTreeMap<&'a Address, Vec<&'a Address>>,
+    path: &mut Vec<&'a Address>,
+    visited: &mut BTreeSet<&'a Address>,
+    cycles: &mut Vec<HandlerCycle>,
+) {
+    if let Some(start) = path.iter().position(|a| *a == node) {
+        cycles.push(HandlerCycle {
+            addresses: path[start..].iter().map(|a| (*a).clone()).collect(),
+        });
+        return;
+    }
+
+    if !visited.insert(node) {
+        return;
+    }
+
+    path.push(node);
+    for target in targets.get(node).into_iter().flatten() {
+        find_cycles(target, targets, path, visited, cycles);
+    }
+    path.pop();
+}
+
+/// Escapes quotes so that the value can be used as a DOT identifier
+#[inline]
+fn escape(value: &str) -> String {
+    value.replace('"', "\\\"")
+}
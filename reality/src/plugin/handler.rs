@@ -1,6 +1,7 @@
 use clap::{ArgMatches, FromArgMatches};
 use runir::repr::Labels;
 use serde::de::DeserializeOwned;
+use std::future::Future;
 use tracing::debug;
 
 use super::{Address, Bind, Call, Plugin, State, Work};
@@ -16,6 +17,20 @@ pub trait Handler: Plugin {
     /// Returns an error if handling should be skipped
     fn handle(other: Bind<Self::Target>, handler: Bind<Self>) -> Result<()>;
 
+    /// Called after the other plugin has completed successfully, for handlers that need to do async work while
+    /// handling, i.e. sending another request
+    ///
+    /// By default, calls `handle`
+    ///
+    /// Returns an error if handling should be skipped
+    #[inline]
+    fn handle_async(
+        other: Bind<Self::Target>,
+        handler: Bind<Self>,
+    ) -> impl Future<Output = Result<()>> + Send {
+        async move { Self::handle(other, handler) }
+    }
+
     /// Thunk function that wraps another thunk function in order to sequence the handler to execute
     /// after the call of the plugin it is handling
     fn wrap_thunk(call: Call) -> Result<Work> {
@@ -31,20 +46,27 @@ pub trait Handler: Plugin {
                     item: handler.clone(),
                     fork_fn: Self::fork,
                     cancel: call.state.cancel.child_token(),
+                    cause: call.state.cause.child(),
                     runtime: call.runtime.clone(),
                     handler: None,
+                    budget: call.budget.clone(),
+                    timeout: call.timeout,
+                    scratch: Default::default(),
                 };
                 let binding = handler_call.bind::<Self>()?;
                 binding.defer(|b, _| async move {
                     debug!("Waiting for target plugin to complete work");
                     // Wait for the original plugin to complete
                     work.await?;
+                    // Yield between steps so that a long chain of handlers does not monopolize the worker thread
+                    tokio::task::yield_now().await;
                     // Rebind the other plugin
                     let other = call.bind::<Self::Target>()?;
 
                     // Update the state of the handling plugin w/ the latest state of the other plugin
-                    match Self::handle(other, b.clone()) {
+                    match Self::handle_async(other, b.clone()).await {
                         Ok(_) => {
+                            tokio::task::yield_now().await;
                             debug!("Calling handler");
                             Self::call(b)?.await
                         }
@@ -85,3 +107,11 @@ pub trait Handler: Plugin {
         state.load_handler_by_args::<Self>(args, labels)
     }
 }
+
+/// Trait for a handler that declares the type of the message data it returns
+///
+/// See `Event::returns_output`
+pub trait TypedHandler: Handler {
+    /// Type the handler's returned message data is deserialized into
+    type Output: DeserializeOwned;
+}
@@ -0,0 +1,23 @@
+use super::Address;
+use crate::Result;
+
+/// Trait for hooks that run around each plugin call made from a state
+///
+/// Hooks are the extension point for cross-cutting concerns such as auth, logging, and metrics. Hooks are added to
+/// a state w/ `State::add_call_hook` and are run in the order they were added.
+pub trait CallHook: Send + Sync + 'static {
+    /// Called before a plugin is called
+    ///
+    /// Returning an error vetoes the call, the plugin is not called and the error is returned to the caller
+    #[inline]
+    fn before(&self, address: &Address) -> Result<()> {
+        let _ = address;
+        Ok(())
+    }
+
+    /// Called after a plugin call completes w/ the result of the call, including calls vetoed by a hook
+    #[inline]
+    fn after(&self, address: &Address, result: &Result<()>) {
+        let _ = (address, result);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::sync::Notify;
+
+/// Registry of the plugin work spawned from a state that has not completed
+///
+/// Work is registered when it is spawned and deregistered when the task completes or is aborted
+#[derive(Clone, Default)]
+pub(crate) struct InFlight {
+    /// Shared count of registered work and notify for waiters
+    inner: Arc<InFlightInner>,
+}
+
+/// Shared inner state of an in-flight registry
+#[derive(Default)]
+struct InFlightInner {
+    /// Number of registered work that has not completed
+    count: AtomicUsize,
+    /// Number of registered work that has not completed for each plugin commit
+    commits: Mutex<HashMap<u64, usize>>,
+    /// Notified each time registered work is deregistered
+    deregistered: Notify,
+}
+
+/// Registration of work in an in-flight registry, the work is deregistered when this guard is dropped
+pub(crate) struct InFlightGuard {
+    /// Registry the work was registered with
+    inner: Arc<InFlightInner>,
+    /// Commit of the plugin the work was registered for
+    commit: u64,
+}
+
+impl InFlight {
+    /// Registers work for the plugin at `commit`, returning a guard that should be moved into the spawned task
+    #[inline]
+    pub(crate) fn register(&self, commit: u64) -> InFlightGuard {
+        self.inner.count.fetch_add(1, Ordering::AcqRel);
+        *self.inner.commits().entry(commit).or_default() += 1;
+        InFlightGuard {
+            inner: self.inner.clone(),
+            commit,
+        }
+    }
+
+    /// Returns true if there is registered work for the plugin at `commit` that has not completed
+    #[inline]
+    pub(crate) fn contains(&self, commit: u64) -> bool {
+        self.inner.commits().contains_key(&commit)
+    }
+
+    /// Returns the number of registered work that has not completed
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.inner.count.load(Ordering::Acquire)
+    }
+
+    /// Waits until there is no registered work
+    pub(crate) async fn idle(&self) {
+        loop {
+            let deregistered = self.inner.deregistered.notified();
+            tokio::pin!(deregistered);
+            // Enable before checking the count so that a deregistration in between is not missed
+            deregistered.as_mut().enable();
+            if self.len() == 0 {
+                return;
+            }
+            deregistered.await;
+        }
+    }
+}
+
+impl InFlightInner {
+    /// Locks the count of registered work for each plugin commit
+    #[inline]
+    fn commits(&self) -> std::sync::MutexGuard<'_, HashMap<u64, usize>> {
+        match self.commits.lock() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        {
+            let mut commits = self.inner.commits();
+            if let Some(count) = commits.get_mut(&self.commit) {
+                *count -= 1;
+                if *count == 0 {
+                    commits.remove(&self.commit);
+                }
+            }
+        }
+        self.inner.count.fetch_sub(1, Ordering::AcqRel);
+        self.inner.deregistered.notify_waiters();
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::{Address, PluginKind};
+
+/// Description of a plugin loaded into state
+#[derive(Clone, Debug, Serialize)]
+pub struct PluginDescription {
+    /// Address of the plugin
+    pub address: Address,
+    /// Plugin reference w/o the version, i.e. `kioto/plugins.request`
+    pub name: String,
+    /// Version of the plugin
+    pub version: semver::Version,
+    /// Kind of data flow the plugin participates in, see `Plugin::kind`
+    pub kind: PluginKind,
+    /// Labels the plugin was loaded with
+    pub labels: BTreeMap<String, String>,
+    /// Addresses of the loaded handlers that target this plugin
+    pub handlers: Vec<Address>,
+}
+
+impl PluginDescription {
+    /// Returns true if a loaded handler targets this plugin
+    #[inline]
+    pub fn has_handler(&self) -> bool {
+        !self.handlers.is_empty()
+    }
+}
+
+/// Inventory of the plugins loaded into state, see `State::describe_all`
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct StateInventory {
+    /// Description of each plugin and handler loaded into state, in the order of `State::addresses`
+    pub plugins: Vec<PluginDescription>,
+}
+
+impl StateInventory {
+    /// Returns the description of the first plugin matching `matcher`, see `Address::matches`
+    #[inline]
+    pub fn find(&self, matcher: &str) -> Option<&PluginDescription> {
+        self.plugins.iter().find(|p| p.address.matches(matcher))
+    }
+
+    /// Serializes this inventory into pretty-printed JSON
+    #[inline]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
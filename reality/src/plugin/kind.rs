@@ -0,0 +1,18 @@
+use serde::Serialize;
+
+/// Classification of how a plugin participates in the flow of data, see `Plugin::kind`
+///
+/// Tooling can use the kind of each plugin to validate how plugins are wired together, i.e. that the first step of an
+/// operation is a source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+pub enum PluginKind {
+    /// Plugin produces data w/o requiring input, i.e. reading a file or sending a request
+    Source,
+    /// Plugin consumes data w/o producing output, i.e. writing a file
+    Sink,
+    /// Plugin receives data and produces data
+    #[default]
+    Transform,
+    /// Plugin handles the output of a target plugin, see `Handler`
+    Handler,
+}
@@ -1,21 +1,49 @@
 use bytes::Bytes;
+use futures_util::Stream;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, VecDeque},
+    pin::Pin,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
 /// Type-alias for a json map
 pub type JsonMap = serde_json::Map<String, serde_json::Value>;
 
+/// Type-alias for a boxed stream of bytes that can be read incrementally
+///
+/// **Note**: The stream must also be `Sync` since message data is shared between threads by the broker
+pub type MessageStream = Pin<Box<dyn Stream<Item = crate::Result<Bytes>> + Send + Sync>>;
+
+/// Type-alias for the queue of messages pending for a commit, w/ the time each message was sent
+type PendingMessages = VecDeque<(Instant, MessageData)>;
+
 /// Struct containing request state for plugin calls
 ///
 /// This is stored centrally w/ a State object so to consolidate,
 /// statefulness away from the Call objects themselves. Instead the call
 /// objects can check state for requests and remove requests from a single location
+///
+/// By default, each commit can only hold a single pending message. A broker created with `Broker::queue` will
+/// instead hold a bounded queue of pending messages per commit, which are received in the order they were sent.
+///
+/// By default, pending messages never expire. A broker created w/ `Broker::with_ttl` evicts messages that have not been
+/// received within the ttl, see `Broker::evict_expired`.
 #[derive(Clone, Default)]
 pub struct Broker {
-    data: Arc<RwLock<BTreeMap<u64, MessageData>>>,
+    /// Pending messages per commit
+    data: Arc<RwLock<BTreeMap<u64, PendingMessages>>>,
+    /// If set, the maximum number of pending messages per commit
+    capacity: Option<usize>,
+    /// If set, how long a message can be pending before it is evicted
+    ttl: Option<Duration>,
+    /// Maximum size of a message in bytes, 0 means unlimited
+    max_message_bytes: usize,
+    /// If set, the log broker operations are recorded to
+    #[cfg(feature = "record")]
+    log: Arc<RwLock<Option<super::BrokerLog>>>,
 }
 /// Enum of supported request data that can be accepted by plugins
 #[derive(Default)]
@@ -28,6 +56,8 @@ pub enum MessageData {
     Bytes(Bytes),
     /// Message data is a store item
     Item(runir::store::Item),
+    /// Message data is a stream of bytes which can be consumed incrementally
+    Stream(MessageStream),
     /// Empty message data
     #[default]
     Empty,
@@ -40,6 +70,26 @@ impl MessageData {
         matches!(self, MessageData::Empty)
     }
 
+    /// Returns the size of the message data in bytes
+    ///
+    /// The size of `Json` and `Toml` data is the length of the data when serialized.
+    ///
+    /// **Note**: The size of `Item` and `Stream` data cannot be known ahead of time, so is always 0
+    #[inline]
+    pub fn size(&self) -> usize {
+        match self {
+            MessageData::Toml(table) => toml::to_string(table).map(|t| t.len()).unwrap_or(0),
+            MessageData::Json(map) => {
+                let mut counter = ByteCounter(0);
+                serde_json::to_writer(&mut counter, map)
+                    .map(|_| counter.0)
+                    .unwrap_or(0)
+            }
+            MessageData::Bytes(bytes) => bytes.len(),
+            MessageData::Item(_) | MessageData::Stream(_) | MessageData::Empty => 0,
+        }
+    }
+
     /// Returns true if the message data is json
     #[inline]
     pub fn is_json(&self) -> bool {
@@ -64,6 +114,47 @@ impl MessageData {
         matches!(self, MessageData::Bytes(..))
     }
 
+    /// Returns true if the message is a byte stream
+    #[inline]
+    pub fn is_stream(&self) -> bool {
+        matches!(self, MessageData::Stream(..))
+    }
+
+    /// If message data is a Stream, returns a mutable reference to the stream so that it can be polled
+    #[inline]
+    pub fn as_stream(&mut self) -> Option<&mut MessageStream> {
+        if let MessageData::Stream(stream) = self {
+            Some(stream)
+        } else {
+            None
+        }
+    }
+
+    /// If message data is a Stream, consumes the message data and returns the stream
+    #[inline]
+    pub fn into_stream(self) -> Option<MessageStream> {
+        if let MessageData::Stream(stream) = self {
+            Some(stream)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a copy of the message data
+    ///
+    /// Returns None if the message data is a `Stream`, since a stream can only be consumed once
+    #[inline]
+    pub fn try_clone(&self) -> Option<MessageData> {
+        match self {
+            MessageData::Toml(table) => Some(MessageData::Toml(table.clone())),
+            MessageData::Json(map) => Some(MessageData::Json(map.clone())),
+            MessageData::Bytes(bytes) => Some(MessageData::Bytes(bytes.clone())),
+            MessageData::Item(item) => Some(MessageData::Item(item.clone())),
+            MessageData::Stream(_) => None,
+            MessageData::Empty => Some(MessageData::Empty),
+        }
+    }
+
     /// If message data is Bytes, returns a reference to the Bytes
     #[inline]
     pub fn as_bytes(&self) -> Option<&Bytes> {
@@ -84,6 +175,14 @@ impl MessageData {
         }
     }
 
+    /// If message data is an runir::store::Item w/ a resource of type `T`, returns a reference to the resource
+    ///
+    /// Returns None if the message data is not an Item, or if the Item's resource is not a `T`
+    #[inline]
+    pub fn item_as<T: runir::Resource>(&self) -> Option<&T> {
+        self.as_item().and_then(|item| item.borrow::<T>())
+    }
+
     /// If message data is JSON, returns a reference to the inner JSON value
     #[inline]
     pub fn as_json(&self) -> Option<&JsonMap> {
@@ -103,21 +202,212 @@ impl MessageData {
             None
         }
     }
+
+    /// Consumes and deserializes the message data into `T`
+    ///
+    /// `Json` and `Toml` data are deserialized directly, `Bytes` are deserialized as JSON.
+    ///
+    /// Returns `Error::EmptyHandlerOutput` if the message data is `Empty`, and `Error::HandlerOutputMismatch` if the
+    /// data could not be deserialized into `T` or is an `Item` or `Stream`
+    #[inline]
+    pub fn deserialize<T: serde::de::DeserializeOwned>(self) -> crate::Result<T> {
+        let mismatch = |message: String| crate::Error::HandlerOutputMismatch { message };
+        match self {
+            MessageData::Json(map) => serde_json::from_value(serde_json::Value::Object(map))
+                .map_err(|e| mismatch(e.to_string())),
+            MessageData::Toml(table) => table.try_into().map_err(|e| mismatch(e.to_string())),
+            MessageData::Bytes(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|e| mismatch(e.to_string()))
+            }
+            MessageData::Item(_) => Err(mismatch("message data is a store item".to_string())),
+            MessageData::Stream(_) => Err(mismatch("message data is a stream".to_string())),
+            MessageData::Empty => Err(crate::Error::EmptyHandlerOutput),
+        }
+    }
+}
+
+/// Writer that only counts the number of bytes written
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 impl Broker {
+    /// Returns a new broker in queue mode, where each commit can hold up to `capacity` pending messages
+    ///
+    /// **Note**: A capacity of 0 is treated as a capacity of 1
+    #[inline]
+    pub fn queue(capacity: usize) -> Self {
+        Self {
+            data: Arc::new(RwLock::new(BTreeMap::new())),
+            capacity: Some(capacity.max(1)),
+            ttl: None,
+            max_message_bytes: 0,
+            #[cfg(feature = "record")]
+            log: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Returns the queue capacity if this broker is in queue mode
+    #[inline]
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Returns a new broker w/ the same settings as this broker, but w/o any pending messages
+    #[inline]
+    pub(crate) fn detached(&self) -> Self {
+        Self {
+            data: Arc::new(RwLock::new(BTreeMap::new())),
+            capacity: self.capacity,
+            ttl: self.ttl,
+            max_message_bytes: self.max_message_bytes,
+            #[cfg(feature = "record")]
+            log: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Returns this broker w/ a max message size in bytes, sending a larger message will return
+    /// `Error::MessageTooLarge`
+    ///
+    /// **Note**: A limit of 0 means messages are unlimited in size, see `MessageData::size` for how the size of a
+    /// message is measured
+    #[inline]
+    pub fn with_max_message_bytes(mut self, limit: usize) -> Self {
+        self.max_message_bytes = limit;
+        self
+    }
+
+    /// Returns the max message size in bytes, 0 if messages are unlimited in size
+    #[inline]
+    pub fn max_message_bytes(&self) -> usize {
+        self.max_message_bytes
+    }
+
+    /// Returns this broker w/ a ttl for pending messages, messages that are not received within the ttl are evicted
+    /// by `Broker::evict_expired`
+    ///
+    /// Once the pending message for a commit is evicted, a new message can be sent to the commit
+    #[inline]
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Returns the ttl for pending messages, None if messages never expire
+    #[inline]
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Removes pending messages that have not been received within the ttl of this broker, and returns the commits
+    /// that messages were evicted from
+    ///
+    /// Does nothing if this broker does not have a ttl
+    pub fn evict_expired(&self) -> Vec<u64> {
+        let Some(ttl) = self.ttl else {
+            return vec![];
+        };
+
+        let mut g = match self.data.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        let mut evicted = vec![];
+        g.retain(|commit, queue| {
+            let pending = queue.len();
+            // **Note**: Messages are queued in the order they were sent, so the oldest message is at the front
+            while queue.front().is_some_and(|(sent, _)| sent.elapsed() >= ttl) {
+                queue.pop_front();
+            }
+            if queue.len() < pending {
+                debug!(
+                    commit = format!("{commit:x}"),
+                    count = pending - queue.len(),
+                    "broker_message_expired"
+                );
+                evicted.push(*commit);
+            }
+            !queue.is_empty()
+        });
+        evicted
+    }
+
+    /// Spawns a background task that evicts expired messages every `period`, see `Broker::evict_expired`
+    ///
+    /// The task stops once `cancel` is cancelled, i.e. w/ `State::child_token` so that the task stops when the state is
+    /// closed
+    ///
+    /// Panic: Can panic if not called within a tokio runtime
+    pub fn start_expiry(
+        &self,
+        period: Duration,
+        cancel: CancellationToken,
+    ) -> tokio::task::JoinHandle<()> {
+        let broker = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = interval.tick() => {
+                        broker.evict_expired();
+                    }
+                }
+            }
+        })
+    }
+
     /// Sends a request to a dest handle
     ///
     /// Returns an error if previous data has already been set for the handle, or if in between
     /// acquiring the write lock, an entry was written before this function could write.
+    ///
+    /// If this broker is in queue mode, the data is pushed to the back of the queue for the handle and
+    /// an error is only returned if the queue is full.
+    ///
+    /// Returns an error if the data is larger than the max message size of this broker.
     pub fn send(&self, dest: u64, data: impl Into<MessageData>) -> crate::Result<()> {
         debug!("Send data to {dest:x}");
+        let data = data.into();
+        if self.max_message_bytes > 0 {
+            let size = data.size();
+            if size > self.max_message_bytes {
+                return Err(crate::Error::MessageTooLarge {
+                    size,
+                    limit: self.max_message_bytes,
+                });
+            }
+        }
+        if let Some(capacity) = self.capacity {
+            let mut g = match self.data.write() {
+                Ok(g) => g,
+                Err(e) => e.into_inner(),
+            };
+            let queue = g.entry(dest).or_default();
+            if queue.len() >= capacity {
+                return Err(crate::Error::BrokerQueueFull);
+            }
+            #[cfg(feature = "record")]
+            self.log(super::BrokerOp::Send, dest, &data);
+            queue.push_back((Instant::now(), data));
+            return Ok(());
+        }
+
         let g = match self.data.read() {
             Ok(g) => g,
             Err(err) => err.into_inner(),
         };
 
-        if g.contains_key(&dest) {
+        if g.get(&dest).is_some_and(|q| !q.is_empty()) {
             Err(crate::Error::PreviousUnhandledRequest)
         } else {
             drop(g);
@@ -126,18 +416,21 @@ impl Broker {
                 Err(e) => e.into_inner(),
             };
 
-            if let Some(previous) = g.insert(dest, data.into()) {
-                let _ = g.remove(&dest);
-                g.insert(dest, previous);
-
-                Err(crate::Error::WriteRequestRaceCondition)
-            } else {
+            let queue = g.entry(dest).or_default();
+            if queue.is_empty() {
+                #[cfg(feature = "record")]
+                self.log(super::BrokerOp::Send, dest, &data);
+                queue.push_back((Instant::now(), data));
                 Ok(())
+            } else {
+                Err(crate::Error::WriteRequestRaceCondition)
             }
         }
     }
 
     /// Receive a request for a handle
+    ///
+    /// If this broker is in queue mode, the message at the front of the queue is returned
     #[inline]
     pub fn receive(&self, commit: u64) -> MessageData {
         debug!("Receive data for {commit:x}");
@@ -145,7 +438,87 @@ impl Broker {
             Ok(g) => g,
             Err(err) => err.into_inner(),
         };
-        g.remove(&commit).unwrap_or(MessageData::Empty)
+        let data = match g.get_mut(&commit) {
+            Some(queue) => {
+                let data = queue
+                    .pop_front()
+                    .map(|(_, data)| data)
+                    .unwrap_or(MessageData::Empty);
+                if queue.is_empty() {
+                    g.remove(&commit);
+                }
+                data
+            }
+            None => MessageData::Empty,
+        };
+        #[cfg(feature = "record")]
+        self.log(super::BrokerOp::Receive, commit, &data);
+        data
+    }
+
+    /// Removes and returns all pending messages, ordered by commit and then by the order they were sent
+    ///
+    /// Can be used when shutting down to report or persist messages that were not received
+    #[inline]
+    pub fn drain(&self) -> Vec<(u64, MessageData)> {
+        let mut g = match self.data.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        std::mem::take(&mut *g)
+            .into_iter()
+            .flat_map(|(commit, queue)| queue.into_iter().map(move |(_, data)| (commit, data)))
+            .collect()
+    }
+
+    /// Starts recording broker operations, returns the log operations will be recorded to
+    ///
+    /// **Note**: If the broker was already recording, the previous log is replaced
+    #[cfg(feature = "record")]
+    pub fn record(&self) -> super::BrokerLog {
+        let log = super::BrokerLog::new();
+        let mut g = match self.log.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        *g = Some(log.clone());
+        log
+    }
+
+    /// Stops recording broker operations, returns the current log if the broker was recording
+    #[cfg(feature = "record")]
+    pub fn stop_recording(&self) -> Option<super::BrokerLog> {
+        let mut g = match self.log.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        g.take()
+    }
+
+    /// Re-sends each message that was sent while the log was recording, in the order they were sent
+    ///
+    /// **Note**: Messages that could not be copied when they were recorded, i.e. `MessageData::Stream`, are skipped
+    #[cfg(feature = "record")]
+    pub fn replay(&self, log: &super::BrokerLog) -> crate::Result<()> {
+        for entry in log.entries() {
+            if let (super::BrokerOp::Send, Some(data)) = (entry.op, entry.data) {
+                self.send(entry.commit, data)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a broker operation if the broker is recording
+    #[cfg(feature = "record")]
+    #[inline]
+    fn log(&self, op: super::BrokerOp, commit: u64, data: &MessageData) {
+        let g = match self.log.read() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        if let Some(log) = g.as_ref() {
+            log.push(op, commit, data);
+        }
     }
 }
 
@@ -187,8 +560,199 @@ impl From<Bytes> for MessageData {
     }
 }
 
+impl From<MessageStream> for MessageData {
+    fn from(value: MessageStream) -> Self {
+        MessageData::Stream(value)
+    }
+}
+
 impl From<()> for MessageData {
     fn from(_: ()) -> Self {
         Self::Empty
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn test_broker_single_slot() {
+        let broker = Broker::default();
+        broker.send(0, Bytes::from_static(b"a")).unwrap();
+        assert_eq!(
+            Error::PreviousUnhandledRequest,
+            broker.send(0, Bytes::from_static(b"b")).unwrap_err()
+        );
+        assert!(broker.receive(0).is_bytes());
+        assert!(broker.receive(0).is_empty());
+    }
+
+    #[test]
+    fn test_message_data_item_as() {
+        let mut store = runir::Store::new();
+        let handle = store.put(String::from("hello world")).commit();
+        let data = MessageData::from(store.item(handle.commit()).unwrap().clone());
+        assert_eq!(Some(&String::from("hello world")), data.item_as::<String>());
+        assert_eq!(None, data.item_as::<u64>());
+        assert_eq!(
+            None,
+            MessageData::from(Bytes::from_static(b"a")).item_as::<String>()
+        );
+    }
+
+    #[test]
+    fn test_broker_queue() {
+        let broker = Broker::queue(2);
+        broker.send(0, Bytes::from_static(b"a")).unwrap();
+        broker.send(0, Bytes::from_static(b"b")).unwrap();
+        assert_eq!(
+            Error::BrokerQueueFull,
+            broker.send(0, Bytes::from_static(b"c")).unwrap_err()
+        );
+
+        assert_eq!(
+            Some(&Bytes::from_static(b"a")),
+            broker.receive(0).as_bytes()
+        );
+        broker.send(0, Bytes::from_static(b"c")).unwrap();
+        assert_eq!(
+            Some(&Bytes::from_static(b"b")),
+            broker.receive(0).as_bytes()
+        );
+        assert_eq!(
+            Some(&Bytes::from_static(b"c")),
+            broker.receive(0).as_bytes()
+        );
+        assert!(broker.receive(0).is_empty());
+    }
+
+    #[test]
+    fn test_broker_drain() {
+        let broker = Broker::queue(2);
+        broker.send(1, Bytes::from_static(b"c")).unwrap();
+        broker.send(0, Bytes::from_static(b"a")).unwrap();
+        broker.send(0, Bytes::from_static(b"b")).unwrap();
+
+        let drained = broker
+            .drain()
+            .into_iter()
+            .map(|(commit, data)| (commit, data.as_bytes().cloned().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (0, Bytes::from_static(b"a")),
+                (0, Bytes::from_static(b"b")),
+                (1, Bytes::from_static(b"c"))
+            ],
+            drained
+        );
+        assert!(broker.drain().is_empty());
+        assert!(broker.receive(0).is_empty());
+    }
+
+    #[test]
+    fn test_broker_max_message_bytes() {
+        let broker = Broker::default().with_max_message_bytes(4);
+        assert_eq!(4, broker.max_message_bytes());
+        assert_eq!(
+            Error::MessageTooLarge { size: 5, limit: 4 },
+            broker.send(0, Bytes::from_static(b"hello")).unwrap_err()
+        );
+
+        let mut json = JsonMap::new();
+        json.insert("a".to_string(), serde_json::Value::Bool(true));
+        assert_eq!(10, MessageData::Json(json.clone()).size());
+        assert!(broker.send(0, MessageData::Json(json.clone())).is_err());
+
+        broker.send(0, Bytes::from_static(b"hey")).unwrap();
+        assert!(broker.receive(0).is_bytes());
+
+        let broker = Broker::queue(1);
+        assert_eq!(0, broker.max_message_bytes());
+        broker.send(0, MessageData::Json(json)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_broker_ttl() {
+        let broker = Broker::default();
+        assert_eq!(None, broker.ttl());
+        broker.send(0, Bytes::from_static(b"a")).unwrap();
+        assert!(broker.evict_expired().is_empty());
+        assert!(broker.receive(0).is_bytes());
+
+        let broker = Broker::default().with_ttl(Duration::from_millis(20));
+        broker.send(0, Bytes::from_static(b"a")).unwrap();
+        assert!(broker.evict_expired().is_empty());
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        broker.send(1, Bytes::from_static(b"b")).unwrap();
+        assert_eq!(vec![0], broker.evict_expired());
+
+        // The slot for the commit can be sent to again once the message expires
+        broker.send(0, Bytes::from_static(b"c")).unwrap();
+        assert_eq!(
+            Some(&Bytes::from_static(b"c")),
+            broker.receive(0).as_bytes()
+        );
+        assert!(broker.receive(1).is_bytes());
+
+        let cancel = CancellationToken::new();
+        let expiry = broker.start_expiry(Duration::from_millis(10), cancel.clone());
+        broker.send(2, Bytes::from_static(b"d")).unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(broker.drain().is_empty());
+        cancel.cancel();
+        expiry.await.unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "record")]
+    fn test_broker_record_replay() {
+        let broker = Broker::queue(4);
+        let log = broker.record();
+        broker.send(0, Bytes::from_static(b"a")).unwrap();
+        broker.send(1, Bytes::from_static(b"b")).unwrap();
+        broker.receive(0);
+        broker.receive(1);
+        assert!(broker.stop_recording().is_some());
+
+        let entries = log.entries();
+        assert_eq!(4, entries.len());
+        assert_eq!(crate::plugin::BrokerOp::Send, entries[0].op);
+        assert_eq!(crate::plugin::BrokerOp::Receive, entries[3].op);
+        assert!(entries[0].elapsed <= entries[3].elapsed);
+
+        let replay = Broker::queue(4);
+        replay.replay(&log).unwrap();
+        assert_eq!(
+            Some(&Bytes::from_static(b"a")),
+            replay.receive(0).as_bytes()
+        );
+        assert_eq!(
+            Some(&Bytes::from_static(b"b")),
+            replay.receive(1).as_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_broker_stream() {
+        use futures_util::StreamExt;
+
+        let broker = Broker::default();
+        let stream: MessageStream = Box::pin(futures_util::stream::iter([
+            Ok(Bytes::from_static(b"hello ")),
+            Ok(Bytes::from_static(b"world")),
+        ]));
+        broker.send(0, stream).unwrap();
+
+        let mut received = broker.receive(0);
+        let stream = received.as_stream().expect("should be a stream");
+        let mut body = vec![];
+        while let Some(chunk) = stream.next().await {
+            body.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(b"hello world", body.as_slice());
+        assert!(MessageData::default().as_stream().is_none());
+    }
+}
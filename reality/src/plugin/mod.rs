@@ -1,25 +1,72 @@
 mod address;
+mod budget;
+mod cache;
 mod call;
+mod cancel;
 mod event;
+mod framework;
+mod graph;
 mod handler;
+mod hook;
+mod inflight;
+mod inventory;
+mod kind;
 mod messages;
+#[cfg(feature = "record")]
+mod record;
+mod registry;
+#[cfg(feature = "http")]
+mod response;
+mod scratch;
+#[cfg(feature = "signal")]
+mod signal;
 mod state;
+mod stats;
 mod thunk;
 mod work;
 
 pub mod name;
 pub use address::Address;
+pub use budget::ResourceBudget;
+pub use budget::ResourceLimit;
 pub use call::Bind;
 pub use call::Call;
+pub use call::DRY_RUN_LABEL;
+pub(crate) use cancel::CancelCause;
+pub use cancel::CancelReason;
 pub use event::Event;
+pub use framework::FrameworkCheck;
+pub use framework::FRAMEWORK;
+pub use graph::Graph;
+pub use graph::GraphEdge;
+pub use graph::GraphNode;
+pub use graph::HandlerCycle;
+pub use graph::NodeKind;
 pub use handler::Handler;
+pub use handler::TypedHandler;
+pub use hook::CallHook;
+pub use inventory::PluginDescription;
+pub use inventory::StateInventory;
+pub use kind::PluginKind;
 pub use messages::Broker;
 pub use messages::MessageData;
+pub use messages::MessageStream;
 pub use name::Name;
+pub use name::NameBuilder;
+#[cfg(feature = "record")]
+pub use record::{BrokerLog, BrokerLogEntry, BrokerOp};
+pub use registry::LoadFn;
+pub use registry::TypeRegistry;
+pub use scratch::Scratch;
 pub use state::State;
+pub use stats::CallOutcome;
+pub use stats::PluginStats;
+pub use stats::RuntimeMetrics;
+pub use stats::StateStats;
 pub use thunk::HandlerThunk;
 pub use thunk::Thunk;
 pub use work::Work;
+pub use work::WorkSet;
 
 use crate::CallResult;
 use clap::{ArgMatches, FromArgMatches};
@@ -27,6 +74,7 @@ use runir::repr::Labels;
 use runir::{store::Item, Content, Resource};
 use semver::Version;
 use serde::de::DeserializeOwned;
+use std::time::Duration;
 
 /// Type-alias for the a thunk function
 pub type ThunkFn = fn(Call) -> CallResult;
@@ -34,6 +82,15 @@ pub type ThunkFn = fn(Call) -> CallResult;
 /// Type-alias for forking an item
 pub type ForkFn = fn(&Item) -> Item;
 
+/// Type-alias for returning the key an item's call results are cached by, see `Plugin::cacheable`
+pub type CacheKeyFn = fn(&Item) -> Option<uuid::Uuid>;
+
+/// Type-alias for returning the kind of plugin an item holds, see `Plugin::kind`
+pub type KindFn = fn(&Item) -> PluginKind;
+
+/// Type-alias for returning the plugins a plugin depends on, see `Plugin::dependencies`
+pub type DependenciesFn = fn() -> Vec<Name>;
+
 /// Plugin trait for implementing extensions within the reality framework
 pub trait Plugin: Resource + Content + Sized {
     /// Invoked when the thunk assigned to this plugin successfully binds a call to the plugin
@@ -44,11 +101,67 @@ pub trait Plugin: Resource + Content + Sized {
     /// **Recommendation**: Implementation should just use `env!("CARGO_PKG_VERSION")` to avoid confusion
     fn version() -> Version;
 
+    /// Default timeout of a call to this plugin
+    ///
+    /// If the caller has not set a max duration w/ `Event::with_timeout` or `Event::with_budget`, the call is cancelled
+    /// once the timeout elapses and returns `Error::PluginCallCancelled` w/ `CancelReason::Timeout`
+    #[inline]
+    fn default_timeout() -> Option<Duration> {
+        None
+    }
+
+    /// If true, results returned by `Event::returns` for this plugin are cached by the state, see
+    /// `State::enable_result_cache` for which calls use the cache
+    ///
+    /// Calls to plugins w/ the same `state_uuid` share a cached result, so this should only be overridden by plugins
+    /// whose result is a pure function of their config
+    #[inline]
+    fn cacheable() -> bool {
+        false
+    }
+
+    /// Returns the kind of plugin this is, which can be used by tooling to validate how plugins are wired together
+    ///
+    /// Since the kind can depend on the plugin's config, i.e. a plugin that can either read or write a file, this is
+    /// called w/ the loaded plugin. Handlers are always classified as `PluginKind::Handler`.
+    #[inline]
+    fn kind(&self) -> PluginKind {
+        PluginKind::Transform
+    }
+
+    /// Returns the names of the plugins that must be loaded in state for this plugin to be called, i.e. the steps of an
+    /// operation
+    ///
+    /// Dependencies are matched by plugin, ignoring the version, and can be verified w/ `State::check_dependencies`
+    #[inline]
+    fn dependencies() -> Vec<Name> {
+        vec![]
+    }
+
     /// Invoked when a binding is created when the thunk is invoked
     fn receive(&self, _data: MessageData) -> Option<Self> {
         None
     }
 
+    /// Invoked when the plugin is being loaded into state, before the plugin is stored
+    ///
+    /// Can be overridden to check the plugin's config up front, so that an invalid config is returned as an error when
+    /// the plugin is loaded rather than when the plugin is called. An invalid config should be returned as
+    /// `Error::InvalidPluginConfig`.
+    #[inline]
+    fn validate(&self) -> crate::Result<()> {
+        Ok(())
+    }
+
+    /// Invoked when the plugin is reloaded in place w/ `State::reload`
+    ///
+    /// Can be overridden to carry runtime-only state, such as an open connection, over from the existing plugin
+    #[inline]
+    fn reload(&mut self, new: Self) -> crate::Result<()> {
+        *self = new;
+        Ok(())
+    }
+
     /// Invoked when the plugin is being called
     ///
     /// Returns an error if the call cannot be bound to this plugin, or if the underlying plugin call returns an error
@@ -67,6 +180,15 @@ pub trait Plugin: Resource + Content + Sized {
         state.load_by_toml::<Self>(toml, labels)
     }
 
+    /// Loads this plugin by bincode serialized bytes
+    #[inline]
+    fn load_by_bincode(state: &mut State, bytes: &[u8], labels: Labels) -> std::io::Result<Address>
+    where
+        Self: DeserializeOwned,
+    {
+        state.load_by_bincode::<Self>(bytes, labels)
+    }
+
     /// Loads this plugin by cli args
     #[inline]
     fn load_by_args(
@@ -92,6 +214,21 @@ pub trait Plugin: Resource + Content + Sized {
         item.clone()
     }
 
+    /// Forks the item w/ a deep copy of the plugin
+    ///
+    /// Unlike `fork`, the forked item does not share the plugin's state w/ the original item, so changes made
+    /// by the fork are not visible to the original.
+    ///
+    /// ## Guidance
+    /// Can be overriden to customize how the plugin is copied, the default implementation clones the plugin.
+    #[inline]
+    fn fork_isolated(item: &Item) -> Item
+    where
+        Self: Clone,
+    {
+        item.isolate::<Self>().unwrap_or_else(|| item.clone())
+    }
+
     /// Name of this plugin
     #[inline]
     fn name() -> Name {
@@ -101,7 +238,7 @@ pub trait Plugin: Resource + Content + Sized {
     /// Version of the framework
     #[inline]
     fn framework() -> (&'static str, &'static str) {
-        (env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+        FRAMEWORK
     }
 
     /// Invoked when this plugin is loaded into state
@@ -111,6 +248,19 @@ pub trait Plugin: Resource + Content + Sized {
     fn load(put: runir::store::Put<'_, Self>) -> runir::store::Put<'_, Self> {
         put
     }
+
+    /// Invoked when this plugin is loaded into state w/ `State::load_async`
+    ///
+    /// Can be overridden to run fallible async setup before this plugin is registered, i.e. resolving a secret or warming
+    /// a connection. If an error is returned the load fails and the plugin is not registered.
+    ///
+    /// By default, calls `Plugin::load`
+    #[inline]
+    fn load_async(
+        put: runir::store::Put<'_, Self>,
+    ) -> impl std::future::Future<Output = crate::Result<runir::store::Put<'_, Self>>> + Send {
+        async move { Ok(Self::load(put)) }
+    }
 }
 
 /// Trait to centralize attributes that must be loaded with a plugin
@@ -1,6 +1,6 @@
 use runir::*;
 use semver::Version;
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::str::FromStr;
 use std::{borrow::Cow, collections::BTreeSet, fmt::Display, path::PathBuf};
 
@@ -14,7 +14,13 @@ use crate::BincodeContent;
 pub type PluginRef<'a> = Cow<'a, str>;
 
 /// Struct containing name data
-#[derive(Debug, Serialize, Clone, PartialEq, PartialOrd, Eq, Ord)]
+///
+/// A name is serialized as its full plugin reference, i.e. `kioto/plugins.request@0.1.0`, and is deserialized w/
+/// `parse_name`
+///
+/// **Note**: Qualifiers are not included in the plugin reference, so a deserialized name only has the qualifiers
+/// returned by `parse_name`. The `state_uuid` of a name is still computed from all of its fields, see `NameContent`.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub struct Name {
     pub(crate) package: String,
     pub(crate) version: Version,
@@ -49,21 +55,7 @@ impl Name {
         let qualifiers = rest;
         match package.zip(module).zip(plugin) {
             Some(((package, module), plugin)) => {
-                let path = PathBuf::from(&package)
-                    .join(version.to_string())
-                    .join(&module)
-                    .join(&plugin);
-                Name {
-                    package,
-                    version,
-                    module,
-                    plugin,
-                    path,
-                    qualifiers,
-                    framework: T::framework(),
-                    matchers: BTreeSet::new(),
-                }
-                .init_matchers()
+                Name::from_parts(package, version, module, plugin, qualifiers, T::framework())
             }
             _ => Name {
                 package: format!("unknown"),
@@ -79,12 +71,67 @@ impl Name {
         }
     }
 
+    /// Returns a builder for a name w/ an explicitly set package, module, plugin, version, and qualifiers
+    ///
+    /// Unlike `Name::new`, the name is not derived from the type path of a plugin, which is useful for testing name
+    /// matching w/ arbitrary names.
+    ///
+    /// # Example
+    /// ```
+    /// # use reality::plugin::Name;
+    /// let name = Name::builder()
+    ///     .package("kioto")
+    ///     .module("plugins")
+    ///     .plugin("request")
+    ///     .version(reality::Version::new(0, 1, 0))
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(name.matches("kioto/plugins.request@0.1.0"));
+    /// ```
+    #[inline]
+    pub fn builder() -> NameBuilder {
+        NameBuilder::default()
+    }
+
+    /// Creates a name from its parts, generating the path and matchers of the name
+    #[inline]
+    pub(crate) fn from_parts(
+        package: String,
+        version: Version,
+        module: String,
+        plugin: String,
+        qualifiers: Vec<String>,
+        framework: (&'static str, &'static str),
+    ) -> Name {
+        let path = PathBuf::from(&package)
+            .join(version.to_string())
+            .join(&module)
+            .join(&plugin);
+        Name {
+            package,
+            version,
+            module,
+            plugin,
+            path,
+            qualifiers,
+            framework,
+            matchers: BTreeSet::new(),
+        }
+        .init_matchers()
+    }
+
     /// Returns this name in a path format
     #[inline]
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
 
+    /// Returns the crate name and version of the framework that produced this name, see `Plugin::framework`
+    #[inline]
+    pub fn framework(&self) -> (&'static str, &'static str) {
+        self.framework
+    }
+
     /// Returns this name in the full plugin reference format which includes the version
     ///
     /// **Note**: This is the "alternate" display format of `Name::to_string`
@@ -101,6 +148,15 @@ impl Name {
         Cow::Owned(format!("{self}"))
     }
 
+    /// Returns true if both names refer to the same plugin, ignoring the version
+    ///
+    /// **Note**: Qualifiers are not compared since names parsed from a plugin reference do not include qualifiers, this
+    /// is equivalent to comparing `plugin_ref()` of both names
+    #[inline]
+    pub fn same_plugin(&self, other: &Name) -> bool {
+        self.package == other.package && self.module == other.module && self.plugin == other.plugin
+    }
+
     /// Returns name qualifiers for this plugin
     ///
     /// Name qualifiers are the symbols between the plugins type name and package name
@@ -109,6 +165,15 @@ impl Name {
         self.qualifiers.iter().map(|q| q.as_str())
     }
 
+    /// Returns true if the matcher is one of the reference formats of this name
+    ///
+    /// A name can be matched by its short plugin reference, full plugin reference, or path, i.e.
+    /// `kioto/plugins.request`, `kioto/plugins.request@0.1.0`, or `kioto/0.1.0/plugins/request`
+    #[inline]
+    pub fn matches(&self, matcher: &str) -> bool {
+        self.matchers.contains(matcher)
+    }
+
     /// Returns an address w/ commit
     #[inline]
     pub fn address(&self, commit: u64) -> Address {
@@ -129,6 +194,100 @@ impl Name {
     }
 }
 
+/// Builder for a `Name` w/ explicitly set parts, see `Name::builder`
+///
+/// The version defaults to `LATEST_VERSION` and the framework defaults to the current framework.
+#[derive(Debug, Clone)]
+pub struct NameBuilder {
+    package: String,
+    version: Version,
+    module: String,
+    plugin: String,
+    qualifiers: Vec<String>,
+    framework: (&'static str, &'static str),
+}
+
+impl Default for NameBuilder {
+    fn default() -> Self {
+        Self {
+            package: String::new(),
+            version: LATEST_VERSION,
+            module: String::new(),
+            plugin: String::new(),
+            qualifiers: vec![],
+            framework: super::FRAMEWORK,
+        }
+    }
+}
+
+impl NameBuilder {
+    /// Sets the package of the name
+    #[inline]
+    pub fn package(mut self, package: impl Into<String>) -> Self {
+        self.package = package.into();
+        self
+    }
+
+    /// Sets the version of the name
+    #[inline]
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets the upper-most module of the name
+    #[inline]
+    pub fn module(mut self, module: impl Into<String>) -> Self {
+        self.module = module.into();
+        self
+    }
+
+    /// Sets the plugin type name of the name
+    #[inline]
+    pub fn plugin(mut self, plugin: impl Into<String>) -> Self {
+        self.plugin = plugin.into();
+        self
+    }
+
+    /// Adds a qualifier to the name, qualifiers are added in order
+    #[inline]
+    pub fn qualifier(mut self, qualifier: impl Into<String>) -> Self {
+        self.qualifiers.push(qualifier.into());
+        self
+    }
+
+    /// Sets the crate name and version of the framework that produced the name
+    #[inline]
+    pub fn framework(mut self, framework: (&'static str, &'static str)) -> Self {
+        self.framework = framework;
+        self
+    }
+
+    /// Builds the name
+    ///
+    /// **Note**: The package, module, plugin, and qualifiers are lowercased to match names created w/ `Name::new`
+    ///
+    /// Returns `Error::IncompletePluginName` if the package, module, or plugin is not set
+    #[inline]
+    pub fn build(self) -> crate::Result<Name> {
+        if [&self.package, &self.module, &self.plugin]
+            .iter()
+            .any(|s| s.is_empty())
+        {
+            return Err(crate::Error::IncompletePluginName);
+        }
+
+        Ok(Name::from_parts(
+            self.package.to_lowercase(),
+            self.version,
+            self.module.to_lowercase(),
+            self.plugin.to_lowercase(),
+            self.qualifiers.iter().map(|q| q.to_lowercase()).collect(),
+            self.framework,
+        ))
+    }
+}
+
 impl Display for Name {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if f.alternate() {
@@ -143,12 +302,60 @@ impl Display for Name {
     }
 }
 
+impl Serialize for Name {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.full_plugin_ref())
+    }
+}
+
+impl<'de> Deserialize<'de> for Name {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let plugin_ref = String::deserialize(deserializer)?;
+        utils::parse_name(&plugin_ref).map_err(|e| {
+            serde::de::Error::custom(format!("Could not parse plugin name `{plugin_ref}`: {e:?}"))
+        })
+    }
+}
+
 impl Repr for Name {}
 impl Resource for Name {}
 
+/// Fields of a name that its `state_uuid` is computed from
+///
+/// Mirrors the field layout `Name` was serialized with before it was serialized as a plugin reference, so that the
+/// `state_uuid` of a name, and the addresses derived from it, do not change
+#[derive(Serialize)]
+struct NameContent<'a> {
+    package: &'a String,
+    version: &'a Version,
+    module: &'a String,
+    plugin: &'a String,
+    path: &'a PathBuf,
+    qualifiers: &'a Vec<String>,
+    framework: &'a (&'static str, &'static str),
+    matchers: &'a BTreeSet<String>,
+}
+
+impl<'a> From<&'a Name> for NameContent<'a> {
+    fn from(name: &'a Name) -> Self {
+        Self {
+            package: &name.package,
+            version: &name.version,
+            module: &name.module,
+            plugin: &name.plugin,
+            path: &name.path,
+            qualifiers: &name.qualifiers,
+            framework: &name.framework,
+            matchers: &name.matchers,
+        }
+    }
+}
+
 impl Content for Name {
     fn state_uuid(&self) -> uuid::Uuid {
-        BincodeContent::new(self).unwrap().state_uuid()
+        BincodeContent::new(&NameContent::from(self))
+            .unwrap()
+            .state_uuid()
     }
 }
 
@@ -164,9 +371,10 @@ pub mod utils {
     use crate::Error;
     use runir::util::*;
     use semver::{BuildMetadata, Prerelease, Version};
-    use std::{collections::BTreeSet, path::PathBuf, str::FromStr};
+    use std::str::FromStr;
 
     use super::Name;
+    use crate::plugin::FRAMEWORK;
     use crate::Result;
 
     /// Type-alias for a plugin ref
@@ -189,9 +397,20 @@ pub mod utils {
     ///
     /// If the name is not a full reference, `LATEST_VERSION` is used as the version.
     ///
+    /// Accepts the short plugin reference, full plugin reference, and path formats of a name, i.e.
+    /// `kioto/plugins.request`, `kioto/plugins.request@0.1.0`, or `kioto/0.1.0/plugins/request`
+    ///
     /// The framework will always default to the current framework that is parsing the string.
+    ///
+    /// **Note**: The package, module, and plugin are lowercased to match names created w/ `Name::new`, and `\` is
+    /// accepted as a separator in place of `/`, i.e. `Kioto\0.1.0\Plugins\Request`
     pub fn parse_name(name: &str) -> Result<Name> {
-        let mut iter = PluginRefStr::from_str(name).expect("should be infallible");
+        let name = name.replace('\\', "/");
+        if let Some(name) = parse_path(&name) {
+            return Ok(name);
+        }
+
+        let mut iter = PluginRefStr::from_str(&name).expect("should be infallible");
         match iter.next().zip(iter.next()) {
             Some((package, plugin_ref)) if name.contains("@") => {
                 let mut plugin_ref = FullPluginRefStr::from_str(&plugin_ref).unwrap();
@@ -203,21 +422,15 @@ pub mod utils {
                         let mut plugin_module = PluginModuleStr::from_str(&module_plugin).unwrap();
                         match plugin_module.next().zip(plugin_module.next()) {
                             Some((module, plugin)) => {
-                                let path = PathBuf::from(&package)
-                                    .join(version.to_string())
-                                    .join(&module)
-                                    .join(&plugin);
-                                Ok(Name {
+                                let (package, module, plugin) = lowercase(package, module, plugin);
+                                Ok(Name::from_parts(
                                     package,
                                     version,
                                     module,
                                     plugin,
-                                    path,
-                                    qualifiers: vec![],
-                                    framework: (env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
-                                    matchers: BTreeSet::new(),
-                                }
-                                .init_matchers())
+                                    vec![],
+                                    FRAMEWORK,
+                                ))
                             }
                             None => Err(Error::IncompletePluginName),
                         }
@@ -229,21 +442,15 @@ pub mod utils {
                 let mut plugin_module = PluginModuleStr::from_str(&plugin_ref).unwrap();
                 match plugin_module.next().zip(plugin_module.next()) {
                     Some((module, plugin)) => {
-                        let path = PathBuf::from(&package)
-                            .join(LATEST_VERSION.to_string())
-                            .join(&module)
-                            .join(&plugin);
-                        Ok(Name {
+                        let (package, module, plugin) = lowercase(package, module, plugin);
+                        Ok(Name::from_parts(
                             package,
-                            version: LATEST_VERSION,
+                            LATEST_VERSION,
                             module,
                             plugin,
-                            path,
-                            qualifiers: vec![],
-                            framework: (env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
-                            matchers: BTreeSet::new(),
-                        }
-                        .init_matchers())
+                            vec![],
+                            FRAMEWORK,
+                        ))
                     }
                     None => Err(Error::IncompletePluginName),
                 }
@@ -252,6 +459,45 @@ pub mod utils {
         }
     }
 
+    /// Parses a `Name` from the path format, i.e. `{package-name}/{package-version}/{upper-most-module}/{type-name}`
+    ///
+    /// Returns None if the name is not in the path format
+    fn parse_path(name: &str) -> Option<Name> {
+        let segments = name.split('/').collect::<Vec<_>>();
+        let [package, version, module, plugin] = segments[..] else {
+            return None;
+        };
+        let version = Version::from_str(version).ok()?;
+        if [package, module, plugin].iter().any(|s| s.is_empty()) {
+            return None;
+        }
+
+        let (package, module, plugin) = lowercase(package, module, plugin);
+        Some(Name::from_parts(
+            package,
+            version,
+            module,
+            plugin,
+            vec![],
+            FRAMEWORK,
+        ))
+    }
+
+    /// Lowercases the package, module, and plugin of a name, consistent w/ `Name::new`
+    ///
+    /// **Note**: The version is not lowercased, since pre-release and build identifiers are case-sensitive
+    fn lowercase(
+        package: impl AsRef<str>,
+        module: impl AsRef<str>,
+        plugin: impl AsRef<str>,
+    ) -> (String, String, String) {
+        (
+            package.as_ref().to_lowercase(),
+            module.as_ref().to_lowercase(),
+            plugin.as_ref().to_lowercase(),
+        )
+    }
+
     #[test]
     fn test_parse_name() {
         use crate::Plugin;
@@ -268,6 +514,36 @@ pub mod utils {
             "reality/0.0.0/tests/testplugin",
             name.unwrap().path().to_string_lossy()
         );
+
+        let path_name = parse_name("reality/0.1.0/tests/testplugin").unwrap();
+        assert_eq!(
+            "reality/tests.testplugin@0.1.0",
+            path_name.full_plugin_ref()
+        );
+        assert_eq!(
+            parse_name("reality/tests.testplugin@0.1.0").unwrap(),
+            path_name
+        );
+        assert_eq!(
+            path_name,
+            Name::from_str(&path_name.path().to_string_lossy()).unwrap()
+        );
+
+        assert!(parse_name("reality/latest/tests/testplugin").is_err());
+
+        // Case and separators are normalized
+        assert_eq!(
+            parse_name("reality/tests.testplugin").unwrap(),
+            parse_name("Reality/Tests.TestPlugin").unwrap()
+        );
+        assert_eq!(
+            path_name,
+            parse_name("Reality/tests.TestPlugin@0.1.0").unwrap()
+        );
+        assert_eq!(
+            path_name,
+            parse_name("reality\\0.1.0\\Tests\\testplugin").unwrap()
+        );
     }
 
     #[test]
@@ -304,7 +580,7 @@ pub mod utils {
 mod tests {
     use crate::Plugin;
 
-    use super::Name;
+    use super::{parse_name, Name};
     use runir::{Content, Repr, Resource};
     use semver::Version;
     use uuid::Uuid;
@@ -337,4 +613,126 @@ mod tests {
         assert_eq!("reality/plugin.test@0.0.0", name.full_plugin_ref().as_ref());
         assert_eq!("reality/0.0.0/plugin/test", name.path().to_string_lossy());
     }
+
+    #[test]
+    fn test_name_same_plugin() {
+        let name = Name::new::<Test>();
+        assert!(name.same_plugin(&parse_name("reality/plugin.test").unwrap()));
+        assert!(name.same_plugin(&parse_name("reality/plugin.test@1.2.3").unwrap()));
+        assert!(!name.same_plugin(&parse_name("reality/plugin.other").unwrap()));
+        assert_ne!(name, parse_name("reality/plugin.test@1.2.3").unwrap());
+    }
+
+    #[test]
+    fn test_name_serde_round_trip() {
+        let name = Name::new::<Test>();
+        let json = serde_json::to_string(&name).unwrap();
+        assert_eq!("\"reality/plugin.test@0.0.0\"", json);
+
+        let parsed = serde_json::from_str::<Name>(&json).unwrap();
+        assert_eq!(parse_name("reality/plugin.test@0.0.0").unwrap(), parsed);
+        assert_eq!(name.full_plugin_ref(), parsed.full_plugin_ref());
+        assert_eq!(name.path(), parsed.path());
+
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Config {
+            plugin: Name,
+        }
+        let toml = toml::to_string(&Config { plugin: name }).unwrap();
+        let config = toml::from_str::<Config>(&toml).unwrap();
+        assert_eq!(parsed, config.plugin);
+
+        assert!(serde_json::from_str::<Name>("\"not a plugin ref\"").is_err());
+    }
+
+    #[test]
+    fn test_name_state_uuid() {
+        use crate::BincodeContent;
+        use std::{collections::BTreeSet, path::PathBuf};
+
+        // Field layout of a name before it was serialized as a plugin reference
+        #[derive(serde::Serialize)]
+        struct Fields {
+            package: String,
+            version: Version,
+            module: String,
+            plugin: String,
+            path: PathBuf,
+            qualifiers: Vec<String>,
+            framework: (&'static str, &'static str),
+            matchers: BTreeSet<String>,
+        }
+
+        let name = Name::new::<Test>();
+        let fields = Fields {
+            package: name.package.clone(),
+            version: name.version.clone(),
+            module: name.module.clone(),
+            plugin: name.plugin.clone(),
+            path: name.path.clone(),
+            qualifiers: name.qualifiers.clone(),
+            framework: name.framework,
+            matchers: name.matchers.clone(),
+        };
+        assert_eq!(
+            BincodeContent::new(&fields).unwrap().state_uuid(),
+            name.state_uuid()
+        );
+        assert_ne!(
+            BincodeContent::new(&name).unwrap().state_uuid(),
+            name.state_uuid()
+        );
+    }
+
+    #[test]
+    fn test_name_builder() {
+        let name = Name::builder()
+            .package("Custom")
+            .module("plugins")
+            .qualifier("nested")
+            .plugin("thing")
+            .version(Version::new(1, 2, 3))
+            .build()
+            .unwrap();
+        let mut parsed = parse_name("custom/plugins.thing@1.2.3").unwrap();
+        parsed.qualifiers = vec![String::from("nested")];
+        assert_eq!(parsed, name);
+        assert_eq!("custom/1.2.3/plugins/thing", name.path().to_string_lossy());
+        assert_eq!(vec!["nested"], name.qualifiers().collect::<Vec<_>>());
+        assert!(name.matches("custom/plugins.thing"));
+        assert!(name.matches("custom/plugins.thing@1.2.3"));
+        assert!(name.matches("custom/1.2.3/plugins/thing"));
+        assert!(name.same_plugin(&parse_name("custom/plugins.thing").unwrap()));
+
+        // Builds the same name as the reflected name
+        let reflected = Name::new::<Test>();
+        let built = Name::builder()
+            .package("reality")
+            .module("plugin")
+            .qualifier("name")
+            .qualifier("tests")
+            .plugin("test")
+            .build()
+            .unwrap();
+        assert_eq!(reflected, built);
+
+        assert_eq!(
+            Err(crate::Error::IncompletePluginName),
+            Name::builder().package("custom").plugin("thing").build()
+        );
+    }
+
+    #[test]
+    fn test_name_matches() {
+        let name = Name::new::<Test>();
+        assert!(name.matches("reality/plugin.test"));
+        assert!(name.matches("reality/plugin.test@0.0.0"));
+        assert!(name.matches("reality/0.0.0/plugin/test"));
+        assert!(!name.matches("reality/plugin.test@1.2.3"));
+
+        let address = name.address(1);
+        assert!(address.matches("reality/plugin.test"));
+        assert!(address.matches(&address.to_string()));
+        assert!(!name.address(2).matches(&address.to_string()));
+    }
 }
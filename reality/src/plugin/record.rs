@@ -0,0 +1,104 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use super::MessageData;
+
+/// Kind of broker operation recorded to a broker log
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrokerOp {
+    /// Message data was sent to a commit
+    Send,
+    /// Message data was received for a commit
+    Receive,
+}
+
+/// Entry recorded to a broker log
+pub struct BrokerLogEntry {
+    /// Broker operation
+    pub op: BrokerOp,
+    /// Commit id of the destination of the message
+    pub commit: u64,
+    /// Time elapsed since the recording started
+    pub elapsed: Duration,
+    /// Copy of the message data
+    ///
+    /// **Note**: This will be `None` if the message data could not be copied, i.e. `MessageData::Stream`
+    pub data: Option<MessageData>,
+}
+
+impl Clone for BrokerLogEntry {
+    fn clone(&self) -> Self {
+        Self {
+            op: self.op,
+            commit: self.commit,
+            elapsed: self.elapsed,
+            data: self.data.as_ref().and_then(MessageData::try_clone),
+        }
+    }
+}
+
+/// Log of broker operations, returned by `Broker::record`
+///
+/// The log is shared w/ the broker, so entries continue to be added until the broker stops recording
+#[derive(Clone)]
+pub struct BrokerLog {
+    /// Instant the recording started
+    start: Instant,
+    /// Recorded entries
+    entries: Arc<RwLock<Vec<BrokerLogEntry>>>,
+}
+
+impl BrokerLog {
+    /// Returns a new empty broker log
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            entries: Arc::new(RwLock::new(vec![])),
+        }
+    }
+
+    /// Records a broker operation
+    #[inline]
+    pub(crate) fn push(&self, op: BrokerOp, commit: u64, data: &MessageData) {
+        let entry = BrokerLogEntry {
+            op,
+            commit,
+            elapsed: self.start.elapsed(),
+            data: data.try_clone(),
+        };
+        let mut entries = match self.entries.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        entries.push(entry);
+    }
+
+    /// Returns a copy of the entries recorded so far
+    #[inline]
+    pub fn entries(&self) -> Vec<BrokerLogEntry> {
+        let entries = match self.entries.read() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        entries.clone()
+    }
+
+    /// Returns the number of entries recorded so far
+    #[inline]
+    pub fn len(&self) -> usize {
+        let entries = match self.entries.read() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        entries.len()
+    }
+
+    /// Returns true if no entries have been recorded
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+use runir::repr::Labels;
+use serde::de::DeserializeOwned;
+
+use super::{Address, Handler, Name, Plugin, State};
+
+/// Type-alias for a function that deserializes a plugin from bincode serialized bytes and loads it into state
+pub type LoadFn = fn(&mut State, &[u8], Labels) -> std::io::Result<Address>;
+
+/// Registry of plugin types that can be reconstructed by name from bincode serialized bytes
+///
+/// # Example
+/// ```ignore
+/// let mut registry = TypeRegistry::new();
+/// registry.register::<Request>();
+///
+/// let address = registry.load(&mut state, &Request::name(), &bytes, Labels::default())?;
+/// ```
+#[derive(Clone, Default)]
+pub struct TypeRegistry {
+    /// Map of plugin names to the function that loads the plugin
+    loaders: BTreeMap<Name, LoadFn>,
+}
+
+impl TypeRegistry {
+    /// Returns a new empty registry
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a plugin type w/ this registry
+    ///
+    /// **Note**: Registering the same plugin type again replaces the previous registration
+    #[inline]
+    pub fn register<P: Plugin + DeserializeOwned>(&mut self) -> &mut Self {
+        self.loaders.insert(P::name(), load_plugin::<P>);
+        self
+    }
+
+    /// Registers a handler type w/ this registry
+    #[inline]
+    pub fn register_handler<H: Handler + DeserializeOwned>(&mut self) -> &mut Self {
+        self.loaders.insert(H::name(), load_handler::<H>);
+        self
+    }
+
+    /// Returns true if a type is registered that matches the plugin name, see `TypeRegistry::find`
+    #[inline]
+    pub fn is_registered(&self, name: &Name) -> bool {
+        self.find(name).is_some()
+    }
+
+    /// Returns the names of all registered types
+    #[inline]
+    pub fn names(&self) -> impl Iterator<Item = &Name> {
+        self.loaders.keys()
+    }
+
+    /// Finds the load function for a plugin name
+    ///
+    /// The full plugin reference is matched first, and then the plugin reference w/o the version
+    #[inline]
+    pub fn find(&self, name: &Name) -> Option<LoadFn> {
+        self.loaders
+            .iter()
+            .find(|(n, _)| n.full_plugin_ref() == name.full_plugin_ref())
+            .or_else(|| self.loaders.iter().find(|(n, _)| n.same_plugin(name)))
+            .map(|(_, l)| *l)
+    }
+
+    /// Reconstructs a plugin by name from bincode serialized bytes and loads it into state
+    ///
+    /// Returns an error w/ `ErrorKind::NotFound` if a type is not registered for the name, otherwise returns the error
+    /// from deserializing or loading the plugin
+    #[inline]
+    pub fn load(
+        &self,
+        state: &mut State,
+        name: &Name,
+        bytes: &[u8],
+        labels: Labels,
+    ) -> std::io::Result<Address> {
+        match self.find(name) {
+            Some(load) => load(state, bytes, labels),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Type is not registered for `{}`", name.full_plugin_ref()),
+            )),
+        }
+    }
+}
+
+/// Loads a plugin from bincode serialized bytes
+fn load_plugin<P: Plugin + DeserializeOwned>(
+    state: &mut State,
+    bytes: &[u8],
+    labels: Labels,
+) -> std::io::Result<Address> {
+    state.load_by_bincode::<P>(bytes, labels)
+}
+
+/// Loads a handler from bincode serialized bytes
+fn load_handler<H: Handler + DeserializeOwned>(
+    state: &mut State,
+    bytes: &[u8],
+    labels: Labels,
+) -> std::io::Result<Address> {
+    let handler = bincode::deserialize::<H>(bytes)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+    state
+        .try_load_handler(handler, labels)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{e:?}")))
+}
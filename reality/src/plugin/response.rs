@@ -0,0 +1,210 @@
+use super::MessageData;
+use crate::Result;
+use bytes::Bytes;
+use http::{header, response::Parts, Response};
+use http_body::Body;
+use http_body_util::BodyExt;
+
+impl MessageData {
+    /// Collects the body of an http response into message data
+    ///
+    /// The body is returned as `MessageData::Json` if the response has a json content type, otherwise as
+    /// `MessageData::Bytes`. If the response does not have a body, `MessageData::Empty` is returned.
+    ///
+    /// **Note**: A json body that is not an object or array is returned as `MessageData::Empty`, see
+    /// `From<serde_json::Value> for MessageData`
+    pub async fn from_response<B>(response: Response<B>) -> Result<MessageData>
+    where
+        B: Body,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let (parts, body) = collect(response).await?;
+        if body.is_empty() {
+            Ok(MessageData::Empty)
+        } else if is_json(&parts) {
+            Ok(serde_json::from_slice::<serde_json::Value>(&body)?.into())
+        } else {
+            Ok(MessageData::Bytes(body))
+        }
+    }
+
+    /// Collects an http response into a `MessageData::Json` envelope w/ the status, headers and body of the response
+    ///
+    /// # Example
+    /// ```json
+    /// { "status": 200, "headers": { "content-type": "application/json" }, "body": { "id": 1 } }
+    /// ```
+    ///
+    /// A json body is parsed, a utf-8 body is returned as a string, and any other body is returned as an array of
+    /// bytes. If the response does not have a body, the body is `null`.
+    ///
+    /// **Note**: If a header is repeated, the values are joined w/ `, `
+    pub async fn from_response_envelope<B>(response: Response<B>) -> Result<MessageData>
+    where
+        B: Body,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let (parts, body) = collect(response).await?;
+        let body = if body.is_empty() {
+            serde_json::Value::Null
+        } else if is_json(&parts) {
+            serde_json::from_slice(&body)?
+        } else {
+            match std::str::from_utf8(&body) {
+                Ok(text) => text.into(),
+                Err(_) => body.to_vec().into(),
+            }
+        };
+
+        let mut headers = serde_json::Map::new();
+        for (name, value) in parts.headers.iter() {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            match headers.get_mut(name.as_str()) {
+                Some(serde_json::Value::String(existing)) => {
+                    existing.push_str(", ");
+                    existing.push_str(&value);
+                }
+                _ => {
+                    headers.insert(name.to_string(), value.into());
+                }
+            }
+        }
+
+        let mut envelope = serde_json::Map::new();
+        envelope.insert("status".to_string(), parts.status.as_u16().into());
+        envelope.insert("headers".to_string(), headers.into());
+        envelope.insert("body".to_string(), body);
+        Ok(MessageData::Json(envelope))
+    }
+}
+
+/// Collects the body of an http response
+async fn collect<B>(response: Response<B>) -> Result<(Parts, Bytes)>
+where
+    B: Body,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let (parts, body) = response.into_parts();
+    let body = body
+        .collect()
+        .await
+        .map_err(std::io::Error::other)?
+        .to_bytes();
+    Ok((parts, body))
+}
+
+/// Returns true if the response has a json content type
+#[inline]
+fn is_json(parts: &Parts) -> bool {
+    parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|c| c.to_str().ok())
+        .is_some_and(is_json_content_type)
+}
+
+/// Returns true if a content type is `application/json` or uses the `+json` structured syntax suffix
+#[inline]
+fn is_json_content_type(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    essence == "application/json" || essence.ends_with("+json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::Full;
+
+    /// Returns a response w/ a content type and body
+    fn response(content_type: &str, body: &'static [u8]) -> Response<Full<Bytes>> {
+        Response::builder()
+            .status(201)
+            .header(header::CONTENT_TYPE, content_type)
+            .header("x-id", "a")
+            .header("x-id", "b")
+            .body(Full::new(Bytes::from_static(body)))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_is_json_content_type() {
+        assert!(is_json_content_type("application/json"));
+        assert!(is_json_content_type("application/json; charset=utf-8"));
+        assert!(is_json_content_type("application/problem+json"));
+        assert!(!is_json_content_type("text/plain"));
+    }
+
+    #[tokio::test]
+    async fn test_message_data_from_response() {
+        let data = MessageData::from_response(response("application/json", br#"{"id":1}"#))
+            .await
+            .unwrap();
+        assert_eq!(
+            Some(&serde_json::json!(1)),
+            data.as_json().and_then(|j| j.get("id"))
+        );
+
+        let data = MessageData::from_response(response("text/plain", b"ok"))
+            .await
+            .unwrap();
+        assert_eq!(Some(&Bytes::from("ok")), data.as_bytes());
+
+        let data = MessageData::from_response(response("application/json", b""))
+            .await
+            .unwrap();
+        assert!(data.is_empty());
+
+        assert!(
+            MessageData::from_response(response("application/json", b"{"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_message_data_from_response_envelope() {
+        let data = MessageData::from_response_envelope(response("application/json", br#"[1]"#))
+            .await
+            .unwrap();
+        assert_eq!(
+            Some(&serde_json::json!({
+                "status": 201,
+                "headers": { "content-type": "application/json", "x-id": "a, b" },
+                "body": [1],
+            })),
+            data.as_json()
+                .map(|j| serde_json::Value::Object(j.clone()))
+                .as_ref()
+        );
+
+        let data = MessageData::from_response_envelope(response("text/plain", b"ok"))
+            .await
+            .unwrap();
+        assert_eq!(
+            Some(&serde_json::json!("ok")),
+            data.as_json().and_then(|j| j.get("body"))
+        );
+
+        let data =
+            MessageData::from_response_envelope(response("application/octet-stream", b"\xff"))
+                .await
+                .unwrap();
+        assert_eq!(
+            Some(&serde_json::json!([255])),
+            data.as_json().and_then(|j| j.get("body"))
+        );
+
+        let data = MessageData::from_response_envelope(response("text/plain", b""))
+            .await
+            .unwrap();
+        assert_eq!(
+            Some(&serde_json::Value::Null),
+            data.as_json().and_then(|j| j.get("body"))
+        );
+    }
+}
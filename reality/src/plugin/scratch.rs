@@ -0,0 +1,75 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// Type-alias for the values stored in scratch state, keyed by type
+type ScratchMap = HashMap<TypeId, Box<dyn Any + Send + Sync>>;
+
+/// Typed scratch state that lives for the duration of a plugin call
+///
+/// Scratch state is shared by the clones of a call, i.e. the deferred work of a binding, but a forked call starts w/
+/// an independent scratch state. The scratch state is cleared once the call completes.
+#[derive(Clone, Default)]
+pub struct Scratch {
+    values: Arc<Mutex<ScratchMap>>,
+}
+
+impl Scratch {
+    /// Returns a clone of the value of type `T`, or None if a value has not been set
+    #[inline]
+    pub fn get<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        self.lock()
+            .get(&TypeId::of::<T>())
+            .and_then(|v| v.downcast_ref::<T>())
+            .cloned()
+    }
+
+    /// Calls `f` w/ a mutable reference to the value of type `T`, inserting the default value if a value has not been
+    /// set, and returns the result of `f`
+    ///
+    /// **Note**: The scratch state is locked while `f` is called
+    #[inline]
+    pub fn with_mut<T: Default + Send + Sync + 'static, R>(
+        &self,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> R {
+        let mut values = self.lock();
+        let value = values
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(T::default()));
+        f(value
+            .downcast_mut::<T>()
+            .expect("should be the type the entry is keyed by"))
+    }
+
+    /// Removes the value of type `T` and returns it, or None if a value has not been set
+    #[inline]
+    pub fn take<T: Send + Sync + 'static>(&self) -> Option<T> {
+        self.lock()
+            .remove(&TypeId::of::<T>())
+            .and_then(|v| v.downcast::<T>().ok())
+            .map(|v| *v)
+    }
+
+    /// Returns true if a value has not been set
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Removes all values
+    #[inline]
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, ScratchMap> {
+        match self.values.lock() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::{Arc, Mutex};
+
+use tokio_util::sync::CancellationToken;
+
+use super::{CancelCause, CancelReason};
+use tracing::debug;
+
+/// Type-alias for a signal handler slot shared by all clones of a state
+pub(crate) type SignalSlot = Arc<Mutex<Option<SignalGuard>>>;
+
+/// Guard for a running signal listener, the listener is stopped when the guard is dropped
+///
+/// **Note**: tokio installs its signal handler once per process and never uninstalls it. Once the guard is dropped the
+/// listener stops cancelling the state, but SIGINT and SIGTERM no longer terminate the process w/ the default handling.
+pub(crate) struct SignalGuard {
+    /// Task listening for signals
+    listener: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for SignalGuard {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+/// Registers SIGINT/SIGTERM (Ctrl-C on non-unix platforms) and spawns a listener that cancels `cancel` w/
+/// `CancelReason::Shutdown` when a signal is received
+///
+/// Signals are registered before this function returns so that a signal received immediately after is not missed
+pub(crate) fn listen(
+    handle: &tokio::runtime::Handle,
+    cancel: CancellationToken,
+    cause: CancelCause,
+) -> std::io::Result<SignalGuard> {
+    #[cfg(unix)]
+    let listener = {
+        use tokio::signal::unix::{signal, SignalKind};
+        let _enter = handle.enter();
+        let mut interrupt = signal(SignalKind::interrupt())?;
+        let mut terminate = signal(SignalKind::terminate())?;
+        handle.spawn(async move {
+            tokio::select! {
+                _ = interrupt.recv() => {
+                    debug!("Received SIGINT, cancelling state");
+                    cause.set(CancelReason::Shutdown);
+                    cancel.cancel();
+                },
+                _ = terminate.recv() => {
+                    debug!("Received SIGTERM, cancelling state");
+                    cause.set(CancelReason::Shutdown);
+                    cancel.cancel();
+                },
+            }
+        })
+    };
+
+    #[cfg(not(unix))]
+    let listener = handle.spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            debug!("Received Ctrl-C, cancelling state");
+            cause.set(CancelReason::Shutdown);
+            cancel.cancel();
+        }
+    });
+
+    Ok(SignalGuard { listener })
+}
@@ -1,39 +1,157 @@
-use super::{thunk::HandlerThunk, Address, Broker, Handler, Name, Plugin};
+use super::{
+    cache::ResultCache,
+    framework::{is_compatible, FrameworkCheck, FRAMEWORK},
+    graph::{GraphEdge, GraphNode, HandlerCycle, NodeKind},
+    inflight::InFlight,
+    inventory::{PluginDescription, StateInventory},
+    stats::Recorder,
+    thunk::HandlerThunk,
+    Address, Broker, CallHook, CancelCause, CancelReason, Graph, Handler, MessageData, Name,
+    Plugin, PluginKind, PluginStats, StateStats,
+};
 use crate::{
     plugin::{event::Event, Call, Thunk},
-    Error, Result,
+    Error, Result, SerializationFormat,
 };
 use clap::ArgMatches;
 use runir::{
     repo::Handle,
-    repr::{Attributes, Labels},
-    store::Item,
+    repr::{Attributes, Identifier, Labels, Tags},
+    store::{Item, ObservationEvent, Put},
     Store,
 };
 use serde::de::DeserializeOwned;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet, HashMap},
     future::Future,
+    io::Read,
     ops::Deref,
-    path::PathBuf,
+    path::{Path, PathBuf},
     pin::Pin,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 use tokio_util::sync::CancellationToken;
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// Type-alias for a boxed future
-type BoxFuture = Pin<Box<dyn Future<Output = Result<()>>>>;
+type BoxFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+type PluginMap = std::sync::Arc<std::sync::RwLock<Plugins>>;
+
+/// Registered plugins and the indexes over them
+///
+/// Indexes are kept under the same lock as the registered paths, so that they are updated together when a plugin is
+/// loaded
+#[derive(Clone, Default)]
+struct Plugins {
+    /// Map of each path a plugin is registered under
+    paths: BTreeMap<PathBuf, (PathKind, Handle)>,
+    /// Commits of the plugins loaded w/ each label, keyed by the label's key and value
+    labels: BTreeMap<(String, String), BTreeSet<u64>>,
+    /// When each plugin loaded w/ a time-to-live expires, keyed by commit, see `Put::ttl`
+    expires: BTreeMap<u64, Instant>,
+}
+
+impl Plugins {
+    /// Adds a plugin's commit to the index of each label it was loaded with
+    #[inline]
+    fn index_labels(&mut self, labels: &Labels, commit: u64) {
+        for (k, v) in labels.iter() {
+            self.labels
+                .entry((k.to_string(), v.to_string()))
+                .or_default()
+                .insert(commit);
+        }
+    }
+
+    /// Removes every path mapped to a commit and removes the commit from the label index
+    ///
+    /// Returns false if no path was mapped to the commit
+    #[inline]
+    fn remove(&mut self, commit: u64) -> bool {
+        let len = self.paths.len();
+        self.paths.retain(|_, (_, h)| h.commit() != commit);
+        self.labels.retain(|_, commits| {
+            commits.remove(&commit);
+            !commits.is_empty()
+        });
+        self.expires.remove(&commit);
+        len != self.paths.len()
+    }
+
+    /// Removes every plugin that has expired and does not have work in flight, returns the commit of each plugin that
+    /// was removed
+    #[inline]
+    fn remove_expired(&mut self, in_flight: &InFlight) -> Vec<u64> {
+        let now = Instant::now();
+        let expired = self
+            .expires
+            .iter()
+            .filter(|(commit, expires)| **expires <= now && !in_flight.contains(**commit))
+            .map(|(commit, _)| *commit)
+            .collect::<Vec<_>>();
+        for commit in expired.iter() {
+            self.remove(*commit);
+        }
+        expired
+    }
+}
+
+/// Unloads every expired plugin that does not have work in flight, returns the commit of each plugin that was unloaded
+fn unregister_expired(
+    plugins: &PluginMap,
+    observers: &ObserverMap,
+    in_flight: &InFlight,
+) -> Vec<u64> {
+    let evicted = match plugins.write() {
+        Ok(mut g) => g.remove_expired(in_flight),
+        Err(e) => e.into_inner().remove_expired(in_flight),
+    };
+    if !evicted.is_empty() {
+        let mut observers = match observers.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        for commit in evicted.iter() {
+            observers.remove(commit);
+        }
+    }
+    evicted
+}
+
+/// Kind of path a plugin is registered under
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PathKind {
+    /// Short plugin path, i.e. `{package}/{version}/{module}/{plugin}`, which maps to the most recently loaded commit
+    Short,
+    /// Commit-qualified address of the plugin, i.e. `{package}/{version}/{module}/{plugin}/{commit}`
+    Address,
+    /// Alias added w/ `State::alias`
+    Alias,
+}
+
+/// Type-alias for a map of each commit a short plugin path has been mapped to
+type PathHistory = Arc<RwLock<BTreeMap<PathBuf, Vec<u64>>>>;
 
-type PluginMap = std::sync::Arc<std::sync::RwLock<BTreeMap<PathBuf, Handle>>>;
+/// Type-alias for a map of observation events by plugin commit id
+type ObserverMap = Arc<RwLock<BTreeMap<u64, ObservationEvent>>>;
 
 /// State contains manages registering and calling plugins
+///
+/// **Note**: Cloning a state returns a shared handle, messages sent and cancellation by a clone are visible to the
+/// original. Use `share` or `deep_clone` to make the intention explicit.
+///
+/// Clones can be used from multiple threads, i.e. loading from one thread while calling from another. Locks on the
+/// plugins map are never held across an await, and the futures returned by `call` and `spawn` are `Send`.
 #[derive(Clone)]
 pub struct State {
     /// Store for resources owned by this state
     store: runir::Store,
     /// Cancellation token to stop any work related to this state
     pub(crate) cancel: CancellationToken,
+    /// Reason the cancel token was cancelled
+    pub(crate) cause: CancelCause,
     /// Handle to runtime to create work from state
     handle: tokio::runtime::Handle,
     /// Map of registered plugins
@@ -43,6 +161,25 @@ pub struct State {
     /// If set to true, will return an error if a plugin being loaded
     /// will overwrite an existing plugin
     disallow_commit_conflicts: bool,
+    /// If set to true, side-effecting plugins should skip their side effects, see `Bind::is_dry_run`
+    dry_run: bool,
+    /// Strictness of the check applied to the framework of plugins being loaded
+    framework_check: FrameworkCheck,
+    /// Plugin call stats
+    pub(crate) stats: Recorder,
+    /// Hooks run around each plugin call
+    pub(crate) hooks: Vec<Arc<dyn CallHook>>,
+    /// If set, caches results returned by calls to cacheable plugins
+    pub(crate) cache: Option<ResultCache>,
+    /// Plugin work spawned from this state that has not completed
+    pub(crate) in_flight: InFlight,
+    /// Observation events shared by plugins and observers
+    observers: ObserverMap,
+    /// Commits each short plugin path has been mapped to, in load order
+    path_history: PathHistory,
+    /// Signal listener installed by `install_signal_handler`, shared by all clones of this state
+    #[cfg(feature = "signal")]
+    signal: super::signal::SignalSlot,
 }
 
 impl State {
@@ -54,10 +191,21 @@ impl State {
         Self {
             store: runir::Store::new(),
             cancel: CancellationToken::new(),
+            cause: CancelCause::default(),
             handle: tokio::runtime::Handle::current(),
-            plugins: Arc::new(RwLock::new(BTreeMap::new())),
+            plugins: Arc::new(RwLock::new(Plugins::default())),
             messages: Broker::default(),
             disallow_commit_conflicts: false,
+            dry_run: false,
+            framework_check: FrameworkCheck::default(),
+            stats: Recorder::default(),
+            hooks: vec![],
+            cache: None,
+            in_flight: InFlight::default(),
+            observers: Arc::new(RwLock::new(BTreeMap::new())),
+            path_history: Arc::new(RwLock::new(BTreeMap::new())),
+            #[cfg(feature = "signal")]
+            signal: Default::default(),
         }
     }
 
@@ -67,10 +215,57 @@ impl State {
         Self {
             store: runir::Store::new(),
             cancel: CancellationToken::new(),
+            cause: CancelCause::default(),
             handle,
-            plugins: Arc::new(RwLock::new(BTreeMap::new())),
+            plugins: Arc::new(RwLock::new(Plugins::default())),
             messages: Broker::default(),
             disallow_commit_conflicts: false,
+            dry_run: false,
+            framework_check: FrameworkCheck::default(),
+            stats: Recorder::default(),
+            hooks: vec![],
+            cache: None,
+            in_flight: InFlight::default(),
+            observers: Arc::new(RwLock::new(BTreeMap::new())),
+            path_history: Arc::new(RwLock::new(BTreeMap::new())),
+            #[cfg(feature = "signal")]
+            signal: Default::default(),
+        }
+    }
+
+    /// Installs a handler that cancels this state when the process receives SIGINT or SIGTERM (Ctrl-C on non-unix platforms),
+    /// so that all in-flight plugin calls receive cancellation
+    ///
+    /// Calling this more than once has no effect. The listener is stopped when the last clone of this state is dropped.
+    ///
+    /// **Note**: tokio cannot uninstall its signal handler, so once installed SIGINT and SIGTERM no longer terminate the
+    /// process for its lifetime, even after the listener is stopped. This is intended to be called by binaries that own
+    /// the process, not by libraries.
+    #[cfg(feature = "signal")]
+    #[inline]
+    pub fn install_signal_handler(&self) -> std::io::Result<()> {
+        let mut signal = match self.signal.lock() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        if signal.is_none() {
+            *signal = Some(super::signal::listen(
+                &self.handle,
+                self.cancel.clone(),
+                self.cause.clone(),
+            )?);
+        }
+        Ok(())
+    }
+
+    /// Returns a new state w/ specified tokio runtime that shares a message broker
+    ///
+    /// Plugins loaded by states that share a broker can send messages to each other by commit id
+    #[inline]
+    pub fn with_broker(handle: tokio::runtime::Handle, broker: Broker) -> Self {
+        Self {
+            messages: broker,
+            ..Self::with(handle)
         }
     }
 
@@ -89,6 +284,215 @@ impl State {
         self.disallow_commit_conflicts = disallow;
     }
 
+    /// If set to true, plugins called from this state are called in dry-run mode, see `Bind::is_dry_run`
+    ///
+    /// **Note**: Events created before this is set are not affected
+    #[inline]
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Returns true if plugins called from this state are called in dry-run mode
+    #[inline]
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Sets the strictness of the check applied to the framework a plugin was produced under, defaults to
+    /// `FrameworkCheck::Warn`
+    ///
+    /// The check is applied by `try_load`, `try_load_handler`, and the `load_by_*` functions, see `State::check_framework`
+    #[inline]
+    pub fn set_framework_check(&mut self, check: FrameworkCheck) {
+        self.framework_check = check;
+    }
+
+    /// Checks that a plugin produced under `framework`, i.e. `("reality", "0.1.0")`, is compatible w/ the current
+    /// framework
+    ///
+    /// Returns `Error::IncompatibleFramework` if the framework is incompatible and the check is `FrameworkCheck::Deny`
+    pub fn check_framework(&self, framework: (&str, &str)) -> Result<()> {
+        if self.framework_check == FrameworkCheck::Ignore || is_compatible(framework) {
+            return Ok(());
+        }
+
+        let framework = format!("{}@{}", framework.0, framework.1);
+        let current = format!("{}@{}", FRAMEWORK.0, FRAMEWORK.1);
+        if self.framework_check == FrameworkCheck::Deny {
+            Err(Error::IncompatibleFramework { framework, current })
+        } else {
+            warn!(framework, current, "incompatible_framework");
+            Ok(())
+        }
+    }
+
+    /// Adds a hook that is run around each plugin call made from this state
+    ///
+    /// **Note**: Events created before the hook is added are not affected
+    #[inline]
+    pub fn add_call_hook(&mut self, hook: Arc<dyn CallHook>) {
+        self.hooks.push(hook);
+    }
+
+    /// Enables queue mode on the message broker, each plugin will be able to hold up to `capacity` pending messages
+    ///
+    /// **Note**: This replaces the current broker, any pending messages will be dropped. The max message size of the
+    /// current broker is kept.
+    #[inline]
+    pub fn enable_message_queue(&mut self, capacity: usize) {
+        self.messages =
+            Broker::queue(capacity).with_max_message_bytes(self.messages.max_message_bytes());
+    }
+
+    /// Sets the max size in bytes of messages sent through this state's broker, larger messages are rejected w/
+    /// `Error::MessageTooLarge`
+    ///
+    /// **Note**: A limit of 0 means messages are unlimited in size, see `Broker::with_max_message_bytes`
+    #[inline]
+    pub fn set_max_message_bytes(&mut self, limit: usize) {
+        self.messages = self.messages.clone().with_max_message_bytes(limit);
+    }
+
+    /// Enables caching the results of calls to cacheable plugins, see `Plugin::cacheable`
+    ///
+    /// Results returned by `Event::returns` are cached by the plugin's `state_uuid`, so calling a plugin w/ the same
+    /// config returns the cached result until it is older than `ttl`, instead of calling the plugin again. Once
+    /// `max_entries` results are cached, the oldest result is evicted.
+    ///
+    /// The cache only applies to calls that return a result, i.e. `Event::returns` and `State::call_returns`. Calls
+    /// started w/ `State::call`, `State::spawn`, `Event::start` or `Event::spawn` always call the plugin.
+    ///
+    /// **Note**: This replaces the current cache, any cached results will be dropped. Calls in dry-run mode and calls
+    /// that did not return any message data are not cached.
+    #[inline]
+    pub fn enable_result_cache(&mut self, ttl: Duration, max_entries: usize) {
+        self.cache = Some(ResultCache::new(ttl, max_entries));
+    }
+
+    /// Removes all results cached by this state
+    #[inline]
+    pub fn clear_result_cache(&self) {
+        if let Some(cache) = self.cache.as_ref() {
+            cache.clear();
+        }
+    }
+
+    /// Returns a snapshot of call stats for each plugin that has been called from this state
+    #[inline]
+    pub fn metrics_snapshot(&self) -> HashMap<Name, PluginStats> {
+        self.stats.snapshot()
+    }
+
+    /// Returns counts of the entries held by this state
+    #[inline]
+    pub fn stats(&self) -> StateStats {
+        let plugins = match self.plugins.read() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        StateStats {
+            store: self.store.stats(),
+            plugin_count: self.plugin_count(),
+            plugin_paths: plugins.paths.len(),
+        }
+    }
+
+    /// Returns the number of distinct plugins and handlers loaded in state
+    ///
+    /// **Note**: Each plugin is registered under both its short path and its commit address, so this counts distinct
+    /// commits rather than registered paths
+    #[inline]
+    pub fn plugin_count(&self) -> usize {
+        let plugins = match self.plugins.read() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        plugins
+            .paths
+            .values()
+            .map(|(_, h)| h.commit())
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+
+    /// Returns each short plugin path that has been mapped to more than one commit, w/ the commits in load order
+    ///
+    /// When a plugin is loaded w/ a different config, the short path is remapped to the newest commit and any lookup
+    /// by the short path no longer finds the earlier plugin. This can be used to diagnose unexpected lookups.
+    #[inline]
+    pub fn detect_conflicts(&self) -> Vec<(PathBuf, Vec<u64>)> {
+        let history = match self.path_history.read() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        history
+            .iter()
+            .filter(|(_, commits)| commits.len() > 1)
+            .map(|(path, commits)| (path.clone(), commits.clone()))
+            .collect()
+    }
+
+    /// Records that a short plugin path was mapped to a commit
+    #[inline]
+    fn record_short_path(&self, path: &Path, commit: u64) {
+        let mut history = match self.path_history.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        let commits = history.entry(path.to_path_buf()).or_default();
+        if !commits.contains(&commit) {
+            commits.push(commit);
+        }
+    }
+
+    /// Returns a handle to this state, equivalent to `clone`
+    ///
+    /// The handle shares the plugins map, messages, stats, and cancellation w/ this state
+    ///
+    /// **Note**: The inner store is cloned like `Store::clone`, so plugins should be loaded before a state is shared
+    #[inline]
+    pub fn share(&self) -> State {
+        self.clone()
+    }
+
+    /// Returns a copy of this state that can load plugins and send messages independently of this state
+    ///
+    /// The copy has a copy of the plugins map, a fresh broker w/ the same settings, and its own stats, cancellation
+    /// token, and observers. Closing or cancelling either state does not affect the other.
+    ///
+    /// **Note**: Plugin resources are not copied, so mutating a plugin w/ `Bind::update` is still visible to both
+    /// states. Use `Call::fork_isolated` to mutate a plugin in isolation.
+    #[inline]
+    pub fn deep_clone(&self) -> State {
+        let plugins = match self.plugins.read() {
+            Ok(g) => g.clone(),
+            Err(e) => e.into_inner().clone(),
+        };
+        let path_history = match self.path_history.read() {
+            Ok(g) => g.clone(),
+            Err(e) => e.into_inner().clone(),
+        };
+        State {
+            store: self.store.clone(),
+            cancel: CancellationToken::new(),
+            cause: CancelCause::default(),
+            handle: self.handle.clone(),
+            plugins: Arc::new(RwLock::new(plugins)),
+            messages: self.messages.detached(),
+            disallow_commit_conflicts: self.disallow_commit_conflicts,
+            dry_run: self.dry_run,
+            framework_check: self.framework_check,
+            stats: Recorder::default(),
+            hooks: self.hooks.clone(),
+            cache: self.cache.as_ref().map(ResultCache::detached),
+            in_flight: InFlight::default(),
+            observers: Arc::new(RwLock::new(BTreeMap::new())),
+            path_history: Arc::new(RwLock::new(path_history)),
+            #[cfg(feature = "signal")]
+            signal: Default::default(),
+        }
+    }
+
     /// Returns a reference to the inner store
     #[inline]
     pub fn store(&self) -> &Store {
@@ -102,11 +506,85 @@ impl State {
     }
 
     /// Closes this state by cancelling the inner cancel token
+    ///
+    /// In-flight calls are cancelled w/ `CancelReason::Shutdown`
     #[inline]
     pub fn close(&self) {
+        self.cause.set(CancelReason::Shutdown);
         self.cancel.cancel()
     }
 
+    /// Arms a timer that cancels this state at `deadline`, bounding the lifetime of all work spawned from this state
+    ///
+    /// In-flight calls are cancelled w/ `CancelReason::GlobalDeadline`. The timer stops if this state is closed before
+    /// the deadline.
+    ///
+    /// **Note**: Setting a deadline more than once arms another timer, so the earliest deadline applies
+    #[inline]
+    pub fn set_global_deadline(&self, deadline: Instant) {
+        let cancel = self.cancel.clone();
+        let cause = self.cause.clone();
+        self.handle.spawn(async move {
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline.into()) => {
+                    cause.set(CancelReason::GlobalDeadline);
+                    cancel.cancel();
+                    debug!("global_deadline_elapsed");
+                }
+                _ = cancel.cancelled() => {}
+            }
+        });
+    }
+
+    /// Closes this state, waits until no work is in flight, and returns the messages that were not received
+    ///
+    /// If `timeout` is set, stops waiting for in-flight work once it elapses. Pending messages are drained from the
+    /// broker, see `Broker::drain`, so that the caller can log or persist them
+    #[inline]
+    pub async fn shutdown(&self, timeout: Option<Duration>) -> Vec<(u64, MessageData)> {
+        self.close();
+        if !self.await_idle(timeout).await {
+            warn!(in_flight = self.in_flight(), "shutdown_timeout");
+        }
+        let undelivered = self.broker().drain();
+        if !undelivered.is_empty() {
+            warn!(count = undelivered.len(), "shutdown_undelivered_messages");
+        }
+        undelivered
+    }
+
+    /// Returns the number of plugin calls and tasks spawned from this state that have not completed
+    #[inline]
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Waits until no plugin calls or tasks spawned from this state are in flight
+    ///
+    /// If `timeout` is set and work is still in flight once it elapses, stops waiting and returns false, otherwise
+    /// returns true once idle
+    ///
+    /// **Note**: Work is in flight from when the plugin spawns it, so a call that has been created but not started is
+    /// not waited on
+    #[inline]
+    pub async fn await_idle(&self, timeout: Option<Duration>) -> bool {
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.in_flight.idle())
+                .await
+                .is_ok(),
+            None => {
+                self.in_flight.idle().await;
+                true
+            }
+        }
+    }
+
+    /// Returns a child of this state's cancel token, which is cancelled when this state is closed
+    #[inline]
+    pub fn child_token(&self) -> CancellationToken {
+        self.cancel.child_token()
+    }
+
     /// Returns a reference to messagge state
     #[inline]
     pub fn broker(&self) -> &Broker {
@@ -123,7 +601,7 @@ impl State {
         let plugin = P::from_arg_matches(matches)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
 
-        Ok(self.load(plugin, labels))
+        self.try_load(plugin, labels).map_err(invalid_config)
     }
 
     /// Loads and registers a plugin from toml
@@ -135,21 +613,186 @@ impl State {
     ) -> std::io::Result<Address> {
         let plugin = toml::from_str::<P>(toml)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.message()))?;
+        self.try_load(plugin, labels).map_err(invalid_config)
+    }
+
+    /// Loads and registers a plugin from json
+    #[inline]
+    pub fn load_by_json<P: Plugin + DeserializeOwned>(
+        &mut self,
+        json: &str,
+        labels: Labels,
+    ) -> std::io::Result<Address> {
+        let plugin = serde_json::from_str::<P>(json)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+        self.try_load(plugin, labels).map_err(invalid_config)
+    }
+
+    /// Loads and registers a plugin from bincode serialized bytes
+    ///
+    /// **Note**: Bincode is not self-describing, so the plugin must be serialized w/ the same field layout it is
+    /// deserialized with, and serde attributes that depend on the data format such as `skip_serializing_if` are not
+    /// supported
+    #[inline]
+    pub fn load_by_bincode<P: Plugin + DeserializeOwned>(
+        &mut self,
+        bytes: &[u8],
+        labels: Labels,
+    ) -> std::io::Result<Address> {
+        let plugin = bincode::deserialize::<P>(bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+        self.try_load(plugin, labels).map_err(invalid_config)
+    }
+
+    /// Loads and registers a plugin from toml read from a reader
+    ///
+    /// **Note**: The toml parser does not support streaming, so the reader is read to the end before parsing
+    #[inline]
+    pub fn load_by_toml_reader<P: Plugin + DeserializeOwned>(
+        &mut self,
+        mut reader: impl Read,
+        labels: Labels,
+    ) -> std::io::Result<Address> {
+        let mut toml = String::new();
+        reader.read_to_string(&mut toml)?;
+        self.load_by_toml::<P>(&toml, labels)
+    }
+
+    /// Loads and registers a plugin from json read from a reader
+    #[inline]
+    pub fn load_by_json_reader<P: Plugin + DeserializeOwned>(
+        &mut self,
+        reader: impl Read,
+        labels: Labels,
+    ) -> std::io::Result<Address> {
+        let plugin = serde_json::from_reader::<_, P>(reader)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+        self.try_load(plugin, labels).map_err(invalid_config)
+    }
+
+    /// Loads and registers a plugin from source content in an explicit format
+    #[inline]
+    pub fn load_by_format<P: Plugin + DeserializeOwned>(
+        &mut self,
+        src: &str,
+        format: SerializationFormat,
+        labels: Labels,
+    ) -> std::io::Result<Address> {
+        match format {
+            SerializationFormat::Toml => self.load_by_toml::<P>(src, labels),
+            SerializationFormat::Json => self.load_by_json::<P>(src, labels),
+        }
+    }
+
+    /// Loads and registers a plugin from source content, detecting the format w/ `SerializationFormat::detect`
+    ///
+    /// If the content cannot be parsed in the detected format, the other format is tried before returning an error
+    /// that includes both parser errors. Use `load_by_format` when the format is known.
+    #[inline]
+    pub fn load_auto<P: Plugin + DeserializeOwned>(
+        &mut self,
+        src: &str,
+        labels: Labels,
+    ) -> std::io::Result<Address> {
+        let (first, second) = match SerializationFormat::detect(src) {
+            SerializationFormat::Toml => (SerializationFormat::Toml, SerializationFormat::Json),
+            SerializationFormat::Json => (SerializationFormat::Json, SerializationFormat::Toml),
+        };
+
+        let plugin = match parse_plugin::<P>(src, &first) {
+            Ok(plugin) => plugin,
+            Err(first_err) => match parse_plugin::<P>(src, &second) {
+                Ok(plugin) => plugin,
+                Err(second_err) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Could not parse as {first:?}: {first_err}, could not parse as {second:?}: {second_err}"
+                        ),
+                    ))
+                }
+            },
+        };
+        self.try_load(plugin, labels).map_err(invalid_config)
+    }
+
+    /// Validates and registers a plugin w/ the current state
+    ///
+    /// Returns the error from `Plugin::validate` if the plugin's config is invalid, or the error from
+    /// `State::check_framework`
+    #[inline]
+    pub fn try_load<P: Plugin>(&mut self, plugin: P, labels: Labels) -> Result<Address> {
+        self.check_framework(P::framework())?;
+        plugin.validate()?;
         Ok(self.load(plugin, labels))
     }
 
     /// Registers a plugin w/ the the current state
     #[inline]
     pub fn load<P: Plugin>(&mut self, plugin: P, labels: Labels) -> Address {
+        self.load_with_ident(plugin, labels, Identifier::Unit)
+    }
+
+    /// Validates and registers a plugin w/ the current state, awaiting `Plugin::load_async` before the plugin is
+    /// registered
+    ///
+    /// Returns the error from `Plugin::validate`, `State::check_framework`, or `Plugin::load_async`. If an error is
+    /// returned the plugin is not registered.
+    pub async fn load_async<P: Plugin>(&mut self, plugin: P, labels: Labels) -> Result<Address> {
         use crate::plugin::MustLoad;
-        let name = P::name();
+        self.check_framework(P::framework())?;
+        plugin.validate()?;
+
+        let put = self.put_with_ident(plugin, &labels, Identifier::Unit);
+        let handle = P::load_async(P::must_load(put)).await?.commit();
+        Ok(self.register::<P>(handle, &labels))
+    }
+
+    /// Registers a plugin w/ the current state as a distinct instance, even if a plugin w/ the same content and labels
+    /// is already loaded
+    ///
+    /// The plugin is committed w/ a random identifier, so each call returns a new address. This is intended for plugins
+    /// that should never be deduplicated, i.e. plugins using `NilContent` whose content always hashes to the same uuid.
+    ///
+    /// **Note**: The short path of the plugin is remapped to the most recently loaded instance
+    #[inline]
+    pub fn load_ephemeral<P: Plugin>(&mut self, plugin: P, labels: Labels) -> Address {
+        let ident = Identifier::from(uuid::Uuid::new_v4().as_u64_pair().0);
+        self.load_with_ident(plugin, labels, ident)
+    }
+
+    /// Registers a plugin w/ the current state, committing the plugin w/ an identifier
+    fn load_with_ident<P: Plugin>(
+        &mut self,
+        plugin: P,
+        labels: Labels,
+        ident: Identifier<'static>,
+    ) -> Address {
+        use crate::plugin::MustLoad;
+        let put = self.put_with_ident(plugin, &labels, ident);
+        let handle = P::load(P::must_load(put)).commit();
+        self.register::<P>(handle, &labels)
+    }
 
+    /// Returns a put for a plugin w/ labels, committed w/ an identifier
+    fn put_with_ident<P: Plugin>(
+        &mut self,
+        plugin: P,
+        labels: &Labels,
+        ident: Identifier<'static>,
+    ) -> Put<'_, P> {
         // TODO: Might want to refactor this to return a Load builder
         let mut put = self.store.put(plugin);
+        put.ident(ident);
         for (k, v) in labels.iter() {
             put = put.label(k, v);
         }
-        let handle = P::load(P::must_load(put)).commit();
+        put
+    }
+
+    /// Registers the paths and labels of a plugin committed to the store, and returns the address of the plugin
+    fn register<P: Plugin>(&mut self, handle: Handle, labels: &Labels) -> Address {
+        let name = P::name();
         let address = name.path().join(hex::encode(handle.commit().to_be_bytes()));
 
         let mut plugins = match self.plugins.write() {
@@ -157,9 +800,17 @@ impl State {
             Err(e) => e.into_inner(),
         };
 
-        plugins.insert(name.path().clone(), handle.clone());
+        plugins
+            .paths
+            .insert(name.path().clone(), (PathKind::Short, handle.clone()));
+        self.record_short_path(name.path(), handle.commit());
+        plugins.index_labels(labels, handle.commit());
+        if let Some(expires) = self.store.item(handle.commit()).and_then(Item::expires_at) {
+            plugins.expires.insert(handle.commit(), expires);
+        }
         if let Some(_) = plugins
-            .insert(address, handle.clone())
+            .paths
+            .insert(address, (PathKind::Address, handle.clone()))
             .filter(|_| self.disallow_commit_conflicts)
         {
             todo!("Commit conflicts disallowed")
@@ -171,6 +822,136 @@ impl State {
         }
     }
 
+    /// Adds an alias for the plugin at an address, so that the plugin can also be found by the alias, i.e.
+    /// `State::event("my-alias")`
+    ///
+    /// If the alias was already added for a different plugin, the alias is remapped to this plugin. Aliases are removed
+    /// when the plugin is unloaded.
+    ///
+    /// Returns `Error::PluginNotFound` if the plugin is not loaded, or `Error::PluginAliasConflict` if the alias is
+    /// the short path or address of a loaded plugin
+    pub fn alias(&mut self, address: &Address, alias: &str) -> Result<()> {
+        let mut plugins = match self.plugins.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        let handle = match plugins.paths.get(&PathBuf::from(address)) {
+            Some((_, handle)) => handle.clone(),
+            None => return Err(Error::PluginNotFound),
+        };
+
+        let path = PathBuf::from(alias);
+        if plugins
+            .paths
+            .get(&path)
+            .is_some_and(|(kind, _)| *kind != PathKind::Alias)
+        {
+            return Err(Error::PluginAliasConflict {
+                alias: alias.to_string(),
+            });
+        }
+        plugins.paths.insert(path, (PathKind::Alias, handle));
+        Ok(())
+    }
+
+    /// Unloads the plugin at an address, removing its short path, address, and aliases from state
+    ///
+    /// **Note**: If the short path was remapped to a plugin loaded later, the short path is not removed. The resource
+    /// is not removed from the store, so calls to the plugin that are in flight are not affected.
+    ///
+    /// Returns `Error::PluginNotFound` if the plugin is not loaded
+    pub fn unload(&mut self, address: &Address) -> Result<()> {
+        let mut plugins = match self.plugins.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        if !plugins.remove(address.commit) {
+            return Err(Error::PluginNotFound);
+        }
+
+        let mut observers = match self.observers.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        observers.remove(&address.commit);
+        Ok(())
+    }
+
+    /// Evicts every plugin that has expired, see `Put::ttl`
+    ///
+    /// Expired plugins are unloaded and their resources are removed from the store. Plugins w/ calls or tasks in flight
+    /// are not evicted until the work completes.
+    ///
+    /// Returns the commit id of each evicted plugin
+    pub fn evict_expired(&mut self) -> Vec<u64> {
+        let mut evicted = unregister_expired(&self.plugins, &self.observers, &self.in_flight);
+        for commit in self.store.expired() {
+            if !self.in_flight.contains(commit)
+                && self.store.remove(commit).is_some()
+                && !evicted.contains(&commit)
+            {
+                evicted.push(commit);
+            }
+        }
+        evicted
+    }
+
+    /// Spawns a background task that evicts expired plugins every `period`, see `Put::ttl`
+    ///
+    /// Expired plugins are unloaded and their resources are evicted from the store of this state and every clone of it,
+    /// see `Store::evict`. Plugins w/ calls or tasks in flight are not evicted until the work completes. The task stops
+    /// once this state is closed.
+    pub fn start_eviction(&self, period: Duration) -> tokio::task::JoinHandle<()> {
+        let store = self.store.clone();
+        let plugins = self.plugins.clone();
+        let observers = self.observers.clone();
+        let in_flight = self.in_flight.clone();
+        let cancel = self.cancel.clone();
+        self.handle.spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = interval.tick() => {
+                        let evicted = unregister_expired(&plugins, &observers, &in_flight);
+                        for commit in evicted.iter() {
+                            store.evict(*commit);
+                        }
+                        if !evicted.is_empty() {
+                            debug!(count = evicted.len(), "evicted_expired_plugins");
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Reloads the plugin at an address in place from toml
+    ///
+    /// The new config is validated and then passed to `Plugin::reload` on the existing plugin, so the address stays the
+    /// same and calls made through it use the new config.
+    ///
+    /// **Note**: The commit of the address is not recomputed, so it no longer matches the content of the reloaded plugin
+    ///
+    /// Returns an error if the plugin could not be found, does not match `P`, or if the new config is invalid
+    pub fn reload<P: Plugin + DeserializeOwned>(
+        &mut self,
+        address: &Address,
+        toml: &str,
+    ) -> Result<()> {
+        let plugin = toml::from_str::<P>(toml)?;
+        plugin.validate()?;
+
+        let mut item = self
+            .find_plugin(address)
+            .cloned()
+            .ok_or(Error::PluginNotFound)?;
+        match item.borrow_mut::<P>() {
+            Some(existing) => existing.reload(plugin),
+            None => Err(Error::PluginMismatch),
+        }
+    }
+
     /// Registers a plugin from parsing cli arg matches
     #[inline]
     pub fn load_handler_by_args<H: Handler + clap::FromArgMatches>(
@@ -181,7 +962,8 @@ impl State {
         let plugin = H::from_arg_matches(matches)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
 
-        Ok(self.load_handler(plugin, labels))
+        self.try_load_handler(plugin, labels)
+            .map_err(invalid_config)
     }
 
     /// Loads and registers a plugin from toml
@@ -193,28 +975,67 @@ impl State {
     ) -> std::io::Result<Address> {
         let plugin = toml::from_str::<H>(toml)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.message()))?;
+        self.try_load_handler(plugin, labels)
+            .map_err(invalid_config)
+    }
+
+    /// Validates and registers a handler plugin w/ the current state
+    ///
+    /// Returns the error from `Plugin::validate` if the plugin's config is invalid, or the error from
+    /// `State::check_framework`
+    #[inline]
+    pub fn try_load_handler<H: Handler>(&mut self, plugin: H, labels: Labels) -> Result<Address> {
+        self.check_framework(H::framework())?;
+        plugin.validate()?;
         Ok(self.load_handler(plugin, labels))
     }
 
-    /// Registers a plugin w/ the the current state
+    /// Registers a handler plugin w/ the the current state
+    ///
+    /// If a handler w/ the same content and labels is already loaded, the existing handler is kept and its address is
+    /// returned, so that reloading a handler from unchanged config does not replace it.
+    ///
+    /// **Note**: Unlike `load`, the short path of the handler is not remapped if it is already mapped to a handler
+    /// loaded earlier, use the address returned to refer to a specific handler
     #[inline]
     pub fn load_handler<H: Handler>(&mut self, plugin: H, labels: Labels) -> Address {
         use crate::plugin::MustLoadHandler;
         let name = H::name();
-        let mut put = self.store.put(plugin);
+        let mut put = self.store.put(plugin).keep_existing();
         for (k, v) in labels.iter() {
             put = put.label(k, v);
         }
         let handle = H::load(H::must_load(put)).commit();
+        let address = name.path().join(hex::encode(handle.commit().to_be_bytes()));
         let mut plugins = match self.plugins.write() {
             Ok(g) => g,
             Err(e) => e.into_inner(),
         };
-        plugins.insert(name.path().clone(), handle.clone());
-        plugins.insert(
-            name.path().join(hex::encode(handle.commit().to_be_bytes())),
-            handle.clone(),
-        );
+        if plugins.paths.contains_key(&address) {
+            debug!(commit = handle.commit(), "handler_unchanged");
+            return Address {
+                name,
+                commit: handle.commit(),
+            };
+        }
+
+        if !plugins
+            .paths
+            .get(name.path())
+            .is_some_and(|(kind, _)| *kind == PathKind::Short)
+        {
+            plugins
+                .paths
+                .insert(name.path().clone(), (PathKind::Short, handle.clone()));
+            self.record_short_path(name.path(), handle.commit());
+        }
+        plugins.index_labels(&labels, handle.commit());
+        if let Some(expires) = self.store.item(handle.commit()).and_then(Item::expires_at) {
+            plugins.expires.insert(handle.commit(), expires);
+        }
+        plugins
+            .paths
+            .insert(address, (PathKind::Address, handle.clone()));
 
         Address {
             name,
@@ -239,6 +1060,41 @@ impl State {
         f.await
     }
 
+    /// Calls a plugin and returns the message data the plugin published w/ `Bind::emit`, see `Event::returns`
+    ///
+    /// Returns `MessageData::Empty` if the plugin did not publish any message data
+    ///
+    /// ## Errors
+    /// - `Error::PluginNotFound` if the plugin is not registered
+    /// - Any error returned by the plugin
+    #[inline]
+    pub async fn call_returns(&self, plugin: impl Into<PathBuf>) -> Result<MessageData> {
+        self.event(plugin)?.returns().await
+    }
+
+    /// Calls a plugin w/ a handler attached and returns the message data received by the handler, see
+    /// `Event::returns`
+    ///
+    /// ## Errors
+    /// - `Error::PluginNotFound` if the plugin or the handler is not registered
+    /// - `Error::PluginMismatch` if the handler is not an `H`, or if `H::Target` does not match the plugin
+    /// - Any error returned by the plugin or the handler
+    #[inline]
+    pub async fn call_with_handler<H: Handler>(
+        &self,
+        target: impl Into<PathBuf>,
+        handler: Address,
+    ) -> Result<MessageData> {
+        match self.find_plugin(&handler) {
+            Some(item) if item.is_type::<H>() => {}
+            Some(_) => return Err(Error::PluginMismatch),
+            None => return Err(Error::PluginNotFound),
+        }
+        let mut event = self.event(target)?;
+        event.with_handler::<H>(handler)?;
+        event.returns().await
+    }
+
     /// Spawns a call to a plugin
     ///
     /// Returns the future and the associated cancellation token
@@ -256,7 +1112,7 @@ impl State {
             Ok(g) => g,
             Err(e) => e.into_inner(),
         };
-        match plugins.get(&path).and_then(|h| {
+        match plugins.paths.get(&path).and_then(|(_, h)| {
             let id = h.commit();
             self.store
                 .item(id)
@@ -281,8 +1137,12 @@ impl State {
                     item: item.clone(),
                     fork_fn: thunk.fork_fn(),
                     cancel: cancel.clone(),
+                    cause: self.cause.child(),
                     runtime: self.handle.clone(),
                     handler: None,
+                    budget: None,
+                    timeout: None,
+                    scratch: Default::default(),
                 };
                 let labels = item.attributes().get::<Labels>();
 
@@ -307,8 +1167,9 @@ impl State {
             Err(e) => e.into_inner(),
         };
         match plugins
+            .paths
             .get(&path)
-            .and_then(|h| h.cast::<Attributes>().and_then(|a| a.get::<HandlerThunk>()))
+            .and_then(|(_, h)| h.cast::<Attributes>().and_then(|a| a.get::<HandlerThunk>()))
         {
             Some(h) => Ok(h.deref().clone()),
             None => Err(Error::PluginNotFound),
@@ -323,12 +1184,60 @@ impl State {
             Ok(g) => g,
             Err(e) => e.into_inner(),
         };
-        plugins.get(&path).and_then(|h| {
+        plugins.paths.get(&path).and_then(|(_, h)| {
             let id = h.commit();
             self.store.item(id)
         })
     }
 
+    /// Returns true if a plugin is loaded at the path
+    ///
+    /// Unlike `find_plugin`, the item is not looked up
+    #[inline]
+    pub fn is_loaded(&self, plugin: impl Into<PathBuf>) -> bool {
+        let path = plugin.into();
+        let plugins = match self.plugins.read() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        plugins
+            .paths
+            .get(&path)
+            .is_some_and(|(_, h)| self.store.contains(h.commit()))
+    }
+
+    /// Returns the labels a plugin was loaded w/
+    ///
+    /// Returns None if the plugin could not be found, or was loaded w/o labels
+    #[inline]
+    pub fn labels_of(&self, plugin: impl Into<PathBuf>) -> Option<Arc<Labels>> {
+        self.find_plugin(plugin)
+            .and_then(|item| item.attributes().get::<Labels>())
+    }
+
+    /// Returns the observation event for a plugin, which receives progress published by the plugin w/
+    /// `Bind::observable`
+    ///
+    /// Returns None if the plugin could not be found
+    #[inline]
+    pub fn observe(&self, address: &Address) -> Option<ObservationEvent> {
+        self.find_plugin(address)
+            .map(|item| self.observation_event(item))
+    }
+
+    /// Returns the observation event for an item, creating the event if it does not exist
+    #[inline]
+    pub(crate) fn observation_event(&self, item: &Item) -> ObservationEvent {
+        let mut observers = match self.observers.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        observers
+            .entry(item.commit())
+            .or_insert_with(|| item.observe().event())
+            .clone()
+    }
+
     /// Returns each unique address stored in state
     #[inline]
     pub fn addresses(&self) -> Vec<Address> {
@@ -337,9 +1246,10 @@ impl State {
             Err(e) => e.into_inner(),
         };
         plugins
+            .paths
             .iter()
-            .filter(|(p, _)| p.ancestors().count() > 5)
-            .filter_map(|(_, h)| {
+            .filter(|(_, (kind, _))| *kind == PathKind::Address)
+            .filter_map(|(_, (_, h))| {
                 let id = h.commit();
                 self.store
                     .item(id)
@@ -352,4 +1262,255 @@ impl State {
             })
             .collect::<Vec<Address>>()
     }
+
+    /// Returns the address of the highest version of a plugin loaded in state that satisfies a version requirement
+    ///
+    /// The version of `plugin_ref` is ignored, i.e. `kioto/plugins.request` and `kioto/plugins.request@0.1.0` resolve the
+    /// same plugins. If the same version was loaded more than once, the plugin w/ the highest commit id is returned.
+    ///
+    /// Returns None if `plugin_ref` could not be parsed, or if no loaded plugin satisfies the requirement
+    #[inline]
+    pub fn resolve_req(&self, plugin_ref: &str, req: &semver::VersionReq) -> Option<Address> {
+        let name = plugin_ref.parse::<Name>().ok()?;
+        self.addresses()
+            .into_iter()
+            .filter(|a| a.name.same_plugin(&name) && req.matches(&a.name.version))
+            .max_by(|a, b| {
+                a.name
+                    .version
+                    .cmp(&b.name.version)
+                    .then(a.commit.cmp(&b.commit))
+            })
+    }
+
+    /// Checks that the plugins a loaded plugin depends on are loaded, see `Plugin::dependencies`
+    ///
+    /// Returns the names of the dependencies that are not loaded, or the name of the plugin itself if the plugin is not
+    /// loaded
+    #[inline]
+    pub fn check_dependencies(&self, address: &Address) -> std::result::Result<(), Vec<Name>> {
+        let Some(thunk) = self
+            .find_plugin(address)
+            .and_then(|item| item.attributes().get::<Thunk>())
+        else {
+            return Err(vec![address.name.clone()]);
+        };
+
+        let loaded = self.addresses();
+        let missing = thunk
+            .dependencies()
+            .into_iter()
+            .filter(|dep| !loaded.iter().any(|a| a.name.same_plugin(dep)))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(missing)
+        }
+    }
+
+    /// Returns the address of each plugin or handler that was loaded w/ the label `key = value`
+    ///
+    /// Plugins are found from an index of the labels passed to `load` or `load_handler`, so the lookup does not scan
+    /// every plugin loaded in state
+    #[inline]
+    pub fn find_by_label(&self, key: &str, value: &str) -> Vec<Address> {
+        let plugins = match self.plugins.read() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        plugins
+            .labels
+            .get(&(key.to_string(), value.to_string()))
+            .into_iter()
+            .flatten()
+            .filter_map(|id| {
+                self.store
+                    .item(*id)
+                    .and_then(|i| i.attributes().get::<Name>())
+                    .map(|name| Address {
+                        name: name.deref().clone(),
+                        commit: *id,
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the address of each plugin that was loaded w/ `tag`
+    #[inline]
+    pub fn find_by_tag(&self, tag: &str) -> Vec<Address> {
+        self.addresses()
+            .into_iter()
+            .filter(|a| self.tags_of(a).is_some_and(|t| t.contains(tag)))
+            .collect()
+    }
+
+    /// Returns the tags a plugin was loaded with
+    #[inline]
+    pub fn tags_of(&self, address: &Address) -> Option<Arc<Tags>> {
+        self.store
+            .item(address.commit)
+            .and_then(|i| i.attributes().get::<Tags>())
+    }
+
+    /// Returns the kind of a plugin, see `Plugin::kind`
+    ///
+    /// Plugins loaded w/ `load_handler` are always classified as `PluginKind::Handler`
+    ///
+    /// Returns None if the plugin could not be found
+    #[inline]
+    pub fn kind_of(&self, plugin: impl Into<PathBuf>) -> Option<PluginKind> {
+        let item = self.find_plugin(plugin)?;
+        let attributes = item.attributes();
+        if attributes.get::<HandlerThunk>().is_some() {
+            return Some(PluginKind::Handler);
+        }
+        attributes.get::<Thunk>().map(|t| t.kind(item))
+    }
+
+    /// Exports a graph of the plugins loaded into state and the handler relationships between them
+    ///
+    /// An edge is created from each handler to every loaded plugin matching the handler's target type. If no plugin
+    /// matches, the edge is still created w/o a target, which can be used to find misconfigured handler pairings.
+    pub fn export_graph(&self) -> Graph {
+        let mut graph = Graph::default();
+        let mut handlers = vec![];
+        for address in self.addresses() {
+            let Some(item) = self.store.item(address.commit) else {
+                continue;
+            };
+            let attributes = item.attributes();
+            let kind = match attributes.get::<HandlerThunk>() {
+                Some(handler) => {
+                    handlers.push((address.clone(), handler));
+                    NodeKind::Handler
+                }
+                None => NodeKind::Plugin,
+            };
+            let labels = attributes
+                .get::<Labels>()
+                .map(|l| l.0.clone())
+                .unwrap_or_default();
+            let plugin_kind = match kind {
+                NodeKind::Handler => PluginKind::Handler,
+                NodeKind::Plugin => attributes
+                    .get::<Thunk>()
+                    .map(|t| t.kind(item))
+                    .unwrap_or_default(),
+            };
+            graph.nodes.push(GraphNode {
+                address,
+                kind,
+                plugin_kind,
+                labels,
+            });
+        }
+
+        for (handler, thunk) in handlers {
+            let mut targets = graph
+                .nodes
+                .iter()
+                .filter(|n| {
+                    self.store
+                        .item(n.address.commit)
+                        .is_some_and(|i| i.matches_type(thunk.target_type()))
+                })
+                .map(|n| n.address.clone())
+                .peekable();
+
+            if targets.peek().is_none() {
+                graph.edges.push(GraphEdge {
+                    handler,
+                    target_name: thunk.target_name(),
+                    target: None,
+                });
+                continue;
+            }
+
+            let edges = targets
+                .map(|target| GraphEdge {
+                    handler: handler.clone(),
+                    target_name: thunk.target_name(),
+                    target: Some(target),
+                })
+                .collect::<Vec<_>>();
+            graph.edges.extend(edges);
+        }
+        graph
+    }
+
+    /// Returns an inventory of every plugin and handler loaded into state
+    ///
+    /// Each plugin is described w/ its address, name, version, kind, labels, and the loaded handlers that target it,
+    /// which can be serialized to produce a snapshot of what the state contains
+    pub fn describe_all(&self) -> StateInventory {
+        let graph = self.export_graph();
+        let plugins = graph
+            .nodes
+            .into_iter()
+            .map(|node| {
+                let handlers = graph
+                    .edges
+                    .iter()
+                    .filter(|e| e.target.as_ref() == Some(&node.address))
+                    .map(|e| e.handler.clone())
+                    .collect();
+                PluginDescription {
+                    name: node.address.name.plugin_ref().to_string(),
+                    version: node.address.name.version.clone(),
+                    address: node.address,
+                    kind: node.plugin_kind,
+                    labels: node.labels,
+                    handlers,
+                }
+            })
+            .collect();
+        StateInventory { plugins }
+    }
+
+    /// Returns the plugin graph in the DOT format, see `Graph::to_dot`
+    #[inline]
+    pub fn graph_to_dot(&self) -> String {
+        self.export_graph().to_dot()
+    }
+
+    /// Validates that handlers loaded into state do not form a cycle, i.e. handler A targets handler B whose target is
+    /// handler A
+    ///
+    /// Calling a plugin in a handler cycle would recurse w/o end, so this can be used to reject a configuration before
+    /// any event is started
+    ///
+    /// Returns each cycle that was found
+    pub fn validate_handlers(&self) -> std::result::Result<(), Vec<HandlerCycle>> {
+        let cycles = self.export_graph().cycles();
+        if cycles.is_empty() {
+            Ok(())
+        } else {
+            Err(cycles)
+        }
+    }
+}
+
+/// Parses a plugin from source content in a specific format, returns the parser's error message on failure
+#[inline]
+fn parse_plugin<P: DeserializeOwned>(
+    src: &str,
+    format: &SerializationFormat,
+) -> std::result::Result<P, String> {
+    match format {
+        SerializationFormat::Toml => toml::from_str(src).map_err(|e| e.message().to_string()),
+        SerializationFormat::Json => serde_json::from_str(src).map_err(|e| e.to_string()),
+    }
+}
+
+/// Converts an error returned by `Plugin::validate` into an io error
+#[inline]
+fn invalid_config(err: Error) -> std::io::Error {
+    let message = match err {
+        Error::InvalidPluginConfig { name, message } | Error::PluginCallError { name, message } => {
+            format!("{name}: {message}")
+        }
+        err => format!("{err:?}"),
+    };
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
 }
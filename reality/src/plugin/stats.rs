@@ -0,0 +1,171 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use super::Name;
+use crate::{Error, Result};
+use runir::store::StoreStats;
+
+/// Type-alias for a shared map of plugin stats
+type StatsMap = Arc<RwLock<HashMap<Name, PluginStats>>>;
+
+/// Counts of entries held by a state
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StateStats {
+    /// Counts of entries held by the state's store
+    pub store: StoreStats,
+    /// Number of unique plugins and handlers registered w/ the state
+    pub plugin_count: usize,
+    /// Number of paths in the plugin map, each plugin is registered by name and by address
+    pub plugin_paths: usize,
+}
+
+/// Snapshot of the metrics of the tokio runtime a call is running on, see `Bind::runtime_metrics`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RuntimeMetrics {
+    /// Number of worker threads used by the runtime
+    pub workers: usize,
+    /// Number of tasks that are alive on the runtime
+    pub alive_tasks: usize,
+    /// Number of tasks scheduled on the runtime's global queue that have not been polled yet
+    ///
+    /// **Note**: Queue depth is an unstable tokio metric, so this is only set when built w/ `--cfg tokio_unstable`
+    pub queue_depth: Option<usize>,
+}
+
+impl RuntimeMetrics {
+    /// Returns a snapshot of the metrics of a tokio runtime
+    #[inline]
+    pub fn of(handle: &tokio::runtime::Handle) -> Self {
+        let metrics = handle.metrics();
+        #[cfg(tokio_unstable)]
+        let queue_depth = Some(metrics.injection_queue_depth());
+        #[cfg(not(tokio_unstable))]
+        let queue_depth = None;
+        RuntimeMetrics {
+            workers: metrics.num_workers(),
+            alive_tasks: metrics.num_alive_tasks(),
+            queue_depth,
+        }
+    }
+}
+
+/// Outcome of a plugin call
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallOutcome {
+    /// The plugin call completed successfully
+    Ok,
+    /// The plugin call returned an error
+    Error,
+    /// The plugin call was cancelled
+    Cancelled,
+    /// The plugin call was skipped by the plugin
+    Skipped,
+}
+
+impl CallOutcome {
+    /// Returns the outcome of a plugin call result
+    #[inline]
+    pub fn of(result: &Result<()>) -> Self {
+        match result {
+            Ok(_) => CallOutcome::Ok,
+            Err(Error::PluginCallCancelled { .. }) => CallOutcome::Cancelled,
+            Err(Error::PluginCallSkipped) | Err(Error::PluginHandlerCallSkipped) => {
+                CallOutcome::Skipped
+            }
+            Err(_) => CallOutcome::Error,
+        }
+    }
+
+    /// Returns the outcome as a str
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallOutcome::Ok => "ok",
+            CallOutcome::Error => "error",
+            CallOutcome::Cancelled => "cancelled",
+            CallOutcome::Skipped => "skipped",
+        }
+    }
+}
+
+/// Call statistics for a single plugin
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PluginStats {
+    /// Total number of calls
+    pub calls: u64,
+    /// Number of calls that completed successfully
+    pub ok: u64,
+    /// Number of calls that returned an error
+    pub errors: u64,
+    /// Number of calls that were cancelled
+    pub cancelled: u64,
+    /// Number of calls that were skipped
+    pub skipped: u64,
+    /// Total time spent in calls
+    pub total_duration: Duration,
+    /// Longest call duration
+    pub max_duration: Duration,
+}
+
+impl PluginStats {
+    /// Returns the mean duration of a call
+    #[inline]
+    pub fn mean_duration(&self) -> Duration {
+        if self.calls == 0 {
+            Duration::ZERO
+        } else {
+            self.total_duration / self.calls as u32
+        }
+    }
+}
+
+/// Records plugin call statistics for a state
+#[derive(Clone, Default)]
+pub(crate) struct Recorder {
+    stats: StatsMap,
+}
+
+impl Recorder {
+    /// Records the result of a plugin call
+    ///
+    /// If the `metrics` feature is enabled, `plugin_calls_total{name,outcome}` and
+    /// `plugin_call_duration_seconds{name}` are also emitted
+    pub(crate) fn record(&self, name: &Name, elapsed: Duration, result: &Result<()>) {
+        let outcome = CallOutcome::of(result);
+        #[cfg(feature = "metrics")]
+        {
+            let name = name.to_string();
+            metrics::counter!("plugin_calls_total", "name" => name.clone(), "outcome" => outcome.as_str())
+                .increment(1);
+            metrics::histogram!("plugin_call_duration_seconds", "name" => name)
+                .record(elapsed.as_secs_f64());
+        }
+
+        let mut stats = match self.stats.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        let entry = stats.entry(name.clone()).or_default();
+        entry.calls += 1;
+        match outcome {
+            CallOutcome::Ok => entry.ok += 1,
+            CallOutcome::Error => entry.errors += 1,
+            CallOutcome::Cancelled => entry.cancelled += 1,
+            CallOutcome::Skipped => entry.skipped += 1,
+        }
+        entry.total_duration += elapsed;
+        entry.max_duration = entry.max_duration.max(elapsed);
+    }
+
+    /// Returns a copy of the current stats
+    pub(crate) fn snapshot(&self) -> HashMap<Name, PluginStats> {
+        let stats = match self.stats.read() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        stats.clone()
+    }
+}
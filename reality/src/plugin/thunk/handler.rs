@@ -41,6 +41,12 @@ impl HandlerThunk {
         std::any::TypeId::of::<T>() == self.target
     }
 
+    /// Returns the type name of the target this handler targets
+    #[inline]
+    pub fn target_name(&self) -> &'static str {
+        self.target_name
+    }
+
     /// Returns the current target type id
     #[inline]
     pub fn target_type(&self) -> TypeId {
@@ -1,9 +1,13 @@
 mod handler;
 pub use handler::HandlerThunk;
 
-use super::{Call, ForkFn, Handler, Name, Plugin, ThunkFn};
-use crate::Result;
-use runir::{Content, Repr, Resource};
+use super::{
+    Address, CacheKeyFn, Call, CancelReason, DependenciesFn, ForkFn, Handler, KindFn, Name, Plugin,
+    PluginKind, ResourceLimit, Scratch, ThunkFn,
+};
+use crate::{Error, Result};
+use runir::{store::Item, Content, Repr, Resource};
+use std::time::{Duration, Instant};
 
 /// Attribute created by a plugin
 #[derive(Clone)]
@@ -14,6 +18,14 @@ pub struct Thunk {
     thunk: ThunkFn,
     /// Fork function
     fork: ForkFn,
+    /// Default timeout declared by the plugin
+    default_timeout: Option<Duration>,
+    /// Returns the key the plugin's call results are cached by
+    cache_key: CacheKeyFn,
+    /// Returns the kind of plugin an item holds
+    kind: KindFn,
+    /// Returns the plugins the plugin depends on
+    dependencies: DependenciesFn,
 }
 
 impl Thunk {
@@ -24,6 +36,10 @@ impl Thunk {
             name: P::name(),
             thunk: P::thunk,
             fork: P::fork,
+            default_timeout: P::default_timeout(),
+            cache_key: cache_key::<P>,
+            kind: kind::<P>,
+            dependencies: P::dependencies,
         }
     }
 
@@ -34,6 +50,10 @@ impl Thunk {
             name: H::name(),
             thunk: H::wrap_thunk,
             fork: H::fork,
+            default_timeout: H::default_timeout().or_else(H::Target::default_timeout),
+            cache_key: cache_key::<H>,
+            kind: |_| PluginKind::Handler,
+            dependencies: H::dependencies,
         }
     }
 
@@ -49,6 +69,34 @@ impl Thunk {
         self.fork
     }
 
+    /// Returns the default timeout declared by the plugin that created this thunk
+    ///
+    /// **Note**: For handler thunks, the handler's default timeout is used before the target's default timeout
+    #[inline]
+    pub fn default_timeout(&self) -> Option<Duration> {
+        self.default_timeout
+    }
+
+    /// Returns the key the results of calls to an item are cached by, or None if the plugin is not cacheable
+    #[inline]
+    pub fn cache_key(&self, item: &Item) -> Option<uuid::Uuid> {
+        (self.cache_key)(item)
+    }
+
+    /// Returns the kind of plugin an item holds
+    ///
+    /// **Note**: Handler thunks always return `PluginKind::Handler`
+    #[inline]
+    pub fn kind(&self, item: &Item) -> PluginKind {
+        (self.kind)(item)
+    }
+
+    /// Returns the names of the plugins the plugin that created this thunk depends on
+    #[inline]
+    pub fn dependencies(&self) -> Vec<Name> {
+        (self.dependencies)()
+    }
+
     /// Returns the name of the plugin that created this thunk
     #[inline]
     pub fn name(&self) -> &Name {
@@ -56,13 +104,120 @@ impl Thunk {
     }
 
     /// Executes the thunk
+    ///
+    /// The outcome and duration of the call is recorded to the stats of the call's state, and the call hooks of the
+    /// call's state are run around the call
+    ///
+    /// If the call has a resource budget w/ a max duration, the call is cancelled once the duration elapses. If the
+    /// event set a timeout w/ `Event::with_timeout`, or otherwise the plugin declared a default timeout, the call is
+    /// cancelled once the shorter of the timeout and the budget's max duration elapses.
     #[inline]
     #[must_use = "If the future is not awaited, then the call cannot be executed"]
     pub async fn exec(&self, call: Call) -> Result<()> {
-        (self.thunk)(call)?.await
+        self.exec_timed(call).await.map(|_| ())
+    }
+
+    /// Executes the thunk and returns how long the call took
+    ///
+    /// The duration is measured from when the thunk is invoked until the plugin's work completes, including any
+    /// deferred work spawned by the plugin
+    #[inline]
+    #[must_use = "If the future is not awaited, then the call cannot be executed"]
+    pub async fn exec_timed(&self, call: Call) -> Result<Duration> {
+        let budget = call.budget.as_ref().and_then(|b| b.max_duration());
+        let max_duration = match (budget, call.timeout.or(self.default_timeout)) {
+            (Some(budget), Some(timeout)) if timeout <= budget => {
+                Some((timeout, CancelReason::Timeout))
+            }
+            (Some(budget), _) => Some((budget, CancelReason::ResourceBudget)),
+            (None, timeout) => timeout.map(|t| (t, CancelReason::Timeout)),
+        };
+        self.exec_with_max_duration(call, max_duration).await
+    }
+
+    /// Executes the thunk and cancels the call if it does not complete within `timeout`
+    ///
+    /// If the call has a resource budget w/ a shorter max duration, the budget's max duration is used instead. Returns
+    /// `Error::PluginCallCancelled` w/ `CancelReason::Timeout` if the call was cancelled once the timeout elapsed, or
+    /// `Error::ResourceBudgetExceeded` if the call was cancelled once the budget's max duration elapsed.
+    #[inline]
+    #[must_use = "If the future is not awaited, then the call cannot be executed"]
+    pub async fn exec_with_timeout(&self, call: Call, timeout: Duration) -> Result<()> {
+        let max_duration = match call.budget.as_ref().and_then(|b| b.max_duration()) {
+            Some(budget) if budget < timeout => (budget, CancelReason::ResourceBudget),
+            _ => (timeout, CancelReason::Timeout),
+        };
+        self.exec_with_max_duration(call, Some(max_duration))
+            .await
+            .map(|_| ())
+    }
+
+    /// Executes the thunk, cancelling the call w/ a reason once `max_duration` elapses, and returns how long the call
+    /// took
+    ///
+    /// The call is executed w/ a new scratch state, which is cleared once the call completes
+    async fn exec_with_max_duration(
+        &self,
+        mut call: Call,
+        max_duration: Option<(Duration, CancelReason)>,
+    ) -> Result<Duration> {
+        call.scratch = Scratch::default();
+        let scratch = call.scratch.clone();
+        let recorder = call.state.stats.clone();
+        let hooks = call.state.hooks.clone();
+        let address = Address {
+            name: self.name.clone(),
+            commit: call.item.commit(),
+        };
+        let cancel = call.cancel.clone();
+        let cause = call.cause.clone();
+        let start = Instant::now();
+        let result = match hooks.iter().try_for_each(|h| h.before(&address)) {
+            Ok(()) => match ((self.thunk)(call), max_duration) {
+                (Ok(work), Some((max_duration, reason))) => {
+                    match tokio::time::timeout(max_duration, work).await {
+                        Ok(result) => result,
+                        Err(_) => {
+                            cause.set(reason);
+                            cancel.cancel();
+                            Err(match reason {
+                                CancelReason::ResourceBudget => Error::ResourceBudgetExceeded {
+                                    limit: ResourceLimit::Duration(max_duration),
+                                },
+                                reason => Error::PluginCallCancelled { reason },
+                            })
+                        }
+                    }
+                }
+                (Ok(work), None) => work.await,
+                (Err(err), _) => Err(err),
+            },
+            Err(err) => Err(err),
+        };
+        let elapsed = start.elapsed();
+        scratch.clear();
+        recorder.record(&self.name, elapsed, &result);
+        for hook in hooks.iter() {
+            hook.after(&address, &result);
+        }
+        result.map(|_| elapsed)
     }
 }
 
+/// Returns the state uuid of the plugin an item holds if the plugin is cacheable
+fn cache_key<P: Plugin>(item: &Item) -> Option<uuid::Uuid> {
+    if P::cacheable() {
+        item.borrow::<P>().map(Content::state_uuid)
+    } else {
+        None
+    }
+}
+
+/// Returns the kind of plugin an item holds, or the default kind if the item does not hold the plugin
+fn kind<P: Plugin>(item: &Item) -> PluginKind {
+    item.borrow::<P>().map(P::kind).unwrap_or_default()
+}
+
 impl Repr for Thunk {}
 impl Resource for Thunk {}
 
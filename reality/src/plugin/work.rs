@@ -1,4 +1,7 @@
+use super::{CancelCause, CancelReason, Name};
 use crate::Error;
+use futures_util::future::{join_all, select_all};
+use std::any::Any;
 use std::future::Future;
 use std::pin::pin;
 use tokio_util::sync::CancellationToken;
@@ -8,6 +11,31 @@ pub struct Work {
     pub(super) task: tokio::task::JoinHandle<crate::Result<()>>,
     /// Cancellation token for this work
     pub(super) cancel: CancellationToken,
+    /// Reason the cancellation token was cancelled
+    pub(super) cause: CancelCause,
+    /// Name of the plugin that created this work
+    pub(super) name: Name,
+}
+
+impl Work {
+    /// Returns the name of the plugin that created this work
+    #[inline]
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    /// Cancels this work w/ `CancelReason::User`
+    #[inline]
+    pub fn cancel(&self) {
+        self.cause.set(CancelReason::User);
+        self.cancel.cancel();
+    }
+
+    /// Waits for all work to complete and returns the results in the same order as the input
+    #[inline]
+    pub async fn join_all(work: impl IntoIterator<Item = Work>) -> Vec<crate::Result<()>> {
+        work.into_iter().collect::<WorkSet>().join_all().await
+    }
 }
 
 impl Future for Work {
@@ -19,13 +47,140 @@ impl Future for Work {
     ) -> std::task::Poll<Self::Output> {
         if self.cancel.is_cancelled() {
             self.task.abort();
-            return std::task::Poll::Ready(Err(Error::PluginCallCancelled));
+            return std::task::Poll::Ready(Err(Error::PluginCallCancelled {
+                reason: self.cause.reason(),
+            }));
         }
         let task = &mut self.as_mut().task;
         let pinned = pin!(task);
         match pinned.poll(cx) {
+            std::task::Poll::Ready(Err(err)) if err.is_panic() => {
+                std::task::Poll::Ready(Err(Error::PluginCallError {
+                    name: self.name.clone(),
+                    message: format!("plugin call panicked: {}", panic_message(err.into_panic())),
+                }))
+            }
             std::task::Poll::Ready(r) => std::task::Poll::Ready(r?),
             std::task::Poll::Pending => std::task::Poll::Pending,
         }
     }
 }
+
+/// Returns the message of a panic payload, if the payload is a string
+#[inline]
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => String::from("panic payload is not a string"),
+        },
+    }
+}
+
+/// Collection of work that can be joined or cancelled as a group
+///
+/// Results are returned in the order the work was added, use `names` to attribute each result to the plugin that
+/// created the work
+#[derive(Default)]
+pub struct WorkSet {
+    /// Work in this set w/ the index of the work in the order it was added
+    work: Vec<(usize, Work)>,
+    /// Index assigned to the next work added to the set
+    next: usize,
+}
+
+impl WorkSet {
+    /// Returns a new empty work set
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds work to the set, returns the index of the work in the order it was added, see `WorkSet::join_next`
+    #[inline]
+    pub fn push(&mut self, work: Work) -> usize {
+        let index = self.next;
+        self.next += 1;
+        self.work.push((index, work));
+        index
+    }
+
+    /// Returns the number of work in the set
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.work.len()
+    }
+
+    /// Returns true if the set is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.work.is_empty()
+    }
+
+    /// Returns the names of the plugins that created each work, in the order the work was added
+    #[inline]
+    pub fn names(&self) -> Vec<&Name> {
+        self.work.iter().map(|(_, w)| w.name()).collect()
+    }
+
+    /// Cancels all work in the set
+    #[inline]
+    pub fn cancel_all(&self) {
+        for (_, work) in self.work.iter() {
+            work.cancel();
+        }
+    }
+
+    /// Waits for all work to complete and returns the results in the order the work was added
+    #[inline]
+    pub async fn join_all(self) -> Vec<crate::Result<()>> {
+        join_all(self.work.into_iter().map(|(_, w)| w)).await
+    }
+
+    /// Waits for the next work to complete and returns the index the work was added at w/ its result
+    ///
+    /// Unlike `any`, the remaining work is not cancelled and stays in the set. Returns None if the set is empty.
+    #[inline]
+    pub async fn join_next(&mut self) -> Option<(usize, crate::Result<()>)> {
+        if self.work.is_empty() {
+            return None;
+        }
+
+        let (result, position, _) = select_all(self.work.iter_mut().map(|(_, w)| w)).await;
+        let (index, _) = self.work.remove(position);
+        Some((index, result))
+    }
+
+    /// Waits for the first work to complete and returns its result
+    ///
+    /// **Note**: The remaining work is cancelled. Returns `Ok(())` if the set is empty.
+    #[inline]
+    pub async fn any(self) -> crate::Result<()> {
+        if self.work.is_empty() {
+            return Ok(());
+        }
+
+        let (result, _, remaining) = select_all(self.work.into_iter().map(|(_, w)| w)).await;
+        for work in remaining {
+            work.cancel();
+        }
+        result
+    }
+}
+
+impl FromIterator<Work> for WorkSet {
+    fn from_iter<T: IntoIterator<Item = Work>>(iter: T) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<Work> for WorkSet {
+    fn extend<T: IntoIterator<Item = Work>>(&mut self, iter: T) {
+        for work in iter {
+            self.push(work);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! Re-exports of the common traits and types needed to implement and call plugins
+//!
+//! ```rust,ignore
+//! use reality::prelude::*;
+//! ```
+//!
+//! **Note**: `runir` and `plugin` are included since code generated by the derive macros refers to them by path
+
+pub use crate::plugin;
+pub use crate::plugin::{
+    Address, Bind, Broker, Call, Event, Handler, MessageData, Name, State, Work,
+};
+pub use crate::repr::{Labels, Tags};
+pub use crate::runir;
+pub use crate::{BincodeContent, CallResult, Content, Error, Result, Uuid, Version};
+/// Traits and their derive macros
+pub use crate::{Plugin, Repr, Resource};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Plugin, Serialize)]
+    #[reality(call = call_prelude, content_from = BincodeContent)]
+    struct PreludePlugin;
+
+    fn call_prelude(bind: Bind<PreludePlugin>) -> CallResult {
+        bind.work(|_, _| async { Ok(()) })
+    }
+
+    #[tokio::test]
+    async fn test_prelude() {
+        let mut state = State::new();
+        let address = state.load(PreludePlugin, Labels::default());
+        state.call(&address).await.unwrap();
+    }
+}
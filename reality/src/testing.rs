@@ -0,0 +1,128 @@
+//! Helpers for testing plugins against the framework
+//!
+//! # Example
+//! ```ignore
+//! let mut harness = TestHarness::new();
+//! let address = harness.load_and_call(Counter::default()).await?;
+//! assert_eq!(1, harness.loaded::<Counter>(&address).count);
+//! ```
+
+use runir::repr::Labels;
+
+use crate::{plugin::Address, Plugin, Result, State};
+
+/// Wraps a state to reduce the setup needed to load, call, and inspect plugins in tests
+pub struct TestHarness {
+    /// State plugins are loaded into
+    state: State,
+}
+
+impl TestHarness {
+    /// Returns a new harness w/ an empty state
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            state: State::new(),
+        }
+    }
+
+    /// Returns the state of this harness
+    #[inline]
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Returns a mutable reference to the state of this harness
+    #[inline]
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    /// Loads a plugin w/o labels and returns the address of the plugin
+    #[inline]
+    pub fn load<P: Plugin>(&mut self, plugin: P) -> Address {
+        self.state.load(plugin, Labels::default())
+    }
+
+    /// Calls a loaded plugin and waits for the call to complete, see `State::call`
+    #[inline]
+    pub async fn call(&self, address: &Address) -> Result<()> {
+        self.state.call(address).await
+    }
+
+    /// Loads a plugin w/o labels and calls it, returning the address of the plugin once the call completes
+    ///
+    /// Returns an error if the plugin could not be loaded or returned an error when called
+    #[inline]
+    pub async fn load_and_call<P: Plugin>(&mut self, plugin: P) -> Result<Address> {
+        let address = self.state.try_load(plugin, Labels::default())?;
+        self.call(&address).await?;
+        Ok(address)
+    }
+
+    /// Returns a copy of the current state of a loaded plugin
+    ///
+    /// **Note**: Panics if a plugin of type `P` is not loaded at the address
+    #[inline]
+    pub fn loaded<P: Plugin + Clone>(&self, address: &Address) -> P {
+        match self
+            .state
+            .find_plugin(address)
+            .and_then(|item| item.borrow::<P>())
+        {
+            Some(plugin) => plugin.clone(),
+            None => panic!("plugin `{}` is not loaded at `{address}`", P::name()),
+        }
+    }
+}
+
+impl Default for TestHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{plugin::Bind, plugin::Work, BincodeContent, Content, Resource, Version};
+    use serde::Serialize;
+
+    #[derive(Clone, Default, Serialize)]
+    struct Counter {
+        count: u32,
+    }
+
+    impl Resource for Counter {}
+    impl Content for Counter {
+        fn state_uuid(&self) -> uuid::Uuid {
+            BincodeContent::new(self).unwrap().state_uuid()
+        }
+    }
+    impl Plugin for Counter {
+        fn call(bind: Bind<Self>) -> Result<Work> {
+            bind.defer(|mut b, _| async move {
+                b.plugin_mut()?.count += 1;
+                Ok(())
+            })
+        }
+
+        fn version() -> Version {
+            Version::new(0, 1, 0)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_harness_load_and_call() {
+        let mut harness = TestHarness::new();
+        let address = harness
+            .load_and_call(Counter::default())
+            .await
+            .expect("should call the plugin");
+        assert_eq!(1, harness.loaded::<Counter>(&address).count);
+
+        harness.call(&address).await.unwrap();
+        assert_eq!(2, harness.loaded::<Counter>(&address).count);
+        assert!(harness.state().is_loaded(&address));
+    }
+}
@@ -3,10 +3,15 @@ use repo::{Handle, Journal};
 use std::{collections::BTreeMap, sync::Arc};
 
 /// Maps attribute typtes to their commit id in the journal
+///
+/// More than one instance of an attribute type can be inserted, `get` returns the most recently inserted instance and
+/// `get_all` returns every instance
 #[derive(Clone)]
 pub struct Attributes {
     /// Map of associated attributes commits
     attrs: BTreeMap<u64, u64>,
+    /// Map of every commit inserted for each attribute type, in insertion order
+    all: BTreeMap<u64, Vec<u64>>,
     /// Journal for accessing attributes
     journal: Journal,
 }
@@ -17,18 +22,26 @@ impl Attributes {
     pub const fn new(journal: Journal) -> Self {
         Self {
             attrs: BTreeMap::new(),
+            all: BTreeMap::new(),
             journal,
         }
     }
 
     /// Inserts a new handle into attributes
+    ///
+    /// If an attribute of the same type was already inserted, the existing instance is kept and can be returned w/
+    /// `get_all`
     #[inline]
     pub fn insert<Attribute: Repr>(&mut self, handle: &Handle) {
-        self.attrs
-            .insert(Self::get_ty_bits::<Attribute>(), handle.commit());
+        let ty = Self::get_ty_bits::<Attribute>();
+        self.attrs.insert(ty, handle.commit());
+        let all = self.all.entry(ty).or_default();
+        if !all.contains(&handle.commit()) {
+            all.push(handle.commit());
+        }
     }
 
-    /// Gets an attribute
+    /// Gets an attribute, if more than one instance was inserted returns the most recently inserted instance
     #[inline]
     pub fn get<Attribute: Repr>(&self) -> Option<Arc<Attribute>> {
         self.attrs
@@ -36,6 +49,17 @@ impl Attributes {
             .and_then(|a| self.journal.get(*a).and_then(|r| r.cast::<Attribute>()))
     }
 
+    /// Gets every instance of an attribute in the order they were inserted
+    #[inline]
+    pub fn get_all<Attribute: Repr>(&self) -> Vec<Arc<Attribute>> {
+        self.all
+            .get(&Self::get_ty_bits::<Attribute>())
+            .into_iter()
+            .flatten()
+            .filter_map(|a| self.journal.get(*a).and_then(|r| r.cast::<Attribute>()))
+            .collect()
+    }
+
     /// Returns the hi bits for an attribute
     fn get_ty_bits<Attribute: Repr>() -> u64 {
         let (hi, _) = TyRepr::new::<Attribute>()
@@ -55,6 +79,13 @@ impl Content for Attributes {
             crc.update(&k.to_be_bytes());
             crc.update(&v.to_be_bytes());
         }
+        // Only types w/ more than one instance are included, so the state of single instance attributes is unchanged
+        for (k, all) in self.all.iter().filter(|(_, all)| all.len() > 1) {
+            crc.update(&k.to_be_bytes());
+            for v in all.iter() {
+                crc.update(&v.to_be_bytes());
+            }
+        }
         uuid::Uuid::from_u64_pair(crc.finalize(), 0)
     }
 }
\ No newline at end of file
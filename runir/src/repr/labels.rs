@@ -1,11 +1,31 @@
 use super::Repr;
 use crate::{Content, Resource};
-use std::{collections::BTreeMap, ops::{Deref, DerefMut}};
+use std::{collections::BTreeMap, ops::{Deref, DerefMut}, str::FromStr};
 
 /// Wrapper struct for a ordered label representation
 #[derive(Default, Debug)]
 pub struct Labels(pub BTreeMap<String, String>);
 
+impl Labels {
+    /// Merges labels from `other` into these labels
+    ///
+    /// **Note**: If both contain the same key, the value from `other` wins
+    #[inline]
+    pub fn merge(&mut self, other: &Labels) {
+        for (k, v) in other.iter() {
+            self.0.insert(k.to_string(), v.to_string());
+        }
+    }
+
+    /// Returns the value of a label parsed as `T`
+    ///
+    /// Returns None if the label is not set or could not be parsed
+    #[inline]
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.0.get(key).and_then(|v| v.parse().ok())
+    }
+}
+
 impl Repr for Labels {}
 impl Resource for Labels {}
 
@@ -74,6 +94,18 @@ mod tests {
         assert_eq!("rust string", labels.get("media-type").unwrap());
     }
 
+    #[test]
+    fn test_labels_merge() {
+        let mut labels = Labels::from(&[("env", "dev"), ("retries", "3")][..]);
+        labels.merge(&Labels::from(&[("env", "prod"), ("team", "runplat")][..]));
+
+        assert_eq!("prod", labels.get("env").unwrap());
+        assert_eq!("runplat", labels.get("team").unwrap());
+        assert_eq!(Some(3), labels.get_parsed::<u32>("retries"));
+        assert_eq!(None, labels.get_parsed::<u32>("env"));
+        assert_eq!(None, labels.get_parsed::<u32>("missing"));
+    }
+
     #[test]
     fn test_labels_from_btree() {
         let mut store = Store::new();
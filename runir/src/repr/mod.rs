@@ -1,12 +1,14 @@
 mod attribute;
 mod labels;
 pub mod repo;
+mod tags;
 mod ty;
 use crate::{Content, Resource};
 pub use attribute::Attributes;
 pub use labels::Labels;
 pub use repo::Repo;
 use std::{any::TypeId, borrow::Cow, fmt::Debug, pin::Pin, sync::Arc};
+pub use tags::Tags;
 pub use ty::TyRepr;
 
 /// Enumeration of identifier variants
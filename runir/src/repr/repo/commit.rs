@@ -69,7 +69,7 @@ impl<'op, R: Repr> Commit<'op, R> {
         let commit = hi ^ lo;
         let head = Head::new(commit, self.repr);
         let handle = head.handle();
-        self.repo.journal.log(handle.clone());
+        self.repo.log(handle.clone());
         handle
     }
 }
@@ -132,6 +132,30 @@ mod tests {
         assert!(recalled.unwrap().cast::<TyRepr>().is_some());
     }
 
+    #[test]
+    fn test_try_checkout() {
+        let mut repo = Repo::new();
+        let handle = repo.commit(TyRepr::new::<String>()).finish();
+        let commit = handle.commit();
+        assert!(repo.try_checkout(commit).is_ok());
+        assert_eq!(
+            Some(RepoError::NotFound { commit: commit ^ 1 }),
+            repo.try_checkout(commit ^ 1).err()
+        );
+
+        assert!(repo.prune(commit).is_some());
+        assert!(repo.prune(commit).is_none());
+        assert_eq!(
+            Some(RepoError::Pruned { commit }),
+            repo.try_checkout(commit).err()
+        );
+        assert!(repo.checkout(commit).is_none());
+
+        // Committing the representation again clears the tombstone
+        let _ = repo.commit(TyRepr::new::<String>()).finish();
+        assert!(repo.try_checkout(commit).is_ok());
+    }
+
     struct TestRepr {
         value: usize,
     }
@@ -0,0 +1,25 @@
+/// Enumeration of errors returned when checking out a representation from a repo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RepoError {
+    /// A representation was never committed to the repo w/ this commit id
+    NotFound {
+        /// Commit id that was checked out
+        commit: u64,
+    },
+    /// A representation was committed to the repo w/ this commit id, but has since been pruned
+    Pruned {
+        /// Commit id that was checked out
+        commit: u64,
+    },
+}
+
+impl std::fmt::Display for RepoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepoError::NotFound { commit } => write!(f, "Commit {commit:x} was not found"),
+            RepoError::Pruned { commit } => write!(f, "Commit {commit:x} was pruned"),
+        }
+    }
+}
+
+impl std::error::Error for RepoError {}
@@ -1,7 +1,7 @@
 use super::Handle;
 use crate::Resource;
 use std::{
-    collections::BTreeMap,
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
     ops::Deref,
     sync::{Arc, Mutex},
 };
@@ -29,6 +29,12 @@ impl Journal {
         self.log.record(&handle)
     }
 
+    /// Logs a batch of handles while holding the journal lock once
+    #[inline]
+    pub fn log_all(&self, handles: impl IntoIterator<Item = Handle>) {
+        self.log.record_all(handles)
+    }
+
     /// Returns a repr handle mapped to a link value
     ///
     /// Returns None if the handle has not been journaled
@@ -37,6 +43,20 @@ impl Journal {
         self.log.snapshot().get(&link).cloned()
     }
 
+    /// Removes a handle from the journal and records the link as pruned
+    ///
+    /// Returns the removed handle, or None if the handle has not been journaled
+    #[inline]
+    pub fn prune(&self, link: u64) -> Option<Handle> {
+        self.log.remove(link)
+    }
+
+    /// Returns true if the handle mapped to a link value was pruned and has not been journaled since
+    #[inline]
+    pub fn is_pruned(&self, link: u64) -> bool {
+        self.log.is_pruned(link)
+    }
+
     /// Returns a snapshot of the underlying logs
     #[inline]
     pub fn logs(&self) -> Arc<BTreeMap<u64, Handle>> {
@@ -54,6 +74,8 @@ pub struct Log {
 struct LogState {
     snapshot: LogSnapshot,
     recorded: BTreeMap<u64, Handle>,
+    /// Tombstones of links that were removed from the log
+    pruned: BTreeSet<u64>,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -82,11 +104,56 @@ impl Log {
                 Err(error) => error.into_inner(),
             };
             state.recorded.insert(commit, handle.clone());
+            state.pruned.remove(&commit);
             state.snapshot = LogSnapshot(Arc::new(state.recorded.clone()));
             commit
         }
     }
 
+    /// Records a batch of handles, the snapshot is only updated once after all handles are recorded
+    #[inline]
+    fn record_all(&self, handles: impl IntoIterator<Item = Handle>) {
+        let mut state = match self.sync().lock() {
+            Ok(state) => state,
+            Err(error) => error.into_inner(),
+        };
+        let mut changed = false;
+        for handle in handles {
+            let commit = handle.commit();
+            if let Entry::Vacant(entry) = state.recorded.entry(commit) {
+                entry.insert(handle);
+                state.pruned.remove(&commit);
+                changed = true;
+            }
+        }
+        if changed {
+            state.snapshot = LogSnapshot(Arc::new(state.recorded.clone()));
+        }
+    }
+
+    /// Removes a handle and records a tombstone for its link value
+    #[inline]
+    fn remove(&self, link: u64) -> Option<Handle> {
+        let mut state = match self.sync().lock() {
+            Ok(state) => state,
+            Err(error) => error.into_inner(),
+        };
+        let removed = state.recorded.remove(&link)?;
+        state.pruned.insert(link);
+        state.snapshot = LogSnapshot(Arc::new(state.recorded.clone()));
+        Some(removed)
+    }
+
+    /// Returns true if a tombstone was recorded for a link value
+    #[inline]
+    fn is_pruned(&self, link: u64) -> bool {
+        let state = match self.sync().lock() {
+            Ok(state) => state,
+            Err(error) => error.into_inner(),
+        };
+        state.pruned.contains(&link)
+    }
+
     /// Returns a reference to the inner map
     #[inline]
     fn snapshot(&self) -> LogSnapshot {
@@ -1,11 +1,13 @@
 mod add;
 mod commit;
+mod error;
 mod handle;
 mod head;
 mod journal;
 
 pub use add::Add;
 pub use commit::Commit;
+pub use error::RepoError;
 pub use handle::Handle;
 pub use journal::Journal;
 
@@ -17,6 +19,8 @@ use head::Head;
 pub struct Repo {
     /// Journal storing repr's that have been checked out
     pub(crate) journal: Journal,
+    /// Handles committed during a batch that have not been logged to the journal yet
+    pending: Option<Vec<Handle>>,
 }
 
 impl Repo {
@@ -28,6 +32,7 @@ impl Repo {
     pub fn new() -> Self {
         Self {
             journal: Journal::new(),
+            pending: None,
         }
     }
 
@@ -45,10 +50,62 @@ impl Repo {
         }
     }
 
+    /// Begins a batch, handles committed until `end_batch` is called are logged to the journal at once
+    ///
+    /// **Note**: Handles committed during a batch cannot be checked out until the batch ends
+    #[inline]
+    pub(crate) fn begin_batch(&mut self) {
+        self.pending.get_or_insert_with(Vec::new);
+    }
+
+    /// Ends a batch and logs all pending handles to the journal
+    #[inline]
+    pub(crate) fn end_batch(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            self.journal.log_all(pending);
+        }
+    }
+
+    /// Logs a handle to the journal, or defers logging if a batch is in progress
+    #[inline]
+    fn log(&mut self, handle: Handle) {
+        match self.pending.as_mut() {
+            Some(pending) => pending.push(handle),
+            None => {
+                self.journal.log(handle);
+            }
+        }
+    }
+
     /// Returns a committed representation stored in this repo
+    ///
+    /// Returns None if the commit does not exist, use `try_checkout` to find out why
     #[inline]
     pub fn checkout(&self, commit: u64) -> Option<Handle> {
-        self.journal.get(commit)
+        self.try_checkout(commit).ok()
+    }
+
+    /// Returns a committed representation stored in this repo
+    ///
+    /// Returns `RepoError::Pruned` if the commit was removed w/ `prune`, otherwise `RepoError::NotFound` if the
+    /// commit does not exist
+    #[inline]
+    pub fn try_checkout(&self, commit: u64) -> Result<Handle, RepoError> {
+        match self.journal.get(commit) {
+            Some(handle) => Ok(handle),
+            None if self.journal.is_pruned(commit) => Err(RepoError::Pruned { commit }),
+            None => Err(RepoError::NotFound { commit }),
+        }
+    }
+
+    /// Removes a committed representation from this repo
+    ///
+    /// The commit id is remembered so that checking it out returns `RepoError::Pruned` until it is committed again.
+    ///
+    /// Returns the removed handle, or None if the commit does not exist
+    #[inline]
+    pub fn prune(&mut self, commit: u64) -> Option<Handle> {
+        self.journal.prune(commit)
     }
 
     /// Begins an assign operation for use with a `Store`
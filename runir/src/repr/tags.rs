@@ -0,0 +1,86 @@
+use super::Repr;
+use crate::{Content, Resource};
+use std::{
+    collections::BTreeSet,
+    ops::{Deref, DerefMut},
+};
+
+/// Wrapper struct for an ordered set of tags
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Tags(pub BTreeSet<String>);
+
+impl Tags {
+    /// Returns true if all of the tags in `other` are also in these tags
+    #[inline]
+    pub fn contains_all(&self, other: &Tags) -> bool {
+        self.0.is_superset(&other.0)
+    }
+
+    /// Returns true if any of the tags in `other` are also in these tags
+    #[inline]
+    pub fn contains_any(&self, other: &Tags) -> bool {
+        !self.0.is_disjoint(&other.0)
+    }
+}
+
+impl Repr for Tags {}
+impl Resource for Tags {}
+
+impl From<&[&str]> for Tags {
+    fn from(value: &[&str]) -> Self {
+        Tags(value.iter().map(|t| t.to_string()).collect())
+    }
+}
+
+impl From<BTreeSet<String>> for Tags {
+    fn from(value: BTreeSet<String>) -> Self {
+        Tags(value)
+    }
+}
+
+impl Deref for Tags {
+    type Target = BTreeSet<String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Tags {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Content for Tags {
+    fn state_uuid(&self) -> uuid::Uuid {
+        let mut crc = crate::content::crc().digest();
+        for t in self.0.iter() {
+            crc.update(t.as_bytes());
+        }
+        uuid::Uuid::from_u64_pair(crc.finalize(), 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{repr::Attributes, Store};
+
+    #[test]
+    fn test_tags() {
+        let mut store = Store::new();
+
+        let handle = store
+            .put(String::from("hello world"))
+            .attr(Tags::from(&["smoke-test", "string"][..]))
+            .commit();
+
+        let attributes = handle.cast::<Attributes>().unwrap();
+        let tags = attributes.get::<Tags>().unwrap();
+        assert!(tags.contains("smoke-test"));
+        assert!(tags.contains_all(&Tags::from(&["string"][..])));
+        assert!(tags.contains_any(&Tags::from(&["string", "other"][..])));
+        assert!(!tags.contains_all(&Tags::from(&["string", "other"][..])));
+    }
+}
@@ -2,7 +2,11 @@ use crate::{repo::Journal, repr::Attributes, Resource};
 use std::{
     any::TypeId,
     pin::Pin,
-    sync::{Arc, RwLock}
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
 };
 
 use super::{observe::Observable, ObservationEvent};
@@ -20,12 +24,18 @@ pub struct Item {
     cell: ResourceCell,
     /// Type-id of the stored resource (TODO: Can store this in attributes)
     type_id: TypeId,
+    /// Type name of the stored resource
+    type_name: &'static str,
     /// Handle associated with this item and to the resource's attribute map
     journal: Journal,
     /// Commit id
     commit: u64,
     /// Optional, observation event
     observe: Option<ObservationEvent>,
+    /// Set if the resource was being mutated by a task that panicked, see `Item::poison_guard`
+    poisoned: Arc<AtomicBool>,
+    /// When this item expires, see `Put::ttl`
+    expires_at: Option<Instant>,
 }
 
 impl Item {
@@ -36,23 +46,54 @@ impl Item {
         Self {
             cell: Arc::new(RwLock::new(Box::pin(resource))),
             type_id,
+            type_name: std::any::type_name::<R>(),
             journal,
             commit,
             observe: None,
+            poisoned: Arc::new(AtomicBool::new(false)),
+            expires_at: None,
         }
     }
 
+    /// Sets this item to expire once `ttl` elapses
+    #[inline]
+    pub(crate) fn expire_after(mut self, ttl: Duration) -> Self {
+        self.expires_at = Some(Instant::now() + ttl);
+        self
+    }
+
+    /// Returns when this item expires, or None if the item does not expire
+    #[inline]
+    pub fn expires_at(&self) -> Option<Instant> {
+        self.expires_at
+    }
+
+    /// Returns true if this item has expired
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|e| e <= Instant::now())
+    }
+
     /// Returns the commit id of this item
     #[inline]
     pub fn commit(&self) -> u64 {
         self.commit
     }
 
+    /// Returns the type name of the stored resource
+    ///
+    /// **Note**: The format of the name is not guaranteed, see `std::any::type_name`
+    #[inline]
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
     /// Returns attributes for this item
     ///
     /// If an item exists, it means that it will have an associated "Attributes" store which points to various attributes owned by this resource.
     ///
-    /// **Note**: An item currently, may only store a single type of each attribute.
+    /// **Note**: If an item stores more than one instance of an attribute type, `Attributes::get` returns the most
+    /// recently added instance, use `Attributes::get_all` to return every instance.
     pub fn attributes(&self) -> Arc<Attributes> {
         self.journal
             .get(self.commit)
@@ -71,6 +112,38 @@ impl Item {
         }
     }
 
+    /// Creates an observable that notifies an existing observation event
+    ///
+    /// Unlike `observe`, listeners that already hold a clone of `event` will receive changes made through the
+    /// returned observable
+    pub fn observe_with(&self, event: ObservationEvent) -> Observable {
+        let mut inner = self.clone();
+        inner.observe = Some(event.clone());
+        Observable {
+            inner,
+            event,
+        }
+    }
+
+    /// Returns a new item w/ a deep copy of the inner resource
+    ///
+    /// Unlike `clone`, the returned item does not share the resource cell w/ this item, so changes to either item are not
+    /// visible to the other. The returned item keeps the same commit, so attributes are still shared.
+    ///
+    /// Returns None if `T` does not match the stored resource
+    pub fn isolate<T: Resource + Clone>(&self) -> Option<Item> {
+        self.borrow::<T>().cloned().map(|resource| Self {
+            cell: Arc::new(RwLock::new(Box::pin(resource))),
+            type_id: self.type_id,
+            type_name: self.type_name,
+            journal: self.journal.clone(),
+            commit: self.commit,
+            observe: None,
+            poisoned: Arc::new(AtomicBool::new(self.is_poisoned())),
+            expires_at: self.expires_at,
+        })
+    }
+
     /// Borrows and casts a mutable reference for the inner resource
     ///
     /// Returns None if `T` does not match the stored resource
@@ -95,17 +168,20 @@ impl Item {
 
     /// Borrows and casts a reference for teh inner resource
     ///
+    /// **Note**: The lock is released before the reference is returned, so the reference must not be held while the
+    /// resource is mutated w/ `borrow_mut`
+    ///
     /// Returns None if `T` does not match the stored resource
     pub fn borrow<T: Resource>(&self) -> Option<&T> {
         if std::any::TypeId::of::<T>() == self.type_id {
-            let mut resource = match self.cell.write() {
+            let resource = match self.cell.read() {
                 Ok(guard) => guard,
                 Err(err) => err.into_inner(),
             };
 
-            let resource = resource.as_mut();
+            let resource = resource.as_ref();
             unsafe {
-                let inner = Pin::into_inner_unchecked(resource);
+                let inner = Pin::get_ref(resource);
                 let cast = cast_ref(inner).cast::<T>();
                 cast.as_ref()
             }
@@ -114,6 +190,25 @@ impl Item {
         }
     }
 
+    /// Returns true if a panic occurred while the resource was being mutated, in which case the resource may be in an
+    /// inconsistent state
+    ///
+    /// **Note**: `borrow` and `borrow_mut` recover the resource of a poisoned item, so this must be checked explicitly
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.cell.is_poisoned() || self.poisoned.load(Ordering::Acquire)
+    }
+
+    /// Returns a guard that poisons the resource if the guard is dropped while the thread is panicking
+    ///
+    /// The guard should be held for as long as the resource is being mutated, see `Item::is_poisoned`
+    #[inline]
+    pub fn poison_guard(&self) -> PoisonGuard {
+        PoisonGuard {
+            poisoned: self.poisoned.clone(),
+        }
+    }
+
     /// Returns true if this item matches the resource
     #[inline]
     pub fn is_type<T: Resource>(&self) -> bool {
@@ -127,6 +222,20 @@ impl Item {
     }
 }
 
+/// Guard that poisons the resource of an item if it is dropped while the thread is panicking, see `Item::poison_guard`
+pub struct PoisonGuard {
+    /// Poisoned flag of the item
+    poisoned: Arc<AtomicBool>,
+}
+
+impl Drop for PoisonGuard {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.poisoned.store(true, Ordering::Release);
+        }
+    }
+}
+
 /// Casts a mutable reference to a raw mutable pointer
 fn cast_mut_ref<T: ?Sized>(r: &mut T) -> *mut T {
     r
@@ -157,6 +266,16 @@ mod tests {
         assert_eq!("HELLO WORLDtest", item);
     }
 
+    #[test]
+    fn test_item_type_name() {
+        let mut store = Store::new();
+        let handle = store.put(String::from("HELLO WORLD")).commit();
+
+        let item = store.item(handle.commit()).unwrap();
+        assert_eq!(handle.commit(), item.commit());
+        assert_eq!(std::any::type_name::<String>(), item.type_name());
+    }
+
     #[test]
     fn test_item_attributes() {
         let mut store = Store::new();
@@ -200,6 +319,37 @@ mod tests {
         assert_eq!(3, last_state.version);
     }
 
+    #[tokio::test]
+    async fn test_item_observe_wait_async() {
+        let mut store = Store::new();
+        let handle = store.put(String::from("HELLO WORLD")).commit();
+
+        let item = store.item(handle.commit()).unwrap().clone();
+        let mut observable = item.observe();
+        let mut observe = observable.event();
+        let mut timeout = observable.event();
+        let changes = tokio::spawn(async move {
+            let mut last_state = observe.wait_async().await;
+            while last_state.progress < 100 {
+                last_state = observe.wait_async().await;
+            }
+            last_state
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        observable.notify_change_with_message("change happend");
+        observable.notify_change_with_progress(100);
+        let last_state = changes.await.unwrap();
+        assert_eq!("change happend", last_state.message);
+        assert_eq!(2, last_state.version);
+
+        timeout.timeout(Duration::from_millis(100));
+        let state = timeout.wait_async().await;
+        assert_eq!(2, state.version);
+        let state = timeout.wait_async().await;
+        assert_eq!(2, state.version);
+    }
+
     #[test]
     fn test_item_borrow_resource_multi_thread_observe_timeout() {
         let mut store = Store::new();
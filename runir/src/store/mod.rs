@@ -2,30 +2,58 @@ mod item;
 mod observe;
 mod put;
 
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, RwLock},
+};
+use tokio::sync::broadcast;
 
 pub use item::Item;
+pub use item::PoisonGuard;
 
+pub use observe::CurrentState;
+pub use observe::Observable;
 pub use observe::ObservationEvent;
 
 pub use put::Put;
 
 use crate::{
+    repo::Handle,
     repr::{Attributes, Identifier, Labels, Repo},
-    Resource,
+    Content, Resource,
 };
 
+/// Capacity of the channel used to publish commit ids of inserted items
+const INSERT_EVENT_CAPACITY: usize = 64;
+
 /// Represents resources consumed by the application in a single map
 ///
 /// ## Considerations
 /// - When cloning a store directly, you capture a reference with the current mapped items. Although, the map
-///   will not be updated, the items can be updated by a different owner.
+///   will not be updated, the items can be updated by a different owner. Items removed w/ `Store::evict` are removed
+///   from every clone.
+/// - `Item::borrow` and `Item::borrow_mut` only lock the resource cell while the resource is cast, the returned
+///   reference is not guarded by the lock. A reference from `borrow` must not be held while the same resource is
+///   mutated w/ `borrow_mut`, including from another thread.
 #[derive(Clone)]
 pub struct Store {
     /// Items in the store
     items: BTreeMap<u64, Item>,
     /// Repository of resource representation data
     repo: Repo,
+    /// Sender used to publish the commit id of each item added to the store
+    inserts: broadcast::Sender<u64>,
+    /// Commit ids of items evicted from any clone of this store
+    evicted: Arc<RwLock<BTreeSet<u64>>>,
+}
+
+/// Counts of entries held by a store
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StoreStats {
+    /// Number of items in the store
+    pub item_count: usize,
+    /// Number of representations logged in the store's journal
+    pub journal_entries: usize,
 }
 
 impl Store {
@@ -35,6 +63,8 @@ impl Store {
         Self {
             repo: Repo::new(),
             items: BTreeMap::new(),
+            inserts: broadcast::channel(INSERT_EVENT_CAPACITY).0,
+            evicted: Arc::new(RwLock::new(BTreeSet::new())),
         }
     }
 
@@ -49,13 +79,121 @@ impl Store {
             ident: Identifier::Unit,
             attributes: Attributes::new(journal),
             labels: Labels(BTreeMap::new()),
+            ttl: None,
+            keep_existing: false,
+        }
+    }
+
+    /// Puts and commits a batch of resources into the store
+    ///
+    /// Commits are identical to calling `put(..).commit()` for each resource, except the journal lock is only taken once
+    /// for the entire batch
+    #[inline]
+    pub fn put_all<R: Resource + Content>(
+        &mut self,
+        resources: impl IntoIterator<Item = R>,
+    ) -> Vec<Handle> {
+        self.repo.begin_batch();
+        let handles = resources
+            .into_iter()
+            .map(|resource| self.put(resource).commit())
+            .collect();
+        self.repo.end_batch();
+        handles
+    }
+
+    /// Returns a receiver for the commit id of each item added to the store by `put(..).commit()`
+    ///
+    /// Only items committed after subscribing are received, and committing a resource that is already in the store
+    /// does not publish its commit id again.
+    ///
+    /// **Note**: Clones of a store share the same channel, and a receiver that falls more than 64 commits behind will
+    /// receive `RecvError::Lagged`
+    #[inline]
+    pub fn on_insert(&self) -> broadcast::Receiver<u64> {
+        self.inserts.subscribe()
+    }
+
+    /// Returns counts of the entries held by this store
+    #[inline]
+    pub fn stats(&self) -> StoreStats {
+        let evicted = self.evicted();
+        StoreStats {
+            item_count: self.items.keys().filter(|c| !evicted.contains(c)).count(),
+            journal_entries: self.repo.journal.logs().len(),
         }
     }
 
     /// Returns an item in the store mapped to the commit id
     #[inline]
     pub fn item(&self, commit: u64) -> Option<&Item> {
-        self.items.get(&commit)
+        self.items.get(&commit).filter(|_| !self.is_evicted(commit))
+    }
+
+    /// Returns true if an item is mapped to the commit id
+    #[inline]
+    pub fn contains(&self, commit: u64) -> bool {
+        self.items.contains_key(&commit) && !self.is_evicted(commit)
+    }
+
+    /// Returns the commit id of each item that has expired, see `Put::ttl`
+    #[inline]
+    pub fn expired(&self) -> Vec<u64> {
+        let evicted = self.evicted();
+        self.items
+            .iter()
+            .filter(|(commit, item)| item.is_expired() && !evicted.contains(commit))
+            .map(|(commit, _)| *commit)
+            .collect()
+    }
+
+    /// Removes the item mapped to the commit id from the store and returns it
+    ///
+    /// **Note**: The representations of the item are not removed from the journal, and clones of the item are not
+    /// affected
+    #[inline]
+    pub fn remove(&mut self, commit: u64) -> Option<Item> {
+        let evicted = self.is_evicted(commit);
+        self.items.remove(&commit).filter(|_| !evicted)
+    }
+
+    /// Removes the item mapped to the commit id from this store and every clone of it
+    ///
+    /// Unlike `remove`, this does not require a mutable reference, so that items can be evicted by a task that holds a
+    /// clone of the store. The item is dropped from the map of each clone the next time `remove` is called for it,
+    /// committing the same resource again adds it back to the store.
+    ///
+    /// Returns false if the item was already evicted
+    #[inline]
+    pub fn evict(&self, commit: u64) -> bool {
+        match self.evicted.write() {
+            Ok(mut g) => g.insert(commit),
+            Err(e) => e.into_inner().insert(commit),
+        }
+    }
+
+    /// Returns true if the item mapped to the commit id was evicted, see `Store::evict`
+    #[inline]
+    fn is_evicted(&self, commit: u64) -> bool {
+        self.evicted().contains(&commit)
+    }
+
+    /// Locks the commit ids of evicted items for reading
+    #[inline]
+    fn evicted(&self) -> std::sync::RwLockReadGuard<'_, BTreeSet<u64>> {
+        match self.evicted.read() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        }
+    }
+
+    /// Clears the eviction of an item when it is committed again
+    #[inline]
+    fn restore(&self, commit: u64) {
+        match self.evicted.write() {
+            Ok(mut g) => g.remove(&commit),
+            Err(e) => e.into_inner().remove(&commit),
+        };
     }
 }
 
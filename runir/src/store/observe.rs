@@ -4,6 +4,7 @@ use std::{
         Arc, Condvar, Mutex, MutexGuard,
     }, time::{Duration, Instant}
 };
+use tokio::sync::Notify;
 use crate::Resource;
 use super::Item;
 
@@ -39,41 +40,48 @@ impl Observable {
     /// Notifies listeners that the event has started
     #[inline]
     pub fn notify_start(&mut self) {
-        let (mut state, v) = self.notify();
+        let (mut state, v, n) = self.notify();
         state.start = Some(Instant::now());
         v.notify_all();
         drop(state);
+        n.notify_waiters();
     }
 
     /// Notifies a change has occurred and updates the message
     #[inline]
     pub fn notify_change_with_message(&mut self, message: impl Into<String>) {
-        let (mut state, v) = self.notify();
+        let (mut state, v, n) = self.notify();
         state.message = message.into();
         v.notify_all();
         drop(state);
+        n.notify_waiters();
     }
 
     /// Notifies a change has occurred and updates progress
     #[inline]
     pub fn notify_change_with_progress(&mut self, progress: u64) {
-        let (mut state, v) = self.notify();
+        let (mut state, v, n) = self.notify();
         state.progress = progress;
         v.notify_all();
         drop(state);
+        n.notify_waiters();
     }
 
     /// Notifies a change has occurred
     #[inline]
     pub fn notify_change(&mut self) {
-        let (state, v) = self.notify();
+        let (state, v, n) = self.notify();
         v.notify_all();
         drop(state);
+        n.notify_waiters();
     }
 
     /// Begins a notification and increments the version
+    ///
+    /// Returns the condvar to signal sync waiters, and the notify to signal async waiters after the state guard is
+    /// dropped
     #[inline]
-    fn notify(&mut self) -> (MutexGuard<'_, ObvservationState>, &Condvar) {
+    fn notify(&mut self) -> (MutexGuard<'_, ObvservationState>, &Condvar, &Notify) {
         let sync = &*self.event.sync;
         let g = match sync.0.lock() {
             Ok(g) => g,
@@ -81,7 +89,7 @@ impl Observable {
         };
         g.version
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        (g, &sync.1)
+        (g, &sync.1, &sync.2)
     }
 }
 
@@ -109,7 +117,9 @@ pub struct CurrentState {
 }
 
 /// Type-alias for a synchronization context
-type SyncContext = Arc<(Mutex<ObvservationState>, Condvar)>;
+///
+/// **Note**: The condvar signals waiters blocking a thread, and the notify signals waiters in async code
+type SyncContext = Arc<(Mutex<ObvservationState>, Condvar, Notify)>;
 
 /// Observation state shared by observer and observed
 pub(crate) struct ObvservationState {
@@ -143,6 +153,7 @@ impl ObservationEvent {
                     start: None,
                 }),
                 Condvar::new(),
+                Notify::new(),
             )),
             timeout: None,
             last_version: 0,
@@ -159,7 +170,7 @@ impl ObservationEvent {
     ///
     /// Returns true after a change has occurred
     pub fn wait(&mut self) -> CurrentState {
-        let sync = &*self.sync;
+        let sync = self.sync.clone();
         let guard = match sync.0.lock() {
             Ok(g) => g,
             Err(err) => err.into_inner(),
@@ -183,6 +194,59 @@ impl ObservationEvent {
             }
         };
 
+        self.current(&g)
+    }
+
+    /// Waits for a modification from the observed item w/o blocking the current thread
+    ///
+    /// Unlike `wait`, this can be used from async code such as a plugin call. If a timeout is set, the current state
+    /// is returned after the timeout elapses.
+    pub async fn wait_async(&mut self) -> CurrentState {
+        let sync = self.sync.clone();
+        let last_state = self.last_version;
+        let changed = async {
+            loop {
+                let notified = sync.2.notified();
+                tokio::pin!(notified);
+                // Register before checking the version so that a notification sent in between is not missed
+                notified.as_mut().enable();
+                let version = self
+                    .lock()
+                    .version
+                    .load(std::sync::atomic::Ordering::Relaxed);
+                if last_state != version {
+                    break;
+                }
+                notified.await;
+            }
+        };
+
+        if let Some(timeout) = self.timeout {
+            let _ = tokio::time::timeout(timeout, changed).await;
+        } else {
+            changed.await;
+        }
+
+        let sync = self.sync.clone();
+        let g = match sync.0.lock() {
+            Ok(g) => g,
+            Err(err) => err.into_inner(),
+        };
+        self.current(&g)
+    }
+
+    /// Locks the observation state
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, ObvservationState> {
+        match self.sync.0.lock() {
+            Ok(g) => g,
+            Err(err) => err.into_inner(),
+        }
+    }
+
+    /// Updates the last version observed and returns the current state
+    #[inline]
+    fn current(&mut self, g: &ObvservationState) -> CurrentState {
         self.last_version = g.version.load(std::sync::atomic::Ordering::Relaxed);
 
         CurrentState {
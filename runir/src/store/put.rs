@@ -1,5 +1,6 @@
 use super::*;
 use crate::{repo::Handle, repr::{Labels, Repr}, Content};
+use std::time::Duration;
 
 /// Constructs a "put" operation to the store
 pub struct Put<'put, R> {
@@ -13,6 +14,10 @@ pub struct Put<'put, R> {
     pub(crate) ident: Identifier<'put>,
     /// Labels to store with these attributes
     pub(crate) labels: Labels,
+    /// If set, the item expires once this duration elapses after it is committed
+    pub(crate) ttl: Option<Duration>,
+    /// If true, an item already committed w/ the same commit id is kept instead of being replaced
+    pub(crate) keep_existing: bool,
 }
 
 impl<'put, R: Resource + Content> Put<'put, R> {
@@ -36,6 +41,8 @@ impl<'put, R: Resource + Content> Put<'put, R> {
     }
 
     /// Adds an attribute for this resource
+    ///
+    /// Adding more than one attribute of the same type keeps each instance, see `Attributes::get_all`
     #[inline]
     pub fn attr<Attr: Repr + Content>(mut self, attr: Attr) -> Self {
         let handle = self
@@ -48,6 +55,24 @@ impl<'put, R: Resource + Content> Put<'put, R> {
         self
     }
 
+    /// Sets the time-to-live of the item, the item expires once `ttl` elapses after the resource is committed
+    ///
+    /// **Note**: Expired items are not removed from the store automatically, see `Store::expired`
+    #[inline]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Keeps the item already in the store if the resource commits to an existing commit id
+    ///
+    /// By default, committing a resource replaces the existing item w/ the same commit id
+    #[inline]
+    pub fn keep_existing(mut self) -> Self {
+        self.keep_existing = true;
+        self
+    }
+
     /// Applies an identifier for this resource
     #[inline]
     pub fn ident(&mut self, ident: impl Into<Identifier<'put>>) -> &mut Self {
@@ -75,15 +100,28 @@ impl<'put, R: Resource + Content> Put<'put, R> {
             .assign(self.attributes, &self.resource)
             .ident(self.ident.clone())
             .complete();
+        if self.keep_existing && self.store.contains(handle.commit()) {
+            return handle;
+        }
 
-        self.store.items.insert(
+        let mut item = Item::new(
+            self.store.repo.journal.clone(),
             handle.commit(),
-            Item::new(
-                self.store.repo.journal.clone(),
-                handle.commit(),
-                self.resource,
-            ),
+            self.resource,
         );
+        if let Some(ttl) = self.ttl {
+            item = item.expire_after(ttl);
+        }
+        let replaced = self
+            .store
+            .items
+            .insert(handle.commit(), item)
+            .filter(|_| !self.store.is_evicted(handle.commit()));
+        self.store.restore(handle.commit());
+        if replaced.is_none() {
+            // Only fails if there are no receivers
+            let _ = self.store.inserts.send(handle.commit());
+        }
         handle
     }
 }
@@ -106,4 +144,125 @@ mod tests {
         let ty_repr = attributes.get::<TyRepr>().expect("should have a ty_repr");
         assert_eq!(ty_repr.as_ref(), &TyRepr::new::<u64>())
     }
+
+    #[test]
+    fn test_put_resource_add_multiple_attrs() {
+        let mut store = Store::new();
+
+        let handle = store
+            .put(String::from("hello world"))
+            .attr(TyRepr::new::<u64>())
+            .attr(TyRepr::new::<String>())
+            .commit();
+
+        let attributes = handle.cast::<Attributes>().expect("should have attributes");
+        let ty_repr = attributes.get::<TyRepr>().expect("should have a ty_repr");
+        assert_eq!(ty_repr.as_ref(), &TyRepr::new::<String>());
+        let all = attributes.get_all::<TyRepr>();
+        assert_eq!(
+            vec![&TyRepr::new::<u64>(), &TyRepr::new::<String>()],
+            all.iter().map(|t| t.as_ref()).collect::<Vec<_>>()
+        );
+        assert_eq!(1, attributes.get_all::<Labels>().len());
+
+        // The attributes are part of the commit of the resource
+        let single = Store::new()
+            .put(String::from("hello world"))
+            .attr(TyRepr::new::<String>())
+            .commit();
+        assert_ne!(single.commit(), handle.commit());
+    }
+
+    #[test]
+    fn test_put_all() {
+        let mut store = Store::new();
+        let expected = Store::new().put(String::from("a")).commit();
+
+        let handles = store.put_all([String::from("a"), String::from("b")]);
+        assert_eq!(2, handles.len());
+        assert_eq!(2, store.stats().item_count);
+        assert!(store.stats().journal_entries >= 2);
+        assert_eq!(expected.commit(), handles[0].commit());
+        for handle in handles {
+            let item = store.item(handle.commit()).expect("should have an item");
+            assert!(item.attributes().get::<Labels>().is_some());
+        }
+    }
+
+    #[test]
+    fn test_put_ttl() {
+        let mut store = Store::new();
+        let expiring = store
+            .put(String::from("a"))
+            .ttl(Duration::from_millis(10))
+            .commit();
+        let kept = store.put(String::from("b")).commit();
+        assert!(store.expired().is_empty());
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(vec![expiring.commit()], store.expired());
+        assert!(store.item(kept.commit()).unwrap().expires_at().is_none());
+
+        assert!(store.remove(expiring.commit()).unwrap().is_expired());
+        assert!(store.expired().is_empty());
+        assert_eq!(1, store.stats().item_count);
+    }
+
+    #[test]
+    fn test_store_evict() {
+        let mut store = Store::new();
+        let a = store.put(String::from("a")).commit();
+        let clone = store.clone();
+
+        assert!(clone.evict(a.commit()));
+        assert!(!clone.evict(a.commit()));
+        assert!(!store.contains(a.commit()));
+        assert!(store.item(a.commit()).is_none());
+        assert_eq!(0, store.stats().item_count);
+        assert!(store.remove(a.commit()).is_none());
+
+        // Committing the resource again adds it back
+        let mut inserts = store.on_insert();
+        let _ = store.put(String::from("a")).keep_existing().commit();
+        assert!(store.contains(a.commit()));
+        assert_eq!(Ok(a.commit()), inserts.try_recv());
+    }
+
+    #[test]
+    fn test_put_keep_existing() {
+        let mut store = Store::new();
+        let a = store.put(String::from("a")).commit();
+        let mut item = store.item(a.commit()).unwrap().clone();
+        item.borrow_mut::<String>().unwrap().push('b');
+
+        let kept = store.put(String::from("a")).keep_existing().commit();
+        assert_eq!(a.commit(), kept.commit());
+        assert_eq!(
+            Some(&String::from("ab")),
+            store.item(a.commit()).unwrap().borrow::<String>()
+        );
+
+        let _ = store.put(String::from("a")).commit();
+        assert_eq!(
+            Some(&String::from("a")),
+            store.item(a.commit()).unwrap().borrow::<String>()
+        );
+    }
+
+    #[test]
+    fn test_put_on_insert() {
+        let mut store = Store::new();
+        let mut inserts = store.on_insert();
+        let mut cloned = store.clone().on_insert();
+
+        let a = store.put(String::from("a")).commit();
+        let _ = store.put(String::from("a")).commit();
+        let b = store.put(String::from("b")).label("kind", "test").commit();
+
+        for receiver in [&mut inserts, &mut cloned] {
+            assert_eq!(Ok(a.commit()), receiver.try_recv());
+            assert_eq!(Ok(b.commit()), receiver.try_recv());
+            assert!(receiver.try_recv().is_err());
+        }
+    }
 }
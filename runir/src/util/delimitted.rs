@@ -99,6 +99,121 @@ pub fn scan_for_headers(source: &str) -> impl Iterator<Item = (&str, Vec<&str>)>
     })
 }
 
+/// Scans for headers from a string and returns an iterator over the results, w/ each value percent-decoded
+///
+/// **Note**: Values are only allocated if decoding changes the value
+#[inline]
+pub fn scan_for_headers_decoded(source: &str) -> impl Iterator<Item = (&str, Vec<Cow<'_, str>>)> {
+    scan_for_headers(source).map(|(key, values)| {
+        (
+            key,
+            values.into_iter().map(decode_header_value).collect(),
+        )
+    })
+}
+
+/// Scans for records from a string w/ rows separated by `row_delim` and fields separated by `field_delim`, and returns
+/// an iterator over the fields of each record
+///
+/// Delimiters found between matching single or double quotes are treated as literal, and quotes are kept in the field.
+/// Empty fields are returned as empty strs, and rows are returned as-is even if they have a differing number of fields.
+/// Empty rows, i.e. from a trailing row delimiter, are skipped.
+#[inline]
+pub fn scan_records(
+    source: &str,
+    row_delim: char,
+    field_delim: char,
+) -> impl Iterator<Item = Vec<&str>> {
+    let offset = |s: &str| s.as_ptr() as usize - source.as_ptr() as usize;
+
+    let records = split_quoted(source, row_delim.encode_utf8(&mut [0; 4]), 0)
+        .into_iter()
+        .filter(|row| !row.is_empty())
+        .map(|row| Record {
+            start: offset(row),
+            delim: field_delim.len_utf8(),
+            fields: split_quoted(row, field_delim.encode_utf8(&mut [0; 4]), 0)
+                .into_iter()
+                .map(|f| Item(f.len()))
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    StrIter::new(source, records, |record, cursor, source| {
+        *cursor = record.start;
+
+        let mut fields = vec![];
+        for f in record.fields {
+            fields.push(f.span(*cursor).view(source));
+            *cursor += f.0 + record.delim;
+        }
+        fields
+    })
+}
+
+/// Percent-decodes a header value
+///
+/// Returns the borrowed value if decoding does not change the value, or if the decoded value is not valid utf8
+#[inline]
+pub fn decode_header_value(value: &str) -> Cow<'_, str> {
+    percent_encoding::percent_decode_str(value)
+        .decode_utf8()
+        .unwrap_or(Cow::Borrowed(value))
+}
+
+/// Splits a str on a delimiter, treating any delimiter found between matching single or double quotes as literal
+///
+/// A quote only opens a quoted segment at the start of the str or after a delimiter, i.e. a character that is not
+/// alphanumeric, ignoring whitespace. A quote within a word, i.e. the apostrophe in `don't`, is literal.
+///
+/// If `count` is greater than 0, at most `count` segments are returned w/ the last segment containing the remainder.
+///
+/// **Note**: If a quote is left unmatched, the str is split w/o respecting quotes
+fn split_quoted<'a>(value: &'a str, delim: &str, count: usize) -> Vec<&'a str> {
+    let unquoted = || match count {
+        0 => value.split(delim).collect(),
+        _ => value.splitn(count, delim).collect(),
+    };
+
+    if !value.contains(['\'', '"']) {
+        return unquoted();
+    }
+
+    let mut segments = vec![];
+    let mut quote = None;
+    let mut start = 0;
+    // Last character before the current character that is not whitespace
+    let mut previous = None::<char>;
+    let mut chars = value.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        let opens = !previous.is_some_and(|p| p.is_alphanumeric());
+        if !c.is_whitespace() {
+            previous = Some(c);
+        }
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if (c == '\'' || c == '"') && opens => quote = Some(c),
+            None if (count == 0 || segments.len() + 1 < count)
+                && value[idx..].starts_with(delim) =>
+            {
+                segments.push(&value[start..idx]);
+                start = idx + delim.len();
+                for _ in 1..delim.chars().count() {
+                    chars.next();
+                }
+            }
+            None => {}
+        }
+    }
+
+    if quote.is_some() {
+        return unquoted();
+    }
+    segments.push(&value[start..]);
+    segments
+}
+
 #[derive(Clone, Copy, Debug)]
 struct Item(usize);
 
@@ -113,6 +228,15 @@ impl Item {
     }
 }
 
+#[derive(Debug)]
+struct Record {
+    /// Offset of the start of the record in the source
+    start: usize,
+    /// Length of the field delimiter
+    delim: usize,
+    fields: Vec<Item>,
+}
+
 #[derive(Debug)]
 enum Span {
     Start(RangeTo<usize>),
@@ -211,15 +335,11 @@ impl<const DELIM: char, const COUNT: usize, T: FromStr + Send + Sync + 'static>
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if COUNT == 0 {
-            let mut value = self.value.split(DELIM).skip(self.cursor);
-            self.cursor += 1;
-            value.next().and_then(|v| T::from_str(v.trim()).ok())
-        } else {
-            let mut value = self.value.splitn(COUNT, DELIM).skip(self.cursor);
-            self.cursor += 1;
-            value.next().and_then(|v| T::from_str(v.trim()).ok())
-        }
+        let mut value = split_quoted(&self.value, DELIM.encode_utf8(&mut [0; 4]), COUNT)
+            .into_iter()
+            .skip(self.cursor);
+        self.cursor += 1;
+        value.next().and_then(|v| T::from_str(v.trim()).ok())
     }
 }
 
@@ -243,22 +363,12 @@ impl<const DELIM: char, const COUNT: usize> DelimittedStr<DELIM, COUNT> {
 
     /// Returns the current value
     fn value<'a: 'b, 'b>(&'a self) -> std::option::Option<Cow<'b, str>> {
-        if COUNT == 0 {
-            self.value
-                .split(DELIM)
-                .nth(self.cursor.load(std::sync::atomic::Ordering::Relaxed))
-                .map(str::trim)
-                .filter(|s| !s.is_empty())
-                .map(Cow::Borrowed)
-        } else {
-            self.value
-                .splitn(COUNT, DELIM)
-                .nth(self.cursor.load(std::sync::atomic::Ordering::Relaxed))
-                // .next()
-                .map(str::trim)
-                .filter(|s| !s.is_empty())
-                .map(Cow::Borrowed)
-        }
+        split_quoted(&self.value, DELIM.encode_utf8(&mut [0; 4]), COUNT)
+            .into_iter()
+            .nth(self.cursor.load(std::sync::atomic::Ordering::Relaxed))
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(Cow::Borrowed)
     }
 }
 
@@ -300,21 +410,12 @@ impl<const DELIM1: char, const DELIM2: char, const COUNT: usize> DelimittedStr2<
 
     /// Returns the current value
     fn value<'a: 'b, 'b>(&'a self) -> std::option::Option<Cow<'b, str>> {
-        if COUNT == 0 {
-            self.value
-                .split(&format!("{DELIM1}{DELIM2}"))
-                .nth(self.cursor.load(std::sync::atomic::Ordering::Relaxed))
-                .map(str::trim)
-                .filter(|s| !s.is_empty())
-                .map(Cow::Borrowed)
-        } else {
-            self.value
-                .splitn(COUNT, &format!("{DELIM1}{DELIM2}"))
-                .nth(self.cursor.load(std::sync::atomic::Ordering::Relaxed))
-                .map(str::trim)
-                .filter(|s| !s.is_empty())
-                .map(Cow::Borrowed)
-        }
+        split_quoted(&self.value, &format!("{DELIM1}{DELIM2}"), COUNT)
+            .into_iter()
+            .nth(self.cursor.load(std::sync::atomic::Ordering::Relaxed))
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(Cow::Borrowed)
     }
 }
 
@@ -381,3 +482,84 @@ fn test_delimitted_str() {
     assert_eq!(("Accept", vec!["text"]), results.next().unwrap());
     assert_eq!(("Names", vec!["test", "abc"]), results.next().unwrap());
 }
+
+#[test]
+fn test_delimitted_quoted() {
+    let mut values = "'test, abc', \"def, ghi\", jkl"
+        .parse::<Delimitted<',', String>>()
+        .unwrap();
+    assert_eq!("'test, abc'", values.next().unwrap());
+    assert_eq!("\"def, ghi\"", values.next().unwrap());
+    assert_eq!("jkl", values.next().unwrap());
+    assert!(values.next().is_none());
+
+    let mut headers = "Accept = text; Names = 'test, abc';"
+        .parse::<Delimitted<';', Delimitted<'=', Delimitted<',', String>>>>()
+        .unwrap()
+        .into_pairs();
+    assert_eq!(
+        ("Accept".to_string(), vec!["text".to_string()]),
+        headers.next().unwrap()
+    );
+    assert_eq!(
+        ("Names".to_string(), vec!["'test, abc'".to_string()]),
+        headers.next().unwrap()
+    );
+
+    let mut values = "don't, split".parse::<Delimitted<',', String>>().unwrap();
+    assert_eq!("don't", values.next().unwrap());
+    assert_eq!("split", values.next().unwrap());
+
+    let mut values = "don't,stop,'a,b',it's"
+        .parse::<Delimitted<',', String>>()
+        .unwrap();
+    assert_eq!("don't", values.next().unwrap());
+    assert_eq!("stop", values.next().unwrap());
+    assert_eq!("'a,b'", values.next().unwrap());
+    assert_eq!("it's", values.next().unwrap());
+    assert!(values.next().is_none());
+
+    let mut results = scan_for_headers("Accept=text;;Names='test,abc';;");
+    assert_eq!(("Accept", vec!["text"]), results.next().unwrap());
+    assert_eq!(("Names", vec!["'test,abc'"]), results.next().unwrap());
+}
+
+#[test]
+fn test_decode_header_value() {
+    assert!(matches!(decode_header_value("text"), Cow::Borrowed("text")));
+    assert_eq!("hello world", decode_header_value("hello%20world"));
+    assert!(matches!(decode_header_value("%FF"), Cow::Borrowed("%FF")));
+
+    let mut results = scan_for_headers_decoded("Accept=text;;Names=hello%20world,abc;;");
+    assert_eq!(("Accept", vec![Cow::Borrowed("text")]), results.next().unwrap());
+    let (key, values) = results.next().unwrap();
+    assert_eq!("Names", key);
+    assert!(matches!(values[0], Cow::Owned(_)));
+    assert_eq!(["hello world", "abc"], &values[..]);
+}
+
+#[test]
+fn test_scan_records() {
+    let mut records = scan_records("a,b,c\n1,,3\n4,5\n", '\n', ',');
+    assert_eq!(vec!["a", "b", "c"], records.next().unwrap());
+    assert_eq!(vec!["1", "", "3"], records.next().unwrap());
+    assert_eq!(vec!["4", "5"], records.next().unwrap());
+    assert!(records.next().is_none());
+
+    let mut records = scan_records("'a,b';c,;\n\n\"d;e\";f", ';', ',');
+    assert_eq!(vec!["'a,b'"], records.next().unwrap());
+    assert_eq!(vec!["c", ""], records.next().unwrap());
+    assert_eq!(vec!["\n\n\"d;e\""], records.next().unwrap());
+    assert_eq!(vec!["f"], records.next().unwrap());
+    assert!(records.next().is_none());
+
+    let mut records = scan_records("don't,'a\nb'\nwon't,stop\n", '\n', ',');
+    assert_eq!(vec!["don't", "'a\nb'"], records.next().unwrap());
+    assert_eq!(vec!["won't", "stop"], records.next().unwrap());
+    assert!(records.next().is_none());
+
+    let mut records = scan_records("\u{e9}\t\u{e9}\u{e9}\n\tx", '\n', '\t');
+    assert_eq!(vec!["\u{e9}", "\u{e9}\u{e9}"], records.next().unwrap());
+    assert_eq!(vec!["", "x"], records.next().unwrap());
+    assert!(scan_records("", '\n', ',').next().is_none());
+}
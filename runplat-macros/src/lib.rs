@@ -10,6 +10,10 @@ use syn::parse_macro_input;
 use syn::DeriveInput;
 
 /// Derives reality `Plugin` trait and enables helper attribute for defining callbacks
+///
+/// **Note**: The derive also implements `Resource`, unless `#[reality(skip_resource)]` is set. Deriving `Resource` or
+/// `Repr` in a later `#[derive]` attribute is reported w/ a hint to set it, but deriving either in the same or an
+/// earlier `#[derive]` attribute is only reported as conflicting implementations
 #[proc_macro_derive(
     Plugin,
     attributes(
@@ -11,11 +11,17 @@ pub struct Plugin {
     content_with: Option<Path>,
     load: Option<Path>,
     load_with: Option<ExprClosure>,
+    skip_resource: bool,
+    skip_content: bool,
 }
 
 impl Plugin {
     fn render_content_state_uuid_impl(&self) -> TokenStream {
-        if let Some(content_with) = self.content_with.as_ref() {
+        if self.skip_content && (self.content_with.is_some() || self.content_from.is_some()) {
+            quote! {
+                compile_error!("`skip_content` cannot be used w/ `content_from` or `content_with`, since `Content` is not implemented by the derive")
+            }
+        } else if let Some(content_with) = self.content_with.as_ref() {
             quote! {
                 #content_with(self)
             }
@@ -42,6 +48,13 @@ impl Plugin {
   content_with = function_that_generates_content
 )]
 ```
+
+If `Content` is implemented manually, use `skip_content` so that the derive does not implement it
+```
+#[reality(
+  skip_content
+)]
+```
 "#)
             }
         }
@@ -79,19 +92,69 @@ impl Plugin {
         }
     }
 
+    fn render_resource_impl(&self) -> TokenStream {
+        let name = &self.input.ident;
+        let (impl_generic, ty_generic, where_clause) = self.input.generics.split_for_impl();
+        if self.skip_resource {
+            quote! {}
+        } else if let Some(derive) = self.derives_resource() {
+            let message = format!("`#[derive({derive})]` already implements `Resource`, add `#[reality(skip_resource)]` so that the `Plugin` derive does not implement it");
+            quote! {
+                compile_error!(#message);
+            }
+        } else {
+            quote! {
+                impl #impl_generic runir::Resource for #name #ty_generic #where_clause {}
+            }
+        }
+    }
+
+    fn render_content_impl(&self) -> TokenStream {
+        let name = &self.input.ident;
+        let (impl_generic, ty_generic, where_clause) = self.input.generics.split_for_impl();
+        if self.skip_content && self.content_with.is_none() && self.content_from.is_none() {
+            quote! {}
+        } else {
+            let impl_content_state_uuid = self.render_content_state_uuid_impl();
+            quote! {
+                impl #impl_generic runir::Content for #name #ty_generic #where_clause {
+                    fn state_uuid(&self) -> uuid::Uuid {
+                        #impl_content_state_uuid
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the name of a derive on the input that also implements `Resource`, i.e. `Resource` or `Repr`
+    ///
+    /// **Note**: A derive macro only receives the `#[derive]` attributes that follow the one it was invoked from, so
+    /// `Resource` or `Repr` derived in the same or an earlier `#[derive]` attribute as `Plugin` cannot be detected. In
+    /// that case the conflicting implementations are reported by the compiler, and `#[reality(skip_resource)]` is
+    /// required.
+    fn derives_resource(&self) -> Option<String> {
+        let mut derives = vec![];
+        for attr in self.input.attrs.iter().filter(|a| a.path().is_ident("derive")) {
+            let _ = attr.parse_nested_meta(|meta| {
+                if let Some(ident) = meta.path.segments.last() {
+                    derives.push(ident.ident.to_string());
+                }
+                Ok(())
+            });
+        }
+        derives.into_iter().find(|d| d == "Resource" || d == "Repr")
+    }
+
     pub fn render(self) -> TokenStream {
         let name = &self.input.ident;
         let (impl_generic, ty_generic, where_clause) = self.input.generics.split_for_impl();
         let impl_plugin_call = self.render_plugin_call_impl();
         let impl_plugin_load = self.render_plugin_load_impl();
-        let impl_content_state_uuid = self.render_content_state_uuid_impl();
+        let impl_resource = self.render_resource_impl();
+        let impl_content = self.render_content_impl();
         quote! {
-            impl #impl_generic runir::Resource for #name #ty_generic #where_clause {}
-            impl #impl_generic runir::Content for #name #ty_generic #where_clause {
-                fn state_uuid(&self) -> uuid::Uuid {
-                    #impl_content_state_uuid
-                }
-            }
+            #impl_resource
+            #impl_content
             impl #impl_generic Plugin for #name #ty_generic #where_clause {
                 fn call(binding: plugin::Bind<Self>) -> CallResult {
                     #impl_plugin_call
@@ -118,6 +181,8 @@ impl Parse for Plugin {
         let mut content_with = None;
         let mut load = None;
         let mut load_with = None;
+        let mut skip_resource = false;
+        let mut skip_content = false;
         for attr in input.attrs.iter() {
             if attr.path().is_ident("reality") {
                 attr.parse_nested_meta(|meta| {
@@ -144,6 +209,14 @@ impl Parse for Plugin {
                             load = Some(meta.input.parse::<Path>()?);
                         }
                     }
+
+                    if meta.path.is_ident("skip_resource") {
+                        skip_resource = true;
+                    }
+
+                    if meta.path.is_ident("skip_content") {
+                        skip_content = true;
+                    }
                     Ok(())
                 })?;
             }
@@ -156,6 +229,53 @@ impl Parse for Plugin {
             content_with,
             load,
             load_with,
+            skip_resource,
+            skip_content,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the expansion of the `Plugin` derive for `item`
+    fn expand(item: &str) -> String {
+        syn::parse_str::<Plugin>(item).unwrap().render().to_string()
+    }
+
+    #[test]
+    fn test_plugin_derives_resource() {
+        let expanded = expand(
+            r#"
+#[derive(Plugin)]
+#[derive(Serialize, runir::Resource)]
+#[reality(content_from = BincodeContent)]
+struct Test;
+"#,
+        );
+        assert!(expanded.contains("compile_error"));
+        assert!(expanded.contains("skip_resource"));
+
+        let expanded = expand(
+            r#"
+#[derive(Plugin)]
+#[derive(Repr)]
+#[reality(content_from = BincodeContent, skip_resource)]
+struct Test;
+"#,
+        );
+        assert!(!expanded.contains("compile_error"));
+        assert!(!expanded.contains("runir :: Resource"));
+
+        let expanded = expand(
+            r#"
+#[derive(Clone)]
+#[reality(content_from = BincodeContent)]
+struct Test;
+"#,
+        );
+        assert!(!expanded.contains("compile_error"));
+        assert!(expanded.contains("impl runir :: Resource for Test"));
+    }
+}